@@ -0,0 +1,32 @@
+#!/bin/bash
+
+# Builds the engine for each x86-64 CPU target into dist/, for running on
+# machines other than the build machine (build_release.sh builds for this
+# one). Each build checks its CPU features at startup.
+#   generic - any x86-64 CPU
+#   popcnt  - adds POPCNT (Nehalem, 2008, and later)
+#   bmi2    - adds BMI1/BMI2 (Haswell, 2013, and later)
+#   avx2    - adds AVX2
+
+set -e
+
+declare -A FEATURES=(
+    [generic]=""
+    [popcnt]="+popcnt"
+    [bmi2]="+popcnt,+bmi1,+bmi2"
+    [avx2]="+popcnt,+bmi1,+bmi2,+avx,+avx2"
+)
+
+mkdir -p dist
+
+for target in generic popcnt bmi2 avx2; do
+    rustflags="-C target-cpu=x86-64"
+    if [ -n "${FEATURES[$target]}" ]; then
+        rustflags="$rustflags -C target-feature=${FEATURES[$target]}"
+    fi
+
+    # a target directory per build, so they don't rebuild each other
+    RUSTFLAGS="$rustflags" CARGO_TARGET_DIR="target/$target" \
+        cargo build --release -p dolphin_engine
+    cp "target/$target/release/dolphin_engine" "dist/dolphin_engine-$target"
+done
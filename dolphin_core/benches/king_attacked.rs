@@ -0,0 +1,58 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use dolphin_core::board::occupancy_masks::OccupancyMasks;
+use dolphin_core::io::fen;
+use dolphin_core::moves::move_gen::MoveGenerator;
+use dolphin_core::moves::move_list::MoveList;
+use dolphin_core::position::attack_checker::AttackChecker;
+use dolphin_core::position::game_position::MoveLegality;
+use dolphin_core::position::game_position::Position;
+
+// Make/take with a check test at every node, to compare the incrementally
+// updated attack table with working out attacks on demand:
+//      cargo bench --bench king_attacked
+//      cargo bench --bench king_attacked --features attack_table
+
+// "Kiwipete", lots of captures, castles and promotions
+const FEN: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const DEPTH: u8 = 3;
+
+fn count_checks(depth: u8, pos: &mut Position, move_gen: &MoveGenerator) -> u64 {
+    if depth == 0 {
+        return pos.is_king_sq_attacked() as u64;
+    }
+
+    let mut move_list = MoveList::new();
+    move_gen.generate_moves(pos, &mut move_list);
+
+    let mut checks = 0;
+    for mv in move_list.iterator() {
+        if pos.make_move(mv) == MoveLegality::Legal {
+            checks += count_checks(depth - 1, pos, move_gen);
+        }
+        pos.take_move();
+    }
+    checks
+}
+
+fn king_attacked_benchmark(c: &mut Criterion) {
+    let attack_checker = AttackChecker::new();
+    let move_gen = MoveGenerator::new();
+
+    let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) = fen::decompose_fen(FEN);
+    let mut pos = Position::new(
+        board,
+        castle_permissions,
+        move_cntr,
+        en_pass_sq,
+        side_to_move,
+        OccupancyMasks::instance(),
+        &attack_checker,
+    );
+
+    c.bench_function("king_attacked", |b| {
+        b.iter(|| count_checks(DEPTH, &mut pos, &move_gen))
+    });
+}
+
+criterion_group!(benches, king_attacked_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,77 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use dolphin_core::board::occupancy_masks::OccupancyMasks;
+use dolphin_core::io::fen;
+use dolphin_core::moves::move_gen::MoveGenerator;
+use dolphin_core::moves::move_list::MoveList;
+use dolphin_core::position::attack_checker::AttackChecker;
+use dolphin_core::position::game_position::MoveLegality;
+use dolphin_core::position::game_position::Position;
+
+// "Kiwipete", lots of captures, castles and promotions
+const FEN: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+const DEPTH: u8 = 3;
+
+fn perft_make_take(depth: u8, pos: &mut Position, move_gen: &MoveGenerator) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut move_list = MoveList::new();
+    move_gen.generate_moves(pos, &mut move_list);
+
+    let mut nodes = 0;
+    for mv in move_list.iterator() {
+        if pos.make_move(mv) == MoveLegality::Legal {
+            nodes += perft_make_take(depth - 1, pos, move_gen);
+        }
+        pos.take_move();
+    }
+    nodes
+}
+
+fn perft_copy_make(depth: u8, pos: &Position, move_gen: &MoveGenerator) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut move_list = MoveList::new();
+    move_gen.generate_moves(pos, &mut move_list);
+
+    let mut nodes = 0;
+    for mv in move_list.iterator() {
+        let (child, move_legality) = pos.make_move_copied(mv);
+        if move_legality == MoveLegality::Legal {
+            nodes += perft_copy_make(depth - 1, &child, move_gen);
+        }
+    }
+    nodes
+}
+
+fn make_move_benchmark(c: &mut Criterion) {
+    let occ_masks = OccupancyMasks::instance();
+    let attack_checker = AttackChecker::new();
+    let move_gen = MoveGenerator::new();
+
+    let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) = fen::decompose_fen(FEN);
+    let mut pos = Position::new(
+        board,
+        castle_permissions,
+        move_cntr,
+        en_pass_sq,
+        side_to_move,
+        occ_masks,
+        &attack_checker,
+    );
+
+    let mut group = c.benchmark_group("make_move");
+    group.bench_function("make_take", |b| {
+        b.iter(|| perft_make_take(DEPTH, &mut pos, &move_gen))
+    });
+    group.bench_function("copy_make", |b| {
+        b.iter(|| perft_copy_make(DEPTH, &pos, &move_gen))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, make_move_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,46 @@
+use crate::board::bitboard::Bitboard;
+use crate::board::colour::Colour;
+use crate::board::game_board::Board;
+use crate::board::square::Square;
+
+// The squares attacked from each occupied square, and by each colour, kept
+// up to date by the board as pieces are added, removed and moved (feature
+// "attack_table"). An experiment against working the attacks out on demand
+// in AttackChecker - only the pieces on the changed squares, and the sliders
+// whose lines run through them, are recalculated.
+#[derive(Eq, PartialEq, Copy, Clone)]
+pub struct AttackTable {
+    attacks_from: [Bitboard; Board::NUM_SQUARES],
+    attacked_by: [Bitboard; Colour::NUM_COLOURS],
+}
+
+impl Default for AttackTable {
+    fn default() -> Self {
+        AttackTable {
+            attacks_from: [Bitboard::default(); Board::NUM_SQUARES],
+            attacked_by: [Bitboard::default(); Colour::NUM_COLOURS],
+        }
+    }
+}
+
+impl AttackTable {
+    pub fn attacks_from(&self, sq: &Square) -> Bitboard {
+        self.attacks_from[sq.as_index()]
+    }
+
+    pub fn attacked_by(&self, colour: &Colour) -> Bitboard {
+        self.attacked_by[colour.as_index()]
+    }
+
+    pub fn is_attacked_by(&self, sq: &Square, colour: &Colour) -> bool {
+        self.attacked_by[colour.as_index()].is_set(sq)
+    }
+
+    pub fn set_attacks_from(&mut self, sq: &Square, attacks: Bitboard) {
+        self.attacks_from[sq.as_index()] = attacks;
+    }
+
+    pub fn set_attacked_by(&mut self, colour: &Colour, attacks: Bitboard) {
+        self.attacked_by[colour.as_index()] = attacks;
+    }
+}
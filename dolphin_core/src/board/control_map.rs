@@ -0,0 +1,92 @@
+use crate::board::colour::Colour;
+use crate::board::file::File;
+use crate::board::piece::Piece;
+use crate::board::rank::Rank;
+use crate::board::square::Square;
+use std::fmt;
+
+// Number of pieces of one colour attacking each square, indexed [rank][file]
+#[derive(Eq, PartialEq, Default, Copy, Clone)]
+pub struct ControlMap {
+    counts: [[u8; ControlMap::NUM_FILES]; ControlMap::NUM_RANKS],
+}
+
+impl ControlMap {
+    const NUM_RANKS: usize = 8;
+    const NUM_FILES: usize = 8;
+
+    pub fn new() -> ControlMap {
+        ControlMap::default()
+    }
+
+    pub fn add_attack(&mut self, sq: &Square) {
+        self.counts[sq.rank().as_index()][sq.file().as_index()] += 1;
+    }
+
+    pub fn num_attackers(&self, sq: &Square) -> u8 {
+        self.counts[sq.rank().as_index()][sq.file().as_index()]
+    }
+
+    pub const fn as_array(&self) -> [[u8; ControlMap::NUM_FILES]; ControlMap::NUM_RANKS] {
+        self.counts
+    }
+}
+
+impl fmt::Debug for ControlMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug_str = String::new();
+        debug_str.push_str("\n\n");
+
+        for r in Rank::reverse_iterator() {
+            debug_str.push(r.to_char());
+            debug_str.push('\t');
+
+            for f in File::iterator() {
+                let count = self.counts[r.as_index()][f.as_index()];
+                if count == 0 {
+                    debug_str.push_str(".\t");
+                } else {
+                    debug_str.push_str(&count.to_string());
+                    debug_str.push('\t');
+                }
+            }
+
+            debug_str.push('\n');
+        }
+        debug_str.push_str("\n\tA\tB\tC\tD\tE\tF\tG\tH\n\n");
+        write!(f, "{}", debug_str)
+    }
+}
+
+impl fmt::Display for ControlMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self, f)
+    }
+}
+
+// The pieces of each colour attacking a single square, least valuable first
+// (the order they'd be used in an exchange)
+#[derive(Eq, PartialEq, Default, Clone, Debug)]
+pub struct AttackersBreakdown {
+    attackers: [Vec<(Piece, Square)>; Colour::NUM_COLOURS],
+}
+
+impl AttackersBreakdown {
+    pub fn new() -> AttackersBreakdown {
+        AttackersBreakdown::default()
+    }
+
+    pub fn add_attacker(&mut self, piece: &Piece, colour: &Colour, sq: &Square) {
+        let attackers = &mut self.attackers[colour.as_index()];
+        let pos = attackers.partition_point(|(pce, _)| pce.value() <= piece.value());
+        attackers.insert(pos, (*piece, *sq));
+    }
+
+    pub fn attackers(&self, colour: &Colour) -> &[(Piece, Square)] {
+        &self.attackers[colour.as_index()]
+    }
+
+    pub fn num_attackers(&self, colour: &Colour) -> usize {
+        self.attackers[colour.as_index()].len()
+    }
+}
@@ -1,6 +1,11 @@
+#[cfg(feature = "attack_table")]
+use crate::board::attack_table::AttackTable;
 use crate::board::bitboard::Bitboard;
 use crate::board::colour::Colour;
+use crate::board::control_map::AttackersBreakdown;
+use crate::board::control_map::ControlMap;
 use crate::board::file::File;
+use crate::board::occupancy_masks::OccupancyMasks;
 use crate::board::piece::Piece;
 use crate::board::rank::Rank;
 use crate::board::square::Square;
@@ -13,7 +18,8 @@ struct ColourInfo {
     piece_bb: [Bitboard; Piece::NUM_PIECE_TYPES],
     colour_bb: Bitboard,
     material: Score,
-    king_sq: Square,
+    // None once the king's been captured (antichess)
+    king_sq: Option<Square>,
 }
 
 #[derive(Eq, PartialEq, Default, Copy, Clone)]
@@ -22,10 +28,12 @@ pub struct Material {
     black: Score,
 }
 
-#[derive(Eq, PartialEq)]
+#[derive(Eq, PartialEq, Copy, Clone)]
 pub struct Board {
     colour_info: [ColourInfo; Colour::NUM_COLOURS],
     pieces: [Option<Piece>; Board::NUM_SQUARES],
+    #[cfg(feature = "attack_table")]
+    attack_table: AttackTable,
 }
 
 impl Board {
@@ -40,10 +48,12 @@ impl Board {
 
         self.colour_info[colour.as_index()].material += piece.value();
         self.pieces[sq.as_index()] = Some(*piece);
-        match piece {
-            Piece::King => self.colour_info[colour.as_index()].king_sq = *sq,
-            _ => (),
+        if *piece == Piece::King {
+            self.colour_info[colour.as_index()].king_sq = Some(*sq);
         }
+
+        #[cfg(feature = "attack_table")]
+        self.update_attack_table(Bitboard::from_square(sq));
     }
 
     pub fn remove_piece(&mut self, piece: &Piece, colour: &Colour, sq: &Square) {
@@ -51,6 +61,13 @@ impl Board {
 
         self.colour_info[colour.as_index()].material -= piece.value();
         self.pieces[sq.as_index()] = None;
+
+        if *piece == Piece::King {
+            self.colour_info[colour.as_index()].king_sq = None;
+        }
+
+        #[cfg(feature = "attack_table")]
+        self.update_attack_table(Bitboard::from_square(sq));
     }
 
     pub fn move_piece(&mut self, from_sq: &Square, to_sq: &Square, piece: &Piece, colour: &Colour) {
@@ -60,10 +77,12 @@ impl Board {
         self.pieces[from_sq.as_index()] = None;
         self.pieces[to_sq.as_index()] = Some(*piece);
 
-        match piece {
-            Piece::King => self.colour_info[colour.as_index()].king_sq = *to_sq,
-            _ => (),
+        if *piece == Piece::King {
+            self.colour_info[colour.as_index()].king_sq = Some(*to_sq);
         }
+
+        #[cfg(feature = "attack_table")]
+        self.update_attack_table(Bitboard::from_square(from_sq) | Bitboard::from_square(to_sq));
     }
 
     #[inline(always)]
@@ -96,13 +115,20 @@ impl Board {
     }
 
     pub fn is_sq_empty(&self, sq: &Square) -> bool {
-        self.get_bitboard().is_set(sq) == false
+        self.empty().is_set(sq)
     }
 
     pub const fn get_piece_bitboard(&self, piece: &Piece, colour: &Colour) -> Bitboard {
         self.colour_info[colour.as_index()].piece_bb[piece.as_index()]
     }
 
+    // Squares of the colour's pieces of the type, a1 first. Code visiting
+    // each piece goes through here rather than the bitboard, so the order
+    // pieces are visited in can be changed in one place.
+    pub fn squares_of(&self, piece: &Piece, colour: &Colour) -> impl Iterator<Item = Square> {
+        self.get_piece_bitboard(piece, colour).iterator()
+    }
+
     pub const fn get_colour_bb(&self, colour: &Colour) -> Bitboard {
         self.colour_info[colour.as_index()].colour_bb
     }
@@ -120,12 +146,265 @@ impl Board {
             .wrapping_sub(self.colour_info[Colour::Black.as_index()].material) as Score
     }
 
-    pub fn get_bitboard(&self) -> Bitboard {
+    pub fn occupied(&self) -> Bitboard {
         self.get_colour_bb(&Colour::White) | self.get_colour_bb(&Colour::Black)
     }
 
-    pub fn get_king_sq(&self, colour: &Colour) -> Square {
-        self.colour_info[colour.as_index()].king_sq
+    pub fn empty(&self) -> Bitboard {
+        !self.occupied()
+    }
+
+    // the colour's pieces and the squares they're on, in square order
+    pub fn iter_pieces<'a>(
+        &'a self,
+        colour: &Colour,
+    ) -> impl Iterator<Item = (Piece, Square)> + 'a {
+        self.get_colour_bb(colour).iterator().map(move |sq| {
+            let piece = self
+                .get_piece_on_square(&sq)
+                .expect("Unexpected empty square");
+            (piece, sq)
+        })
+    }
+
+    // Panics if the bitboards, the mailbox and the incremental state don't
+    // agree, to catch make/take bugs where they happen rather than in a
+    // perft count much later. Too slow to leave on. A side can have lost
+    // its king if the variant's kings can be captured.
+    #[cfg(feature = "paranoid")]
+    pub fn assert_consistent(&self, king_capturable: bool) {
+        const PIECES: [Piece; Piece::NUM_PIECE_TYPES] = [
+            Piece::Pawn,
+            Piece::Bishop,
+            Piece::Knight,
+            Piece::Rook,
+            Piece::Queen,
+            Piece::King,
+        ];
+
+        let mut all_pieces_bb = Bitboard::default();
+        for colour in [Colour::White, Colour::Black] {
+            for piece in PIECES {
+                let piece_bb = self.get_piece_bitboard(&piece, &colour);
+                assert!(
+                    (all_pieces_bb & piece_bb).is_empty(),
+                    "{} {} on a square that's already occupied",
+                    colour,
+                    piece
+                );
+                all_pieces_bb |= piece_bb;
+            }
+        }
+
+        for colour in [Colour::White, Colour::Black] {
+            let colour_info = &self.colour_info[colour.as_index()];
+
+            let mut colour_bb = Bitboard::default();
+            let mut material = 0;
+            for piece in PIECES {
+                let piece_bb = colour_info.piece_bb[piece.as_index()];
+                colour_bb |= piece_bb;
+
+                for sq in piece_bb.iterator() {
+                    assert_eq!(
+                        self.pieces[sq.as_index()],
+                        Some(piece),
+                        "mailbox doesn't match the {} {} bitboard on {}",
+                        colour,
+                        piece,
+                        sq
+                    );
+                    material += piece.value();
+                }
+            }
+            assert!(
+                colour_bb == colour_info.colour_bb,
+                "{} bitboard isn't the union of its piece bitboards",
+                colour
+            );
+            assert_eq!(material, colour_info.material, "{} material", colour);
+
+            let king_bb = colour_info.piece_bb[Piece::King.as_index()];
+            let num_kings = king_bb.iterator().count();
+            assert!(
+                num_kings == 1 || (king_capturable && num_kings == 0),
+                "{} has no king, or more than one",
+                colour
+            );
+            assert!(
+                king_bb
+                    == colour_info
+                        .king_sq
+                        .map_or(Bitboard::default(), |sq| Bitboard::from_square(&sq)),
+                "{} king square",
+                colour
+            );
+        }
+
+        for sq in Square::iterator() {
+            assert_eq!(
+                self.pieces[sq.as_index()].is_some(),
+                all_pieces_bb.is_set(sq),
+                "mailbox doesn't match the bitboards on {}",
+                sq
+            );
+        }
+
+        #[cfg(feature = "attack_table")]
+        for colour in Colour::iterator() {
+            let mut attacked_by = Bitboard::default();
+            for (piece, sq) in self.iter_pieces(colour) {
+                let attacks =
+                    self.attacked_squares(OccupancyMasks::instance(), &piece, colour, &sq);
+                assert!(
+                    attacks == self.attack_table.attacks_from(&sq),
+                    "attack table out of date for the {} {} on {}",
+                    colour,
+                    piece,
+                    sq
+                );
+                attacked_by |= attacks;
+            }
+            assert!(
+                attacked_by == self.attack_table.attacked_by(colour),
+                "attack table out of date for {}",
+                colour
+            );
+        }
+    }
+
+    #[cfg(feature = "attack_table")]
+    pub fn attack_table(&self) -> &AttackTable {
+        &self.attack_table
+    }
+
+    // Recalculates the attacks of the pieces on the changed squares, and of
+    // any slider whose lines reach them, then the attacks of each colour
+    #[cfg(feature = "attack_table")]
+    fn update_attack_table(&mut self, changed: Bitboard) {
+        let occ_masks = OccupancyMasks::instance();
+
+        for sq in changed.iterator() {
+            if self.pieces[sq.as_index()].is_none() {
+                self.attack_table.set_attacks_from(&sq, Bitboard::default());
+            }
+        }
+
+        for colour in Colour::iterator() {
+            let mut attacked_by = Bitboard::default();
+            for sq in self.get_colour_bb(colour).iterator() {
+                let piece = self.pieces[sq.as_index()].expect("Unexpected empty square");
+                let is_slider = matches!(piece, Piece::Bishop | Piece::Rook | Piece::Queen);
+                let attacks_from = self.attack_table.attacks_from(&sq);
+
+                if changed.is_set(&sq) || (is_slider && !(attacks_from & changed).is_empty()) {
+                    let attacks = self.attacked_squares(occ_masks, &piece, colour, &sq);
+                    self.attack_table.set_attacks_from(&sq, attacks);
+                }
+                attacked_by |= self.attack_table.attacks_from(&sq);
+            }
+            self.attack_table.set_attacked_by(colour, attacked_by);
+        }
+    }
+
+    pub fn king_square(&self, colour: &Colour) -> Square {
+        self.colour_info[colour.as_index()]
+            .king_sq
+            .expect("No king on the board")
+    }
+
+    // counts how many of the colour's pieces attack each square
+    pub fn control_map(&self, occ_masks: &OccupancyMasks, colour: &Colour) -> ControlMap {
+        let mut control_map = ControlMap::new();
+
+        for (piece, sq) in self.iter_pieces(colour) {
+            let attacks = self.attacked_squares(occ_masks, &piece, colour, &sq);
+            for attacked_sq in attacks.iterator() {
+                control_map.add_attack(&attacked_sq);
+            }
+        }
+
+        control_map
+    }
+
+    // all pieces of both colours attacking the square, for "is this square
+    // safe" queries and exchange evaluation
+    pub fn attackers_breakdown(
+        &self,
+        occ_masks: &OccupancyMasks,
+        sq: &Square,
+    ) -> AttackersBreakdown {
+        let mut breakdown = AttackersBreakdown::new();
+
+        for colour in Colour::iterator() {
+            for (piece, pce_sq) in self.iter_pieces(colour) {
+                if self
+                    .attacked_squares(occ_masks, &piece, colour, &pce_sq)
+                    .is_set(sq)
+                {
+                    breakdown.add_attacker(&piece, colour, &pce_sq);
+                }
+            }
+        }
+
+        breakdown
+    }
+
+    fn attacked_squares(
+        &self,
+        occ_masks: &OccupancyMasks,
+        piece: &Piece,
+        colour: &Colour,
+        sq: &Square,
+    ) -> Bitboard {
+        let sq_bb = Bitboard::from_square(sq);
+        match piece {
+            Piece::Pawn => match colour {
+                Colour::White => sq_bb.north_east() | sq_bb.north_west(),
+                Colour::Black => sq_bb.south_east() | sq_bb.south_west(),
+            },
+            Piece::Knight => occ_masks.get_occupancy_mask_knight(sq),
+            Piece::King => occ_masks.get_occupancy_mask_king(sq),
+            Piece::Bishop => {
+                self.sliding_attacks(occ_masks, sq, Self::diagonal_lines(occ_masks, sq))
+            }
+            Piece::Rook => {
+                self.sliding_attacks(occ_masks, sq, Self::rank_file_lines(occ_masks, sq))
+            }
+            Piece::Queen => self.sliding_attacks(
+                occ_masks,
+                sq,
+                Self::diagonal_lines(occ_masks, sq) | Self::rank_file_lines(occ_masks, sq),
+            ),
+        }
+    }
+
+    fn diagonal_lines(occ_masks: &OccupancyMasks, sq: &Square) -> Bitboard {
+        occ_masks.get_diagonal_mask(sq) | occ_masks.get_antidiagonal_mask(sq)
+    }
+
+    fn rank_file_lines(occ_masks: &OccupancyMasks, sq: &Square) -> Bitboard {
+        occ_masks.get_horizontal_mask(sq) | occ_masks.get_vertical_mask(sq)
+    }
+
+    // squares on the lines that aren't blocked by an intervening piece
+    fn sliding_attacks(
+        &self,
+        occ_masks: &OccupancyMasks,
+        sq: &Square,
+        lines: Bitboard,
+    ) -> Bitboard {
+        let all_pce_bb = self.occupied();
+        let mut attacks = Bitboard::default();
+
+        for target_sq in lines.iterator() {
+            if target_sq != *sq
+                && (occ_masks.get_inbetween_squares(sq, &target_sq) & all_pce_bb).is_empty()
+            {
+                attacks.set_bit(&target_sq);
+            }
+        }
+        attacks
     }
 }
 
@@ -169,6 +448,8 @@ impl Default for Board {
         Board {
             colour_info: [ColourInfo::default(); Colour::NUM_COLOURS],
             pieces: [None; Board::NUM_SQUARES],
+            #[cfg(feature = "attack_table")]
+            attack_table: AttackTable::default(),
         }
     }
 }
@@ -177,6 +458,7 @@ impl Default for Board {
 pub mod tests {
     use crate::board::colour::Colour;
     use crate::board::game_board::Board;
+    use crate::board::occupancy_masks::OccupancyMasks;
     use crate::board::piece::Piece;
     use crate::board::square::Square;
     use crate::io::fen;
@@ -189,11 +471,11 @@ pub mod tests {
             let mut board = Board::new();
 
             for sq in Square::iterator() {
-                assert!(board.get_bitboard().is_empty());
+                assert!(board.occupied().is_empty());
                 board.add_piece(&Piece::King, col, sq);
-                assert!(!board.get_bitboard().is_empty());
+                assert!(!board.occupied().is_empty());
 
-                assert_eq!(board.get_king_sq(col), *sq);
+                assert_eq!(board.king_square(col), *sq);
 
                 // remove so state is restored.
                 board.remove_piece(&Piece::King, col, sq);
@@ -299,6 +581,124 @@ pub mod tests {
         }
     }
 
+    #[test]
+    pub fn occupied_and_empty_as_expected() {
+        let fen = "4k3/8/8/3p4/8/2N5/8/R3K3 w - - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+
+        let occupied = board.occupied();
+        let empty = board.empty();
+        for sq in Square::iterator() {
+            assert_eq!(occupied.is_set(sq), !board.is_sq_empty(sq));
+            assert_eq!(empty.is_set(sq), board.is_sq_empty(sq));
+        }
+        assert_eq!(occupied.iterator().count(), 5);
+    }
+
+    #[test]
+    pub fn squares_of_as_expected() {
+        let fen = "4k3/8/8/3p4/8/2N5/8/R3K1NR w - - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+
+        let knights: Vec<Square> = board.squares_of(&Piece::Knight, &Colour::White).collect();
+        assert_eq!(knights, [Square::G1, Square::C3]);
+
+        let rooks: Vec<Square> = board.squares_of(&Piece::Rook, &Colour::White).collect();
+        assert_eq!(rooks, [Square::A1, Square::H1]);
+
+        assert_eq!(board.squares_of(&Piece::Pawn, &Colour::White).count(), 0);
+        let pawns: Vec<Square> = board.squares_of(&Piece::Pawn, &Colour::Black).collect();
+        assert_eq!(pawns, [Square::D5]);
+    }
+
+    #[test]
+    pub fn iter_pieces_as_expected() {
+        let fen = "4k3/8/8/3p4/8/2N5/8/R3K3 w - - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+
+        let white: Vec<(Piece, Square)> = board.iter_pieces(&Colour::White).collect();
+        assert_eq!(
+            white,
+            [
+                (Piece::Rook, Square::A1),
+                (Piece::King, Square::E1),
+                (Piece::Knight, Square::C3),
+            ]
+        );
+
+        let black: Vec<(Piece, Square)> = board.iter_pieces(&Colour::Black).collect();
+        assert_eq!(
+            black,
+            [(Piece::Pawn, Square::D5), (Piece::King, Square::E8)]
+        );
+
+        assert_eq!(board.king_square(&Colour::White), Square::E1);
+        assert_eq!(board.king_square(&Colour::Black), Square::E8);
+    }
+
+    #[cfg(feature = "paranoid")]
+    #[test]
+    pub fn assert_consistent_passes_for_valid_board() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let (mut board, _, _, _, _) = fen::decompose_fen(fen);
+        board.assert_consistent(false);
+
+        board.move_piece(&Square::E1, &Square::F1, &Piece::King, &Colour::White);
+        board.remove_piece(&Piece::Pawn, &Colour::Black, &Square::H3);
+        board.assert_consistent(false);
+    }
+
+    #[cfg(feature = "paranoid")]
+    #[test]
+    #[should_panic(expected = "already occupied")]
+    pub fn assert_consistent_catches_two_pieces_on_a_square() {
+        let (mut board, _, _, _, _) = fen::decompose_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        board.add_piece(&Piece::Knight, &Colour::Black, &Square::E1);
+        board.assert_consistent(false);
+    }
+
+    #[cfg(feature = "paranoid")]
+    #[test]
+    #[should_panic(expected = "no king")]
+    pub fn assert_consistent_catches_missing_king() {
+        let (mut board, _, _, _, _) = fen::decompose_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        board.remove_piece(&Piece::King, &Colour::Black, &Square::E8);
+        board.assert_consistent(false);
+    }
+
+    #[cfg(feature = "paranoid")]
+    #[test]
+    pub fn assert_consistent_allows_captured_king_if_capturable() {
+        let (mut board, _, _, _, _) = fen::decompose_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1");
+        board.remove_piece(&Piece::King, &Colour::Black, &Square::E8);
+        board.assert_consistent(true);
+
+        board.add_piece(&Piece::King, &Colour::Black, &Square::D8);
+        assert_eq!(board.king_square(&Colour::Black), Square::D8);
+        board.assert_consistent(true);
+    }
+
+    #[cfg(feature = "attack_table")]
+    #[test]
+    pub fn attack_table_matches_board_built_from_scratch() {
+        // rook on a1 blocked by the knight, which moves off the file, then
+        // a bishop that gets blocked and a piece that's captured
+        let (mut board, _, _, _, _) = fen::decompose_fen("4k3/8/8/8/8/N3p3/6B1/R3K3 w - - 0 1");
+        board.move_piece(&Square::A3, &Square::C4, &Piece::Knight, &Colour::White);
+        board.move_piece(&Square::E1, &Square::F1, &Piece::King, &Colour::White);
+        board.remove_piece(&Piece::Pawn, &Colour::Black, &Square::E3);
+        board.add_piece(&Piece::Pawn, &Colour::Black, &Square::E4);
+
+        let (expected, _, _, _, _) = fen::decompose_fen("4k3/8/8/8/2N1p3/8/6B1/R4K2 w - - 0 1");
+        assert!(board == expected);
+        assert!(board
+            .attack_table()
+            .is_attacked_by(&Square::A8, &Colour::White));
+        assert!(!board
+            .attack_table()
+            .is_attacked_by(&Square::D5, &Colour::White));
+    }
+
     #[test]
     pub fn board_equality_as_expected() {
         let fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n w - - 0 1";
@@ -308,4 +708,104 @@ pub mod tests {
 
         assert_eq!(board_1, board_2);
     }
+
+    #[test]
+    pub fn control_map_counts_attackers_per_square() {
+        let fen = "4k3/8/8/3p4/8/2N5/8/R3K3 w - - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+        let occ_masks = OccupancyMasks::instance();
+
+        let white = board.control_map(occ_masks, &Colour::White);
+
+        // rook, knight and king
+        assert_eq!(white.num_attackers(&Square::D1), 3);
+        // rook, up the a-file
+        assert_eq!(white.num_attackers(&Square::A8), 1);
+        // rook, blocked by the king
+        assert_eq!(white.num_attackers(&Square::G1), 0);
+        // knight attacks the pawn
+        assert_eq!(white.num_attackers(&Square::D5), 1);
+        // knight and king
+        assert_eq!(white.num_attackers(&Square::E2), 2);
+        assert_eq!(white.num_attackers(&Square::H8), 0);
+
+        let black = board.control_map(occ_masks, &Colour::Black);
+
+        // pawn attacks diagonally towards rank 1
+        assert_eq!(black.num_attackers(&Square::C4), 1);
+        assert_eq!(black.num_attackers(&Square::E4), 1);
+        assert_eq!(black.num_attackers(&Square::D4), 0);
+        // king
+        assert_eq!(black.num_attackers(&Square::D7), 1);
+    }
+
+    #[test]
+    pub fn attackers_breakdown_sorted_by_piece_value() {
+        let fen = "3rk3/8/8/3p3Q/4P3/1BN5/8/3RK3 w - - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+        let occ_masks = OccupancyMasks::instance();
+
+        let breakdown = board.attackers_breakdown(occ_masks, &Square::D5);
+
+        let white = [
+            (Piece::Pawn, Square::E4),
+            (Piece::Knight, Square::C3),
+            (Piece::Bishop, Square::B3),
+            (Piece::Rook, Square::D1),
+            (Piece::Queen, Square::H5),
+        ];
+        assert_eq!(breakdown.attackers(&Colour::White), white);
+        // black rook defends its pawn
+        assert_eq!(
+            breakdown.attackers(&Colour::Black),
+            [(Piece::Rook, Square::D8)]
+        );
+
+        let breakdown = board.attackers_breakdown(occ_masks, &Square::E4);
+        assert_eq!(
+            breakdown.attackers(&Colour::Black),
+            [(Piece::Pawn, Square::D5)]
+        );
+        assert_eq!(breakdown.num_attackers(&Colour::White), 1);
+    }
+
+    #[test]
+    pub fn control_map_sliders_stop_at_first_piece() {
+        let fen = "4k3/8/8/8/1p6/8/8/Q3K3 w - - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+        let occ_masks = OccupancyMasks::instance();
+
+        let white = board.control_map(occ_masks, &Colour::White);
+
+        assert_eq!(white.num_attackers(&Square::B2), 1);
+        assert_eq!(white.num_attackers(&Square::C3), 1);
+        assert_eq!(white.num_attackers(&Square::D4), 1);
+        assert_eq!(white.num_attackers(&Square::H8), 1);
+        assert_eq!(white.num_attackers(&Square::A8), 1);
+        assert_eq!(white.num_attackers(&Square::A1), 0);
+
+        let fen = "4k3/8/8/8/8/2p5/8/Q3K3 w - - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+        let white = board.control_map(occ_masks, &Colour::White);
+
+        assert_eq!(white.num_attackers(&Square::C3), 1);
+        assert_eq!(white.num_attackers(&Square::D4), 0);
+    }
+
+    #[test]
+    pub fn control_map_start_position() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+        let occ_masks = OccupancyMasks::instance();
+
+        let white = board.control_map(occ_masks, &Colour::White);
+        let array = white.as_array();
+
+        // rank 3 is attacked by pawns and knights
+        assert_eq!(array[2], [2, 2, 3, 2, 2, 3, 2, 2]);
+        assert!(array[3].iter().all(|c| *c == 0));
+
+        let black = board.control_map(occ_masks, &Colour::Black);
+        assert_eq!(black.as_array()[5], [2, 2, 3, 2, 2, 3, 2, 2]);
+    }
 }
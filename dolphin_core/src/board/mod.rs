@@ -1,5 +1,8 @@
+#[cfg(feature = "attack_table")]
+pub mod attack_table;
 pub mod bitboard;
 pub mod colour;
+pub mod control_map;
 pub mod file;
 pub mod game_board;
 pub mod occupancy_masks;
@@ -5,6 +5,7 @@ use crate::board::game_board::Board;
 use crate::board::rank::Rank;
 use crate::board::square::Square;
 use std::ops::Shl;
+use std::sync::OnceLock;
 
 const RANK_MASK: Bitboard = Bitboard::new(0x0000_0000_0000_00ff);
 const FILE_MASK: Bitboard = Bitboard::new(0x0101_0101_0101_0101);
@@ -12,6 +13,43 @@ const FILE_MASK: Bitboard = Bitboard::new(0x0101_0101_0101_0101);
 pub const FILE_A_BB: Bitboard = FILE_MASK;
 pub const FILE_H_BB: Bitboard = Bitboard::new(0x8080_8080_8080_8080);
 
+// Attacks along the first rank, indexed by [inner 6 occupancy bits][file].
+// The end squares are always attacked if reached, so their occupancy is
+// ignored.
+const FIRST_RANK_ATTACKS: [[u8; 8]; 64] = generate_first_rank_attacks();
+
+const fn generate_first_rank_attacks() -> [[u8; 8]; 64] {
+    let mut attacks = [[0u8; 8]; 64];
+    let mut occ = 0;
+    while occ < 64 {
+        let occupied = (occ << 1) as u8;
+        let mut file = 0;
+        while file < 8 {
+            let mut attack = 0u8;
+            let mut f = file + 1;
+            while f < 8 {
+                attack |= 1 << f;
+                if occupied & (1 << f) != 0 {
+                    break;
+                }
+                f += 1;
+            }
+            let mut f = file;
+            while f > 0 {
+                f -= 1;
+                attack |= 1 << f;
+                if occupied & (1 << f) != 0 {
+                    break;
+                }
+            }
+            attacks[occ][file] = attack;
+            file += 1;
+        }
+        occ += 1;
+    }
+    attacks
+}
+
 #[derive(Default, Eq, PartialEq, Hash, Clone, Copy)]
 struct OccupancyMasksForSquare {
     knight: Bitboard,
@@ -35,7 +73,14 @@ impl Default for OccupancyMasks {
     }
 }
 
+static INSTANCE: OnceLock<Box<OccupancyMasks>> = OnceLock::new();
+
 impl OccupancyMasks {
+    // The masks shared by the whole process, built on first use
+    pub fn instance() -> &'static OccupancyMasks {
+        INSTANCE.get_or_init(OccupancyMasks::new)
+    }
+
     pub fn new() -> Box<OccupancyMasks> {
         let mut occ_masks = Box::<OccupancyMasks>::default();
 
@@ -67,6 +112,15 @@ impl OccupancyMasks {
         get_horizontal_move_mask(sq)
     }
 
+    // squares a rook on the square attacks along its rank, including any
+    // blocking pieces
+    pub fn get_rank_attacks(&self, sq: &Square, occupied: Bitboard) -> Bitboard {
+        let shift = (sq.rank().as_index() as u8) << 3;
+        let inner_occ = (occupied.into_u64() >> (shift + 1)) & 0x3f;
+        let attacks = FIRST_RANK_ATTACKS[inner_occ as usize][sq.file().as_index()];
+        Bitboard::new((attacks as u64) << shift)
+    }
+
     pub fn get_vertical_mask(&self, sq: &Square) -> Bitboard {
         get_vertical_move_mask(sq)
     }
@@ -320,8 +374,49 @@ fn get_horizontal_move_mask(sq: &Square) -> Bitboard {
 #[cfg(test)]
 pub mod tests {
     use super::OccupancyMasks;
+    use crate::board::bitboard::Bitboard;
     use crate::board::square::Square;
 
+    #[test]
+    pub fn instance_shared_and_same_as_new() {
+        let masks = OccupancyMasks::instance();
+        assert!(std::ptr::eq(masks, OccupancyMasks::instance()));
+        assert!(*masks == *OccupancyMasks::new());
+    }
+
+    #[test]
+    pub fn rank_attacks_stop_at_first_blocker() {
+        let masks = OccupancyMasks::new();
+
+        // rook on d4, blockers on b4 and g4, with pieces on other ranks
+        let mut occupied = Bitboard::default();
+        for sq in [Square::D4, Square::B4, Square::G4, Square::D5, Square::A3] {
+            occupied.set_bit(&sq);
+        }
+
+        let mut expected = Bitboard::default();
+        for sq in [Square::B4, Square::C4, Square::E4, Square::F4, Square::G4] {
+            expected.set_bit(&sq);
+        }
+        assert!(masks.get_rank_attacks(&Square::D4, occupied) == expected);
+
+        // edge squares, empty rank
+        let mut expected = Bitboard::default();
+        for sq in [
+            Square::B8,
+            Square::C8,
+            Square::D8,
+            Square::E8,
+            Square::F8,
+            Square::G8,
+            Square::H8,
+        ] {
+            expected.set_bit(&sq);
+        }
+        let occupied = Bitboard::from_square(&Square::A8);
+        assert!(masks.get_rank_attacks(&Square::A8, occupied) == expected);
+    }
+
     #[test]
     pub fn white_double_first_move_mask() {
         let masks = OccupancyMasks::new();
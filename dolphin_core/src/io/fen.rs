@@ -7,6 +7,7 @@ use crate::board::square::Square;
 use crate::position::castle_permissions::CastlePermission;
 use crate::position::move_counter::MoveCounter;
 use std::backtrace::Backtrace;
+use std::fmt;
 use std::process;
 
 // FEN fields
@@ -22,39 +23,202 @@ const FEN_CASTLE_PERMISSIONS: usize = 2;
 const FEN_EN_PASSANT: usize = 3;
 const FEN_HALF_MOVE: usize = 4;
 const FEN_FULL_MOVE: usize = 5;
+const FEN_NUM_FIELDS: usize = 6;
+
+pub type FenParts = (Board, MoveCounter, CastlePermission, Colour, Option<Square>);
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum FenError {
+    // not the 6 fields, with the number there are
+    FieldCount(usize),
+    // not 8 ranks of 8 squares, or a character that isn't a piece
+    InvalidBoard(String),
+    // not "w" or "b"
+    InvalidSideToMove(String),
+    // a castle permission that isn't K, Q, or a file (in either case)
+    InvalidCastlePermission(char),
+    // a castle permission for a side with no king to castle with
+    NoKingToCastle(char),
+    // not "-" or a square
+    InvalidEnPassant(String),
+    InvalidHalfMoveClock(String),
+    InvalidFullMoveNumber(String),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::FieldCount(n) => write!(f, "Expected 6 fields, found {}", n),
+            FenError::InvalidBoard(board) => write!(f, "Invalid board '{}'", board),
+            FenError::InvalidSideToMove(side) => write!(f, "Invalid side to move '{}'", side),
+            FenError::InvalidCastlePermission(c) => {
+                write!(f, "Unsupported castle permission '{}'", c)
+            }
+            FenError::NoKingToCastle(c) => {
+                write!(f, "Castle permission '{}' without a king", c)
+            }
+            FenError::InvalidEnPassant(sq) => write!(f, "Invalid en passant square '{}'", sq),
+            FenError::InvalidHalfMoveClock(n) => write!(f, "Invalid half move clock '{}'", n),
+            FenError::InvalidFullMoveNumber(n) => write!(f, "Invalid full move number '{}'", n),
+        }
+    }
+}
 
 /// Parses a FEN string and returns populated structs
 ///
 /// Sample FEN:
 ///      rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2
 ///
-///
-pub fn decompose_fen(fen: &str) -> (Board, MoveCounter, CastlePermission, Colour, Option<Square>) {
+/// Exits on a FEN it can't parse. Use try_decompose_fen for FENs from
+/// elsewhere.
+pub fn decompose_fen(fen: &str) -> FenParts {
+    try_decompose_fen(fen).unwrap_or_else(|err| {
+        eprintln!("{} in FEN '{}'", err, fen);
+        eprintln!("Custom backtrace: {}", Backtrace::force_capture());
+        process::exit(1);
+    })
+}
+
+// As decompose_fen, returning an error for any field it can't parse
+pub fn try_decompose_fen(fen: &str) -> Result<FenParts, FenError> {
     // split FEN into fields
-    let piece_pos: Vec<&str> = fen.split(' ').collect();
+    let piece_pos: Vec<&str> = fen.split_whitespace().collect();
+    if piece_pos.len() != FEN_NUM_FIELDS {
+        return Err(FenError::FieldCount(piece_pos.len()));
+    }
 
-    let board = extract_board_from_fen(piece_pos[FEN_BOARD]);
+    let board = extract_board_from_fen(piece_pos[FEN_BOARD])?;
     let move_cntr = MoveCounter::new(
-        get_half_move_clock(piece_pos[FEN_HALF_MOVE]),
-        get_full_move_number(piece_pos[FEN_FULL_MOVE]),
+        get_half_move_clock(piece_pos[FEN_HALF_MOVE])?,
+        get_full_move_number(piece_pos[FEN_FULL_MOVE])?,
     );
-    let side_to_move = get_side_to_move(piece_pos[FEN_SIDE_TO_MOVE]);
+    let side_to_move = get_side_to_move(piece_pos[FEN_SIDE_TO_MOVE])?;
 
-    let castle_permissions = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS]);
-    let en_pass_sq = get_en_passant_sq(piece_pos[FEN_EN_PASSANT]);
+    let castle_permissions = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS], &board)?;
+    let en_pass_sq = get_en_passant_sq(piece_pos[FEN_EN_PASSANT])?;
 
-    (
+    Ok((
         board,
         move_cntr,
         castle_permissions,
         side_to_move,
         en_pass_sq,
+    ))
+}
+
+/// The reverse of decompose_fen: writes the parts out as a FEN string.
+/// Castle permissions are written as "KQkq", except for a rook that
+/// didn't start on the a or h file, which is written by its file, as in
+/// Shredder-FEN (eg "GBgb" for rooks on the b and g files).
+pub fn compose_fen(
+    board: &Board,
+    side_to_move: Colour,
+    castle_permissions: &CastlePermission,
+    en_pass_sq: Option<Square>,
+    half_move_clock: u16,
+    full_move_number: u16,
+) -> String {
+    let side_to_move = match side_to_move {
+        Colour::White => "w",
+        Colour::Black => "b",
+    };
+    let en_passant = en_pass_sq.map_or("-".to_string(), |sq| sq.to_string());
+
+    format!(
+        "{} {} {} {} {} {}",
+        board_to_fen(board),
+        side_to_move,
+        castle_permissions_to_fen(castle_permissions),
+        en_passant,
+        half_move_clock,
+        full_move_number
     )
 }
 
+fn board_to_fen(board: &Board) -> String {
+    let mut ranks = Vec::new();
+    for rank in Rank::iterator().rev() {
+        let mut rank_str = String::new();
+        let mut empty = 0;
+        for file in File::iterator() {
+            let sq = Square::from_rank_file(rank, file).expect("Invalid square");
+            match board.get_piece_and_colour_on_square(&sq) {
+                Some((piece, colour)) => {
+                    if empty > 0 {
+                        rank_str.push_str(&empty.to_string());
+                        empty = 0;
+                    }
+                    rank_str.push(Piece::label(&piece, &colour));
+                }
+                None => empty += 1,
+            }
+        }
+        if empty > 0 {
+            rank_str.push_str(&empty.to_string());
+        }
+        ranks.push(rank_str);
+    }
+    ranks.join("/")
+}
+
+fn castle_permissions_to_fen(cp: &CastlePermission) -> String {
+    // the standard letter, or the rook's file in the colour's case
+    let label = |set: bool, rook_sq: Square, standard_file: File, standard: char| {
+        if !set {
+            None
+        } else if rook_sq.file() == standard_file {
+            Some(standard)
+        } else if standard.is_ascii_uppercase() {
+            Some(rook_sq.file().to_char().to_ascii_uppercase())
+        } else {
+            Some(rook_sq.file().to_char())
+        }
+    };
+
+    let castling: String = [
+        label(
+            cp.is_white_king_set(),
+            cp.white_king_rook_square(),
+            File::H,
+            'K',
+        ),
+        label(
+            cp.is_white_queen_set(),
+            cp.white_queen_rook_square(),
+            File::A,
+            'Q',
+        ),
+        label(
+            cp.is_black_king_set(),
+            cp.black_king_rook_square(),
+            File::H,
+            'k',
+        ),
+        label(
+            cp.is_black_queen_set(),
+            cp.black_queen_rook_square(),
+            File::A,
+            'q',
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    if castling.is_empty() {
+        "-".to_string()
+    } else {
+        castling
+    }
+}
+
 /// takes the list of ranks (starting at rank 8)
-fn extract_board_from_fen(pieces: &str) -> Board {
+fn extract_board_from_fen(pieces: &str) -> Result<Board, FenError> {
+    let invalid = || FenError::InvalidBoard(pieces.to_string());
     let ranks: Vec<_> = pieces.split('/').collect();
+    if ranks.len() != Rank::iterator().len() {
+        return Err(invalid());
+    }
     let mut retval: Board = Board::new();
 
     for (rank, pieces) in ranks.iter().rev().enumerate() {
@@ -68,94 +232,174 @@ fn extract_board_from_fen(pieces: &str) -> Board {
                 }
                 None => {
                     // not a number, so it's a piece
-                    if let Some((piece, colour)) = Piece::from_char(c) {
-                        if let (Some(r), Some(f)) = (Rank::new(rank as u8), File::new(file as u8)) {
-                            let sq = Square::from_rank_file(&r, &f);
-                            file += 1;
-                            retval.add_piece(&piece, &colour, &sq.expect("Invalid square"));
-                        }
-                    }
+                    let (piece, colour) = Piece::from_char(c).ok_or_else(invalid)?;
+                    let r = Rank::new(rank as u8).ok_or_else(invalid)?;
+                    let f = File::new(file as u8).ok_or_else(invalid)?;
+                    let sq = Square::from_rank_file(&r, &f).ok_or_else(invalid)?;
+                    file += 1;
+                    retval.add_piece(&piece, &colour, &sq);
                 }
             }
         }
+        if file != File::iterator().len() as u32 {
+            return Err(invalid());
+        }
     }
-    retval
+    Ok(retval)
 }
 
-fn get_side_to_move(side: &str) -> Colour {
+fn get_side_to_move(side: &str) -> Result<Colour, FenError> {
     match side.trim() {
-        "w" => Colour::White,
-        "b" => Colour::Black,
-        _ => {
-            eprintln!("Unexpected side-to-move. Parsed character '{}'", side);
-            eprintln!("Custom backtrace: {}", Backtrace::force_capture());
-            process::exit(1);
-        }
+        "w" => Ok(Colour::White),
+        "b" => Ok(Colour::Black),
+        _ => Err(FenError::InvalidSideToMove(side.to_string())),
     }
 }
 
-fn get_en_passant_sq(en_pass: &str) -> Option<Square> {
+fn get_en_passant_sq(en_pass: &str) -> Result<Option<Square>, FenError> {
     if en_pass == "-" {
-        None
-    } else {
-        Some(Square::get_from_string(en_pass).unwrap())
+        return Ok(None);
+    }
+    match en_pass.len() {
+        2 => Square::get_from_string(en_pass)
+            .map(Some)
+            .ok_or(FenError::InvalidEnPassant(en_pass.to_string())),
+        _ => Err(FenError::InvalidEnPassant(en_pass.to_string())),
     }
 }
 
-fn get_half_move_clock(half_cnt: &str) -> u16 {
-    half_cnt.parse::<u16>().unwrap()
+fn get_half_move_clock(half_cnt: &str) -> Result<u16, FenError> {
+    half_cnt
+        .parse::<u16>()
+        .map_err(|_| FenError::InvalidHalfMoveClock(half_cnt.to_string()))
 }
 
-fn get_full_move_number(full_move_num: &str) -> u16 {
-    full_move_num.parse::<u16>().unwrap()
+fn get_full_move_number(full_move_num: &str) -> Result<u16, FenError> {
+    full_move_num
+        .parse::<u16>()
+        .map_err(|_| FenError::InvalidFullMoveNumber(full_move_num.to_string()))
 }
 
-fn get_castle_permissions(castleperm: &str) -> CastlePermission {
+// Accepts the standard "KQkq" field, and the Shredder-FEN/X-FEN forms that
+// name the castling rook by its file (eg "HAha", or "GBgb" for Chess960).
+// A file is king side if it's to the right of the king. As in X-FEN, K and
+// Q are the outermost rook on that side, or the h and a files if there's no
+// rook there.
+fn get_castle_permissions(castleperm: &str, board: &Board) -> Result<CastlePermission, FenError> {
     let mut cp = CastlePermission::NO_CASTLE_PERMS_AVAIL;
-    if castleperm.trim() != "-" {
-        if castleperm.contains('K') {
-            cp.set_white_king();
-        }
-        if castleperm.contains('Q') {
-            cp.set_white_queen();
-        }
-        if castleperm.contains('k') {
-            cp.set_black_king();
-        }
-        if castleperm.contains('q') {
-            cp.set_black_queen();
+    if castleperm.trim() == "-" {
+        return Ok(cp);
+    }
+
+    // king side and queen side, for white and black
+    let mut rook_files = [(File::H, File::A); 2];
+    for c in castleperm.trim().chars() {
+        let colour = if c.is_ascii_uppercase() {
+            Colour::White
+        } else {
+            Colour::Black
+        };
+        let back_rank = match colour {
+            Colour::White => Rank::R1,
+            Colour::Black => Rank::R8,
+        };
+        let king_file = board
+            .squares_of(&Piece::King, &colour)
+            .next()
+            .ok_or(FenError::NoKingToCastle(c))?
+            .file();
+        let is_own_rook = |file: &File| {
+            Square::from_rank_file(&back_rank, file)
+                .and_then(|sq| board.get_piece_and_colour_on_square(&sq))
+                == Some((Piece::Rook, colour))
+        };
+
+        let (file, is_king_side) = match c.to_ascii_lowercase() {
+            'k' => (
+                File::iterator()
+                    .rev()
+                    .take_while(|file| **file != king_file)
+                    .find(|file| is_own_rook(file))
+                    .map_or(File::H, |file| *file),
+                true,
+            ),
+            'q' => (
+                File::iterator()
+                    .take_while(|file| **file != king_file)
+                    .find(|file| is_own_rook(file))
+                    .map_or(File::A, |file| *file),
+                false,
+            ),
+            file_char => {
+                let file =
+                    File::from_char(file_char).ok_or(FenError::InvalidCastlePermission(c))?;
+                (file, file.as_index() > king_file.as_index())
+            }
+        };
+
+        let files = &mut rook_files[colour.as_index()];
+        match (colour, is_king_side) {
+            (Colour::White, true) => {
+                files.0 = file;
+                cp.set_white_king();
+            }
+            (Colour::White, false) => {
+                files.1 = file;
+                cp.set_white_queen();
+            }
+            (Colour::Black, true) => {
+                files.0 = file;
+                cp.set_black_king();
+            }
+            (Colour::Black, false) => {
+                files.1 = file;
+                cp.set_black_queen();
+            }
         }
     }
-    cp
+
+    for colour in [Colour::White, Colour::Black] {
+        let (king_side, queen_side) = rook_files[colour.as_index()];
+        cp.set_rook_files(&colour, &king_side, &queen_side);
+    }
+    Ok(cp)
 }
 
 #[cfg(test)]
 mod tests {
+    use super::compose_fen;
+    use super::decompose_fen;
+    use super::extract_board_from_fen;
     use super::get_castle_permissions;
     use super::get_en_passant_sq;
     use super::get_full_move_number;
     use super::get_half_move_clock;
     use super::get_side_to_move;
+    use super::try_decompose_fen;
+    use super::FenError;
+    use super::FEN_BOARD;
     use super::FEN_CASTLE_PERMISSIONS;
     use super::FEN_EN_PASSANT;
     use super::FEN_FULL_MOVE;
     use super::FEN_HALF_MOVE;
     use super::FEN_SIDE_TO_MOVE;
     use crate::board::colour::Colour;
+    use crate::board::file::File;
     use crate::board::square::*;
+    use crate::position::castle_permissions::CastlePermission;
 
     #[test]
     pub fn side_to_move_white() {
         let fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n w - - 0 1";
         let piece_pos: Vec<&str> = fen.split(' ').collect();
-        let side_to_move = get_side_to_move(piece_pos[FEN_SIDE_TO_MOVE]);
+        let side_to_move = get_side_to_move(piece_pos[FEN_SIDE_TO_MOVE]).unwrap();
         assert_eq!(side_to_move, Colour::White);
     }
     #[test]
     pub fn side_to_move_black() {
         let fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b - - 0 1";
         let piece_pos: Vec<&str> = fen.split(' ').collect();
-        let side_to_move = get_side_to_move(piece_pos[FEN_SIDE_TO_MOVE]);
+        let side_to_move = get_side_to_move(piece_pos[FEN_SIDE_TO_MOVE]).unwrap();
         assert_eq!(side_to_move, Colour::Black);
     }
 
@@ -163,7 +407,8 @@ mod tests {
     pub fn castle_permissions_white_kingside() {
         let fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b K - 0 1";
         let piece_pos: Vec<&str> = fen.split(' ').collect();
-        let perm = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS]);
+        let board = extract_board_from_fen(piece_pos[FEN_BOARD]).unwrap();
+        let perm = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS], &board).unwrap();
         assert!(perm.is_white_king_set());
         assert!(!perm.is_black_king_set());
         assert!(!perm.is_white_queen_set());
@@ -174,7 +419,8 @@ mod tests {
     pub fn castle_permissions_white_queenside() {
         let fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b Q - 0 1";
         let piece_pos: Vec<&str> = fen.split(' ').collect();
-        let perm = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS]);
+        let board = extract_board_from_fen(piece_pos[FEN_BOARD]).unwrap();
+        let perm = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS], &board).unwrap();
 
         assert!(!perm.is_white_king_set());
         assert!(!perm.is_black_king_set());
@@ -186,7 +432,8 @@ mod tests {
     pub fn castle_permissions_black_kingside() {
         let fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b k - 0 1";
         let piece_pos: Vec<&str> = fen.split(' ').collect();
-        let perm = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS]);
+        let board = extract_board_from_fen(piece_pos[FEN_BOARD]).unwrap();
+        let perm = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS], &board).unwrap();
 
         assert!(!perm.is_white_king_set());
         assert!(perm.is_black_king_set());
@@ -198,7 +445,8 @@ mod tests {
     pub fn castle_permissions_black_queenside() {
         let fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b q - 0 1";
         let piece_pos: Vec<&str> = fen.split(' ').collect();
-        let perm = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS]);
+        let board = extract_board_from_fen(piece_pos[FEN_BOARD]).unwrap();
+        let perm = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS], &board).unwrap();
 
         assert!(!perm.is_white_king_set());
         assert!(!perm.is_black_king_set());
@@ -211,7 +459,8 @@ mod tests {
     pub fn castle_permissions_none() {
         let fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b - - 0 1";
         let piece_pos: Vec<&str> = fen.split(' ').collect();
-        let perm = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS]);
+        let board = extract_board_from_fen(piece_pos[FEN_BOARD]).unwrap();
+        let perm = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS], &board).unwrap();
         assert!(!perm.has_castle_permission());
     }
 
@@ -219,7 +468,8 @@ mod tests {
     pub fn castle_permissions_white_kingside_queenside_black_kingside() {
         let fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b KQk - 0 1";
         let piece_pos: Vec<&str> = fen.split(' ').collect();
-        let perm = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS]);
+        let board = extract_board_from_fen(piece_pos[FEN_BOARD]).unwrap();
+        let perm = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS], &board).unwrap();
 
         assert!(perm.is_white_king_set());
         assert!(perm.is_black_king_set());
@@ -228,21 +478,47 @@ mod tests {
         assert!(perm.has_castle_permission());
     }
 
+    #[test]
+    pub fn castle_permissions_shredder_fen_rook_files() {
+        let fens = [
+            "r3k2r/8/8/8/8/8/8/R3K2R w HAha - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+        ];
+        for fen in fens {
+            let piece_pos: Vec<&str> = fen.split(' ').collect();
+            let board = extract_board_from_fen(piece_pos[FEN_BOARD]).unwrap();
+            let perm = get_castle_permissions(piece_pos[FEN_CASTLE_PERMISSIONS], &board).unwrap();
+
+            assert!(perm.is_white_king_set());
+            assert!(perm.is_black_king_set());
+            assert!(perm.is_white_queen_set());
+            assert!(perm.is_black_queen_set());
+        }
+
+        // X-FEN can mix both forms
+        let board = extract_board_from_fen("r3k2r/8/8/8/8/8/8/R3K2R").unwrap();
+        let perm = get_castle_permissions("Ka", &board).unwrap();
+        assert!(perm.is_white_king_set());
+        assert!(!perm.is_black_king_set());
+        assert!(!perm.is_white_queen_set());
+        assert!(perm.is_black_queen_set());
+    }
+
     #[test]
     pub fn parse_half_move_clock() {
         let mut fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b q - 0 1";
         let mut piece_pos: Vec<&str> = fen.split(' ').collect();
-        let mut half_clock = get_half_move_clock(piece_pos[FEN_HALF_MOVE]);
+        let mut half_clock = get_half_move_clock(piece_pos[FEN_HALF_MOVE]).unwrap();
         assert_eq!(half_clock, 0);
 
         fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b q - 22 1";
         piece_pos = fen.split(' ').collect();
-        half_clock = get_half_move_clock(piece_pos[FEN_HALF_MOVE]);
+        half_clock = get_half_move_clock(piece_pos[FEN_HALF_MOVE]).unwrap();
         assert_eq!(half_clock, 22);
 
         fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b q - 5 1";
         piece_pos = fen.split(' ').collect();
-        half_clock = get_half_move_clock(piece_pos[FEN_HALF_MOVE]);
+        half_clock = get_half_move_clock(piece_pos[FEN_HALF_MOVE]).unwrap();
         assert_eq!(half_clock, 5);
     }
 
@@ -250,17 +526,17 @@ mod tests {
     pub fn parse_full_move_count() {
         let mut fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b q - 0 0";
         let mut piece_pos: Vec<&str> = fen.split(' ').collect();
-        let mut full_move_cnt = get_full_move_number(piece_pos[FEN_FULL_MOVE]);
+        let mut full_move_cnt = get_full_move_number(piece_pos[FEN_FULL_MOVE]).unwrap();
         assert_eq!(full_move_cnt, 0);
 
         fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b q - 0 1";
         piece_pos = fen.split(' ').collect();
-        full_move_cnt = get_full_move_number(piece_pos[FEN_FULL_MOVE]);
+        full_move_cnt = get_full_move_number(piece_pos[FEN_FULL_MOVE]).unwrap();
         assert_eq!(full_move_cnt, 1);
 
         fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b q - 0 55";
         piece_pos = fen.split(' ').collect();
-        full_move_cnt = get_full_move_number(piece_pos[FEN_FULL_MOVE]);
+        full_move_cnt = get_full_move_number(piece_pos[FEN_FULL_MOVE]).unwrap();
         assert_eq!(full_move_cnt, 55);
     }
 
@@ -268,17 +544,144 @@ mod tests {
     pub fn parse_en_passant() {
         let mut fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b q c6 0 0";
         let mut piece_pos: Vec<&str> = fen.split(' ').collect();
-        let mut enp_sq = get_en_passant_sq(piece_pos[FEN_EN_PASSANT]).unwrap();
+        let mut enp_sq = get_en_passant_sq(piece_pos[FEN_EN_PASSANT])
+            .unwrap()
+            .unwrap();
         assert_eq!(enp_sq, Square::C6);
 
         fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b q c3 0 0";
         piece_pos = fen.split(' ').collect();
-        enp_sq = get_en_passant_sq(piece_pos[FEN_EN_PASSANT]).unwrap();
+        enp_sq = get_en_passant_sq(piece_pos[FEN_EN_PASSANT])
+            .unwrap()
+            .unwrap();
         assert_eq!(enp_sq, Square::C3);
 
         fen = "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n b q - 0 0";
         piece_pos = fen.split(' ').collect();
-        let no_enp_sq = get_en_passant_sq(piece_pos[FEN_EN_PASSANT]);
+        let no_enp_sq = get_en_passant_sq(piece_pos[FEN_EN_PASSANT]).unwrap();
         assert!(no_enp_sq.is_none());
     }
+
+    #[test]
+    pub fn compose_fen_reverses_decompose_fen() {
+        let fens = [
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2",
+            "1n1k2bp/1PppQpb1/N1p4p/1B2P1K1/1RB2P2/pPR1Np2/P1r1rP1P/P2q3n w - - 0 1",
+            "r3k2r/8/8/8/3pP3/8/8/R3K2R b Kq e3 0 31",
+        ];
+        for fen in fens {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                decompose_fen(fen);
+            let composed = compose_fen(
+                &board,
+                side_to_move,
+                &castle_permissions,
+                en_pass_sq,
+                move_cntr.half_move(),
+                move_cntr.full_move(),
+            );
+            assert_eq!(composed, fen);
+        }
+    }
+
+    #[test]
+    pub fn compose_fen_shredder_castling_for_chess960_rooks() {
+        let (board, _, _, side_to_move, _) = decompose_fen("1r4kr/8/8/8/8/8/8/1R4KR w - - 0 1");
+        let mut cp = CastlePermission::NO_CASTLE_PERMS_AVAIL;
+        cp.set_rook_files(&Colour::White, &File::H, &File::B);
+        cp.set_rook_files(&Colour::Black, &File::H, &File::B);
+        cp.set_white_king();
+        cp.set_white_queen();
+        cp.set_black_queen();
+
+        let fen = compose_fen(&board, side_to_move, &cp, None, 0, 1);
+        assert_eq!(fen, "1r4kr/8/8/8/8/8/8/1R4KR w KBb - 0 1");
+    }
+
+    #[test]
+    pub fn castle_permissions_rook_files_round_trip() {
+        let fens = [
+            "1r4kr/8/8/8/8/8/8/1R4KR w KBb - 0 1",
+            "rk2r3/8/8/8/8/8/8/1R2K1R1 w GBeq - 0 1",
+            "1r2k2r/8/8/8/8/8/8/1R2K2R w KBkb - 0 1",
+        ];
+        for fen in fens {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                decompose_fen(fen);
+            let composed = compose_fen(
+                &board,
+                side_to_move,
+                &castle_permissions,
+                en_pass_sq,
+                move_cntr.half_move(),
+                move_cntr.full_move(),
+            );
+            assert_eq!(composed, fen);
+        }
+
+        // K and Q are the outermost rooks
+        let (_, _, cp, _, _) = decompose_fen("rk2r3/8/8/8/8/8/8/1R2K1R1 w KQkq - 0 1");
+        assert_eq!(cp.white_king_rook_square(), Square::G1);
+        assert_eq!(cp.white_queen_rook_square(), Square::B1);
+        assert_eq!(cp.black_king_rook_square(), Square::E8);
+        assert_eq!(cp.black_queen_rook_square(), Square::A8);
+    }
+
+    #[test]
+    pub fn castle_permissions_errors() {
+        assert_eq!(
+            try_decompose_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkx - 0 1").unwrap_err(),
+            FenError::InvalidCastlePermission('x')
+        );
+        assert_eq!(
+            try_decompose_fen("r6r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap_err(),
+            FenError::NoKingToCastle('k')
+        );
+    }
+
+    #[test]
+    pub fn malformed_fields_errors() {
+        assert_eq!(
+            try_decompose_fen("4k3/8/8/8/8/8/8/4K3 w").unwrap_err(),
+            FenError::FieldCount(2)
+        );
+        assert_eq!(try_decompose_fen("").unwrap_err(), FenError::FieldCount(0));
+        assert_eq!(
+            try_decompose_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1 extra").unwrap_err(),
+            FenError::FieldCount(7)
+        );
+        assert_eq!(
+            try_decompose_fen("4k3/8/8/8/8/8/4K3 w - - 0 1").unwrap_err(),
+            FenError::InvalidBoard("4k3/8/8/8/8/8/4K3".to_string())
+        );
+        assert_eq!(
+            try_decompose_fen("4k3/8/8/8/8/8/8/4K4 w - - 0 1").unwrap_err(),
+            FenError::InvalidBoard("4k3/8/8/8/8/8/8/4K4".to_string())
+        );
+        assert_eq!(
+            try_decompose_fen("4k3/8/8/8/8/8/8/4X3 w - - 0 1").unwrap_err(),
+            FenError::InvalidBoard("4k3/8/8/8/8/8/8/4X3".to_string())
+        );
+        assert_eq!(
+            try_decompose_fen("4k3/8/8/8/8/8/8/4K3 x - - 0 1").unwrap_err(),
+            FenError::InvalidSideToMove("x".to_string())
+        );
+        assert_eq!(
+            try_decompose_fen("4k3/8/8/8/8/8/8/4K3 w - e 0 1").unwrap_err(),
+            FenError::InvalidEnPassant("e".to_string())
+        );
+        assert_eq!(
+            try_decompose_fen("4k3/8/8/8/8/8/8/4K3 w - z9 0 1").unwrap_err(),
+            FenError::InvalidEnPassant("z9".to_string())
+        );
+        assert_eq!(
+            try_decompose_fen("4k3/8/8/8/8/8/8/4K3 w - - x 1").unwrap_err(),
+            FenError::InvalidHalfMoveClock("x".to_string())
+        );
+        assert_eq!(
+            try_decompose_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 -1").unwrap_err(),
+            FenError::InvalidFullMoveNumber("-1".to_string())
+        );
+        assert!(try_decompose_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").is_ok());
+    }
 }
@@ -0,0 +1,299 @@
+use crate::board::colour::Colour;
+use crate::board::file::File;
+use crate::board::game_board::Board;
+use crate::board::occupancy_masks::OccupancyMasks;
+use crate::board::piece::Piece;
+use crate::board::square::Square;
+use crate::moves::mov::Move;
+use crate::moves::move_gen::MoveGenerator;
+use crate::moves::move_list::MoveList;
+use crate::position::attack_checker::AttackChecker;
+use crate::position::castle_permissions::CastlePermission;
+use crate::position::game_position::Position;
+use crate::position::move_counter::MoveCounter;
+use shakmaty::uci::UciMove;
+use shakmaty::CastlingMode;
+use shakmaty::CastlingSide;
+use shakmaty::Chess;
+use shakmaty::EnPassantMode;
+use shakmaty::FromSetup;
+use shakmaty::Position as _;
+use shakmaty::PositionError;
+use shakmaty::Setup;
+use std::num::NonZeroU32;
+
+// Conversions to and from the shakmaty crate's types (feature "interop"),
+// so its tooling (SAN, Syzygy probing, variants, etc) can be used along
+// with the search. Squares, colours, piece types (shakmaty's Role) and
+// boards convert both ways with From. A position converts to a shakmaty
+// Chess with TryFrom, as shakmaty rejects illegal positions, and back with
+// position_from_chess, as a Position needs the attack checker. Moves go
+// through UCI, as a dolphin Move needs the position to be decoded.
+
+impl From<Square> for shakmaty::Square {
+    fn from(sq: Square) -> shakmaty::Square {
+        // both number the squares from a1 = 0 to h8 = 63
+        shakmaty::Square::new(sq.as_index() as u32)
+    }
+}
+
+impl From<shakmaty::Square> for Square {
+    fn from(sq: shakmaty::Square) -> Square {
+        Square::new(sq.to_u32() as u8).expect("Invalid square")
+    }
+}
+
+impl From<Colour> for shakmaty::Color {
+    fn from(colour: Colour) -> shakmaty::Color {
+        match colour {
+            Colour::White => shakmaty::Color::White,
+            Colour::Black => shakmaty::Color::Black,
+        }
+    }
+}
+
+impl From<shakmaty::Color> for Colour {
+    fn from(colour: shakmaty::Color) -> Colour {
+        match colour {
+            shakmaty::Color::White => Colour::White,
+            shakmaty::Color::Black => Colour::Black,
+        }
+    }
+}
+
+impl From<Piece> for shakmaty::Role {
+    fn from(piece: Piece) -> shakmaty::Role {
+        match piece {
+            Piece::Pawn => shakmaty::Role::Pawn,
+            Piece::Bishop => shakmaty::Role::Bishop,
+            Piece::Knight => shakmaty::Role::Knight,
+            Piece::Rook => shakmaty::Role::Rook,
+            Piece::Queen => shakmaty::Role::Queen,
+            Piece::King => shakmaty::Role::King,
+        }
+    }
+}
+
+impl From<shakmaty::Role> for Piece {
+    fn from(role: shakmaty::Role) -> Piece {
+        match role {
+            shakmaty::Role::Pawn => Piece::Pawn,
+            shakmaty::Role::Bishop => Piece::Bishop,
+            shakmaty::Role::Knight => Piece::Knight,
+            shakmaty::Role::Rook => Piece::Rook,
+            shakmaty::Role::Queen => Piece::Queen,
+            shakmaty::Role::King => Piece::King,
+        }
+    }
+}
+
+impl From<&Board> for shakmaty::Board {
+    fn from(board: &Board) -> shakmaty::Board {
+        let mut sm_board = shakmaty::Board::empty();
+        for sq in board.occupied().iterator() {
+            if let Some((piece, colour)) = board.get_piece_and_colour_on_square(&sq) {
+                let sm_piece = shakmaty::Piece {
+                    color: colour.into(),
+                    role: piece.into(),
+                };
+                sm_board.set_piece_at(sq.into(), sm_piece);
+            }
+        }
+        sm_board
+    }
+}
+
+impl From<&shakmaty::Board> for Board {
+    fn from(sm_board: &shakmaty::Board) -> Board {
+        let mut board = Board::new();
+        for (sq, sm_piece) in sm_board {
+            let piece: Piece = sm_piece.role.into();
+            let colour: Colour = sm_piece.color.into();
+            board.add_piece(&piece, &colour, &sq.into());
+        }
+        board
+    }
+}
+
+// The castling rook squares are passed on, so a Chess960 position converts
+// too.
+impl TryFrom<&Position<'_>> for Chess {
+    type Error = PositionError<Chess>;
+
+    fn try_from(pos: &Position) -> Result<Chess, Self::Error> {
+        let perms = pos.castle_permissions();
+        let mut castling_rights = shakmaty::Bitboard::EMPTY;
+        for (set, rook_sq) in [
+            (perms.is_white_king_set(), perms.white_king_rook_square()),
+            (perms.is_white_queen_set(), perms.white_queen_rook_square()),
+            (perms.is_black_king_set(), perms.black_king_rook_square()),
+            (perms.is_black_queen_set(), perms.black_queen_rook_square()),
+        ] {
+            if set {
+                castling_rights.add(shakmaty::Square::from(rook_sq));
+            }
+        }
+
+        let mut setup = Setup::empty();
+        setup.board = pos.board().into();
+        setup.turn = pos.side_to_move().into();
+        setup.castling_rights = castling_rights;
+        setup.ep_square = pos.en_passant_square().map(Into::into);
+        setup.halfmoves = pos.fifty_move_counter() as u32;
+        setup.fullmoves =
+            NonZeroU32::new(pos.move_counter().full_move() as u32).unwrap_or(NonZeroU32::MIN);
+
+        let mode = CastlingMode::detect(&setup);
+        Chess::from_setup(setup, mode)
+    }
+}
+
+pub fn position_from_chess<'a>(chess: &Chess, attack_checker: &'a AttackChecker) -> Position<'a> {
+    let setup = chess.to_setup(EnPassantMode::Legal);
+
+    let castles = chess.castles();
+    let mut castle_permissions = CastlePermission::NO_CASTLE_PERMS_AVAIL;
+    for colour in [Colour::White, Colour::Black] {
+        let king_side = castles.rook(colour.into(), CastlingSide::KingSide);
+        let queen_side = castles.rook(colour.into(), CastlingSide::QueenSide);
+        let file = |rook_sq: Option<shakmaty::Square>, default: File| {
+            rook_sq.map_or(default, |sq| Square::from(sq).file())
+        };
+        castle_permissions.set_rook_files(
+            &colour,
+            &file(king_side, File::H),
+            &file(queen_side, File::A),
+        );
+
+        if king_side.is_some() {
+            match colour {
+                Colour::White => castle_permissions.set_white_king(),
+                Colour::Black => castle_permissions.set_black_king(),
+            }
+        }
+        if queen_side.is_some() {
+            match colour {
+                Colour::White => castle_permissions.set_white_queen(),
+                Colour::Black => castle_permissions.set_black_queen(),
+            }
+        }
+    }
+
+    Position::new(
+        (&setup.board).into(),
+        castle_permissions,
+        MoveCounter::new(setup.halfmoves as u16, setup.fullmoves.get() as u16),
+        setup.ep_square.map(Into::into),
+        setup.turn.into(),
+        OccupancyMasks::instance(),
+        attack_checker,
+    )
+}
+
+impl From<Move> for UciMove {
+    fn from(mv: Move) -> UciMove {
+        mv.to_uci_string().parse().expect("Move isn't valid UCI")
+    }
+}
+
+// the legal move in the position, with castling as either the king's move
+// or the king taking its rook (shakmaty's CastlingMode::Chess960)
+pub fn move_from_uci(pos: &mut Position, uci: &UciMove) -> Option<Move> {
+    let uci = uci.to_string();
+
+    let mut move_list = MoveList::new();
+    MoveGenerator::new().generate_legal_moves(pos, &mut move_list);
+
+    move_list
+        .iterator()
+        .find(|mv| {
+            mv.to_uci_string() == uci || mv.to_uci_string_chess960(&pos.castle_permissions()) == uci
+        })
+        .copied()
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::board::colour::Colour;
+    use crate::board::occupancy_masks::OccupancyMasks;
+    use crate::board::piece::Piece;
+    use crate::board::square::Square;
+    use crate::io::fen;
+    use crate::io::interop::move_from_uci;
+    use crate::io::interop::position_from_chess;
+    use crate::position::attack_checker::AttackChecker;
+    use crate::position::game_position::Position;
+    use shakmaty::fen::Fen;
+    use shakmaty::uci::UciMove;
+    use shakmaty::CastlingMode;
+    use shakmaty::Chess;
+    use shakmaty::EnPassantMode;
+    use shakmaty::Position as _;
+
+    fn new_position<'a>(fen: &str, attack_checker: &'a AttackChecker) -> Position<'a> {
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+        Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            attack_checker,
+        )
+    }
+
+    #[test]
+    pub fn squares_and_pieces_convert_both_ways() {
+        assert_eq!(shakmaty::Square::from(Square::A1), shakmaty::Square::A1);
+        assert_eq!(shakmaty::Square::from(Square::G7), shakmaty::Square::G7);
+        assert_eq!(Square::from(shakmaty::Square::H8), Square::H8);
+
+        assert_eq!(shakmaty::Role::from(Piece::Knight), shakmaty::Role::Knight);
+        assert!(Piece::from(shakmaty::Role::Bishop) == Piece::Bishop);
+        assert_eq!(shakmaty::Color::from(Colour::Black), shakmaty::Color::Black);
+        assert!(Colour::from(shakmaty::Color::White) == Colour::White);
+    }
+
+    #[test]
+    pub fn positions_convert_both_ways() {
+        let attack_checker = AttackChecker::new();
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40",
+        ];
+
+        for fen in fens {
+            let pos = new_position(fen, &attack_checker);
+            let chess = Chess::try_from(&pos).expect("Position not legal in shakmaty");
+            assert_eq!(
+                Fen::from_position(&chess, EnPassantMode::Legal).to_string(),
+                fen
+            );
+            assert!(position_from_chess(&chess, &attack_checker) == pos);
+        }
+    }
+
+    #[test]
+    pub fn moves_convert_through_uci() {
+        let attack_checker = AttackChecker::new();
+        let mut pos = new_position(
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            &attack_checker,
+        );
+        let chess = Chess::try_from(&pos).unwrap();
+
+        // every shakmaty move is found, with castling either way
+        for mode in [CastlingMode::Standard, CastlingMode::Chess960] {
+            for sm_move in chess.legal_moves() {
+                let uci = UciMove::from_move(sm_move, mode);
+                let mv = move_from_uci(&mut pos, &uci).expect("Legal move not found");
+                let uci: UciMove = mv.into();
+                assert_eq!(uci, UciMove::from_move(sm_move, CastlingMode::Standard));
+            }
+        }
+        assert_eq!(move_from_uci(&mut pos, &"e1e3".parse().unwrap()), None);
+    }
+}
@@ -1 +1,5 @@
 pub mod fen;
+#[cfg(feature = "interop")]
+pub mod interop;
+pub mod packed;
+pub mod pgn;
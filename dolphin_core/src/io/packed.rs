@@ -0,0 +1,302 @@
+use crate::board::bitboard::Bitboard;
+use crate::board::colour::Colour;
+use crate::board::file::File;
+use crate::board::game_board::Board;
+use crate::board::piece::Piece;
+use crate::board::rank::Rank;
+use crate::board::square::Square;
+use crate::position::castle_permissions::CastlePermission;
+use crate::position::game_position::Position;
+use crate::position::move_counter::MoveCounter;
+
+// A position packed into 27 bytes, for storing positions in bulk (eg,
+// training data) where FEN strings take too much space:
+//
+//      [0..8]      occupied squares, as a little-endian bitboard
+//      [8..24]     a 4-bit code for each occupied square, a1 first, two to
+//                  a byte (low nibble first)
+//      [24]        half-move clock
+//      [25..27]    full move number, little-endian
+//
+// Codes 0-11 are the pieces (colour * 6 + piece index). The rest of the
+// state is folded into the other codes, so it takes no extra space:
+//
+//      12  a pawn that has just made a double step, so can be taken en
+//          passant (its rank gives its colour)
+//      13  a white rook that can still castle
+//      14  a black rook that can still castle
+//      15  the black king, with black to move
+//
+// As with FEN, only castling rooks on the a and h files are supported.
+#[derive(Debug, Eq, PartialEq, Hash, Clone, Copy)]
+pub struct PackedPosition([u8; PackedPosition::SIZE]);
+
+const CODE_EN_PASSANT_PAWN: u8 = 12;
+const CODE_WHITE_CASTLING_ROOK: u8 = 13;
+const CODE_BLACK_CASTLING_ROOK: u8 = 14;
+const CODE_BLACK_KING_TO_MOVE: u8 = 15;
+
+// in Piece::as_index order
+const PIECES: [Piece; Piece::NUM_PIECE_TYPES] = [
+    Piece::Pawn,
+    Piece::Bishop,
+    Piece::Knight,
+    Piece::Rook,
+    Piece::Queen,
+    Piece::King,
+];
+
+const MAX_PIECES: usize = 32;
+const OFFSET_CODES: usize = 8;
+const OFFSET_HALF_MOVE: usize = OFFSET_CODES + MAX_PIECES / 2;
+const OFFSET_FULL_MOVE: usize = OFFSET_HALF_MOVE + 1;
+
+impl PackedPosition {
+    pub const SIZE: usize = OFFSET_FULL_MOVE + 2;
+
+    // Takes the same parts as fen::decompose_fen returns. The half-move
+    // clock is capped at 255.
+    pub fn pack(
+        board: &Board,
+        move_counter: &MoveCounter,
+        castle_permissions: &CastlePermission,
+        side_to_move: &Colour,
+        en_pass_sq: &Option<Square>,
+    ) -> PackedPosition {
+        let mut bytes = [0u8; PackedPosition::SIZE];
+
+        let occupied = board.occupied();
+        bytes[..OFFSET_CODES].copy_from_slice(&occupied.into_u64().to_le_bytes());
+
+        // the pawn that made the double step is just past the en passant square
+        let en_passant_pawn_sq = en_pass_sq.and_then(|sq| match side_to_move {
+            Colour::White => sq.south(),
+            Colour::Black => sq.north(),
+        });
+
+        for (i, sq) in occupied.iterator().enumerate() {
+            let (piece, colour) = board
+                .get_piece_and_colour_on_square(&sq)
+                .expect("Occupied square is empty");
+
+            let code = match (piece, colour) {
+                (Piece::Pawn, _) if Some(sq) == en_passant_pawn_sq => CODE_EN_PASSANT_PAWN,
+                (Piece::Rook, Colour::White) if is_castling_rook(castle_permissions, &sq) => {
+                    CODE_WHITE_CASTLING_ROOK
+                }
+                (Piece::Rook, Colour::Black) if is_castling_rook(castle_permissions, &sq) => {
+                    CODE_BLACK_CASTLING_ROOK
+                }
+                (Piece::King, Colour::Black) if *side_to_move == Colour::Black => {
+                    CODE_BLACK_KING_TO_MOVE
+                }
+                _ => (colour.as_index() * Piece::NUM_PIECE_TYPES + piece.as_index()) as u8,
+            };
+            bytes[OFFSET_CODES + i / 2] |= code << (4 * (i % 2));
+        }
+
+        let half_move = u8::try_from(move_counter.half_move()).unwrap_or(u8::MAX);
+        bytes[OFFSET_HALF_MOVE] = half_move;
+        bytes[OFFSET_FULL_MOVE..].copy_from_slice(&move_counter.full_move().to_le_bytes());
+
+        PackedPosition(bytes)
+    }
+
+    // the half-move clock is the fifty move counter, as it would be in a FEN
+    pub fn from_position(pos: &Position) -> PackedPosition {
+        let move_counter = MoveCounter::new(
+            pos.fifty_move_counter() as u16,
+            pos.move_counter().full_move(),
+        );
+        PackedPosition::pack(
+            pos.board(),
+            &move_counter,
+            &pos.castle_permissions(),
+            &pos.side_to_move(),
+            &pos.en_passant_square(),
+        )
+    }
+
+    // None if there are more occupied squares than codes
+    pub fn from_bytes(bytes: [u8; PackedPosition::SIZE]) -> Option<PackedPosition> {
+        let occupied = u64::from_le_bytes(bytes[..OFFSET_CODES].try_into().unwrap());
+        if occupied.count_ones() as usize > MAX_PIECES {
+            return None;
+        }
+        Some(PackedPosition(bytes))
+    }
+
+    pub const fn as_bytes(&self) -> &[u8; PackedPosition::SIZE] {
+        &self.0
+    }
+
+    // The parts of the position, in the same form as fen::decompose_fen
+    pub fn unpack(&self) -> (Board, MoveCounter, CastlePermission, Colour, Option<Square>) {
+        let bytes = &self.0;
+        let occupied = Bitboard::new(u64::from_le_bytes(
+            bytes[..OFFSET_CODES].try_into().unwrap(),
+        ));
+
+        let mut board = Board::new();
+        let mut side_to_move = Colour::White;
+        let mut castle_permissions = CastlePermission::NO_CASTLE_PERMS_AVAIL;
+        let mut en_passant_pawn_sq = None;
+
+        for (i, sq) in occupied.iterator().enumerate() {
+            let code = (bytes[OFFSET_CODES + i / 2] >> (4 * (i % 2))) & 0xf;
+
+            let (piece, colour) = match code {
+                CODE_EN_PASSANT_PAWN => {
+                    en_passant_pawn_sq = Some(sq);
+                    let colour = if sq.rank() == Rank::R4 {
+                        Colour::White
+                    } else {
+                        Colour::Black
+                    };
+                    (Piece::Pawn, colour)
+                }
+                CODE_WHITE_CASTLING_ROOK => {
+                    if sq.file() == File::H {
+                        castle_permissions.set_white_king();
+                    } else {
+                        castle_permissions.set_white_queen();
+                    }
+                    (Piece::Rook, Colour::White)
+                }
+                CODE_BLACK_CASTLING_ROOK => {
+                    if sq.file() == File::H {
+                        castle_permissions.set_black_king();
+                    } else {
+                        castle_permissions.set_black_queen();
+                    }
+                    (Piece::Rook, Colour::Black)
+                }
+                CODE_BLACK_KING_TO_MOVE => {
+                    side_to_move = Colour::Black;
+                    (Piece::King, Colour::Black)
+                }
+                _ => {
+                    let colour = if (code as usize) < Piece::NUM_PIECE_TYPES {
+                        Colour::White
+                    } else {
+                        Colour::Black
+                    };
+                    (PIECES[code as usize % Piece::NUM_PIECE_TYPES], colour)
+                }
+            };
+            board.add_piece(&piece, &colour, &sq);
+        }
+
+        let en_pass_sq = en_passant_pawn_sq.and_then(|sq| match side_to_move {
+            Colour::White => sq.north(),
+            Colour::Black => sq.south(),
+        });
+
+        let move_counter = MoveCounter::new(
+            bytes[OFFSET_HALF_MOVE] as u16,
+            u16::from_le_bytes(bytes[OFFSET_FULL_MOVE..].try_into().unwrap()),
+        );
+
+        (
+            board,
+            move_counter,
+            castle_permissions,
+            side_to_move,
+            en_pass_sq,
+        )
+    }
+}
+
+fn is_castling_rook(castle_permissions: &CastlePermission, sq: &Square) -> bool {
+    (castle_permissions.is_white_king_set() && castle_permissions.white_king_rook_square() == *sq)
+        || (castle_permissions.is_white_queen_set()
+            && castle_permissions.white_queen_rook_square() == *sq)
+        || (castle_permissions.is_black_king_set()
+            && castle_permissions.black_king_rook_square() == *sq)
+        || (castle_permissions.is_black_queen_set()
+            && castle_permissions.black_queen_rook_square() == *sq)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::board::occupancy_masks::OccupancyMasks;
+    use crate::board::square::Square;
+    use crate::io::fen;
+    use crate::io::packed::PackedPosition;
+    use crate::moves::mov::Move;
+    use crate::position::attack_checker::AttackChecker;
+    use crate::position::game_position::Position;
+
+    #[test]
+    pub fn packed_fens_unpack_the_same() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3",
+            "rnbqkbnr/pppp1ppp/8/8/3Pp3/8/PPP1PPPP/RNBQKBNR b Kq d3 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 37 180",
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+        ];
+
+        for fen in fens {
+            let packed = pack_fen(fen);
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) = packed.unpack();
+            let expected = fen::decompose_fen(fen);
+
+            assert!(board == expected.0, "{}", fen);
+            assert!(move_cntr == expected.1, "{}", fen);
+            assert_eq!(castle_permissions, expected.2, "{}", fen);
+            assert!(side_to_move == expected.3, "{}", fen);
+            assert!(en_pass_sq == expected.4, "{}", fen);
+
+            let copy = PackedPosition::from_bytes(*packed.as_bytes()).unwrap();
+            assert_eq!(copy, packed);
+        }
+    }
+
+    #[test]
+    pub fn packed_position_after_moves() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+        pos.make_move(&Move::encode_move(&Square::G1, &Square::F3));
+        pos.make_move(&Move::encode_move(&Square::E7, &Square::E5));
+        pos.make_move(&Move::encode_move(&Square::H1, &Square::G1));
+
+        let expected = pack_fen("rnbqkbnr/pppp1ppp/8/4p3/8/5N2/PPPPPPPP/RNBQKBR1 b Qkq - 1 2");
+        assert_eq!(PackedPosition::from_position(&pos), expected);
+    }
+
+    #[test]
+    pub fn too_many_pieces_rejected() {
+        let mut bytes = [0u8; PackedPosition::SIZE];
+        bytes[..8].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert_eq!(PackedPosition::from_bytes(bytes), None);
+    }
+
+    fn pack_fen(fen: &str) -> PackedPosition {
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+        PackedPosition::pack(
+            &board,
+            &move_cntr,
+            &castle_permissions,
+            &side_to_move,
+            &en_pass_sq,
+        )
+    }
+}
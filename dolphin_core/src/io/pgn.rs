@@ -0,0 +1,392 @@
+use std::fmt;
+use std::io::BufRead;
+
+// Reads the games from a (multi-game) PGN file one at a time, so databases
+// too big to fit in memory can be worked through:
+//
+//      let file = BufReader::new(File::open(path)?);
+//      for game in PgnReader::new(file).filter(|game| filter.matches(game)) {
+//          ...
+//      }
+//
+// The move text isn't checked against the board, it's left to the caller
+// to replay the SAN moves. Text that isn't UTF-8 (eg Latin-1 player names)
+// is read with replacement characters, and a read error ends the games.
+pub struct PgnReader<R: BufRead> {
+    reader: R,
+    // a tag line read past the end of the previous game
+    pending_line: Option<String>,
+    // inside a { } comment that runs over more than one line, where a '['
+    // at the start of a line isn't a tag
+    in_comment: bool,
+}
+
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct PgnGame {
+    // in file order
+    pub tags: Vec<(String, String)>,
+    pub movetext: String,
+}
+
+// Games are kept if they match all of the criteria that are set. The Elo
+// range applies to both players, so a game without Elo tags doesn't match
+// once either bound is set.
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct PgnFilter {
+    pub min_elo: Option<u16>,
+    pub max_elo: Option<u16>,
+    // exact match on the TimeControl tag, eg "300+3"
+    pub time_control: Option<String>,
+    // exact match on the Result tag, eg "1-0"
+    pub result: Option<String>,
+}
+
+impl<R: BufRead> PgnReader<R> {
+    pub fn new(reader: R) -> Self {
+        PgnReader {
+            reader,
+            pending_line: None,
+            in_comment: false,
+        }
+    }
+
+    fn next_line(&mut self) -> Option<String> {
+        if let Some(line) = self.pending_line.take() {
+            return Some(line);
+        }
+
+        let mut bytes = Vec::new();
+        match self.reader.read_until(b'\n', &mut bytes) {
+            Ok(0) | Err(_) => None,
+            Ok(_) => Some(String::from_utf8_lossy(&bytes).trim_end().to_string()),
+        }
+    }
+
+    // whether a { } comment is still open at the end of the move text line
+    fn update_in_comment(&mut self, line: &str) {
+        for c in line.chars() {
+            match c {
+                '{' if !self.in_comment => self.in_comment = true,
+                '}' if self.in_comment => self.in_comment = false,
+                // comment to the end of the line
+                ';' if !self.in_comment => break,
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for PgnReader<R> {
+    type Item = PgnGame;
+
+    fn next(&mut self) -> Option<PgnGame> {
+        let mut game = PgnGame::default();
+
+        while let Some(line) = self.next_line() {
+            let line = line.trim();
+            if line.starts_with('[') && !self.in_comment {
+                // a tag after the move text is the start of the next game
+                if !game.movetext.is_empty() {
+                    self.pending_line = Some(line.to_string());
+                    return Some(game);
+                }
+                if let Some(tag) = parse_tag(line) {
+                    game.tags.push(tag);
+                }
+            } else if !line.is_empty() && (self.in_comment || !line.starts_with('%')) {
+                self.update_in_comment(line);
+                if !game.movetext.is_empty() {
+                    game.movetext.push('\n');
+                }
+                game.movetext.push_str(line);
+            }
+        }
+
+        if game.tags.is_empty() && game.movetext.is_empty() {
+            None
+        } else {
+            Some(game)
+        }
+    }
+}
+
+// [Name "Value"], with \" and \\ escapes in the value
+fn parse_tag(line: &str) -> Option<(String, String)> {
+    let inner = line.strip_prefix('[')?.strip_suffix(']')?;
+    let (name, value) = inner.split_once(char::is_whitespace)?;
+    let value = value.trim().strip_prefix('"')?.strip_suffix('"')?;
+
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unescaped.extend(chars.next()),
+            _ => unescaped.push(c),
+        }
+    }
+    Some((name.to_string(), unescaped))
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn result(&self) -> Option<&str> {
+        self.tag("Result")
+    }
+
+    pub fn time_control(&self) -> Option<&str> {
+        self.tag("TimeControl")
+    }
+
+    pub fn white_elo(&self) -> Option<u16> {
+        self.tag("WhiteElo").and_then(|elo| elo.parse().ok())
+    }
+
+    pub fn black_elo(&self) -> Option<u16> {
+        self.tag("BlackElo").and_then(|elo| elo.parse().ok())
+    }
+
+    // The main line moves in SAN (eg "e4", "Nf3", "O-O"), without the move
+    // numbers, comments, NAGs, variations or the result
+    pub fn san_moves(&self) -> Vec<&str> {
+        let mut moves = Vec::new();
+        let mut comment_depth = 0;
+        let mut variation_depth = 0;
+
+        for line in self.movetext.lines() {
+            let mut rest = line;
+            while !rest.is_empty() {
+                if comment_depth > 0 {
+                    match rest.find('}') {
+                        Some(end) => {
+                            comment_depth = 0;
+                            rest = &rest[end + 1..];
+                        }
+                        None => rest = "",
+                    }
+                    continue;
+                }
+
+                rest = rest.trim_start();
+                let Some(c) = rest.chars().next() else {
+                    break;
+                };
+                match c {
+                    '{' => {
+                        comment_depth = 1;
+                        rest = &rest[1..];
+                    }
+                    // comment to the end of the line
+                    ';' => rest = "",
+                    '(' => {
+                        variation_depth += 1;
+                        rest = &rest[1..];
+                    }
+                    ')' => {
+                        variation_depth -= 1;
+                        rest = &rest[1..];
+                    }
+                    _ => {
+                        let end = rest
+                            .find(|c: char| c.is_whitespace() || "{}();".contains(c))
+                            .unwrap_or(rest.len());
+                        let token = &rest[..end];
+                        rest = &rest[end..];
+
+                        if variation_depth == 0 && !is_result(token) {
+                            // strip any move number stuck to the move, eg "1.e4"
+                            let san =
+                                token.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+                            if !san.is_empty() && !san.starts_with('$') {
+                                moves.push(san);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        moves
+    }
+}
+
+fn is_result(token: &str) -> bool {
+    matches!(token, "1-0" | "0-1" | "1/2-1/2" | "*")
+}
+
+impl fmt::Display for PgnGame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (name, value) in self.tags.iter() {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+            writeln!(f, "[{} \"{}\"]", name, value)?;
+        }
+        writeln!(f)?;
+        writeln!(f, "{}", self.movetext)
+    }
+}
+
+impl PgnFilter {
+    pub fn matches(&self, game: &PgnGame) -> bool {
+        if self.min_elo.is_some() || self.max_elo.is_some() {
+            let (Some(white_elo), Some(black_elo)) = (game.white_elo(), game.black_elo()) else {
+                return false;
+            };
+            let in_range = |elo: u16| {
+                self.min_elo.is_none_or(|min| elo >= min)
+                    && self.max_elo.is_none_or(|max| elo <= max)
+            };
+            if !in_range(white_elo) || !in_range(black_elo) {
+                return false;
+            }
+        }
+
+        if let Some(time_control) = &self.time_control {
+            if game.time_control() != Some(time_control.as_str()) {
+                return false;
+            }
+        }
+        if let Some(result) = &self.result {
+            if game.result() != Some(result.as_str()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::io::pgn::PgnFilter;
+    use crate::io::pgn::PgnGame;
+    use crate::io::pgn::PgnReader;
+
+    const GAMES: &str = r#"[Event "Rated Blitz game"]
+[White "alice"]
+[Black "bob \"the\" builder"]
+[Result "1-0"]
+[WhiteElo "1850"]
+[BlackElo "1790"]
+[TimeControl "300+3"]
+
+1. e4 e5 2. Nf3 {a comment} Nc6 3. Bb5 $1 a6 (3... Nf6 4. O-O) 4. Ba4
+Nf6 5. O-O 1-0
+
+[Event "Rated Bullet game"]
+[White "carol"]
+[Black "dave"]
+[Result "1/2-1/2"]
+[WhiteElo "2210"]
+[BlackElo "2250"]
+[TimeControl "60+0"]
+1.d4 d5 ; to the end of the line
+2.c4 1/2-1/2
+[Event "No Elo"]
+[Result "0-1"]
+
+1. f3 e5 2. g4 Qh4# 0-1
+"#;
+
+    fn read_games() -> Vec<PgnGame> {
+        PgnReader::new(GAMES.as_bytes()).collect()
+    }
+
+    #[test]
+    pub fn games_split_with_tags_and_movetext() {
+        let games = read_games();
+        assert_eq!(games.len(), 3);
+
+        assert_eq!(games[0].tag("White"), Some("alice"));
+        assert_eq!(games[0].tag("Black"), Some("bob \"the\" builder"));
+        assert_eq!(games[0].white_elo(), Some(1850));
+        assert_eq!(games[0].time_control(), Some("300+3"));
+        assert_eq!(games[1].result(), Some("1/2-1/2"));
+        assert_eq!(games[2].white_elo(), None);
+        assert_eq!(games[2].tag("Missing"), None);
+    }
+
+    #[test]
+    pub fn san_moves_skip_numbers_comments_and_variations() {
+        let games = read_games();
+
+        assert_eq!(
+            games[0].san_moves(),
+            ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4", "Nf6", "O-O"]
+        );
+        assert_eq!(games[1].san_moves(), ["d4", "d5", "c4"]);
+        assert_eq!(games[2].san_moves(), ["f3", "e5", "g4", "Qh4#"]);
+    }
+
+    #[test]
+    pub fn games_filtered_by_elo_time_control_and_result() {
+        let filter = PgnFilter {
+            min_elo: Some(2000),
+            ..Default::default()
+        };
+        let events: Vec<String> = PgnReader::new(GAMES.as_bytes())
+            .filter(|game| filter.matches(game))
+            .map(|game| game.tag("Event").unwrap().to_string())
+            .collect();
+        assert_eq!(events, ["Rated Bullet game"]);
+
+        let filter = PgnFilter {
+            max_elo: Some(2000),
+            time_control: Some("300+3".to_string()),
+            result: Some("1-0".to_string()),
+            ..Default::default()
+        };
+        let games: Vec<PgnGame> = PgnReader::new(GAMES.as_bytes())
+            .filter(|game| filter.matches(game))
+            .collect();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].tag("White"), Some("alice"));
+
+        // no criteria keeps everything
+        assert!(read_games()
+            .iter()
+            .all(|game| PgnFilter::default().matches(game)));
+    }
+
+    #[test]
+    pub fn game_written_back_reads_the_same() {
+        for game in read_games() {
+            let written = game.to_string();
+            let reread: Vec<PgnGame> = PgnReader::new(written.as_bytes()).collect();
+            assert_eq!(reread, [game]);
+        }
+    }
+    #[test]
+    pub fn non_utf8_bytes_read_lossily() {
+        // "Müller" in Latin-1
+        let mut pgn = b"[White \"M".to_vec();
+        pgn.push(0xfc);
+        pgn.extend_from_slice(b"ller\"]\n[Result \"1-0\"]\n\n1. e4 e5 1-0\n");
+
+        let games: Vec<PgnGame> = PgnReader::new(pgn.as_slice()).collect();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].tag("White"), Some("M\u{fffd}ller"));
+        assert_eq!(games[0].san_moves(), ["e4", "e5"]);
+    }
+
+    #[test]
+    pub fn bracket_inside_a_multi_line_comment_isnt_a_tag() {
+        let pgn = r#"[Result "1-0"]
+
+1. e4 {a comment
+[that looks like a tag]
+%and an escape} e5 2. Nf3 1-0
+
+[Result "0-1"]
+
+1. f3 e5 0-1
+"#;
+        let games: Vec<PgnGame> = PgnReader::new(pgn.as_bytes()).collect();
+        assert_eq!(games.len(), 2);
+        assert_eq!(games[0].tags.len(), 1);
+        assert_eq!(games[0].san_moves(), ["e4", "e5", "Nf3"]);
+        assert_eq!(games[1].result(), Some("0-1"));
+    }
+}
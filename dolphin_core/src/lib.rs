@@ -3,4 +3,3 @@ pub mod board;
 pub mod io;
 pub mod moves;
 pub mod position;
-pub mod search_engine;
@@ -0,0 +1,203 @@
+use crate::board::occupancy_masks::OccupancyMasks;
+use crate::io::fen;
+use crate::moves::mov::Move;
+use crate::moves::move_gen::MoveGenerator;
+use crate::moves::move_list::MoveList;
+use crate::moves::perft;
+use crate::position::attack_checker::AttackChecker;
+use crate::position::game_position::Position;
+use rand::RngCore;
+use rand_xoshiro::rand_core::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use shakmaty::fen::Fen;
+use shakmaty::uci::UciMove;
+use shakmaty::CastlingMode;
+use shakmaty::Chess;
+use shakmaty::EnPassantMode;
+use shakmaty::Position as _;
+use std::fmt;
+
+// Differential testing of the move generator against the shakmaty crate
+// (feature "difftest"). Random games are played from each start position,
+// and at every position along the way the perft counts of the two move
+// generators are compared. A mismatch is narrowed down through the moves
+// whose counts differ to the first position where the legal moves differ.
+// Moves are compared in Chess960 UCI, with castling as the king taking its
+// rook, so Chess960 start positions (with Shredder-FEN or X-FEN castling)
+// can be checked too.
+
+// a position where the legal moves differ from the reference
+#[derive(Debug)]
+pub struct Mismatch {
+    pub fen: String,
+    // legal in the reference, but not generated
+    pub missing: Vec<String>,
+    // generated, but not legal in the reference
+    pub extra: Vec<String>,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} : missing [{}], extra [{}]",
+            self.fen,
+            self.missing.join(" "),
+            self.extra.join(" ")
+        )
+    }
+}
+
+// Returns the number of positions checked, or the first mismatch found.
+// The games are reproducible for a given seed.
+pub fn run(
+    start_fens: &[&str],
+    num_games: usize,
+    max_plies: usize,
+    perft_depth: u8,
+    seed: u64,
+) -> Result<u64, Mismatch> {
+    let move_gen = MoveGenerator::new();
+    let attack_checker = AttackChecker::new();
+    let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+    let mut num_positions = 0;
+
+    for start_fen in start_fens {
+        for _ in 0..num_games {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(start_fen);
+            let mut pos = Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                OccupancyMasks::instance(),
+                &attack_checker,
+            );
+            let mut chess: Chess = Fen::from_ascii(start_fen.as_bytes())
+                .expect("Invalid FEN")
+                .into_position(CastlingMode::Chess960)
+                .expect("Invalid position");
+
+            for _ in 0..max_plies {
+                num_positions += 1;
+                if let Some(mismatch) = find_mismatch(&mut pos, &chess, perft_depth, &move_gen) {
+                    return Err(mismatch);
+                }
+
+                // the move lists are the same, so either can be picked from
+                let moves = dolphin_moves(&mut pos, &move_gen);
+                if moves.is_empty() {
+                    break;
+                }
+                let (uci, mv) = &moves[(rng.next_u64() % moves.len() as u64) as usize];
+                pos.make_move(mv);
+                play_reference_move(&mut chess, uci);
+            }
+        }
+    }
+    Ok(num_positions)
+}
+
+// compares the perft counts, and if they differ, follows the moves whose
+// counts differ down to the position where the legal moves differ
+fn find_mismatch(
+    pos: &mut Position,
+    chess: &Chess,
+    depth: u8,
+    move_gen: &MoveGenerator,
+) -> Option<Mismatch> {
+    let moves = dolphin_moves(pos, move_gen);
+    let uci_moves: Vec<String> = moves.iter().map(|(uci, _)| uci.clone()).collect();
+    let ref_moves = reference_moves(chess);
+    if uci_moves != ref_moves {
+        return Some(Mismatch {
+            fen: Fen::from_position(chess, EnPassantMode::Legal).to_string(),
+            missing: ref_moves
+                .iter()
+                .filter(|uci| !uci_moves.contains(uci))
+                .cloned()
+                .collect(),
+            extra: uci_moves
+                .iter()
+                .filter(|uci| !ref_moves.contains(uci))
+                .cloned()
+                .collect(),
+        });
+    }
+
+    if depth <= 1 || perft::perft(pos, depth) == shakmaty::perft(chess, depth as u32) {
+        return None;
+    }
+
+    for (uci, mv) in moves.iter() {
+        let mut child = chess.clone();
+        play_reference_move(&mut child, uci);
+
+        pos.make_move(mv);
+        let mismatch = find_mismatch(pos, &child, depth - 1, move_gen);
+        pos.take_move();
+
+        if mismatch.is_some() {
+            return mismatch;
+        }
+    }
+    None
+}
+
+// legal moves, sorted by their UCI string
+fn dolphin_moves(pos: &mut Position, move_gen: &MoveGenerator) -> Vec<(String, Move)> {
+    let mut move_list = MoveList::new();
+    move_gen.generate_legal_moves(pos, &mut move_list);
+
+    let mut moves: Vec<(String, Move)> = move_list
+        .iterator()
+        .map(|mv| (mv.to_uci_string_chess960(&pos.castle_permissions()), *mv))
+        .collect();
+    moves.sort_by(|(uci1, _), (uci2, _)| uci1.cmp(uci2));
+    moves
+}
+
+fn reference_moves(chess: &Chess) -> Vec<String> {
+    let mut moves: Vec<String> = chess
+        .legal_moves()
+        .iter()
+        .map(|mv| mv.to_uci(CastlingMode::Chess960).to_string())
+        .collect();
+    moves.sort();
+    moves
+}
+
+fn play_reference_move(chess: &mut Chess, uci: &str) {
+    let mv = UciMove::from_ascii(uci.as_bytes())
+        .expect("Invalid UCI move")
+        .to_move(chess)
+        .expect("Move not legal in the reference");
+    chess.play_unchecked(mv);
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::moves::difftest;
+
+    #[test]
+    pub fn random_games_match_reference() {
+        let start_fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            // Chess960
+            "bqnb1rkr/pp3ppp/3ppn2/2p5/5P2/P2P4/NPP1P1PP/BQ1BNRKR w HFhf - 2 9",
+            "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9",
+            "1r2k2r/8/8/8/8/8/8/1R2K2R w KQkq - 0 1",
+            "rk2r3/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w GBea - 0 1",
+        ];
+
+        match difftest::run(&start_fens, 5, 60, 2, 1234) {
+            Ok(num_positions) => assert!(num_positions > 0),
+            Err(mismatch) => panic!("Move generator mismatch at {}", mismatch),
+        }
+    }
+}
@@ -1,3 +1,6 @@
+#[cfg(feature = "difftest")]
+pub mod difftest;
 pub mod mov;
 pub mod move_gen;
 pub mod move_list;
+pub mod perft;
@@ -1,11 +1,16 @@
+use crate::board::colour::Colour;
+use crate::board::file::File;
+use crate::board::game_board::Board;
+use crate::board::occupancy_masks::OccupancyMasks;
 use crate::board::piece::Piece;
 use crate::board::square::Square;
+use crate::position::castle_permissions::CastlePermission;
 use enumn::N;
 use std::fmt;
 use std::process;
 
 #[rustfmt::skip]
-#[derive(Eq, PartialEq, Copy, Clone, Hash, N)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash, N)]
 pub enum MoveType {
     Normal      = 0b0000_0000_0000_0000,
     Promotion   = 0b0001_0000_0000_0000,
@@ -35,6 +40,19 @@ enum BitMask{
     PromoTarget = 0b1100_0000_0000_0000,
 }
 
+// Polyglot book move layout
+// ---- ---- --xx xxxx  to square
+// ---- xxxx xx-- ----  from square
+// -xxx ---- ---- ----  promotion piece (0 none, 1 knight, 2 bishop, 3 rook, 4 queen)
+const POLYGLOT_SQ_MASK: u16 = 0b11_1111;
+const POLYGLOT_FROM_SHIFT: u16 = 6;
+const POLYGLOT_PROMO_SHIFT: u16 = 12;
+const POLYGLOT_PROMO_MASK: u16 = 0b111;
+const POLYGLOT_KNIGHT: u16 = 1;
+const POLYGLOT_BISHOP: u16 = 2;
+const POLYGLOT_ROOK: u16 = 3;
+const POLYGLOT_QUEEN: u16 = 4;
+
 // Move bits (copied from StockFish)
 // xxxx xxxx xxxx xxxx
 // ---- ---- --xx xxxx  source (from) square
@@ -142,6 +160,15 @@ impl Move {
         Move { bits }
     }
 
+    /// Encodes a castle move as the king's move, from wherever it starts
+    /// (eg, in Chess960) to the g or c file
+    pub const fn encode_move_castle(king_from_sq: &Square, king_to_sq: &Square) -> Move {
+        let mut bits = Self::encode_from_to_sq(king_from_sq, king_to_sq);
+        bits |= MoveType::Castle as u16;
+
+        Move { bits }
+    }
+
     /// Encodes a White King-side castle move
     ///
     pub const fn encode_move_castle_kingside_white() -> Move {
@@ -178,6 +205,173 @@ impl Move {
         Move { bits }
     }
 
+    /// Returns the raw 16-bit encoding of the move
+    pub const fn as_u16(&self) -> u16 {
+        self.bits
+    }
+
+    /// Rebuilds a move from its raw 16-bit encoding (see `as_u16`)
+    pub const fn from_u16(bits: u16) -> Move {
+        Move { bits }
+    }
+
+    /// Returns the move in the 16-bit Polyglot book layout (to square in bits
+    /// 0-5, from square in bits 6-11, promotion piece in bits 12-14), with
+    /// castling written as the king moving onto its rook, eg e1h1
+    pub fn to_polyglot(&self) -> u16 {
+        let (from_sq, mut to_sq) = self.decode_from_to_sq();
+
+        let mut promo_bits = 0;
+        match self.move_type() {
+            MoveType::Castle => {
+                to_sq = match to_sq {
+                    Square::G1 => Square::H1,
+                    Square::C1 => Square::A1,
+                    Square::G8 => Square::H8,
+                    _ => Square::A8,
+                };
+            }
+            MoveType::Promotion => {
+                promo_bits = match self.decode_promotion_piece() {
+                    Piece::Knight => POLYGLOT_KNIGHT,
+                    Piece::Bishop => POLYGLOT_BISHOP,
+                    Piece::Rook => POLYGLOT_ROOK,
+                    _ => POLYGLOT_QUEEN,
+                };
+            }
+            _ => {}
+        }
+
+        (promo_bits << POLYGLOT_PROMO_SHIFT)
+            | ((from_sq.as_index() as u16) << POLYGLOT_FROM_SHIFT)
+            | to_sq.as_index() as u16
+    }
+
+    /// Rebuilds a move from its Polyglot encoding (see `to_polyglot`). The
+    /// board (before the move) is needed to tell castling and en passant
+    /// apart from normal moves.
+    pub fn from_polyglot(bits: u16, board: &Board) -> Move {
+        let from_sq = Square::new(((bits >> POLYGLOT_FROM_SHIFT) & POLYGLOT_SQ_MASK) as u8)
+            .expect("Bad from_sq");
+        let to_sq = Square::new((bits & POLYGLOT_SQ_MASK) as u8).expect("Bad to_sq");
+
+        let promo_pce = match (bits >> POLYGLOT_PROMO_SHIFT) & POLYGLOT_PROMO_MASK {
+            POLYGLOT_KNIGHT => Some(Piece::Knight),
+            POLYGLOT_BISHOP => Some(Piece::Bishop),
+            POLYGLOT_ROOK => Some(Piece::Rook),
+            POLYGLOT_QUEEN => Some(Piece::Queen),
+            _ => None,
+        };
+        if let Some(promo_pce) = promo_pce {
+            return Move::encode_move_with_promotion(&from_sq, &to_sq, &promo_pce);
+        }
+
+        match board.get_piece_on_square(&from_sq) {
+            Some(Piece::King) => match (from_sq, to_sq) {
+                (Square::E1, Square::H1) => Move::encode_move_castle_kingside_white(),
+                (Square::E1, Square::A1) => Move::encode_move_castle_queenside_white(),
+                (Square::E8, Square::H8) => Move::encode_move_castle_kingside_black(),
+                (Square::E8, Square::A8) => Move::encode_move_castle_queenside_black(),
+                _ => Move::encode_move(&from_sq, &to_sq),
+            },
+            // a diagonal pawn move onto an empty square
+            Some(Piece::Pawn) if from_sq.file() != to_sq.file() && board.is_sq_empty(&to_sq) => {
+                Move::encode_move_en_passant(&from_sq, &to_sq)
+            }
+            _ => Move::encode_move(&from_sq, &to_sq),
+        }
+    }
+
+    /// Returns the move in UCI long algebraic notation, eg "e2e4", "e7e8q"
+    pub fn to_uci_string(&self) -> String {
+        let mut uci = format!("{}{}", self.from_sq(), self.to_sq());
+        if self.move_type() == MoveType::Promotion {
+            let promo_pce = self.decode_promotion_piece();
+            uci.push(Piece::label(&promo_pce, &Colour::Black));
+        }
+        uci
+    }
+
+    /// Returns the move in UCI notation for Chess960 GUIs, where castling is
+    /// written as the king capturing its own rook (eg "e1h1"), using the rook
+    /// files in the castle permissions. Other moves are as `to_uci_string`.
+    pub fn to_uci_string_chess960(&self, castle_permissions: &CastlePermission) -> String {
+        if self.move_type() != MoveType::Castle {
+            return self.to_uci_string();
+        }
+
+        let rook_sq = match self.to_sq() {
+            Square::G1 => castle_permissions.white_king_rook_square(),
+            Square::C1 => castle_permissions.white_queen_rook_square(),
+            Square::G8 => castle_permissions.black_king_rook_square(),
+            _ => castle_permissions.black_queen_rook_square(),
+        };
+        format!("{}{}", self.from_sq(), rook_sq)
+    }
+
+    /// Returns the move in (short) algebraic notation, eg "Nbd2", "exd6", "e8=Q",
+    /// "O-O", using the board (before the move) to find the moving piece,
+    /// captures and any other pieces that could move to the same square.
+    /// Pins aren't considered when disambiguating, and check isn't marked.
+    pub fn to_string_with_board(&self, board: &Board, occ_masks: &OccupancyMasks) -> String {
+        let (from_sq, to_sq) = self.decode_from_to_sq();
+
+        if self.move_type() == MoveType::Castle {
+            return self.castle_string();
+        }
+
+        let (piece, colour) = board
+            .get_piece_and_colour_on_square(&from_sq)
+            .expect("No piece on from square");
+        let is_capture =
+            self.move_type() == MoveType::EnPassant || board.get_piece_on_square(&to_sq).is_some();
+
+        let mut san = String::new();
+        if piece == Piece::Pawn {
+            if is_capture {
+                san.push(from_sq.file().to_char());
+            }
+        } else {
+            san.push(Piece::label(&piece, &Colour::White));
+
+            let others: Vec<Square> = board
+                .attackers_breakdown(occ_masks, &to_sq)
+                .attackers(&colour)
+                .iter()
+                .filter(|(pce, sq)| *pce == piece && *sq != from_sq)
+                .map(|(_, sq)| *sq)
+                .collect();
+
+            if !others.is_empty() {
+                if others.iter().all(|sq| sq.file() != from_sq.file()) {
+                    san.push(from_sq.file().to_char());
+                } else if others.iter().all(|sq| sq.rank() != from_sq.rank()) {
+                    san.push(from_sq.rank().to_char());
+                } else {
+                    san.push_str(&from_sq.to_string());
+                }
+            }
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&to_sq.to_string());
+
+        if self.move_type() == MoveType::Promotion {
+            san.push('=');
+            san.push(Piece::label(&self.decode_promotion_piece(), &Colour::White));
+        }
+        san
+    }
+
+    fn castle_string(&self) -> String {
+        match self.to_sq().file() {
+            File::G => "O-O".to_string(),
+            _ => "O-O-O".to_string(),
+        }
+    }
+
     pub fn print_move(&self) {
         let (from_sq, to_sq) = self.decode_from_to_sq();
         println!("From {:?}, To {:?}", from_sq, to_sq);
@@ -210,33 +404,43 @@ impl Move {
     }
 }
 
-impl fmt::Debug for Move {
+// Without a board, captures (other than en passant) and the moving piece
+// aren't known, so moves are shown by square, eg "e2e4", "e5xd6", "e7e8=Q", "O-O"
+impl fmt::Display for Move {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut debug_str = String::new();
-
-        let from_sq = self.from_sq();
-        let to_sq = self.to_sq();
-
-        debug_str.push_str(&format!("[{}", from_sq.file()));
-        debug_str.push_str(&format!("{}->", from_sq.rank()));
-        debug_str.push_str(&format!("{}", to_sq.file()));
-        debug_str.push_str(&format!("{} ", to_sq.rank()));
+        let (from_sq, to_sq) = self.decode_from_to_sq();
 
-        write!(f, "{}", debug_str)
+        match self.move_type() {
+            MoveType::Normal => write!(f, "{}{}", from_sq, to_sq),
+            MoveType::EnPassant => write!(f, "{}x{}", from_sq, to_sq),
+            MoveType::Promotion => write!(
+                f,
+                "{}{}={}",
+                from_sq,
+                to_sq,
+                Piece::label(&self.decode_promotion_piece(), &Colour::White)
+            ),
+            MoveType::Castle => write!(f, "{}", self.castle_string()),
+        }
     }
 }
 
-impl fmt::Display for Move {
+impl fmt::Debug for Move {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Debug::fmt(&self, f)
+        write!(f, "{} [{:?}]", self, self.move_type())
     }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use crate::board::colour::Colour;
+    use crate::board::file::File;
+    use crate::board::occupancy_masks::OccupancyMasks;
     use crate::board::piece::Piece;
     use crate::board::square::Square;
+    use crate::io::fen;
     use crate::moves::mov::Move;
+    use crate::position::castle_permissions::CastlePermission;
 
     #[test]
     pub fn encode_decode_king_white_castle() {
@@ -320,4 +524,228 @@ pub mod tests {
             }
         }
     }
+
+    #[test]
+    pub fn to_uci_string_as_expected() {
+        let mv = Move::encode_move(&Square::E2, &Square::E4);
+        assert_eq!(mv.to_uci_string(), "e2e4");
+
+        let mv = Move::encode_move_with_promotion(&Square::E7, &Square::E8, &Piece::Queen);
+        assert_eq!(mv.to_uci_string(), "e7e8q");
+
+        let mv = Move::encode_move_with_promotion(&Square::B2, &Square::A1, &Piece::Knight);
+        assert_eq!(mv.to_uci_string(), "b2a1n");
+
+        let mv = Move::encode_move_en_passant(&Square::D5, &Square::E6);
+        assert_eq!(mv.to_uci_string(), "d5e6");
+
+        assert_eq!(
+            Move::encode_move_castle_kingside_white().to_uci_string(),
+            "e1g1"
+        );
+        assert_eq!(
+            Move::encode_move_castle_queenside_black().to_uci_string(),
+            "e8c8"
+        );
+    }
+
+    #[test]
+    pub fn to_uci_string_chess960_as_expected() {
+        let mut cp = CastlePermission::NO_CASTLE_PERMS_AVAIL;
+        assert_eq!(
+            Move::encode_move_castle_kingside_white().to_uci_string_chess960(&cp),
+            "e1h1"
+        );
+        assert_eq!(
+            Move::encode_move_castle_queenside_black().to_uci_string_chess960(&cp),
+            "e8a8"
+        );
+
+        // rooks not on the corner squares
+        cp.set_rook_files(&Colour::White, &File::G, &File::B);
+        assert_eq!(
+            Move::encode_move_castle_kingside_white().to_uci_string_chess960(&cp),
+            "e1g1"
+        );
+        assert_eq!(
+            Move::encode_move_castle_queenside_white().to_uci_string_chess960(&cp),
+            "e1b1"
+        );
+
+        let mv = Move::encode_move_with_promotion(&Square::E7, &Square::E8, &Piece::Queen);
+        assert_eq!(mv.to_uci_string_chess960(&cp), "e7e8q");
+    }
+
+    #[test]
+    pub fn display_and_debug_as_expected() {
+        let mv = Move::encode_move(&Square::E2, &Square::E4);
+        assert_eq!(format!("{}", mv), "e2e4");
+        assert_eq!(format!("{:?}", mv), "e2e4 [Normal]");
+
+        let mv = Move::encode_move_with_promotion(&Square::E7, &Square::E8, &Piece::Queen);
+        assert_eq!(format!("{}", mv), "e7e8=Q");
+        assert_eq!(format!("{:?}", mv), "e7e8=Q [Promotion]");
+
+        let mv = Move::encode_move_en_passant(&Square::E5, &Square::D6);
+        assert_eq!(format!("{}", mv), "e5xd6");
+        assert_eq!(format!("{:?}", mv), "e5xd6 [EnPassant]");
+
+        let mv = Move::encode_move_castle_kingside_white();
+        assert_eq!(format!("{}", mv), "O-O");
+        assert_eq!(format!("{:?}", mv), "O-O [Castle]");
+        let mv = Move::encode_move_castle_queenside_black();
+        assert_eq!(format!("{}", mv), "O-O-O");
+    }
+
+    #[test]
+    pub fn to_string_with_board_as_expected() {
+        let occ_masks = OccupancyMasks::instance();
+
+        let moves = [
+            // pawn moves, captures and promotions
+            (
+                "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1",
+                Move::encode_move(&Square::E4, &Square::E5),
+                "e5",
+            ),
+            (
+                "4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1",
+                Move::encode_move(&Square::E4, &Square::D5),
+                "exd5",
+            ),
+            (
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                Move::encode_move_en_passant(&Square::E5, &Square::D6),
+                "exd6",
+            ),
+            (
+                "3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1",
+                Move::encode_move_with_promotion(&Square::C7, &Square::C8, &Piece::Rook),
+                "c8=R",
+            ),
+            (
+                "3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1",
+                Move::encode_move_with_promotion(&Square::C7, &Square::D8, &Piece::Queen),
+                "cxd8=Q",
+            ),
+            // pieces
+            (
+                "4k3/8/8/4p3/8/5N2/8/4K3 w - - 0 1",
+                Move::encode_move(&Square::F3, &Square::E5),
+                "Nxe5",
+            ),
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+                Move::encode_move(&Square::E1, &Square::E2),
+                "Ke2",
+            ),
+            // disambiguation by file, rank, then square
+            (
+                "4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1",
+                Move::encode_move(&Square::B1, &Square::D2),
+                "Nbd2",
+            ),
+            (
+                "4k3/8/8/R7/8/8/8/R3K3 w - - 0 1",
+                Move::encode_move(&Square::A1, &Square::A3),
+                "R1a3",
+            ),
+            (
+                "4k3/8/8/8/8/Q7/8/Q1Q1K3 w - - 0 1",
+                Move::encode_move(&Square::A1, &Square::B2),
+                "Qa1b2",
+            ),
+            // other rook is blocked, so no disambiguation needed
+            (
+                "4k3/8/8/8/8/8/8/R2NK2R w - - 0 1",
+                Move::encode_move(&Square::H1, &Square::F1),
+                "Rf1",
+            ),
+            // castling
+            (
+                "r3k3/8/8/8/8/8/8/4K2R w K - 0 1",
+                Move::encode_move_castle_kingside_white(),
+                "O-O",
+            ),
+            (
+                "r3k3/8/8/8/8/8/8/4K2R b q - 0 1",
+                Move::encode_move_castle_queenside_black(),
+                "O-O-O",
+            ),
+        ];
+
+        for (fen, mv, expected) in moves {
+            let (board, _, _, _, _) = fen::decompose_fen(fen);
+            assert_eq!(mv.to_string_with_board(&board, occ_masks), expected);
+        }
+    }
+
+    #[test]
+    pub fn polyglot_encoding_round_trips() {
+        // known book entries, in the Polyglot layout
+        let moves = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                Move::encode_move(&Square::E2, &Square::E4),
+                0x031c,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                Move::encode_move(&Square::D2, &Square::D4),
+                0x02db,
+            ),
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                Move::encode_move(&Square::G1, &Square::F3),
+                0x0195,
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                Move::encode_move_castle_kingside_white(),
+                0x0107,
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+                Move::encode_move_castle_queenside_white(),
+                0x0100,
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+                Move::encode_move_castle_kingside_black(),
+                0x0f3f,
+            ),
+            (
+                "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+                Move::encode_move_castle_queenside_black(),
+                0x0f38,
+            ),
+            (
+                "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1",
+                Move::encode_move_en_passant(&Square::E5, &Square::D6),
+                0x092b,
+            ),
+            (
+                "3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1",
+                Move::encode_move_with_promotion(&Square::C7, &Square::D8, &Piece::Queen),
+                0x4cbb,
+            ),
+            (
+                "3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1",
+                Move::encode_move_with_promotion(&Square::C7, &Square::C8, &Piece::Knight),
+                0x1cba,
+            ),
+            // a king move that isn't castling
+            (
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+                Move::encode_move(&Square::E1, &Square::F1),
+                0x0105,
+            ),
+        ];
+
+        for (fen, mv, expected) in moves {
+            let (board, _, _, _, _) = fen::decompose_fen(fen);
+            assert_eq!(mv.to_polyglot(), expected);
+            assert_eq!(Move::from_polyglot(expected, &board), mv);
+        }
+    }
 }
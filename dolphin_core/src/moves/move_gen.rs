@@ -7,7 +7,31 @@ use crate::moves::mov::Move;
 use crate::moves::move_list::MoveList;
 use crate::position::game_position::Position;
 
-pub struct MoveGenerator {}
+// Which pieces a pawn can promote to in the generated moves. Leaving out
+// the underpromotions is faster, but misses the odd position where only
+// an underpromotion wins (or draws), so perft always wants All.
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum Promotions {
+    #[default]
+    All,
+    QueenAndKnight,
+    QueenOnly,
+}
+
+impl Promotions {
+    const fn pieces(&self) -> &'static [Piece] {
+        match self {
+            Promotions::All => &[Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen],
+            Promotions::QueenAndKnight => &[Piece::Knight, Piece::Queen],
+            Promotions::QueenOnly => &[Piece::Queen],
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct MoveGenerator {
+    promotions: Promotions,
+}
 
 impl Default for MoveGenerator {
     fn default() -> Self {
@@ -17,43 +41,81 @@ impl Default for MoveGenerator {
 
 impl MoveGenerator {
     pub fn new() -> MoveGenerator {
-        MoveGenerator {}
+        MoveGenerator {
+            promotions: Promotions::All,
+        }
+    }
+
+    pub fn set_promotions(&mut self, promotions: Promotions) {
+        self.promotions = promotions;
     }
 
     pub fn generate_moves(&self, pos: &Position, move_list: &mut MoveList) -> u16 {
+        self.generate_moves_with_target_mask(pos, Bitboard::new(!0), move_list)
+    }
+
+    // Only the pseudo-legal moves to a square in the target mask (eg, the
+    // squares between a checker and the king, or the enemy pieces for
+    // captures only), so they don't have to be generated and then filtered.
+    // En passant is to the en passant square, and castling to the king's
+    // destination square.
+    pub fn generate_moves_with_target_mask(
+        &self,
+        pos: &Position,
+        target: Bitboard,
+        move_list: &mut MoveList,
+    ) -> u16 {
         let move_cnt_start = move_list.len();
 
         match pos.side_to_move() {
             Colour::White => {
-                self.generate_white_pawn_normal_moves(pos, move_list);
-                self.gen_white_pawn_promotion_moves(pos, move_list);
-                self.generate_white_en_passant_moves(pos, move_list);
-                self.generate_white_castle_moves(pos, move_list);
+                self.generate_white_pawn_normal_moves(pos, target, move_list);
+                self.gen_white_pawn_promotion_moves(pos, target, move_list);
+                self.generate_white_en_passant_moves(pos, target, move_list);
+                self.generate_castle_moves(pos, target, move_list);
             }
             Colour::Black => {
-                self.generate_black_pawn_normal_moves(pos, move_list);
-                self.gen_black_pawn_promotion_moves(pos, move_list);
-                self.generate_black_en_passant_moves(pos, move_list);
-                self.generate_black_castle_moves(pos, move_list);
+                self.generate_black_pawn_normal_moves(pos, target, move_list);
+                self.gen_black_pawn_promotion_moves(pos, target, move_list);
+                self.generate_black_en_passant_moves(pos, target, move_list);
+                self.generate_castle_moves(pos, target, move_list);
             }
         }
 
-        self.generate_non_sliding_moves(pos, move_list);
-        self.generate_sliding_moves(pos, move_list);
+        self.generate_non_sliding_moves(pos, target, move_list);
+        self.generate_sliding_moves(pos, target, move_list);
 
         let move_cnt_end = move_list.len();
 
         (move_cnt_end - move_cnt_start) as u16
     }
 
-    fn generate_white_pawn_normal_moves(&self, pos: &Position, move_list: &mut MoveList) {
+    // Filters the pseudo-legal moves down to the legal ones, by the rules of
+    // the position's variant. Too slow for search, but fine for validating
+    // user input.
+    pub fn generate_legal_moves(&self, pos: &mut Position, move_list: &mut MoveList) -> u16 {
+        let mut pseudo_legal = MoveList::new();
+        self.generate_moves(pos, &mut pseudo_legal);
+
+        let num_before = move_list.len();
+        pos.variant()
+            .filter_legal_moves(pos, &pseudo_legal, move_list);
+        (move_list.len() - num_before) as u16
+    }
+
+    fn generate_white_pawn_normal_moves(
+        &self,
+        pos: &Position,
+        target: Bitboard,
+        move_list: &mut MoveList,
+    ) {
         let wp_bb = pos.board().get_piece_bitboard(&Piece::Pawn, &Colour::White);
-        let opposite_bb = pos.board().get_colour_bb(&Colour::Black);
-        let empty_bb = !pos.board().get_bitboard();
+        let opposite_bb = pos.board().get_colour_bb(&Colour::Black) & target;
+        let empty_bb = pos.board().empty();
 
         // quiet moves
         let wp_r2_6_bb = wp_bb & OccupancyMasks::RANK_2_TO_6_BB;
-        let quiet_pawns_bb = (wp_r2_6_bb.north() & empty_bb).south();
+        let quiet_pawns_bb = (wp_r2_6_bb.north() & empty_bb & target).south();
 
         quiet_pawns_bb.iterator().for_each(|from_sq| {
             let mv = Move::encode_move(&from_sq, &from_sq.north().unwrap());
@@ -64,7 +126,7 @@ impl MoveGenerator {
         let wp_r2_bb = wp_bb & OccupancyMasks::RANK_2_BB;
         if !wp_r2_bb.is_empty() {
             let north_bb = wp_r2_bb.north() & empty_bb;
-            let north_north_bb = north_bb.north() & empty_bb;
+            let north_north_bb = north_bb.north() & empty_bb & target;
 
             let double_pawn_bb = north_north_bb.south().south();
             double_pawn_bb.iterator().for_each(|from_sq| {
@@ -87,8 +149,13 @@ impl MoveGenerator {
         });
     }
 
-    fn generate_white_en_passant_moves(&self, pos: &Position, move_list: &mut MoveList) {
-        if let Some(en_sq) = pos.en_passant_square() {
+    fn generate_white_en_passant_moves(
+        &self,
+        pos: &Position,
+        target: Bitboard,
+        move_list: &mut MoveList,
+    ) {
+        if let Some(en_sq) = pos.en_passant_square().filter(|sq| target.is_set(sq)) {
             let wp_bb = pos.board().get_piece_bitboard(&Piece::Pawn, &Colour::White);
 
             // check south-east
@@ -108,55 +175,71 @@ impl MoveGenerator {
         }
     }
 
-    fn gen_white_pawn_promotion_moves(&self, pos: &Position, move_list: &mut MoveList) {
+    fn gen_white_pawn_promotion_moves(
+        &self,
+        pos: &Position,
+        target: Bitboard,
+        move_list: &mut MoveList,
+    ) {
         let wp_bb = pos.board().get_piece_bitboard(&Piece::Pawn, &Colour::White)
             & OccupancyMasks::RANK_7_BB;
 
         if !wp_bb.is_empty() {
-            let empty_bb = !pos.board().get_bitboard();
+            let empty_bb = pos.board().empty() & target;
 
             // quiet promotion
             let promo_bb = (wp_bb.north() & empty_bb).south();
             promo_bb.iterator().for_each(|from_sq| {
-                self.encode_promotion_moves(&from_sq, &from_sq.north().unwrap(), move_list);
+                self.encode_promotion_moves(pos, &from_sq, &from_sq.north().unwrap(), move_list);
             });
 
             // capture promotion
-            let opposite_bb = pos.board().get_colour_bb(&Colour::Black);
+            let opposite_bb = pos.board().get_colour_bb(&Colour::Black) & target;
             let bb_ne = (wp_bb.north_east() & opposite_bb).south_west();
             bb_ne.iterator().for_each(|from_sq| {
-                self.encode_promotion_moves(&from_sq, &from_sq.north_east().unwrap(), move_list);
+                self.encode_promotion_moves(
+                    pos,
+                    &from_sq,
+                    &from_sq.north_east().unwrap(),
+                    move_list,
+                );
             });
 
             let bb_nw = (wp_bb.north_west() & opposite_bb).south_east();
             bb_nw.iterator().for_each(|from_sq| {
-                self.encode_promotion_moves(&from_sq, &from_sq.north_west().unwrap(), move_list);
+                self.encode_promotion_moves(
+                    pos,
+                    &from_sq,
+                    &from_sq.north_west().unwrap(),
+                    move_list,
+                );
             });
         }
     }
 
-    fn generate_white_castle_moves(&self, pos: &Position, move_list: &mut MoveList) {
-        let cp = pos.castle_permissions();
-        let bb = pos.board().get_bitboard();
-
-        if cp.is_white_king_set() && (bb & OccupancyMasks::CASTLE_MASK_FREE_SQ_WK).is_empty() {
-            let mv = Move::encode_move_castle_kingside_white();
-            move_list.push(&mv);
-        }
-        if cp.is_white_queen_set() && (bb & OccupancyMasks::CASTLE_MASK_FREE_SQ_WQ).is_empty() {
-            let mv = Move::encode_move_castle_queenside_white();
-            move_list.push(&mv);
+    fn generate_castle_moves(&self, pos: &Position, target: Bitboard, move_list: &mut MoveList) {
+        for is_king_side in [true, false] {
+            if let Some(mv) = pos.castle_move(is_king_side) {
+                if target.is_set(&mv.to_sq()) {
+                    move_list.push(&mv);
+                }
+            }
         }
     }
 
-    fn generate_black_pawn_normal_moves(&self, pos: &Position, move_list: &mut MoveList) {
+    fn generate_black_pawn_normal_moves(
+        &self,
+        pos: &Position,
+        target: Bitboard,
+        move_list: &mut MoveList,
+    ) {
         let bp_bb = pos.board().get_piece_bitboard(&Piece::Pawn, &Colour::Black);
-        let empty_bb = !pos.board().get_bitboard();
-        let opposite_bb = pos.board().get_colour_bb(&Colour::White);
+        let empty_bb = pos.board().empty();
+        let opposite_bb = pos.board().get_colour_bb(&Colour::White) & target;
 
         // quiet moves
         let bp_r3_7_bb = bp_bb & OccupancyMasks::RANK_3_TO_7_BB;
-        let quiet_pawns_bb = (bp_r3_7_bb.south() & empty_bb).north();
+        let quiet_pawns_bb = (bp_r3_7_bb.south() & empty_bb & target).north();
 
         quiet_pawns_bb.iterator().for_each(|from_sq| {
             let mv = Move::encode_move(&from_sq, &from_sq.south().unwrap());
@@ -167,7 +250,7 @@ impl MoveGenerator {
         let bp_r7_bb = bp_bb & OccupancyMasks::RANK_7_BB;
         if !bp_r7_bb.is_empty() {
             let south_bb = bp_r7_bb.south() & empty_bb;
-            let south_south_bb = south_bb.south() & empty_bb;
+            let south_south_bb = south_bb.south() & empty_bb & target;
 
             let double_pawn_bb = south_south_bb.north().north();
             double_pawn_bb.iterator().for_each(|from_sq| {
@@ -191,8 +274,13 @@ impl MoveGenerator {
         });
     }
 
-    fn generate_black_en_passant_moves(&self, pos: &Position, move_list: &mut MoveList) {
-        if let Some(en_sq) = pos.en_passant_square() {
+    fn generate_black_en_passant_moves(
+        &self,
+        pos: &Position,
+        target: Bitboard,
+        move_list: &mut MoveList,
+    ) {
+        if let Some(en_sq) = pos.en_passant_square().filter(|sq| target.is_set(sq)) {
             let bp_bb = pos.board().get_piece_bitboard(&Piece::Pawn, &Colour::Black);
 
             // check north-east
@@ -212,62 +300,64 @@ impl MoveGenerator {
         }
     }
 
-    fn gen_black_pawn_promotion_moves(&self, pos: &Position, move_list: &mut MoveList) {
+    fn gen_black_pawn_promotion_moves(
+        &self,
+        pos: &Position,
+        target: Bitboard,
+        move_list: &mut MoveList,
+    ) {
         let bp_bb = pos.board().get_piece_bitboard(&Piece::Pawn, &Colour::Black)
             & OccupancyMasks::RANK_2_BB;
 
         if !bp_bb.is_empty() {
-            let empty_bb = !pos.board().get_bitboard();
+            let empty_bb = pos.board().empty() & target;
 
             // quiet promotion
             let promo_bb = (bp_bb.south() & empty_bb).north();
             promo_bb.iterator().for_each(|from_sq| {
-                self.encode_promotion_moves(&from_sq, &from_sq.south().unwrap(), move_list);
+                self.encode_promotion_moves(pos, &from_sq, &from_sq.south().unwrap(), move_list);
             });
 
             // capture promotion
-            let opposite_bb = pos.board().get_colour_bb(&Colour::White);
+            let opposite_bb = pos.board().get_colour_bb(&Colour::White) & target;
             let bb_se = (bp_bb.south_east() & opposite_bb).north_west();
             bb_se.iterator().for_each(|from_sq| {
-                self.encode_promotion_moves(&from_sq, &from_sq.south_east().unwrap(), move_list);
+                self.encode_promotion_moves(
+                    pos,
+                    &from_sq,
+                    &from_sq.south_east().unwrap(),
+                    move_list,
+                );
             });
 
             let bb_sw = (bp_bb.south_west() & opposite_bb).north_east();
             bb_sw.iterator().for_each(|from_sq| {
-                self.encode_promotion_moves(&from_sq, &from_sq.south_west().unwrap(), move_list);
+                self.encode_promotion_moves(
+                    pos,
+                    &from_sq,
+                    &from_sq.south_west().unwrap(),
+                    move_list,
+                );
             });
         }
     }
 
-    fn generate_black_castle_moves(&self, pos: &Position, move_list: &mut MoveList) {
-        let cp = pos.castle_permissions();
-        let bb = pos.board().get_bitboard();
+    fn generate_sliding_moves(&self, pos: &Position, target: Bitboard, move_list: &mut MoveList) {
+        let all_bb = pos.board().occupied();
+        let col_bb = pos.board().get_colour_bb(&pos.side_to_move());
 
-        if cp.is_black_king_set() && (bb & OccupancyMasks::CASTLE_MASK_FREE_SQ_BK).is_empty() {
-            let mv = Move::encode_move_castle_kingside_black();
-            move_list.push(&mv);
-        }
-        if cp.is_black_queen_set() && (bb & OccupancyMasks::CASTLE_MASK_FREE_SQ_BQ).is_empty() {
-            let mv = Move::encode_move_castle_queenside_black();
-            move_list.push(&mv);
-        }
-    }
-
-    fn generate_sliding_moves(&self, pos: &Position, move_list: &mut MoveList) {
         // rank/file moves
         [Piece::Rook, Piece::Queen].into_iter().for_each(|piece| {
             pos.board()
-                .get_piece_bitboard(&piece, &pos.side_to_move())
-                .iterator()
+                .squares_of(&piece, &pos.side_to_move())
                 .for_each(|from_sq| {
-                    let rank_file_to_sq = self.hyperbola_quintessence(
-                        pos,
-                        pos.occupancy_masks()
-                            .get_horizontal_mask(&from_sq)
-                            .into_u64(),
-                        pos.occupancy_masks().get_vertical_mask(&from_sq).into_u64(),
+                    let rank_moves = pos.occupancy_masks().get_rank_attacks(&from_sq, all_bb);
+                    let file_moves = self.hyperbola_quintessence(
+                        all_bb,
+                        pos.occupancy_masks().get_vertical_mask(&from_sq),
                         &from_sq,
                     );
+                    let rank_file_to_sq = (rank_moves | file_moves) & !col_bb & target;
                     self.gen_multiple_moves(move_list, &from_sq, &rank_file_to_sq);
                 });
         });
@@ -275,17 +365,19 @@ impl MoveGenerator {
         // diagonal/anti-diagonal moves
         [Piece::Bishop, Piece::Queen].into_iter().for_each(|piece| {
             pos.board()
-                .get_piece_bitboard(&piece, &pos.side_to_move())
-                .iterator()
+                .squares_of(&piece, &pos.side_to_move())
                 .for_each(|from_sq| {
-                    let diag_to_sq = self.hyperbola_quintessence(
-                        pos,
-                        pos.occupancy_masks().get_diagonal_mask(&from_sq).into_u64(),
-                        pos.occupancy_masks()
-                            .get_antidiagonal_mask(&from_sq)
-                            .into_u64(),
+                    let diag_moves = self.hyperbola_quintessence(
+                        all_bb,
+                        pos.occupancy_masks().get_diagonal_mask(&from_sq),
+                        &from_sq,
+                    );
+                    let antidiag_moves = self.hyperbola_quintessence(
+                        all_bb,
+                        pos.occupancy_masks().get_antidiagonal_mask(&from_sq),
                         &from_sq,
                     );
+                    let diag_to_sq = (diag_moves | antidiag_moves) & !col_bb & target;
                     self.gen_multiple_moves(move_list, &from_sq, &diag_to_sq);
                 });
         });
@@ -298,45 +390,41 @@ impl MoveGenerator {
         });
     }
 
+    // Attacks along a single file or diagonal (ranks use the first-rank
+    // attack table instead)
     fn hyperbola_quintessence(
         &self,
-        pos: &Position,
-        dir_1_mask: u64,
-        dir_2_mask: u64,
+        all_bb: Bitboard,
+        line_mask: Bitboard,
         square: &Square,
     ) -> Bitboard {
-        let all_bb = pos.board().get_bitboard().into_u64();
-        let col_bb = pos.board().get_colour_bb(&pos.side_to_move()).into_u64();
+        let line_mask = line_mask.into_u64();
         let slider_bb = Bitboard::from_square(&square).into_u64();
+        let line_occ = all_bb.into_u64() & line_mask & !slider_bb;
 
-        let dir_1_a = (all_bb & dir_1_mask).wrapping_sub(slider_bb.wrapping_shl(1));
-        let dir_1_b = ((all_bb & dir_1_mask)
+        let forward = line_occ.wrapping_sub(slider_bb.wrapping_shl(1));
+        let reverse = (line_occ
             .reverse_bits()
             .wrapping_sub(slider_bb.reverse_bits().wrapping_shl(1)))
         .reverse_bits();
-        let dir_1_moves = dir_1_a ^ dir_1_b;
 
-        let dir_2_a = (all_bb & dir_2_mask).wrapping_sub(slider_bb.wrapping_shl(1));
-        let dir_2_b = ((all_bb & dir_2_mask)
-            .reverse_bits()
-            .wrapping_sub(slider_bb.reverse_bits().wrapping_shl(1)))
-        .reverse_bits();
-        let dir_2_moves = dir_2_a ^ dir_2_b;
-
-        let all_moves = (dir_1_moves & dir_1_mask) | (dir_2_moves & dir_2_mask);
-        // return all moves excluding same colour pieces
-        Bitboard::new(all_moves & !col_bb)
+        Bitboard::new((forward ^ reverse) & line_mask)
     }
 
-    fn generate_non_sliding_moves(&self, pos: &Position, move_list: &mut MoveList) {
+    fn generate_non_sliding_moves(
+        &self,
+        pos: &Position,
+        target: Bitboard,
+        move_list: &mut MoveList,
+    ) {
         let opposite_side = pos.side_to_move().flip_side();
-        let opp_occ_sq_bb = pos.board().get_colour_bb(&opposite_side);
-        let unoccupied_squares_bb = !pos.board().get_bitboard();
+        let opp_occ_sq_bb = pos.board().get_colour_bb(&opposite_side) & target;
+        let unoccupied_squares_bb = pos.board().empty() & target;
 
         [Piece::King, Piece::Knight].into_iter().for_each(|piece| {
-            let pce_bb = pos.board().get_piece_bitboard(&piece, &pos.side_to_move());
+            let pce_squares = pos.board().squares_of(&piece, &pos.side_to_move());
 
-            pce_bb.iterator().for_each(|from_sq| {
+            pce_squares.for_each(|from_sq| {
                 let occ_mask = if piece == Piece::Knight {
                     pos.occupancy_masks().get_occupancy_mask_knight(&from_sq)
                 } else {
@@ -361,25 +449,44 @@ impl MoveGenerator {
         })
     }
 
-    fn encode_promotion_moves(&self, from_sq: &Square, to_sq: &Square, move_list: &mut MoveList) {
-        for role in [Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen] {
-            move_list.push(&Move::encode_move_with_promotion(&from_sq, &to_sq, &role));
+    fn encode_promotion_moves(
+        &self,
+        pos: &Position,
+        from_sq: &Square,
+        to_sq: &Square,
+        move_list: &mut MoveList,
+    ) {
+        let variant_pieces = pos.variant().promotion_pieces();
+        for role in self
+            .promotions
+            .pieces()
+            .iter()
+            .filter(|role| variant_pieces.contains(role))
+        {
+            move_list.push(&Move::encode_move_with_promotion(&from_sq, &to_sq, role));
         }
     }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use crate::board::bitboard::Bitboard;
+    use crate::board::colour::Colour;
     use crate::board::occupancy_masks::OccupancyMasks;
     use crate::board::piece::Piece;
+    use crate::board::rank::Rank;
     use crate::board::square::*;
     use crate::io::fen;
     use crate::moves::mov::Move;
+    use crate::moves::mov::MoveType;
     use crate::moves::move_gen::MoveGenerator;
+    use crate::moves::move_gen::Promotions;
     use crate::moves::move_list::MoveList;
     use crate::position::attack_checker::AttackChecker;
     use crate::position::game_position::Position;
-    use crate::position::zobrist_keys::ZobristKeys;
+    use rand::RngCore;
+    use rand_xoshiro::rand_core::SeedableRng;
+    use rand_xoshiro::Xoshiro256PlusPlus;
 
     #[test]
     pub fn move_gen_white_king_knight_move_list_as_expected() {
@@ -388,8 +495,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -398,8 +504,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -451,8 +556,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -461,8 +565,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
         let mut move_list = MoveList::new();
@@ -492,8 +595,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -502,8 +604,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
         let mut move_list = MoveList::new();
@@ -549,8 +650,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -559,8 +659,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -593,8 +692,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -603,8 +701,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -652,8 +749,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -662,8 +758,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -698,8 +793,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -708,8 +802,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -752,8 +845,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -762,8 +854,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -802,8 +893,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -812,8 +902,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -832,8 +921,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -842,8 +930,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -861,8 +948,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -871,8 +957,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -890,8 +975,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -900,8 +984,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -923,8 +1006,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -933,8 +1015,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -961,6 +1042,60 @@ pub mod tests {
         assert!(move_list.contains(&mv));
     }
 
+    #[test]
+    pub fn move_gen_chess960_castle_moves_as_expected() {
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+        let move_gen = MoveGenerator::new();
+
+        let fens = [
+            // queen side rook on b1
+            (
+                "1r2k2r/8/8/8/8/8/8/1R2K2R w KQkq - 0 1",
+                vec![
+                    Move::encode_move_castle(&Square::E1, &Square::G1),
+                    Move::encode_move_castle(&Square::E1, &Square::C1),
+                ],
+            ),
+            // the king ends up on the rook's square, and the rook on the king's
+            (
+                "4k3/8/8/8/8/8/8/5KR1 w G - 0 1",
+                vec![Move::encode_move_castle(&Square::F1, &Square::G1)],
+            ),
+            // the king doesn't move
+            (
+                "4k3/8/8/8/8/8/8/6KR w H - 0 1",
+                vec![Move::encode_move_castle(&Square::G1, &Square::G1)],
+            ),
+            // the king would pass through f1, which the bishop attacks
+            ("4k3/8/8/8/8/8/6b1/1K5R w H - 0 1", vec![]),
+            // a knight on the king's path
+            ("4k3/8/8/8/8/8/8/1KN4R w H - 0 1", vec![]),
+        ];
+        for (fen, expected) in fens {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(fen);
+            let mut pos = Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                occ_masks,
+                &attack_checker,
+            );
+
+            let mut move_list = MoveList::new();
+            move_gen.generate_legal_moves(&mut pos, &mut move_list);
+            let castles: Vec<Move> = move_list
+                .iterator()
+                .filter(|mv| mv.move_type() == MoveType::Castle)
+                .copied()
+                .collect();
+            assert_eq!(castles, expected, "{}", fen);
+        }
+    }
+
     #[test]
     pub fn move_gen_white_promotion_moves_as_expected() {
         let fen = "2b1rkr1/PPpP1pbP/n1p4p/2NpP1p1/1RBqBP2/pPR1NpQ1/P4P1P/5K1n w - - 0 1";
@@ -971,8 +1106,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -981,8 +1115,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1038,6 +1171,60 @@ pub mod tests {
         }
     }
 
+    #[test]
+    pub fn move_gen_promotions_filtered_as_configured() {
+        let fen = "4k3/P7/8/8/8/8/8/4K3 w - - 0 1";
+
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let expected = [
+            (
+                Promotions::All,
+                vec![Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen],
+            ),
+            (
+                Promotions::QueenAndKnight,
+                vec![Piece::Knight, Piece::Queen],
+            ),
+            (Promotions::QueenOnly, vec![Piece::Queen]),
+        ];
+
+        for (promotions, roles) in expected {
+            let mut move_gen = MoveGenerator::new();
+            move_gen.set_promotions(promotions);
+
+            let mut move_list = MoveList::new();
+            move_gen.generate_moves(&pos, &mut move_list);
+
+            let promotion_moves: Vec<_> = move_list
+                .iterator()
+                .filter(|mv| mv.move_type() == MoveType::Promotion)
+                .collect();
+            assert_eq!(promotion_moves.len(), roles.len());
+            for role in roles.iter() {
+                assert!(move_list.contains(&Move::encode_move_with_promotion(
+                    &Square::A7,
+                    &Square::A8,
+                    role
+                )));
+            }
+        }
+    }
+
     #[test]
     pub fn move_gen_black_promotion_moves_as_expected() {
         let fen = "2b1rkr1/PPpP1pbP/n6p/2NpPn2/1RBqBP2/4N1Q1/ppPpRp1P/B4K2 b - - 0 1";
@@ -1047,8 +1234,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -1057,8 +1243,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1093,8 +1278,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -1103,8 +1287,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1134,8 +1317,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -1144,8 +1326,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1172,8 +1353,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -1182,8 +1362,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1210,8 +1389,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -1220,8 +1398,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
         let mut move_list = MoveList::new();
@@ -1252,8 +1429,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -1262,8 +1438,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1333,8 +1508,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -1343,8 +1517,7 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1415,4 +1588,260 @@ pub mod tests {
         // double pawn first move
         assert!(move_list.contains(&Move::encode_move(&Square::A7, &Square::A5)));
     }
+
+    #[test]
+    pub fn move_gen_legal_moves_excludes_moves_into_check() {
+        // e2 pawn is pinned by the rook on e8, king can't move to f2 (bishop on g1)
+        let fen = "4r2k/8/8/8/8/8/4P3/3QK1b1 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let mut move_list = MoveList::new();
+        let move_gen = MoveGenerator::new();
+        let num_legal = move_gen.generate_legal_moves(&mut pos, &mut move_list);
+
+        assert_eq!(num_legal as usize, move_list.len());
+        // pinned pawn can still move along the pin
+        assert!(move_list.contains(&Move::encode_move(&Square::E2, &Square::E3)));
+        assert!(move_list.contains(&Move::encode_move(&Square::E2, &Square::E4)));
+        // king moves into check
+        assert!(!move_list.contains(&Move::encode_move(&Square::E1, &Square::F2)));
+        assert!(move_list.contains(&Move::encode_move(&Square::E1, &Square::F1)));
+        assert!(move_list.contains(&Move::encode_move(&Square::E1, &Square::D2)));
+
+        // position is unchanged
+        let mut pseudo_legal = MoveList::new();
+        move_gen.generate_moves(&pos, &mut pseudo_legal);
+        assert!(pseudo_legal.len() > move_list.len());
+    }
+
+    #[test]
+    pub fn pseudo_legal_moves_hold_invariants_over_random_games() {
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ];
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+        let move_gen = MoveGenerator::new();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(5678);
+        let mut num_positions = 0;
+
+        for fen in fens {
+            for _ in 0..10 {
+                // the same moves are played on both, so they should stay equal
+                let new_position = || {
+                    let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                        fen::decompose_fen(fen);
+                    Position::new(
+                        board,
+                        castle_permissions,
+                        move_cntr,
+                        en_pass_sq,
+                        side_to_move,
+                        occ_masks,
+                        &attack_checker,
+                    )
+                };
+                let mut pos = new_position();
+                let mut reference = new_position();
+
+                for _ in 0..80 {
+                    num_positions += 1;
+
+                    let mut move_list = MoveList::new();
+                    move_gen.generate_moves(&pos, &mut move_list);
+                    for mv in move_list.iterator() {
+                        assert_move_invariants(&pos, mv);
+
+                        pos.make_move(mv);
+                        pos.take_move();
+                        assert!(pos == reference, "{:?} not taken back cleanly", mv);
+                    }
+
+                    let mut legal_moves = MoveList::new();
+                    move_gen.generate_legal_moves(&mut pos, &mut legal_moves);
+                    if legal_moves.is_empty() || pos.is_fifty_move_draw() {
+                        break;
+                    }
+                    let i = (rng.next_u64() % legal_moves.len() as u64) as usize;
+                    let mv = legal_moves.iterator().nth(i).copied().unwrap();
+                    pos.make_move(&mv);
+                    reference.make_move(&mv);
+                }
+            }
+        }
+        assert!(num_positions > 1000);
+    }
+
+    #[test]
+    pub fn target_mask_moves_match_filtered_moves_over_random_games() {
+        let fens = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+        ];
+        let attack_checker = AttackChecker::new();
+        let move_gen = MoveGenerator::new();
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(4321);
+
+        for fen in fens {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(fen);
+            let mut pos = Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                OccupancyMasks::instance(),
+                &attack_checker,
+            );
+
+            for _ in 0..40 {
+                let mut all_moves = MoveList::new();
+                move_gen.generate_moves(&pos, &mut all_moves);
+
+                // the whole board, no squares, and random halves of it
+                let masks = [!0, 0, rng.next_u64(), rng.next_u64()];
+                for mask in masks {
+                    let target = Bitboard::new(mask);
+                    let mut move_list = MoveList::new();
+                    let num_moves =
+                        move_gen.generate_moves_with_target_mask(&pos, target, &mut move_list);
+
+                    let expected: Vec<Move> = all_moves
+                        .iterator()
+                        .filter(|mv| target.is_set(&mv.to_sq()))
+                        .copied()
+                        .collect();
+                    let moves: Vec<Move> = move_list.iterator().copied().collect();
+                    assert!(moves == expected, "{} mask {:#x}", fen, mask);
+                    assert_eq!(num_moves as usize, expected.len());
+                }
+
+                let mut legal_moves = MoveList::new();
+                move_gen.generate_legal_moves(&mut pos, &mut legal_moves);
+                if legal_moves.is_empty() {
+                    break;
+                }
+                let i = (rng.next_u64() % legal_moves.len() as u64) as usize;
+                pos.make_move(&legal_moves.get_move_at_offset(i));
+            }
+        }
+    }
+
+    #[test]
+    pub fn target_mask_of_blocking_squares_gives_check_evasions() {
+        // the rook on a4 checks the king on e4
+        let fen = "4k3/8/8/8/r3K3/8/1N1B4/2R5 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+        let attack_checker = AttackChecker::new();
+        let pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+
+        // capture the checker, or block on b4, c4 or d4
+        let mut target = Bitboard::new(0);
+        for sq in [Square::A4, Square::B4, Square::C4, Square::D4] {
+            target.set_bit(&sq);
+        }
+        let mut move_list = MoveList::new();
+        MoveGenerator::new().generate_moves_with_target_mask(&pos, target, &mut move_list);
+
+        let expected = [
+            Move::encode_move(&Square::B2, &Square::A4),
+            Move::encode_move(&Square::B2, &Square::C4),
+            Move::encode_move(&Square::D2, &Square::B4),
+            Move::encode_move(&Square::C1, &Square::C4),
+            Move::encode_move(&Square::E4, &Square::D4),
+        ];
+        assert_eq!(move_list.len(), expected.len());
+        for mv in expected.iter() {
+            assert!(move_list.contains(mv), "{:?} not generated", mv);
+        }
+    }
+
+    fn assert_move_invariants(pos: &Position, mv: &Move) {
+        let board = pos.board();
+        let side_to_move = pos.side_to_move();
+        let (from_sq, to_sq) = mv.decode_from_to_sq();
+        let (pce, colour) = board
+            .get_piece_and_colour_on_square(&from_sq)
+            .expect("no piece on from square");
+        assert!(colour == side_to_move, "{:?} moves the wrong colour", mv);
+
+        // the last rank for the side to move, and the rank before it
+        let (last_rank, promotion_rank) = match side_to_move {
+            Colour::White => (Rank::R8, Rank::R7),
+            Colour::Black => (Rank::R1, Rank::R2),
+        };
+
+        match mv.move_type() {
+            MoveType::Castle => {
+                assert!(pce == Piece::King, "{:?} castles without the king", mv);
+                assert!(board.is_sq_empty(&to_sq), "{:?} castles onto a piece", mv);
+            }
+            MoveType::EnPassant => {
+                assert!(pce == Piece::Pawn, "{:?} en passant by a non-pawn", mv);
+                assert!(
+                    board.is_sq_empty(&to_sq),
+                    "{:?} en passant onto a piece",
+                    mv
+                );
+                assert_eq!(pos.en_passant_square(), Some(to_sq));
+            }
+            MoveType::Promotion => {
+                assert!(pce == Piece::Pawn, "{:?} promotes a non-pawn", mv);
+                assert!(
+                    from_sq.rank() == promotion_rank,
+                    "{:?} promotes from the wrong rank",
+                    mv
+                );
+                assert!(
+                    to_sq.rank() == last_rank,
+                    "{:?} promotes to the wrong rank",
+                    mv
+                );
+            }
+            MoveType::Normal => {
+                if pce == Piece::Pawn {
+                    assert!(to_sq.rank() != last_rank, "{:?} reaches the last rank", mv);
+                }
+            }
+        }
+
+        // quiet moves land on an empty square, captures on an enemy piece
+        // (never the king, as the side not to move can't be in check)
+        if let Some((target_pce, target_colour)) = board.get_piece_and_colour_on_square(&to_sq) {
+            assert!(
+                target_colour != side_to_move,
+                "{:?} captures its own piece",
+                mv
+            );
+            assert!(target_pce != Piece::King, "{:?} captures the king", mv);
+        }
+    }
 }
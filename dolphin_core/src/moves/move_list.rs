@@ -51,6 +51,11 @@ impl MoveList {
         (0..self.len()).find(|&i| self.ml[i] == *mv)
     }
 
+    // stable, so moves with the same key stay in the order they were added
+    pub fn sort_by_key<K: Ord>(&mut self, f: impl FnMut(&Move) -> K) {
+        self.ml[0..self.count].sort_by_key(f);
+    }
+
     pub fn iterator(&self) -> std::slice::Iter<'_, Move> {
         self.ml[0..self.count].iter()
     }
@@ -146,6 +151,31 @@ pub mod tests {
         assert_eq!(ml.len(), mvs.len());
     }
 
+    #[test]
+    pub fn sort_by_key_keeps_order_of_equal_keys() {
+        let mvs = [
+            Move::encode_move(&Square::H7, &Square::H5),
+            Move::encode_move(&Square::B4, &Square::C5),
+            Move::encode_move(&Square::A3, &Square::A2),
+            Move::encode_move(&Square::D6, &Square::E8),
+            Move::encode_move(&Square::B6, &Square::B7),
+        ];
+
+        let mut ml = MoveList::new();
+        for mv in mvs.iter() {
+            ml.push(mv);
+        }
+        // moves to the 2nd rank first, then the 5th, then the rest
+        ml.sort_by_key(|mv| match mv.to_sq() {
+            Square::A2 => 0,
+            Square::H5 | Square::C5 => 1,
+            _ => 2,
+        });
+
+        let sorted: Vec<Move> = ml.iterator().copied().collect();
+        assert!(sorted == vec![mvs[2], mvs[0], mvs[1], mvs[3], mvs[4]]);
+    }
+
     // #[test]
     // pub fn sort_move_by_score_highest_brought_to_top_sort_from_start() {
     //     let mut mv1 = Move::encode_move_quiet(Square::H7, Square::H5, Piece::Bishop);
@@ -0,0 +1,485 @@
+use crate::moves::mov::Move;
+use crate::moves::mov::MoveType;
+use crate::moves::move_gen::MoveGenerator;
+use crate::moves::move_list::MoveList;
+use crate::position::game_position::MoveLegality;
+use crate::position::game_position::Position;
+
+// Counts the leaf nodes of the legal move tree to the depth, to check the
+// move generator (and make/take) against the known counts at
+// https://www.chessprogramming.org/Perft_Results
+pub fn perft(position: &mut Position, depth: u8) -> u64 {
+    perft_nodes(depth, position, &MoveGenerator::new())
+}
+
+fn perft_nodes(depth: u8, position: &mut Position, move_generator: &MoveGenerator) -> u64 {
+    let mut nodes = 0;
+    if depth == 0 {
+        return 1;
+    }
+
+    let mut move_list = MoveList::new();
+
+    move_generator.generate_moves(position, &mut move_list);
+
+    for mv in move_list.iterator() {
+        let move_legality = position.make_move(mv);
+
+        if move_legality == MoveLegality::Legal {
+            nodes += perft_nodes(depth - 1, position, move_generator);
+        }
+
+        position.take_move();
+    }
+
+    nodes
+}
+
+// The perft count under each legal root move, in move generation order,
+// to narrow down a wrong count by comparing with another engine's divide
+pub fn divide(position: &mut Position, depth: u8) -> Vec<(Move, u64)> {
+    let move_generator = MoveGenerator::new();
+    let mut move_list = MoveList::new();
+    move_generator.generate_legal_moves(position, &mut move_list);
+
+    move_list
+        .iterator()
+        .map(|mv| {
+            position.make_move(mv);
+            let nodes = perft_nodes(depth.saturating_sub(1), position, &move_generator);
+            position.take_move();
+            (*mv, nodes)
+        })
+        .collect()
+}
+
+// Counts of the leaf moves by type, as in the perft results tables at
+// https://www.chessprogramming.org/Perft_Results, to find which kind of move
+// is wrong when the node count doesn't match
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub struct PerftStats {
+    pub nodes: u64,
+    pub captures: u64,
+    pub en_passants: u64,
+    pub castles: u64,
+    pub promotions: u64,
+    pub checks: u64,
+    pub checkmates: u64,
+}
+
+pub fn perft_with_stats(position: &mut Position, depth: u8) -> PerftStats {
+    let mut stats = PerftStats::default();
+    tally_moves(depth, position, &MoveGenerator::new(), &mut stats);
+    stats
+}
+
+fn tally_moves(
+    depth: u8,
+    position: &mut Position,
+    move_generator: &MoveGenerator,
+    stats: &mut PerftStats,
+) {
+    if depth == 0 {
+        return;
+    }
+
+    let mut move_list = MoveList::new();
+
+    move_generator.generate_moves(position, &mut move_list);
+
+    for mv in move_list.iterator() {
+        let is_capture = position.board().get_piece_on_square(&mv.to_sq()).is_some();
+        let move_legality = position.make_move(mv);
+
+        if move_legality == MoveLegality::Legal {
+            if depth == 1 {
+                tally_leaf_move(mv, is_capture, position, move_generator, stats);
+            } else {
+                tally_moves(depth - 1, position, move_generator, stats);
+            }
+        }
+
+        position.take_move();
+    }
+}
+
+// called with the move made
+fn tally_leaf_move(
+    mv: &Move,
+    is_capture: bool,
+    position: &mut Position,
+    move_generator: &MoveGenerator,
+    stats: &mut PerftStats,
+) {
+    stats.nodes += 1;
+
+    match mv.move_type() {
+        MoveType::EnPassant => {
+            stats.en_passants += 1;
+            stats.captures += 1;
+        }
+        MoveType::Castle => stats.castles += 1,
+        MoveType::Promotion => stats.promotions += 1,
+        MoveType::Normal => {}
+    }
+    if is_capture {
+        stats.captures += 1;
+    }
+
+    if position.is_king_sq_attacked() {
+        stats.checks += 1;
+
+        let mut replies = MoveList::new();
+        if move_generator.generate_legal_moves(position, &mut replies) == 0 {
+            stats.checkmates += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::board::occupancy_masks::OccupancyMasks;
+    use crate::io::fen;
+    use crate::moves::perft;
+    use crate::moves::perft::PerftStats;
+    use crate::position::attack_checker::AttackChecker;
+    use crate::position::game_position::Position;
+
+    #[test]
+    pub fn divide_counts_sum_to_perft() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let divide = perft::divide(&mut pos, 3);
+        assert_eq!(divide.len(), 48);
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 97862);
+
+        let counts: Vec<(String, u64)> = divide
+            .iter()
+            .map(|(mv, nodes)| (mv.to_uci_string(), *nodes))
+            .collect();
+        // as Stockfish's "go perft 3" gives them
+        assert!(counts.contains(&("e1g1".to_string(), 2059)));
+        assert!(counts.contains(&("e1c1".to_string(), 1887)));
+        assert!(counts.contains(&("d5e6".to_string(), 2241)));
+    }
+
+    #[test]
+    pub fn sample_perft_1() {
+        let depth = 5;
+        let expected_move_count = 4865609;
+
+        // rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 ;D1 20 ;D2 400 ;D3 8902 ;D4 197281 ;D5 4865609 ;D6 119060324
+
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let num_moves = perft::perft(&mut pos, depth);
+
+        assert_eq!(num_moves, expected_move_count);
+    }
+
+    #[test]
+    pub fn sample_perft_2() {
+        let depth = 6;
+        let expected_move_count = 158065;
+
+        // 8/8/3k4/3p4/8/3P4/3K4/8 w - - 0 1 ;D1 8 ;D2 61 ;D3 411 ;D4 3213 ;D5 21637 ;D6 158065
+
+        let fen = "8/8/3k4/3p4/8/3P4/3K4/8 w - - 0 1 ";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let num_moves = perft::perft(&mut pos, depth);
+
+        assert_eq!(num_moves, expected_move_count);
+    }
+
+    #[test]
+    pub fn sample_perft_3() {
+        let depth = 6;
+        let expected_move_count = 22823890;
+
+        // B6b/8/8/8/2K5/4k3/8/b6B w - - 0 1 ;D1 17 ;D2 278 ;D3 4607 ;D4 76778 ;D5 1320507 ;D6 22823890
+
+        let fen = "B6b/8/8/8/2K5/4k3/8/b6B w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let num_moves = perft::perft(&mut pos, depth);
+
+        assert_eq!(num_moves, expected_move_count);
+    }
+
+    #[test]
+    pub fn sample_perft_4() {
+        let depth = 4;
+        let expected_move_count = 771461;
+
+        // rooks on the edge ranks, with the kings in between
+        // R6r/8/8/2K5/5k2/8/8/r6R w - - 0 1 ;D1 36 ;D2 1027 ;D3 29215 ;D4 771461 ;D5 20506480 ;D6 525169084
+
+        let fen = "R6r/8/8/2K5/5k2/8/8/r6R w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let num_moves = perft::perft(&mut pos, depth);
+
+        assert_eq!(num_moves, expected_move_count);
+    }
+
+    #[test]
+    pub fn sample_perft_5() {
+        let depth = 5;
+        let expected_move_count = 674624;
+
+        // horizontal pins, including an en passant capture that exposes the king
+        // 8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1 ;D1 14 ;D2 191 ;D3 2812 ;D4 43238 ;D5 674624
+
+        let fen = "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let num_moves = perft::perft(&mut pos, depth);
+
+        assert_eq!(num_moves, expected_move_count);
+    }
+
+    #[test]
+    pub fn sample_perft_chess960() {
+        let depth = 4;
+        let expected_move_count = 667366;
+
+        // Chess960, with the castling rooks given by file (Shredder-FEN)
+        // 2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9 ;D1 21 ;D2 807 ;D3 18002 ;D4 667366 ;D5 16253601
+
+        let fen = "2nnrbkr/p1qppppp/8/1ppb4/6PP/3PP3/PPP2P2/BQNNRBKR w HEhe - 1 9";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let num_moves = perft::perft(&mut pos, depth);
+
+        assert_eq!(num_moves, expected_move_count);
+    }
+
+    #[test]
+    pub fn sample_perft_dfrc() {
+        let depth = 4;
+        let expected_move_count = 316066;
+
+        // DFRC, with the sides set up differently, and the kings between
+        // rooks on different files
+        // 2rkr3/pppppppp/8/8/8/8/PPPPPPPP/1R1K2R1 w GBec - 0 1 ;D1 25 ;D2 575 ;D3 13921 ;D4 316066 ;D5 7483273
+
+        let fen = "2rkr3/pppppppp/8/8/8/8/PPPPPPPP/1R1K2R1 w GBec - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let num_moves = perft::perft(&mut pos, depth);
+
+        assert_eq!(num_moves, expected_move_count);
+    }
+
+    #[test]
+    pub fn divide_chess960_castling_with_rook_on_b_file() {
+        let fen = "1r2k2r/8/8/8/8/8/8/1R2K2R w KQkq - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let divide = perft::divide(&mut pos, 3);
+        assert_eq!(divide.iter().map(|(_, nodes)| nodes).sum::<u64>(), 13600);
+
+        let counts: Vec<(String, u64)> = divide
+            .iter()
+            .map(|(mv, nodes)| (mv.to_uci_string_chess960(&castle_permissions), *nodes))
+            .collect();
+        // as shakmaty counts them
+        assert!(counts.contains(&("e1h1".to_string(), 449)));
+        assert!(counts.contains(&("e1b1".to_string(), 431)));
+    }
+
+    #[test]
+    pub fn perft_stats_start_position() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let stats = perft::perft_with_stats(&mut pos, 4);
+
+        let expected = PerftStats {
+            nodes: 197281,
+            captures: 1576,
+            en_passants: 0,
+            castles: 0,
+            promotions: 0,
+            checks: 469,
+            checkmates: 8,
+        };
+        assert_eq!(stats, expected);
+    }
+
+    #[test]
+    pub fn perft_stats_kiwipete() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let stats = perft::perft_with_stats(&mut pos, 3);
+
+        let expected = PerftStats {
+            nodes: 97862,
+            captures: 17102,
+            en_passants: 45,
+            castles: 3162,
+            promotions: 0,
+            checks: 993,
+            checkmates: 1,
+        };
+        assert_eq!(stats, expected);
+    }
+}
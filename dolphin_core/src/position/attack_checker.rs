@@ -13,6 +13,9 @@ impl AttackChecker {
         AttackChecker::default()
     }
 
+    // Attackers are checked cheapest first (pawns, knights, king) since they
+    // only need a mask lookup. Sliders are only considered if they share a
+    // line with the square, and then only need the in-between squares checking.
     pub fn is_sq_attacked(
         &self,
         occ_masks: &OccupancyMasks,
@@ -20,119 +23,23 @@ impl AttackChecker {
         sq: &Square,
         attacking_side: &Colour,
     ) -> bool {
-        match attacking_side {
-            Colour::White => {
-                let pawn_bb = board.get_piece_bitboard(&Piece::Pawn, &Colour::White);
-                let wp_attacking_square =
-                    occ_masks.get_occ_mask_pawns_attacking_sq(&Colour::White, &sq);
-                if !(pawn_bb & wp_attacking_square).is_empty() {
-                    return true;
-                }
-
-                let knight_bb = board.get_piece_bitboard(&Piece::Knight, &Colour::White);
-                for from_sq in knight_bb.iterator() {
-                    if occ_masks.get_occupancy_mask_knight(&from_sq).is_set(&sq) {
-                        return true;
-                    }
-                }
-
-                let horiz_vert_bb = board.get_piece_bitboard(&Piece::Rook, &Colour::White)
-                    | board.get_piece_bitboard(&Piece::Queen, &Colour::White);
-
-                let all_pce_bb = board.get_bitboard();
-                // check to see if the sqaure being attacked shares a rank or file
-                // with any of the rooks or queens before doing a detailed analysis
-                // of potential blocking pieces
-                let horiz_vert_sq_mask =
-                    occ_masks.get_vertical_mask(&sq) | occ_masks.get_horizontal_mask(&sq);
-                let shares_rank_or_file = !(horiz_vert_bb & horiz_vert_sq_mask).is_empty();
-                if shares_rank_or_file
-                    && self.is_horizontal_or_vertical_attacking(
-                        occ_masks,
-                        &all_pce_bb,
-                        &horiz_vert_bb,
-                        sq,
-                    )
-                {
-                    return true;
-                }
-
-                let diag_bb = board.get_piece_bitboard(&Piece::Bishop, &Colour::White)
-                    | board.get_piece_bitboard(&Piece::Queen, &Colour::White);
-                // check to see if the sqaure being attacked shares a diagonal
-                // with any of the bishops or queens before doing a detailed analysis
-                // of potential blocking pieces
-                let sq_mask =
-                    occ_masks.get_diagonal_mask(&sq) | occ_masks.get_antidiagonal_mask(&sq);
-                if !(sq_mask & diag_bb).is_empty() {
-                    // possible attack, check for blocking pieces
-                    if self.is_diagonally_attacked(occ_masks, sq, &diag_bb, &all_pce_bb) {
-                        return true;
-                    }
-                }
-
-                let king_sq = board.get_king_sq(&Colour::White);
-                if occ_masks.get_occupancy_mask_king(&king_sq).is_set(&sq) {
-                    return true;
-                }
-            }
-            Colour::Black => {
-                let pawn_bb = board.get_piece_bitboard(&Piece::Pawn, &Colour::Black);
-                let bp_attacking_square =
-                    occ_masks.get_occ_mask_pawns_attacking_sq(&Colour::Black, &sq);
-                if !(pawn_bb & bp_attacking_square).is_empty() {
-                    return true;
-                }
-
-                let knight_bb = board.get_piece_bitboard(&Piece::Knight, &Colour::Black);
-                for from_sq in knight_bb.iterator() {
-                    if occ_masks.get_occupancy_mask_knight(&from_sq).is_set(&sq) {
-                        return true;
-                    }
-                }
-
-                let horiz_vert_bb = board.get_piece_bitboard(&Piece::Rook, &Colour::Black)
-                    | board.get_piece_bitboard(&Piece::Queen, &Colour::Black);
-
-                let all_pce_bb = board.get_bitboard();
-                // check to see if the sqaure being attacked shares a rank or file
-                // with any of the rooks or queens before doing a detailed analysis
-                // of potential blocking pieces
-                let horiz_vert_sq_mask =
-                    occ_masks.get_vertical_mask(&sq) | occ_masks.get_horizontal_mask(&sq);
-                let shares_rank_or_file = !(horiz_vert_bb & horiz_vert_sq_mask).is_empty();
-                if shares_rank_or_file
-                    && self.is_horizontal_or_vertical_attacking(
-                        occ_masks,
-                        &all_pce_bb,
-                        &horiz_vert_bb,
-                        sq,
-                    )
-                {
-                    return true;
-                }
+        let pawn_bb = board.get_piece_bitboard(&Piece::Pawn, attacking_side);
+        if !(pawn_bb & occ_masks.get_occ_mask_pawns_attacking_sq(attacking_side, sq)).is_empty() {
+            return true;
+        }
 
-                let diag_bb = board.get_piece_bitboard(&Piece::Bishop, &Colour::Black)
-                    | board.get_piece_bitboard(&Piece::Queen, &Colour::Black);
-                // check to see if the sqaure being attacked shares a diagonal
-                // with any of the bishops or queens before doing a detailed analysis
-                // of potential blocking pieces
-                let sq_mask =
-                    occ_masks.get_diagonal_mask(&sq) | occ_masks.get_antidiagonal_mask(&sq);
-                if !(sq_mask & diag_bb).is_empty() {
-                    // possible attack, check for blocking pieces
-                    if self.is_diagonally_attacked(occ_masks, sq, &diag_bb, &all_pce_bb) {
-                        return true;
-                    }
-                }
+        // knight and king masks are symmetric, so look from the square being attacked
+        let knight_bb = board.get_piece_bitboard(&Piece::Knight, attacking_side);
+        if !(knight_bb & occ_masks.get_occupancy_mask_knight(sq)).is_empty() {
+            return true;
+        }
 
-                let king_sq = board.get_king_sq(&Colour::Black);
-                if occ_masks.get_occupancy_mask_king(&king_sq).is_set(&sq) {
-                    return true;
-                }
-            }
+        let king_bb = board.get_piece_bitboard(&Piece::King, attacking_side);
+        if !(king_bb & occ_masks.get_occupancy_mask_king(sq)).is_empty() {
+            return true;
         }
-        false
+
+        self.is_attacked_by_slider(occ_masks, board, sq, attacking_side)
     }
 
     pub fn is_castle_squares_attacked(
@@ -142,70 +49,84 @@ impl AttackChecker {
         sq_array: &[Square],
         attacking_side: &Colour,
     ) -> bool {
+        let mut sq_bb = Bitboard::default();
         for sq in sq_array {
-            if self.is_sq_attacked(occ_masks, board, sq, &attacking_side) {
-                return true;
-            }
+            sq_bb.set_bit(sq);
         }
-
-        false
+        self.is_any_sq_attacked(occ_masks, board, &sq_bb, attacking_side)
     }
 
-    fn is_horizontal_or_vertical_attacking(
+    // Checks a set of squares in one pass, building the squares attacked by
+    // all the pawns, knights and king once rather than per square.
+    pub fn is_any_sq_attacked(
         &self,
         occ_masks: &OccupancyMasks,
-        all_piece_bb: &Bitboard,
-        attack_pce_bb: &Bitboard,
-        attack_sq: &Square,
+        board: &Board,
+        sq_bb: &Bitboard,
+        attacking_side: &Colour,
     ) -> bool {
-        for pce_sq in attack_pce_bb.iterator() {
-            if pce_sq.same_rank(attack_sq) || pce_sq.same_file(attack_sq) {
-                // potentially attacking
-                let blocking_pces = occ_masks.get_inbetween_squares(&pce_sq, &attack_sq);
-                if (blocking_pces & *all_piece_bb).is_empty() {
-                    // no blocking pieces, attacked
-                    return true;
-                }
-            }
+        let pawn_bb = board.get_piece_bitboard(&Piece::Pawn, attacking_side);
+        let pawn_attacks = match attacking_side {
+            Colour::White => pawn_bb.north_east() | pawn_bb.north_west(),
+            Colour::Black => pawn_bb.south_east() | pawn_bb.south_west(),
+        };
+        if !(pawn_attacks & *sq_bb).is_empty() {
+            return true;
+        }
+
+        let mut non_slider_attacks =
+            occ_masks.get_occupancy_mask_king(&board.king_square(attacking_side));
+        for from_sq in board.squares_of(&Piece::Knight, attacking_side) {
+            non_slider_attacks |= occ_masks.get_occupancy_mask_knight(&from_sq);
+        }
+        if !(non_slider_attacks & *sq_bb).is_empty() {
+            return true;
         }
-        false
+
+        sq_bb
+            .iterator()
+            .any(|sq| self.is_attacked_by_slider(occ_masks, board, &sq, attacking_side))
     }
 
-    fn is_diagonally_attacked(
+    fn is_attacked_by_slider(
         &self,
         occ_masks: &OccupancyMasks,
-        attack_sq: &Square,
-        diag_bb: &Bitboard,
-        all_pce_bb: &Bitboard,
+        board: &Board,
+        sq: &Square,
+        attacking_side: &Colour,
     ) -> bool {
-        for pce_sq in diag_bb.iterator() {
-            // diagonal mask will also work for queen
-            if occ_masks
-                .get_occupancy_mask_bishop(&pce_sq)
-                .is_set(&attack_sq)
-            {
-                // potentially attacking....ie, sharing a diagonal
-                let blocking_pces = occ_masks.get_inbetween_squares(&pce_sq, &attack_sq);
-                if (blocking_pces & *all_pce_bb).is_empty() {
-                    // no blocking pieces, attacked
-                    return true;
-                }
-            }
+        let queen_bb = board.get_piece_bitboard(&Piece::Queen, attacking_side);
+
+        let horiz_vert_bb = board.get_piece_bitboard(&Piece::Rook, attacking_side) | queen_bb;
+        let rank_file_mask = occ_masks.get_vertical_mask(sq) | occ_masks.get_horizontal_mask(sq);
+
+        let diag_bb = board.get_piece_bitboard(&Piece::Bishop, attacking_side) | queen_bb;
+        let diag_mask = occ_masks.get_occupancy_mask_bishop(sq);
+
+        // only sliders sharing a line with the square can attack it
+        let candidates =
+            ((horiz_vert_bb & rank_file_mask) | (diag_bb & diag_mask)) & !Bitboard::from_square(sq);
+        if candidates.is_empty() {
+            return false;
         }
 
-        false
+        let all_pce_bb = board.occupied();
+        candidates.iterator().any(|pce_sq| {
+            let blocking_pces = occ_masks.get_inbetween_squares(&pce_sq, sq);
+            (blocking_pces & all_pce_bb).is_empty()
+        })
     }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use crate::board::bitboard::Bitboard;
     use crate::board::colour::Colour;
     use crate::board::occupancy_masks::OccupancyMasks;
     use crate::board::square::*;
     use crate::io::fen;
     use crate::position::attack_checker::AttackChecker;
     use crate::position::game_position::Position;
-    use crate::position::zobrist_keys::ZobristKeys;
 
     #[test]
     pub fn is_attacked_by_white_pawn() {
@@ -213,8 +134,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -223,17 +143,11 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
-        assert!(attack_checker.is_sq_attacked(
-            &occ_masks,
-            pos.board(),
-            &Square::G5,
-            &Colour::White
-        ));
+        assert!(attack_checker.is_sq_attacked(occ_masks, pos.board(), &Square::G5, &Colour::White));
     }
 
     #[test]
@@ -242,8 +156,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -252,16 +165,10 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
-        assert!(attack_checker.is_sq_attacked(
-            &occ_masks,
-            pos.board(),
-            &Square::H4,
-            &Colour::Black
-        ));
+        assert!(attack_checker.is_sq_attacked(occ_masks, pos.board(), &Square::H4, &Colour::Black));
     }
 
     #[test]
@@ -270,8 +177,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -280,17 +186,11 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
-        assert!(attack_checker.is_sq_attacked(
-            &occ_masks,
-            pos.board(),
-            &Square::E5,
-            &Colour::White
-        ));
+        assert!(attack_checker.is_sq_attacked(occ_masks, pos.board(), &Square::E5, &Colour::White));
     }
 
     #[test]
@@ -299,8 +199,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -309,17 +208,11 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
-        assert!(attack_checker.is_sq_attacked(
-            &occ_masks,
-            pos.board(),
-            &Square::E3,
-            &Colour::Black
-        ));
+        assert!(attack_checker.is_sq_attacked(occ_masks, pos.board(), &Square::E3, &Colour::Black));
     }
 
     #[test]
@@ -328,8 +221,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -338,17 +230,11 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
-        assert!(attack_checker.is_sq_attacked(
-            &occ_masks,
-            pos.board(),
-            &Square::E5,
-            &Colour::White
-        ));
+        assert!(attack_checker.is_sq_attacked(occ_masks, pos.board(), &Square::E5, &Colour::White));
     }
 
     #[test]
@@ -357,8 +243,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -367,17 +252,11 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
-        assert!(attack_checker.is_sq_attacked(
-            &occ_masks,
-            pos.board(),
-            &Square::E3,
-            &Colour::Black
-        ));
+        assert!(attack_checker.is_sq_attacked(occ_masks, pos.board(), &Square::E3, &Colour::Black));
     }
 
     #[test]
@@ -386,8 +265,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -396,17 +274,11 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
-        assert!(attack_checker.is_sq_attacked(
-            &occ_masks,
-            pos.board(),
-            &Square::E5,
-            &Colour::White
-        ));
+        assert!(attack_checker.is_sq_attacked(occ_masks, pos.board(), &Square::E5, &Colour::White));
     }
 
     #[test]
@@ -415,8 +287,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -425,17 +296,11 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
-        assert!(attack_checker.is_sq_attacked(
-            &occ_masks,
-            pos.board(),
-            &Square::E3,
-            &Colour::Black
-        ));
+        assert!(attack_checker.is_sq_attacked(occ_masks, pos.board(), &Square::E3, &Colour::Black));
     }
 
     #[test]
@@ -444,8 +309,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -454,17 +318,11 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
-        assert!(attack_checker.is_sq_attacked(
-            &occ_masks,
-            pos.board(),
-            &Square::E5,
-            &Colour::White
-        ));
+        assert!(attack_checker.is_sq_attacked(occ_masks, pos.board(), &Square::E5, &Colour::White));
     }
 
     #[test]
@@ -473,8 +331,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -483,17 +340,11 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
-        assert!(attack_checker.is_sq_attacked(
-            &occ_masks,
-            pos.board(),
-            &Square::E3,
-            &Colour::Black
-        ));
+        assert!(attack_checker.is_sq_attacked(occ_masks, pos.board(), &Square::E3, &Colour::Black));
     }
 
     #[test]
@@ -502,8 +353,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -512,17 +362,11 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
-        assert!(attack_checker.is_sq_attacked(
-            &occ_masks,
-            pos.board(),
-            &Square::B5,
-            &Colour::White
-        ));
+        assert!(attack_checker.is_sq_attacked(occ_masks, pos.board(), &Square::B5, &Colour::White));
     }
 
     #[test]
@@ -531,8 +375,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -541,17 +384,11 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
-        assert!(attack_checker.is_sq_attacked(
-            &occ_masks,
-            pos.board(),
-            &Square::F5,
-            &Colour::Black
-        ));
+        assert!(attack_checker.is_sq_attacked(occ_masks, pos.board(), &Square::F5, &Colour::Black));
     }
 
     #[test]
@@ -562,8 +399,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -572,13 +408,12 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::Black
@@ -593,8 +428,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -603,13 +437,12 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::Black
@@ -624,8 +457,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -634,13 +466,12 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::Black
@@ -655,8 +486,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -665,13 +495,12 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::Black
@@ -686,8 +515,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -696,13 +524,12 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::Black
@@ -717,8 +544,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -727,13 +553,12 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::Black
@@ -748,8 +573,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -758,13 +582,12 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::Black
@@ -779,8 +602,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -789,13 +611,12 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::White
@@ -810,8 +631,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -820,13 +640,12 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::White
@@ -841,8 +660,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -851,13 +669,12 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::White
@@ -872,8 +689,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -882,13 +698,12 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::White
@@ -903,8 +718,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -913,13 +727,12 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::White
@@ -934,8 +747,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -944,13 +756,12 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::White
@@ -965,8 +776,7 @@ pub mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let pos = Position::new(
@@ -975,16 +785,68 @@ pub mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         assert!(attack_checker.is_castle_squares_attacked(
-            &occ_masks,
+            occ_masks,
             pos.board(),
             &SQUARE_TO_CHECK,
             &Colour::White
         ));
     }
+
+    #[test]
+    pub fn is_any_sq_attacked_matches_single_square_queries() {
+        let fens = [
+            "rn2kbnr/pp1p1ppp/8/2p5/4q3/2P5/PP1P2PP/RNBQK2R b KQkq - 0 2",
+            "1b1kN3/Qp1P2p1/q2P1Nn1/PP3r2/3rPnb1/1p1pp3/B1P1P2B/R3K2R w KQ - 5 8",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        ];
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        for fen in fens {
+            let (board, _, _, _, _) = fen::decompose_fen(fen);
+
+            for colour in [Colour::White, Colour::Black] {
+                // every pair of adjacent squares
+                for i in 0..63 {
+                    let sq1 = Square::new(i).unwrap();
+                    let sq2 = Square::new(i + 1).unwrap();
+
+                    let mut sq_bb = Bitboard::default();
+                    sq_bb.set_bit(&sq1);
+                    sq_bb.set_bit(&sq2);
+
+                    let expected = attack_checker.is_sq_attacked(occ_masks, &board, &sq1, &colour)
+                        || attack_checker.is_sq_attacked(occ_masks, &board, &sq2, &colour);
+
+                    assert_eq!(
+                        attack_checker.is_any_sq_attacked(occ_masks, &board, &sq_bb, &colour),
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn is_sq_attacked_slider_blocked() {
+        let fen = "4k3/8/8/8/8/2n5/8/Q3K2r w - - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        // queen on a1 blocked by the knight on c3
+        assert!(attack_checker.is_sq_attacked(occ_masks, &board, &Square::B2, &Colour::White));
+        assert!(!attack_checker.is_sq_attacked(occ_masks, &board, &Square::D4, &Colour::White));
+
+        // rook on h1 blocked by the king on e1
+        assert!(attack_checker.is_sq_attacked(occ_masks, &board, &Square::F1, &Colour::Black));
+        assert!(!attack_checker.is_sq_attacked(occ_masks, &board, &Square::C1, &Colour::Black));
+    }
 }
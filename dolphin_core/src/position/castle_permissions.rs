@@ -1,23 +1,40 @@
+use crate::board::colour::Colour;
+use crate::board::file::File;
+use crate::board::rank::Rank;
+use crate::board::square::Square;
 use std::ops::{BitAnd, BitOr};
 
+// The castle permissions, along with the files the castling rooks started
+// on (a and h in standard chess). The rook files don't change during a
+// game, only the permissions are cleared.
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
-pub struct CastlePermission(u8);
+pub struct CastlePermission(u16);
 
 // Bit fields for CastlePermission
-// ---- ---X    White King
-// ---- --X-    White Queen
-// ---- -X--    Black King
-// ---- X---    Black Queen
+// ---- ---- ---- ---X    White King
+// ---- ---- ---- --X-    White Queen
+// ---- ---- ---- -X--    Black King
+// ---- ---- ---- X---    Black Queen
+// ---- ---- -XXX ----    White King rook file
+// ---- --XX X--- ----    White Queen rook file
+// ---X XX-- ---- ----    Black King rook file
+// XXX- ---- ---- ----    Black Queen rook file
 //
 #[rustfmt::skip]
-const MASK_WHITE_KING: u8 = 0b0000_0001;
-const MASK_WHITE_QUEEN: u8 = 0b0000_0010;
-const MASK_BLACK_KING: u8 = 0b0000_0100;
-const MASK_BLACK_QUEEN: u8 = 0b0000_1000;
+const MASK_WHITE_KING: u16 = 0b0000_0000_0000_0001;
+const MASK_WHITE_QUEEN: u16 = 0b0000_0000_0000_0010;
+const MASK_BLACK_KING: u16 = 0b0000_0000_0000_0100;
+const MASK_BLACK_QUEEN: u16 = 0b0000_0000_0000_1000;
 
-const MASK_WHITE: u8 = 0b0000_0011;
-const MASK_BLACK: u8 = 0b0000_1100;
+const MASK_WHITE: u16 = 0b0000_0000_0000_0011;
+const MASK_BLACK: u16 = 0b0000_0000_0000_1100;
+const MASK_PERMS: u16 = 0b0000_0000_0000_1111;
 
+const SHIFT_ROOK_FILES: u16 = 4;
+const BITS_PER_ROOK_FILE: u16 = 3;
+const MASK_ROOK_FILE: u16 = 0b111;
+
+#[derive(Clone, Copy)]
 enum AsOffset {
     WhiteKing,
     WhiteQueen,
@@ -25,13 +42,78 @@ enum AsOffset {
     BlackQueen,
 }
 
+impl AsOffset {
+    const fn rook_file_shift(self) -> u16 {
+        SHIFT_ROOK_FILES + self as u16 * BITS_PER_ROOK_FILE
+    }
+}
+
 impl CastlePermission {
     pub const NUM_CASTLE_PERMS: usize = 4;
 
-    pub const NO_CASTLE_PERMS_AVAIL: CastlePermission = CastlePermission(0);
+    pub const NO_CASTLE_PERMS_AVAIL: CastlePermission = CastlePermission(
+        (7 << AsOffset::WhiteKing.rook_file_shift()) | (7 << AsOffset::BlackKing.rook_file_shift()),
+    );
+
+    pub const fn has_castle_permission(&self) -> bool {
+        self.0 & MASK_PERMS != 0
+    }
 
-    pub fn has_castle_permission(&self) -> bool {
-        *self != CastlePermission::NO_CASTLE_PERMS_AVAIL
+    pub fn white_king_rook_square(&self) -> Square {
+        self.rook_square(AsOffset::WhiteKing)
+    }
+
+    pub fn white_queen_rook_square(&self) -> Square {
+        self.rook_square(AsOffset::WhiteQueen)
+    }
+
+    pub fn black_king_rook_square(&self) -> Square {
+        self.rook_square(AsOffset::BlackKing)
+    }
+
+    pub fn black_queen_rook_square(&self) -> Square {
+        self.rook_square(AsOffset::BlackQueen)
+    }
+
+    // sets the files the castling rooks start on (eg, for Chess960)
+    pub fn set_rook_files(&mut self, colour: &Colour, king_side: &File, queen_side: &File) {
+        let (king_offset, queen_offset) = match colour {
+            Colour::White => (AsOffset::WhiteKing, AsOffset::WhiteQueen),
+            Colour::Black => (AsOffset::BlackKing, AsOffset::BlackQueen),
+        };
+        self.set_rook_file(king_offset, king_side);
+        self.set_rook_file(queen_offset, queen_side);
+    }
+
+    // clears the permission for a castling rook that has moved from, or been
+    // captured on, the square
+    pub fn clear_for_rook_square(&mut self, sq: &Square) {
+        for offset in [
+            AsOffset::WhiteKing,
+            AsOffset::WhiteQueen,
+            AsOffset::BlackKing,
+            AsOffset::BlackQueen,
+        ] {
+            if self.rook_square(offset) == *sq {
+                self.0 &= !(1 << offset as u16);
+            }
+        }
+    }
+
+    fn rook_square(&self, offset: AsOffset) -> Square {
+        let rank = match offset {
+            AsOffset::WhiteKing | AsOffset::WhiteQueen => Rank::R1,
+            AsOffset::BlackKing | AsOffset::BlackQueen => Rank::R8,
+        };
+        let file_num = (self.0 >> offset.rook_file_shift()) & MASK_ROOK_FILE;
+        let file = File::new(file_num as u8).expect("Invalid rook file");
+        Square::from_rank_file(&rank, &file).expect("Invalid rook square")
+    }
+
+    fn set_rook_file(&mut self, offset: AsOffset, file: &File) {
+        let shift = offset.rook_file_shift();
+        self.0 &= !(MASK_ROOK_FILE << shift);
+        self.0 |= (file.as_index() as u16) << shift;
     }
 
     pub fn set_black_king(&mut self) {
@@ -118,21 +200,63 @@ impl CastlePermission {
 impl BitAnd for CastlePermission {
     type Output = Self;
     fn bitand(self, other: Self) -> Self {
-        CastlePermission(self.0 & other.0)
+        // only the permissions are combined, the rook files are kept
+        CastlePermission(self.0 & (other.0 | !MASK_PERMS))
     }
 }
 
 impl BitOr for CastlePermission {
     type Output = Self;
     fn bitor(self, other: Self) -> Self {
-        CastlePermission(self.0 | other.0)
+        // only the permissions are combined, the rook files are kept
+        CastlePermission(self.0 | (other.0 & MASK_PERMS))
     }
 }
 
 #[cfg(test)]
 pub mod tests {
+    use crate::board::colour::Colour;
+    use crate::board::file::File;
+    use crate::board::square::Square;
     use crate::position::castle_permissions::CastlePermission;
 
+    #[test]
+    pub fn default_rook_squares_are_standard() {
+        let cp = CastlePermission::NO_CASTLE_PERMS_AVAIL;
+
+        assert_eq!(cp.white_king_rook_square(), Square::H1);
+        assert_eq!(cp.white_queen_rook_square(), Square::A1);
+        assert_eq!(cp.black_king_rook_square(), Square::H8);
+        assert_eq!(cp.black_queen_rook_square(), Square::A8);
+    }
+
+    #[test]
+    pub fn clear_for_rook_square_uses_rook_files() {
+        let mut cp = CastlePermission::NO_CASTLE_PERMS_AVAIL;
+        cp.set_white_king();
+        cp.set_white_queen();
+        cp.set_black_king();
+        cp.set_black_queen();
+
+        cp.clear_for_rook_square(&Square::E4);
+        cp.clear_for_rook_square(&Square::H8);
+        assert!(cp.is_white_king_set());
+        assert!(cp.is_white_queen_set());
+        assert!(!cp.is_black_king_set());
+        assert!(cp.is_black_queen_set());
+
+        // rooks starting on the b and g files
+        cp.set_rook_files(&Colour::White, &File::G, &File::B);
+        assert_eq!(cp.white_king_rook_square(), Square::G1);
+        assert_eq!(cp.white_queen_rook_square(), Square::B1);
+        assert_eq!(cp.black_king_rook_square(), Square::H8);
+        cp.clear_for_rook_square(&Square::A1);
+        assert!(cp.is_white_queen_set());
+        cp.clear_for_rook_square(&Square::B1);
+        assert!(!cp.is_white_queen_set());
+        assert!(cp.is_white_king_set());
+    }
+
     #[test]
     pub fn default_castle_permissisons_none_set() {
         let cp = CastlePermission::NO_CASTLE_PERMS_AVAIL;
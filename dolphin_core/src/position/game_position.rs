@@ -1,3 +1,4 @@
+use crate::board::bitboard::Bitboard;
 use crate::board::colour::Colour;
 use crate::board::file::File;
 use crate::board::game_board::Board;
@@ -5,12 +6,17 @@ use crate::board::occupancy_masks::OccupancyMasks;
 use crate::board::piece::Piece;
 use crate::board::rank::Rank;
 use crate::board::square::Square;
+use crate::io::fen;
 use crate::moves::mov::Move;
 use crate::moves::mov::MoveType;
+use crate::moves::move_gen::MoveGenerator;
+use crate::moves::move_list::MoveList;
 use crate::position::attack_checker::AttackChecker;
 use crate::position::castle_permissions::CastlePermission;
 use crate::position::move_counter::MoveCounter;
 use crate::position::position_history::PositionHistory;
+use crate::position::variant::Standard;
+use crate::position::variant::Variant;
 use crate::position::zobrist_keys::ZobristHash;
 use crate::position::zobrist_keys::ZobristKeys;
 use std::fmt;
@@ -23,21 +29,14 @@ pub enum MoveLegality {
     Illegal,
 }
 
-const CASTLE_SQUARES_KING_WHITE: [Square; 3] = [Square::E1, Square::F1, Square::G1];
-
-const CASTLE_SQUARES_QUEEN_WHITE: [Square; 3] = [Square::C1, Square::D1, Square::E1];
-
-const CASTLE_SQUARES_KING_BLACK: [Square; 3] = [Square::E8, Square::F8, Square::G8];
-
-const CASTLE_SQUARES_QUEEN_BLACK: [Square; 3] = [Square::C8, Square::D8, Square::E8];
-
 pub struct Position<'a> {
     board: Board,
-    position_history: Box<PositionHistory>,
+    // None for positions created by make_move_copied
+    position_history: Option<Box<PositionHistory>>,
     occ_masks: &'a OccupancyMasks,
-    zobrist_keys: &'a ZobristKeys,
     attack_checker: &'a AttackChecker,
     game_state: GameState,
+    variant: &'static dyn Variant,
 }
 
 #[derive(Debug, Eq, PartialEq, Clone, Copy)]
@@ -45,7 +44,8 @@ pub struct GameState {
     position_hash: ZobristHash,
     move_cntr: MoveCounter,
     side_to_move: Colour,
-    en_pass_sq: Option<Square>,
+    // square index (an Option<Square> takes 2 bytes)
+    en_pass_sq: u8,
     castle_perm: CastlePermission,
     fifty_move_cntr: u8,
 }
@@ -57,73 +57,122 @@ impl Default for GameState {
             position_hash: 0,
             move_cntr: MoveCounter::default(),
             fifty_move_cntr: 0,
-            en_pass_sq: None,
+            en_pass_sq: GameState::NO_EN_PASSANT_SQ,
             castle_perm: CastlePermission::NO_CASTLE_PERMS_AVAIL,
         }
     }
 }
 
 impl GameState {
+    const NO_EN_PASSANT_SQ: u8 = Square::NUM_SQUARES as u8;
+
     pub fn new() -> GameState {
         GameState::default()
     }
     pub fn get_zobrist_hash(&self) -> ZobristHash {
         self.position_hash
     }
+
+    fn en_pass_sq(&self) -> Option<Square> {
+        if self.en_pass_sq == GameState::NO_EN_PASSANT_SQ {
+            None
+        } else {
+            Square::new(self.en_pass_sq)
+        }
+    }
+
+    fn set_en_pass_sq(&mut self, sq: Option<Square>) {
+        self.en_pass_sq = match sq {
+            Some(sq) => sq.as_index() as u8,
+            None => GameState::NO_EN_PASSANT_SQ,
+        };
+    }
+}
+
+// What's saved in the position history when a move is made: only the parts
+// of the GameState that can't be recalculated when the move is taken back,
+// plus the move and any captured piece.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct UndoState {
+    position_hash: ZobristHash,
+    mv: Move,
+    en_pass_sq: u8,
+    castle_perm: CastlePermission,
+    fifty_move_cntr: u8,
+    capt_pce: Option<Piece>,
+    // false if the move didn't fit the position, so nothing was changed
+    applied: bool,
+}
+
+impl Default for UndoState {
+    fn default() -> Self {
+        UndoState {
+            position_hash: 0,
+            mv: Move::default(),
+            en_pass_sq: GameState::NO_EN_PASSANT_SQ,
+            castle_perm: CastlePermission::NO_CASTLE_PERMS_AVAIL,
+            fifty_move_cntr: 0,
+            capt_pce: None,
+            applied: true,
+        }
+    }
+}
+
+impl UndoState {
+    pub const fn position_hash(&self) -> ZobristHash {
+        self.position_hash
+    }
 }
 
 impl<'a> Position<'a> {
+    const FIFTY_MOVE_RULE_NUM_PLIES: u8 = 100;
+
     pub fn new(
         board: Board,
         castle_permissions: CastlePermission,
         move_counter: MoveCounter,
         en_passant_sq: Option<Square>,
         side_to_move: Colour,
-        zobrist_keys: &'a ZobristKeys,
         occupancy_masks: &'a OccupancyMasks,
         attack_checker: &'a AttackChecker,
     ) -> Position<'a> {
-        let game_state = GameState {
+        let mut game_state = GameState {
             side_to_move,
-            en_pass_sq: en_passant_sq,
             castle_perm: castle_permissions,
             move_cntr: move_counter,
+            fifty_move_cntr: u8::try_from(move_counter.half_move()).unwrap_or(u8::MAX),
             ..Default::default()
         };
+        game_state.set_en_pass_sq(en_passant_sq);
 
         let mut pos = Position {
             board,
             game_state,
-            position_history: PositionHistory::new(),
+            position_history: Some(PositionHistory::new()),
             occ_masks: occupancy_masks,
             attack_checker,
-            zobrist_keys,
+            variant: &Standard,
         };
 
         // generate position hash
-        pos.board.get_bitboard().iterator().for_each(|sq| {
+        pos.board.occupied().iterator().for_each(|sq| {
             if let Some((piece, colour)) = pos.board().get_piece_and_colour_on_square(&sq) {
-                pos.game_state.position_hash ^= pos.zobrist_keys.piece_square(&piece, &colour, &sq);
+                pos.game_state.position_hash ^=
+                    ZobristKeys::instance().piece_square(&piece, &colour, &sq);
             };
         });
 
-        pos.game_state.position_hash ^= pos.zobrist_keys.side();
-
-        if castle_permissions.is_black_king_set() {
-            pos.game_state.position_hash ^= pos.zobrist_keys.castle_permissions_black_king();
-        }
-        if castle_permissions.is_white_king_set() {
-            pos.game_state.position_hash ^= pos.zobrist_keys.castle_permissions_white_king();
-        }
-        if castle_permissions.is_black_queen_set() {
-            pos.game_state.position_hash ^= pos.zobrist_keys.castle_permissions_black_queen();
-        }
-        if castle_permissions.is_white_queen_set() {
-            pos.game_state.position_hash ^= pos.zobrist_keys.castle_permissions_white_queen();
+        // flip_side_to_move() toggles the side key, which is held while
+        // white is to move
+        if side_to_move == Colour::White {
+            pos.game_state.position_hash ^= ZobristKeys::instance().side();
         }
 
+        pos.game_state.position_hash ^= castle_permissions_hash(&castle_permissions);
+
         if let Some(_enp) = en_passant_sq {
-            pos.game_state.position_hash ^= pos.zobrist_keys.en_passant(&en_passant_sq.unwrap());
+            pos.game_state.position_hash ^=
+                ZobristKeys::instance().en_passant(&en_passant_sq.unwrap());
         }
 
         // validate position
@@ -135,6 +184,15 @@ impl<'a> Position<'a> {
         pos
     }
 
+    // the rules the position is played by, standard chess unless set
+    pub fn set_variant(&mut self, variant: &'static dyn Variant) {
+        self.variant = variant;
+    }
+
+    pub fn variant(&self) -> &'static dyn Variant {
+        self.variant
+    }
+
     pub fn side_to_move(&self) -> Colour {
         self.game_state.side_to_move
     }
@@ -143,22 +201,81 @@ impl<'a> Position<'a> {
         &self.board
     }
 
-    pub const fn en_passant_square(&self) -> Option<Square> {
-        self.game_state.en_pass_sq
+    pub fn en_passant_square(&self) -> Option<Square> {
+        self.game_state.en_pass_sq()
     }
 
-    pub const fn is_en_passant_active(&self) -> bool {
-        self.game_state.en_pass_sq.is_some()
+    pub fn is_en_passant_active(&self) -> bool {
+        self.game_state.en_pass_sq().is_some()
     }
 
     pub const fn castle_permissions(&self) -> CastlePermission {
         self.game_state.castle_perm
     }
 
+    // The side to move's castle move, if it has the permission and the
+    // squares the king and rook cross (and end up on) are empty, other than
+    // for the king and rook themselves. The king and rook can start on any
+    // file, as in Chess960, and end up where they would in standard chess.
+    // Whether the king crosses an attacked square is checked by make_move.
+    pub fn castle_move(&self, is_king_side: bool) -> Option<Move> {
+        let colour = self.side_to_move();
+        let cp = &self.game_state.castle_perm;
+        let (is_permitted, king_to_sq) = match (colour, is_king_side) {
+            (Colour::White, true) => (cp.is_white_king_set(), Square::G1),
+            (Colour::White, false) => (cp.is_white_queen_set(), Square::C1),
+            (Colour::Black, true) => (cp.is_black_king_set(), Square::G8),
+            (Colour::Black, false) => (cp.is_black_queen_set(), Square::C8),
+        };
+        if !is_permitted {
+            return None;
+        }
+
+        let king_from_sq = self
+            .board
+            .squares_of(&Piece::King, &colour)
+            .find(|sq| sq.rank() == king_to_sq.rank())?;
+        let (rook_from_sq, rook_to_sq) = self.castle_rook_squares(&king_to_sq);
+        if self.board.get_piece_and_colour_on_square(&rook_from_sq) != Some((Piece::Rook, colour)) {
+            return None;
+        }
+
+        let mut must_be_empty = self.back_rank_span(&king_from_sq, &king_to_sq)
+            | self.back_rank_span(&rook_from_sq, &rook_to_sq);
+        must_be_empty.clear_bit(&king_from_sq);
+        must_be_empty.clear_bit(&rook_from_sq);
+        if !(self.board.occupied() & must_be_empty).is_empty() {
+            return None;
+        }
+        Some(Move::encode_move_castle(&king_from_sq, &king_to_sq))
+    }
+
+    // the squares from one to the other on the same rank, both included
+    fn back_rank_span(&self, from_sq: &Square, to_sq: &Square) -> Bitboard {
+        self.occ_masks.get_inbetween_squares(from_sq, to_sq)
+            | Bitboard::from_square(from_sq)
+            | Bitboard::from_square(to_sq)
+    }
+
     pub const fn move_counter(&self) -> &MoveCounter {
         &self.game_state.move_cntr
     }
 
+    pub const fn fifty_move_counter(&self) -> u8 {
+        self.game_state.fifty_move_cntr
+    }
+
+    pub fn to_fen(&self) -> String {
+        fen::compose_fen(
+            self.board(),
+            self.side_to_move(),
+            &self.castle_permissions(),
+            self.en_passant_square(),
+            self.fifty_move_counter() as u16,
+            self.move_counter().full_move(),
+        )
+    }
+
     pub const fn position_hash(&self) -> ZobristHash {
         self.game_state.position_hash
     }
@@ -169,51 +286,228 @@ impl<'a> Position<'a> {
 
     pub fn flip_side_to_move(&mut self) {
         self.game_state.side_to_move = self.side_to_move().flip_side();
-        self.game_state.position_hash ^= self.zobrist_keys.side();
+        self.game_state.position_hash ^= ZobristKeys::instance().side();
+    }
+
+    // Whether the positions are the same for the repetition rule: the
+    // pieces, side to move, castling and en passant. Unlike ==, the move
+    // counters and history aren't compared. The position hash is made up
+    // of the same parts, so repetition detection and the TT can match on
+    // hashes alone.
+    pub fn same_position(&self, other: &Position) -> bool {
+        self.board == other.board
+            && self.side_to_move() == other.side_to_move()
+            && self.game_state.castle_perm == other.game_state.castle_perm
+            && self.game_state.en_pass_sq == other.game_state.en_pass_sq
     }
 
+    // Hashes of the earlier positions in the game, most recent first. Empty
+    // for positions created by make_move_copied, which don't keep a history.
+    pub fn hash_history(&self) -> impl Iterator<Item = ZobristHash> + '_ {
+        self.position_history
+            .iter()
+            .flat_map(|position_history| position_history.position_hashes())
+    }
+
+    // The current position is the same_position as one earlier in the game
     pub fn is_repetition(&self) -> bool {
+        let Some(position_history) = &self.position_history else {
+            return false;
+        };
+
+        // only positions since the last capture or pawn move can repeat
+        let num_moves = position_history.len();
         let start_offset =
-            self.move_counter().half_move() as usize - self.game_state.fifty_move_cntr as usize;
+            num_moves - usize::min(self.game_state.fifty_move_cntr as usize, num_moves);
 
-        self.position_history
-            .contains_position_hash(&self.position_hash(), start_offset)
+        position_history.contains_repeated_hash(&self.position_hash(), start_offset)
+    }
+
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.game_state.fifty_move_cntr >= Position::FIFTY_MOVE_RULE_NUM_PLIES
     }
 
     pub fn is_king_sq_attacked(&self) -> bool {
-        let king_sq = self.board.get_king_sq(&self.side_to_move());
+        let king_sq = self.board.king_square(&self.side_to_move());
         let opp_side = self.side_to_move().flip_side();
+        self.is_sq_attacked(&king_sq, &opp_side)
+    }
+
+    #[cfg(not(feature = "attack_table"))]
+    fn is_sq_attacked(&self, sq: &Square, attacking_side: &Colour) -> bool {
         self.attack_checker
-            .is_sq_attacked(self.occ_masks, self.board(), &king_sq, &opp_side)
+            .is_sq_attacked(self.occ_masks, self.board(), sq, attacking_side)
+    }
+
+    #[cfg(feature = "attack_table")]
+    fn is_sq_attacked(&self, sq: &Square, attacking_side: &Colour) -> bool {
+        self.board.attack_table().is_attacked_by(sq, attacking_side)
+    }
+
+    // Whether the move is one the move generator makes for this position,
+    // ignoring whether it leaves the king in check. Moves kept in tables
+    // (eg, the TT) can be stale or corrupt, so are checked before use.
+    pub fn is_pseudo_legal(&self, mv: &Move) -> bool {
+        if !self.is_move_applicable(mv) {
+            return false;
+        }
+        let mut move_list = MoveList::new();
+        MoveGenerator::new().generate_moves(self, &mut move_list);
+        move_list.iterator().any(|generated| generated == mv)
+    }
+
+    // As is_pseudo_legal, and the move doesn't leave the king in check
+    pub fn is_legal(&mut self, mv: &Move) -> bool {
+        if !self.is_pseudo_legal(mv) {
+            return false;
+        }
+        let legal = self.make_move(mv) == MoveLegality::Legal;
+        self.take_move();
+        legal
+    }
+
+    // Squares the piece on `from` can legally move to (eg, for highlighting
+    // in a GUI). Empty if there's no piece of the side to move on `from`.
+    pub fn legal_destinations(&mut self, from: &Square) -> Bitboard {
+        let mut move_list = MoveList::new();
+        MoveGenerator::new().generate_legal_moves(self, &mut move_list);
+
+        let mut destinations = Bitboard::default();
+        for mv in move_list.iterator().filter(|mv| mv.from_sq() == *from) {
+            destinations.set_bit(&mv.to_sq());
+        }
+        destinations
+    }
+
+    pub fn is_capture(&self, mv: &Move) -> bool {
+        self.captured_piece(mv).is_some()
+    }
+
+    pub fn captured_piece(&self, mv: &Move) -> Option<Piece> {
+        match mv.move_type() {
+            MoveType::Normal | MoveType::Promotion => self.board.get_piece_on_square(&mv.to_sq()),
+            MoveType::EnPassant => Some(Piece::Pawn),
+            MoveType::Castle => None,
+        }
+    }
+
+    // A move that doesn't fit the position (eg, from a corrupted TT entry or
+    // a bad UCI move) is Illegal and leaves the position unchanged. It still
+    // has to be taken back, as for any other illegal move.
+    pub fn make_move(&mut self, mv: &Move) -> MoveLegality {
+        let applied = self.is_move_applicable(mv);
+        let capt_pce = if applied {
+            self.captured_piece(mv)
+        } else {
+            None
+        };
+        let undo_state = UndoState {
+            position_hash: self.game_state.position_hash,
+            mv: *mv,
+            en_pass_sq: self.game_state.en_pass_sq,
+            castle_perm: self.game_state.castle_perm,
+            fifty_move_cntr: self.game_state.fifty_move_cntr,
+            capt_pce,
+            applied,
+        };
+        self.position_history
+            .as_mut()
+            .expect("Position has no history")
+            .push(undo_state);
+
+        if !applied {
+            return MoveLegality::Illegal;
+        }
+        self.apply_move(mv, &capt_pce)
     }
 
-    fn save_game_state(&mut self, mv: &Move) -> Option<Piece> {
+    // Checks the move could have come from the move generator for this
+    // position (ignoring whether the king is left in check), so applying it
+    // can't leave the board in a state the rest of the code can't handle.
+    fn is_move_applicable(&self, mv: &Move) -> bool {
+        let side_to_move = self.side_to_move();
+        let (from_sq, to_sq) = mv.decode_from_to_sq();
+
+        let Some((pce, colour)) = self.board.get_piece_and_colour_on_square(&from_sq) else {
+            return false;
+        };
+        if colour != side_to_move {
+            return false;
+        }
+        let target = self.board.get_piece_and_colour_on_square(&to_sq);
+        // a castling king can end up on its own square, or its rook's
+        if let (Some((target_pce, target_colour)), true) =
+            (target, mv.move_type() != MoveType::Castle)
+        {
+            if target_colour == side_to_move
+                || (target_pce == Piece::King && !self.variant.is_king_capturable())
+            {
+                return false;
+            }
+        }
+
         match mv.move_type() {
-            MoveType::Normal | MoveType::Promotion => {
-                let to_sq = mv.to_sq();
-                let capt_pce = self.board.get_piece_on_square(&to_sq);
-                self.position_history.push(&self.game_state, mv, &capt_pce);
-                return capt_pce;
+            MoveType::Normal => true,
+            MoveType::Promotion => {
+                let promotion_rank = match side_to_move {
+                    Colour::White => Rank::R8,
+                    Colour::Black => Rank::R1,
+                };
+                pce == Piece::Pawn && to_sq.rank() == promotion_rank
             }
             MoveType::EnPassant => {
-                self.position_history
-                    .push(&self.game_state, mv, &Some(Piece::Pawn));
-                return Some(Piece::Pawn);
+                let capt_sq = match side_to_move {
+                    Colour::White => to_sq.south(),
+                    Colour::Black => to_sq.north(),
+                };
+                pce == Piece::Pawn
+                    && target.is_none()
+                    && self.en_passant_square() == Some(to_sq)
+                    && capt_sq.and_then(|sq| self.board.get_piece_and_colour_on_square(&sq))
+                        == Some((Piece::Pawn, side_to_move.flip_side()))
             }
             MoveType::Castle => {
-                self.position_history.push(&self.game_state, mv, &None);
-                return None;
+                let is_king_side = match to_sq.file() {
+                    File::G => true,
+                    File::C => false,
+                    _ => return false,
+                };
+                pce == Piece::King && self.castle_move(is_king_side) == Some(*mv)
             }
         }
     }
 
-    pub fn make_move(&mut self, mv: &Move) -> MoveLegality {
-        let capt_pce = self.save_game_state(mv);
+    // Copy-make alternative to make_move/take_move, leaving this position
+    // untouched. The copy doesn't carry the position history, so it can't
+    // be taken back (just drop it) and can't detect repetitions itself.
+    pub fn make_move_copied(&self, mv: &Move) -> (Position<'a>, MoveLegality) {
+        let mut pos = Position {
+            board: self.board,
+            game_state: self.game_state,
+            position_history: None,
+            occ_masks: self.occ_masks,
+            attack_checker: self.attack_checker,
+            variant: self.variant,
+        };
+
+        if !pos.is_move_applicable(mv) {
+            return (pos, MoveLegality::Illegal);
+        }
+        let capt_pce = pos.captured_piece(mv);
+        let move_legality = pos.apply_move(mv, &capt_pce);
+        (pos, move_legality)
+    }
+
+    fn apply_move(&mut self, mv: &Move, capt_pce: &Option<Piece>) -> MoveLegality {
         let pce_to_move = self
             .board
             .get_piece_on_square(&mv.from_sq())
             .expect("Unepxected empty square");
-        self.update_move_counters(&capt_pce, &pce_to_move);
+        self.update_move_counters(capt_pce, &pce_to_move);
+
+        // before the rook moves, as it can block an attack on the king's path
+        let is_castle_through_check =
+            mv.move_type() == MoveType::Castle && self.is_castle_path_attacked(mv);
 
         match mv.move_type() {
             MoveType::Normal => self.do_normal_move(mv),
@@ -225,12 +519,21 @@ impl<'a> Position<'a> {
         // update some states based on the move
         self.update_en_passant_sq(mv, &pce_to_move);
         if self.game_state.castle_perm.has_castle_permission() {
-            self.update_castle_perms(mv, &pce_to_move, &capt_pce);
+            self.update_castle_perms(mv, &pce_to_move);
         }
 
-        let move_legality = self.get_move_legality(mv);
+        let move_legality = if is_castle_through_check {
+            MoveLegality::Illegal
+        } else {
+            self.get_move_legality()
+        };
 
         self.flip_side_to_move();
+
+        #[cfg(all(feature = "paranoid", debug_assertions))]
+        self.board
+            .assert_consistent(self.variant.is_king_capturable());
+
         move_legality
     }
 
@@ -250,9 +553,13 @@ impl<'a> Position<'a> {
         self.move_piece_on_board(&pce_to_move, &self.side_to_move(), &from_sq, &to_sq);
 
         if self.is_double_pawn_move(mv, &pce_to_move) {
+            // replacing the en passant square of an earlier double pawn move
+            if let Some(old_sq) = self.game_state.en_pass_sq() {
+                self.game_state.position_hash ^= ZobristKeys::instance().en_passant(&old_sq);
+            }
             let s = self.find_en_passant_sq(&mv.from_sq(), &self.side_to_move());
-            self.game_state.en_pass_sq = Some(s);
-            self.game_state.position_hash ^= self.zobrist_keys.en_passant(&s);
+            self.game_state.set_en_pass_sq(Some(s));
+            self.game_state.position_hash ^= ZobristKeys::instance().en_passant(&s);
         }
     }
 
@@ -296,11 +603,27 @@ impl<'a> Position<'a> {
     }
 
     pub fn take_move(&mut self) {
+        let undo_state = self
+            .position_history
+            .as_mut()
+            .expect("Can't take back a move made with make_move_copied")
+            .pop();
+        if !undo_state.applied {
+            return;
+        }
+
         self.flip_side_to_move();
+        let side_moved = self.game_state.side_to_move;
 
         // restore state
-        let (gs, mv, capt_pce) = self.position_history.pop();
-        self.game_state = gs;
+        self.game_state.position_hash = undo_state.position_hash;
+        self.game_state.en_pass_sq = undo_state.en_pass_sq;
+        self.game_state.castle_perm = undo_state.castle_perm;
+        self.game_state.fifty_move_cntr = undo_state.fifty_move_cntr;
+        self.game_state.move_cntr.decr_half_move(&side_moved);
+
+        let mv = undo_state.mv;
+        let capt_pce = undo_state.capt_pce;
 
         match mv.move_type() {
             MoveType::Normal => self.reverse_normal_move(&mv, &capt_pce),
@@ -308,6 +631,10 @@ impl<'a> Position<'a> {
             MoveType::EnPassant => self.reverse_en_passant_move(&mv),
             MoveType::Castle => self.reverse_castle_move(&mv),
         }
+
+        #[cfg(all(feature = "paranoid", debug_assertions))]
+        self.board
+            .assert_consistent(self.variant.is_king_capturable());
     }
 
     fn reverse_normal_move(&mut self, mv: &Move, capt_pce: &Option<Piece>) {
@@ -328,10 +655,6 @@ impl<'a> Position<'a> {
                 &mv.to_sq(),
             );
         }
-
-        if self.is_double_pawn_move(mv, &pce_moved) {
-            self.game_state.en_pass_sq = None;
-        }
     }
     fn reverse_promotion_move(&mut self, mv: &Move, capt_pce: &Option<Piece>) {
         // remove promoted piece
@@ -376,124 +699,74 @@ impl<'a> Position<'a> {
         }
     }
 
+    // Both pieces are taken off before either is put back, as in Chess960
+    // the king can end up on the rook's square, or the rook on the king's
     fn do_castle_move(&mut self, mv: &Move) {
         let colour = self.side_to_move();
 
-        let (from_sq, to_sq) = mv.decode_from_to_sq();
+        let (king_from_sq, king_to_sq) = mv.decode_from_to_sq();
+        let (rook_from_sq, rook_to_sq) = self.castle_rook_squares(&king_to_sq);
 
-        match (from_sq, to_sq) {
-            (Square::E1, Square::G1) => {
-                // white king castle
-                self.move_piece_on_board(&Piece::King, &Colour::White, &Square::E1, &Square::G1);
-                self.move_piece_on_board(&Piece::Rook, &Colour::White, &Square::H1, &Square::F1);
-            }
-            (Square::E8, Square::G8) => {
-                // black king castle
-                self.move_piece_on_board(&Piece::King, &Colour::Black, &Square::E8, &Square::G8);
-                self.move_piece_on_board(&Piece::Rook, &Colour::Black, &Square::H8, &Square::F8);
-            }
-            (Square::E1, Square::C1) => {
-                // white queen castle
-                self.move_piece_on_board(&Piece::King, &Colour::White, &Square::E1, &Square::C1);
-                self.move_piece_on_board(&Piece::Rook, &Colour::White, &Square::A1, &Square::D1);
-            }
-            (Square::E8, Square::C8) => {
-                // black queen castle
-                self.move_piece_on_board(&Piece::King, &Colour::Black, &Square::E8, &Square::C8);
-                self.move_piece_on_board(&Piece::Rook, &Colour::Black, &Square::A8, &Square::D8);
-            }
-            _ => {
-                eprintln!("Invalid Castle move");
-                process::exit(1);
-            }
-        }
+        self.remove_piece_from_board(&Piece::King, &colour, &king_from_sq);
+        self.remove_piece_from_board(&Piece::Rook, &colour, &rook_from_sq);
+        self.add_piece_to_board(&Piece::King, &colour, &king_to_sq);
+        self.add_piece_to_board(&Piece::Rook, &colour, &rook_to_sq);
 
         self.clear_castle_permissions_for_colour(&colour);
     }
 
     fn reverse_castle_move(&mut self, mv: &Move) {
-        let (from_sq, to_sq) = mv.decode_from_to_sq();
+        let colour = self.side_to_move();
 
-        match (from_sq, to_sq) {
-            (Square::E1, Square::G1) => {
-                // white king castle
-                self.board
-                    .move_piece(&Square::G1, &Square::E1, &Piece::King, &Colour::White);
-                self.board
-                    .move_piece(&Square::F1, &Square::H1, &Piece::Rook, &Colour::White);
-            }
-            (Square::E8, Square::G8) => {
-                // black king castle
-                self.board
-                    .move_piece(&Square::G8, &Square::E8, &Piece::King, &Colour::Black);
-                self.board
-                    .move_piece(&Square::F8, &Square::H8, &Piece::Rook, &Colour::Black);
-            }
-            (Square::E1, Square::C1) => {
-                // white queen castle
-                self.board
-                    .move_piece(&Square::C1, &Square::E1, &Piece::King, &Colour::White);
-                self.board
-                    .move_piece(&Square::D1, &Square::A1, &Piece::Rook, &Colour::White);
-            }
-            (Square::E8, Square::C8) => {
-                // black queen castle
-                self.board
-                    .move_piece(&Square::C8, &Square::E8, &Piece::King, &Colour::Black);
-                self.board
-                    .move_piece(&Square::D8, &Square::A8, &Piece::Rook, &Colour::Black);
-            }
+        let (king_from_sq, king_to_sq) = mv.decode_from_to_sq();
+        let (rook_from_sq, rook_to_sq) = self.castle_rook_squares(&king_to_sq);
+
+        self.board.remove_piece(&Piece::King, &colour, &king_to_sq);
+        self.board.remove_piece(&Piece::Rook, &colour, &rook_to_sq);
+        self.board.add_piece(&Piece::King, &colour, &king_from_sq);
+        self.board.add_piece(&Piece::Rook, &colour, &rook_from_sq);
+    }
+
+    // where the castling rook moves from and to, given where the king ends up
+    fn castle_rook_squares(&self, king_to_sq: &Square) -> (Square, Square) {
+        let cp = &self.game_state.castle_perm;
+        match *king_to_sq {
+            Square::G1 => (cp.white_king_rook_square(), Square::F1),
+            Square::C1 => (cp.white_queen_rook_square(), Square::D1),
+            Square::G8 => (cp.black_king_rook_square(), Square::F8),
+            Square::C8 => (cp.black_queen_rook_square(), Square::D8),
             _ => {
-                eprintln!("Invalid castle move");
+                eprintln!("Invalid Castle move");
                 process::exit(1);
             }
         }
     }
 
-    fn get_move_legality(&self, mv: &Move) -> MoveLegality {
-        // check if move results in king being in check
-        let king_sq = self.board().get_king_sq(&self.game_state.side_to_move);
-        let attacking_side = self.game_state.side_to_move.flip_side();
-
-        if self.attack_checker.is_sq_attacked(
+    // the squares the king crosses, from where it starts to where it ends up
+    fn is_castle_path_attacked(&self, mv: &Move) -> bool {
+        let (king_from_sq, king_to_sq) = mv.decode_from_to_sq();
+        self.attack_checker.is_any_sq_attacked(
             self.occ_masks,
             self.board(),
-            &king_sq,
-            &attacking_side,
-        ) {
-            return MoveLegality::Illegal;
-        }
+            &self.back_rank_span(&king_from_sq, &king_to_sq),
+            &self.side_to_move().flip_side(),
+        )
+    }
 
-        // check castle through attacked squares (or king was in check before the castle move)
-        if mv.move_type() == MoveType::Castle {
-            let squares_to_check = if mv.to_sq().file() == File::G {
-                match self.game_state.side_to_move {
-                    Colour::White => &CASTLE_SQUARES_KING_WHITE,
-                    Colour::Black => &CASTLE_SQUARES_KING_BLACK,
-                }
-            } else if mv.to_sq().file() == File::C {
-                match self.game_state.side_to_move {
-                    Colour::White => &CASTLE_SQUARES_QUEEN_WHITE,
-                    Colour::Black => &CASTLE_SQUARES_QUEEN_BLACK,
-                }
-            } else {
-                eprintln!("Invalid move");
-                process::exit(1);
-            };
+    fn get_move_legality(&self) -> MoveLegality {
+        // there's no check when the king can be captured
+        if self.variant.is_king_capturable() {
+            return MoveLegality::Legal;
+        }
 
-            let is_invalid_castle = self.attack_checker.is_castle_squares_attacked(
-                self.occ_masks,
-                self.board(),
-                squares_to_check,
-                &attacking_side,
-            );
+        // check if move results in king being in check
+        let king_sq = self.board().king_square(&self.game_state.side_to_move);
+        let attacking_side = self.game_state.side_to_move.flip_side();
 
-            if is_invalid_castle {
-                return MoveLegality::Illegal;
-            } else {
-                return MoveLegality::Legal;
-            }
+        if self.is_sq_attacked(&king_sq, &attacking_side) {
+            return MoveLegality::Illegal;
         }
+
         MoveLegality::Legal
     }
 
@@ -514,66 +787,50 @@ impl<'a> Position<'a> {
 
     fn update_en_passant_sq(&mut self, mv: &Move, pce_moved: &Piece) {
         // clear en passant
-        if self.game_state.en_pass_sq.is_some() && !self.is_double_pawn_move(mv, pce_moved) {
-            self.game_state.position_hash ^= self
-                .zobrist_keys
-                .en_passant(&self.game_state.en_pass_sq.unwrap());
-            self.game_state.en_pass_sq = None;
+        if self.game_state.en_pass_sq().is_some() && !self.is_double_pawn_move(mv, pce_moved) {
+            self.game_state.position_hash ^=
+                ZobristKeys::instance().en_passant(&self.game_state.en_pass_sq().unwrap());
+            self.game_state.set_en_pass_sq(None);
         }
     }
 
     // remove castle permissions based on the move
-    fn update_castle_perms(&mut self, mv: &Move, pce_moved: &Piece, capt_pce: &Option<Piece>) {
+    fn update_castle_perms(&mut self, mv: &Move, pce_moved: &Piece) {
         if mv.move_type() == MoveType::Castle {
             // permissions already adjusted when move made
             return;
         }
 
-        // check if rook has just been captured
-        if *capt_pce == Some(Piece::Rook) {
-            match mv.to_sq() {
-                Square::A1 => self.game_state.castle_perm.clear_queen_white(),
-                Square::H1 => self.game_state.castle_perm.clear_king_white(),
-                Square::A8 => self.game_state.castle_perm.clear_queen_black(),
-                Square::H8 => self.game_state.castle_perm.clear_king_black(),
-                _ => (),
-            }
-        }
+        let old_hash = castle_permissions_hash(&self.game_state.castle_perm);
 
-        // check if king or rook have moved
-        match pce_moved {
-            Piece::King => match self.side_to_move() {
+        // a move from or to a castling rook's start square means the rook
+        // has moved or been captured
+        self.game_state
+            .castle_perm
+            .clear_for_rook_square(&mv.from_sq());
+        self.game_state
+            .castle_perm
+            .clear_for_rook_square(&mv.to_sq());
+
+        if *pce_moved == Piece::King {
+            match self.side_to_move() {
                 Colour::White => self.game_state.castle_perm.clear_white_king_and_queen(),
                 Colour::Black => self.game_state.castle_perm.clear_black_king_and_queen(),
-            },
-            Piece::Rook => match self.side_to_move() {
-                Colour::White => {
-                    match mv.from_sq() {
-                        Square::A1 => self.game_state.castle_perm.clear_queen_white(),
-                        Square::H1 => self.game_state.castle_perm.clear_king_white(),
-                        _ => (),
-                    };
-                }
-                Colour::Black => {
-                    match mv.from_sq() {
-                        Square::A8 => self.game_state.castle_perm.clear_queen_black(),
-                        Square::H8 => self.game_state.castle_perm.clear_king_black(),
-                        _ => (),
-                    };
-                }
-            },
-            _ => (),
+            }
         }
+
+        self.game_state.position_hash ^=
+            old_hash ^ castle_permissions_hash(&self.game_state.castle_perm);
     }
 
     fn remove_piece_from_board(&mut self, pce: &Piece, colour: &Colour, sq: &Square) {
         self.board.remove_piece(&pce, &colour, &sq);
-        self.game_state.position_hash ^= self.zobrist_keys.piece_square(&pce, &colour, &sq);
+        self.game_state.position_hash ^= ZobristKeys::instance().piece_square(&pce, &colour, &sq);
     }
 
     fn add_piece_to_board(&mut self, pce: &Piece, colour: &Colour, sq: &Square) {
         self.board.add_piece(&pce, &colour, &sq);
-        self.game_state.position_hash ^= self.zobrist_keys.piece_square(&pce, &colour, &sq);
+        self.game_state.position_hash ^= ZobristKeys::instance().piece_square(&pce, &colour, &sq);
     }
 
     fn move_piece_on_board(
@@ -583,37 +840,52 @@ impl<'a> Position<'a> {
         from_sq: &Square,
         to_sq: &Square,
     ) {
-        self.game_state.position_hash ^= self.zobrist_keys.piece_square(&pce, &colour, &from_sq);
-        self.game_state.position_hash ^= self.zobrist_keys.piece_square(&pce, &colour, &to_sq);
+        self.game_state.position_hash ^=
+            ZobristKeys::instance().piece_square(&pce, &colour, &from_sq);
+        self.game_state.position_hash ^=
+            ZobristKeys::instance().piece_square(&pce, &colour, &to_sq);
         self.board.move_piece(&from_sq, &to_sq, &pce, &colour);
     }
 
     fn update_move_counters(&mut self, capt_pce: &Option<Piece>, pce_moved: &Piece) {
-        let full_move_incr = self.game_state.move_cntr.incr_half_move();
-
-        if full_move_incr {
-            // handle 50 move rule
-            if capt_pce.is_some() || *pce_moved == Piece::Pawn {
-                self.game_state.fifty_move_cntr = 0;
-            } else {
-                self.game_state.fifty_move_cntr += 1;
-            }
+        let side_moved = self.game_state.side_to_move;
+        self.game_state.move_cntr.incr_half_move(&side_moved);
+
+        // handle 50 move rule (counted in half-moves since the last capture or pawn move)
+        if capt_pce.is_some() || *pce_moved == Piece::Pawn {
+            self.game_state.fifty_move_cntr = 0;
+        } else {
+            self.game_state.fifty_move_cntr = self.game_state.fifty_move_cntr.saturating_add(1);
         }
     }
     fn clear_castle_permissions_for_colour(&mut self, col: &Colour) {
+        let old_hash = castle_permissions_hash(&self.game_state.castle_perm);
         match col {
-            Colour::White => {
-                self.game_state.castle_perm.clear_white_king_and_queen();
-                self.game_state.position_hash ^= self.zobrist_keys.castle_permissions_white_king();
-                self.game_state.position_hash ^= self.zobrist_keys.castle_permissions_white_queen();
-            }
-            Colour::Black => {
-                self.game_state.castle_perm.clear_black_king_and_queen();
-                self.game_state.position_hash ^= self.zobrist_keys.castle_permissions_black_king();
-                self.game_state.position_hash ^= self.zobrist_keys.castle_permissions_black_queen();
-            }
+            Colour::White => self.game_state.castle_perm.clear_white_king_and_queen(),
+            Colour::Black => self.game_state.castle_perm.clear_black_king_and_queen(),
         }
+        self.game_state.position_hash ^=
+            old_hash ^ castle_permissions_hash(&self.game_state.castle_perm);
+    }
+}
+
+// the hash keys of the castling rights that are held
+fn castle_permissions_hash(castle_perm: &CastlePermission) -> ZobristHash {
+    let keys = ZobristKeys::instance();
+    let mut hash = 0;
+    if castle_perm.is_white_king_set() {
+        hash ^= keys.castle_permissions_white_king();
+    }
+    if castle_perm.is_white_queen_set() {
+        hash ^= keys.castle_permissions_white_queen();
+    }
+    if castle_perm.is_black_king_set() {
+        hash ^= keys.castle_permissions_black_king();
+    }
+    if castle_perm.is_black_queen_set() {
+        hash ^= keys.castle_permissions_black_queen();
     }
+    hash
 }
 
 impl fmt::Display for MoveLegality {
@@ -635,6 +907,12 @@ impl fmt::Debug for MoveLegality {
     }
 }
 
+impl fmt::Display for UndoState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self, f)
+    }
+}
+
 impl fmt::Display for GameState {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt::Debug::fmt(&self, f)
@@ -647,12 +925,12 @@ impl fmt::Debug for Position<'_> {
 
         debug_str.push_str(&format!("Board : {}\n", self.board()));
         debug_str.push_str(&format!("SideToMove : {}\n", self.game_state.side_to_move));
-        if self.game_state.en_pass_sq.is_none() {
+        if self.game_state.en_pass_sq().is_none() {
             debug_str.push_str("En pass Sq : -\n");
         } else {
             debug_str.push_str(&format!(
                 "En pass Sq : {}\n",
-                self.game_state.en_pass_sq.unwrap()
+                self.game_state.en_pass_sq().unwrap()
             ));
         }
 
@@ -662,7 +940,9 @@ impl fmt::Debug for Position<'_> {
             self.game_state.fifty_move_cntr
         ));
 
-        debug_str.push_str(&format!("Position Hist: {}\n", self.position_history));
+        if let Some(position_history) = &self.position_history {
+            debug_str.push_str(&format!("Position Hist: {}\n", position_history));
+        }
 
         write!(f, "{}", debug_str)
     }
@@ -714,17 +994,22 @@ impl PartialEq for Position<'_> {
 
 #[cfg(test)]
 mod tests {
+    use crate::board::bitboard::Bitboard;
     use crate::board::colour::Colour;
     use crate::board::occupancy_masks::OccupancyMasks;
     use crate::board::piece::Piece;
     use crate::board::square::Square;
     use crate::io::fen;
     use crate::moves::mov::*;
+    use crate::moves::move_gen::MoveGenerator;
+    use crate::moves::move_list::MoveList;
     use crate::position::attack_checker::AttackChecker;
     use crate::position::game_position::process;
 
+    use crate::position::game_position::GameState;
     use crate::position::game_position::MoveLegality;
     use crate::position::game_position::Position;
+    use crate::position::game_position::UndoState;
     use crate::position::zobrist_keys::ZobristKeys;
 
     #[test]
@@ -734,8 +1019,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -744,8 +1028,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -779,8 +1062,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -789,18 +1071,17 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
         // initially no history
-        assert_eq!(pos.position_history.len(), 0);
+        assert_eq!(pos.position_history.as_ref().unwrap().len(), 0);
         let mv = Move::encode_move(&Square::E5, &Square::E6);
         pos.make_move(&mv);
 
         // history updated
-        assert_eq!(pos.position_history.len(), 1);
+        assert_eq!(pos.position_history.as_ref().unwrap().len(), 1);
     }
 
     #[test]
@@ -809,8 +1090,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -819,8 +1099,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -838,8 +1117,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -848,8 +1126,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -871,8 +1148,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -881,8 +1157,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -911,8 +1186,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -921,8 +1195,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -952,8 +1225,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -962,8 +1234,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -988,8 +1259,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -998,8 +1268,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1014,7 +1283,7 @@ mod tests {
         let mv = Move::encode_move(&Square::F2, &Square::F4);
         pos.make_move(&mv);
 
-        assert_eq!(pos.game_state.en_pass_sq.unwrap(), Square::F3);
+        assert_eq!(pos.game_state.en_pass_sq().unwrap(), Square::F3);
 
         assert!(is_piece_on_square_as_expected(
             &pos,
@@ -1032,8 +1301,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -1042,8 +1310,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1058,7 +1325,7 @@ mod tests {
         let mv = Move::encode_move(&Square::D7, &Square::D5);
         pos.make_move(&mv);
 
-        assert_eq!(pos.game_state.en_pass_sq, Some(Square::D6));
+        assert_eq!(pos.game_state.en_pass_sq(), Some(Square::D6));
 
         assert!(is_piece_on_square_as_expected(
             &pos,
@@ -1076,8 +1343,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -1086,8 +1352,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1133,8 +1398,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -1143,8 +1407,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1190,8 +1453,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -1200,8 +1462,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1246,8 +1507,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -1256,8 +1516,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1303,8 +1562,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -1313,8 +1571,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1352,8 +1609,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -1362,8 +1618,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1404,8 +1659,7 @@ mod tests {
             let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
                 fen::decompose_fen(fen);
 
-            let zobrist_keys = ZobristKeys::new();
-            let occ_masks = OccupancyMasks::new();
+            let occ_masks = OccupancyMasks::instance();
             let attack_checker = AttackChecker::new();
 
             let mut pos = Position::new(
@@ -1414,8 +1668,7 @@ mod tests {
                 move_cntr,
                 en_pass_sq,
                 side_to_move,
-                &zobrist_keys,
-                &occ_masks,
+                occ_masks,
                 &attack_checker,
             );
 
@@ -1449,8 +1702,7 @@ mod tests {
             let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
                 fen::decompose_fen(fen);
 
-            let zobrist_keys = ZobristKeys::new();
-            let occ_masks = OccupancyMasks::new();
+            let occ_masks = OccupancyMasks::instance();
             let attack_checker = AttackChecker::new();
 
             let mut pos = Position::new(
@@ -1459,8 +1711,7 @@ mod tests {
                 move_cntr,
                 en_pass_sq,
                 side_to_move,
-                &zobrist_keys,
-                &occ_masks,
+                occ_masks,
                 &attack_checker,
             );
 
@@ -1494,8 +1745,7 @@ mod tests {
             let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
                 fen::decompose_fen(fen);
 
-            let zobrist_keys = ZobristKeys::new();
-            let occ_masks = OccupancyMasks::new();
+            let occ_masks = OccupancyMasks::instance();
             let attack_checker = AttackChecker::new();
 
             let mut pos = Position::new(
@@ -1504,8 +1754,7 @@ mod tests {
                 move_cntr,
                 en_pass_sq,
                 side_to_move,
-                &zobrist_keys,
-                &occ_masks,
+                occ_masks,
                 &attack_checker,
             );
             // check pre-conditions
@@ -1533,8 +1782,7 @@ mod tests {
             let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
                 fen::decompose_fen(fen);
 
-            let zobrist_keys = ZobristKeys::new();
-            let occ_masks = OccupancyMasks::new();
+            let occ_masks = OccupancyMasks::instance();
             let attack_checker = AttackChecker::new();
 
             let mut pos = Position::new(
@@ -1543,8 +1791,7 @@ mod tests {
                 move_cntr,
                 en_pass_sq,
                 side_to_move,
-                &zobrist_keys,
-                &occ_masks,
+                occ_masks,
                 &attack_checker,
             );
 
@@ -1582,8 +1829,7 @@ mod tests {
             let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
                 fen::decompose_fen(fen);
 
-            let zobrist_keys = ZobristKeys::new();
-            let occ_masks = OccupancyMasks::new();
+            let occ_masks = OccupancyMasks::instance();
             let attack_checker = AttackChecker::new();
 
             let mut pos = Position::new(
@@ -1592,8 +1838,7 @@ mod tests {
                 move_cntr,
                 en_pass_sq,
                 side_to_move,
-                &zobrist_keys,
-                &occ_masks,
+                occ_masks,
                 &attack_checker,
             );
 
@@ -1624,8 +1869,7 @@ mod tests {
             let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
                 fen::decompose_fen(fen);
 
-            let zobrist_keys = ZobristKeys::new();
-            let occ_masks = OccupancyMasks::new();
+            let occ_masks = OccupancyMasks::instance();
             let attack_checker = AttackChecker::new();
 
             let mut pos = Position::new(
@@ -1634,8 +1878,7 @@ mod tests {
                 move_cntr,
                 en_pass_sq,
                 side_to_move,
-                &zobrist_keys,
-                &occ_masks,
+                occ_masks,
                 &attack_checker,
             );
 
@@ -1663,8 +1906,7 @@ mod tests {
             let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
                 fen::decompose_fen(fen);
 
-            let zobrist_keys = ZobristKeys::new();
-            let occ_masks = OccupancyMasks::new();
+            let occ_masks = OccupancyMasks::instance();
             let attack_checker = AttackChecker::new();
 
             let mut pos = Position::new(
@@ -1673,8 +1915,7 @@ mod tests {
                 move_cntr,
                 en_pass_sq,
                 side_to_move,
-                &zobrist_keys,
-                &occ_masks,
+                occ_masks,
                 &attack_checker,
             );
 
@@ -1702,8 +1943,7 @@ mod tests {
             let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
                 fen::decompose_fen(fen);
 
-            let zobrist_keys = ZobristKeys::new();
-            let occ_masks = OccupancyMasks::new();
+            let occ_masks = OccupancyMasks::instance();
             let attack_checker = AttackChecker::new();
 
             let mut pos = Position::new(
@@ -1712,8 +1952,7 @@ mod tests {
                 move_cntr,
                 en_pass_sq,
                 side_to_move,
-                &zobrist_keys,
-                &occ_masks,
+                occ_masks,
                 &attack_checker,
             );
 
@@ -1731,8 +1970,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -1741,8 +1979,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1765,8 +2002,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -1775,8 +2011,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1799,8 +2034,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -1809,8 +2043,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1833,8 +2066,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -1843,8 +2075,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1867,8 +2098,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -1877,8 +2107,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1901,8 +2130,7 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -1911,8 +2139,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
 
@@ -1943,7 +2170,6 @@ mod tests {
         let (board1, move_cntr1, castle_permissions1, side_to_move1, en_pass_sq1) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys1 = ZobristKeys::new();
         let occ_masks1 = OccupancyMasks::new();
         let attack_checker = AttackChecker::new();
 
@@ -1953,7 +2179,6 @@ mod tests {
             move_cntr1,
             en_pass_sq1,
             side_to_move1,
-            &zobrist_keys1,
             &occ_masks1,
             &attack_checker,
         );
@@ -1970,7 +2195,6 @@ mod tests {
             move_cntr2,
             en_pass_sq2,
             side_to_move2,
-            &zobrist_keys1,
             &occ_masks2,
             &attack_checker,
         );
@@ -2001,7 +2225,6 @@ mod tests {
         let (board1, move_cntr1, castle_permissions1, side_to_move1, en_pass_sq1) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys1 = ZobristKeys::new();
         let occ_masks1 = OccupancyMasks::new();
         let attack_checker = AttackChecker::new();
 
@@ -2011,7 +2234,6 @@ mod tests {
             move_cntr1,
             en_pass_sq1,
             side_to_move1,
-            &zobrist_keys1,
             &occ_masks1,
             &attack_checker,
         );
@@ -2028,7 +2250,6 @@ mod tests {
             move_cntr2,
             en_pass_sq2,
             side_to_move2,
-            &zobrist_keys1,
             &occ_masks2,
             &attack_checker,
         );
@@ -2058,8 +2279,8 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let zobrist_keys = ZobristKeys::instance();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -2068,8 +2289,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
         let init_hash = pos.position_hash();
@@ -2087,6 +2307,98 @@ mod tests {
         assert!(expected_hash == pos.position_hash());
     }
 
+    #[test]
+    pub fn make_move_hash_matches_fen_after_replies_of_double_pawn_moves() {
+        let attack_checker = AttackChecker::new();
+        let new_position = |fen: &str| {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(fen);
+            Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                OccupancyMasks::instance(),
+                &attack_checker,
+            )
+        };
+
+        let mut pos = new_position("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        pos.make_move(&Move::encode_move(&Square::E2, &Square::E4));
+        pos.make_move(&Move::encode_move(&Square::E7, &Square::E5));
+
+        let expected =
+            new_position("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2");
+        assert!(pos.position_hash() == expected.position_hash());
+
+        pos.make_move(&Move::encode_move(&Square::G1, &Square::F3));
+        pos.make_move(&Move::encode_move(&Square::B8, &Square::C6));
+        let expected =
+            new_position("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        assert!(pos.position_hash() == expected.position_hash());
+    }
+
+    #[test]
+    pub fn make_move_hash_matches_fen_after_castling_rights_lost() {
+        let attack_checker = AttackChecker::new();
+        let new_position = |fen: &str| {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(fen);
+            Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                OccupancyMasks::instance(),
+                &attack_checker,
+            )
+        };
+        let fen = "r3k2r/p7/8/8/8/8/8/R3K2R w KQkq - 0 1";
+
+        // the king moving, a rook moving and a rook being captured, then a
+        // black pawn move
+        let moves_and_fens = [
+            (
+                Move::encode_move(&Square::E1, &Square::E2),
+                "r3k2r/8/p7/8/8/8/4K3/R6R w kq - 0 2",
+            ),
+            (
+                Move::encode_move(&Square::H1, &Square::H2),
+                "r3k2r/8/p7/8/8/8/7R/R3K3 w Qkq - 0 2",
+            ),
+            (
+                Move::encode_move(&Square::A1, &Square::A8),
+                "R3k2r/8/p7/8/8/8/8/4K2R w Kk - 0 2",
+            ),
+        ];
+        for (mv, expected_fen) in moves_and_fens {
+            let mut pos = new_position(fen);
+            pos.make_move(&mv);
+            pos.make_move(&Move::encode_move(&Square::A7, &Square::A6));
+
+            let expected = new_position(expected_fen);
+            assert_eq!(pos.to_fen(), expected_fen);
+            assert!(pos.position_hash() == expected.position_hash());
+        }
+
+        // walking the king out and back loses the rights, so it isn't a
+        // repeat of the start
+        let start = new_position(fen);
+        let mut pos = new_position(fen);
+        let ml = vec![
+            Move::encode_move(&Square::E1, &Square::E2),
+            Move::encode_move(&Square::E8, &Square::E7),
+            Move::encode_move(&Square::E2, &Square::E1),
+            Move::encode_move(&Square::E7, &Square::E8),
+        ];
+        for mv in ml {
+            pos.make_move(&mv);
+        }
+        assert!(pos.position_hash() != start.position_hash());
+    }
+
     #[test]
     pub fn make_move_hash_updated_black_double_pawn_move() {
         let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1";
@@ -2094,8 +2406,8 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let zobrist_keys = ZobristKeys::instance();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -2104,8 +2416,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
         let init_hash = pos.position_hash();
@@ -2130,8 +2441,8 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let zobrist_keys = ZobristKeys::instance();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -2140,8 +2451,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
         let init_hash = pos.position_hash();
@@ -2164,8 +2474,8 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let zobrist_keys = ZobristKeys::instance();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -2174,8 +2484,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
         let init_hash = pos.position_hash();
@@ -2198,8 +2507,8 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let zobrist_keys = ZobristKeys::instance();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -2208,8 +2517,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
         let init_hash = pos.position_hash();
@@ -2237,8 +2545,8 @@ mod tests {
         let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
             fen::decompose_fen(fen);
 
-        let zobrist_keys = ZobristKeys::new();
-        let occ_masks = OccupancyMasks::new();
+        let zobrist_keys = ZobristKeys::instance();
+        let occ_masks = OccupancyMasks::instance();
         let attack_checker = AttackChecker::new();
 
         let mut pos = Position::new(
@@ -2247,8 +2555,7 @@ mod tests {
             move_cntr,
             en_pass_sq,
             side_to_move,
-            &zobrist_keys,
-            &occ_masks,
+            occ_masks,
             &attack_checker,
         );
         let init_hash = pos.position_hash();
@@ -2293,4 +2600,659 @@ mod tests {
 
         true
     }
+
+    #[test]
+    pub fn is_repetition_after_knight_shuffle() {
+        let fen = "1n2k3/8/8/8/8/8/8/1N2K2Q w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let ml = vec![
+            Move::encode_move(&Square::B1, &Square::C3),
+            Move::encode_move(&Square::B8, &Square::C6),
+            Move::encode_move(&Square::C3, &Square::B1),
+        ];
+        for mv in ml {
+            pos.make_move(&mv);
+            assert!(!pos.is_repetition());
+        }
+
+        pos.make_move(&Move::encode_move(&Square::C6, &Square::B8));
+        assert!(pos.is_repetition());
+
+        pos.take_move();
+        assert!(!pos.is_repetition());
+    }
+
+    #[test]
+    pub fn to_fen_round_trips() {
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let fens = [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "rnbqkbnr/ppp1p1pp/8/3pPp2/8/8/PPPP1PPP/RNBQKBNR w Kq f6 0 3",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 b - - 12 40",
+        ];
+        for fen in fens {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(fen);
+            let pos = Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                occ_masks,
+                &attack_checker,
+            );
+            assert_eq!(pos.to_fen(), fen);
+        }
+    }
+
+    #[test]
+    pub fn to_fen_follows_moves() {
+        let fen = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        pos.make_move(&Move::encode_move(&Square::E2, &Square::E4));
+        assert_eq!(
+            pos.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1"
+        );
+        pos.make_move(&Move::encode_move(&Square::G8, &Square::F6));
+        pos.make_move(&Move::encode_move(&Square::E1, &Square::E2));
+        assert_eq!(
+            pos.to_fen(),
+            "rnbqkb1r/pppppppp/5n2/8/4P3/8/PPPPKPPP/RNBQ1BNR b kq - 2 2"
+        );
+    }
+
+    #[test]
+    pub fn make_take_chess960_castle_moves() {
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let fens = [
+            (
+                "1r2k2r/8/8/8/8/8/8/1R2K2R w KBkb - 0 1",
+                Move::encode_move_castle(&Square::E1, &Square::C1),
+                "1r2k2r/8/8/8/8/8/8/2KR3R b kb - 1 1",
+            ),
+            (
+                "4k3/8/8/8/8/8/8/5KR1 w G - 0 1",
+                Move::encode_move_castle(&Square::F1, &Square::G1),
+                "4k3/8/8/8/8/8/8/5RK1 b - - 1 1",
+            ),
+            (
+                "4k3/8/8/8/8/8/8/6KR w K - 0 1",
+                Move::encode_move_castle(&Square::G1, &Square::G1),
+                "4k3/8/8/8/8/8/8/5RK1 b - - 1 1",
+            ),
+            (
+                "rk2r3/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 b GBeq - 0 1",
+                Move::encode_move_castle(&Square::B8, &Square::G8),
+                "r4rk1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w GB - 1 2",
+            ),
+        ];
+        for (fen, mv, expected) in fens {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(fen);
+            let mut pos = Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                occ_masks,
+                &attack_checker,
+            );
+            let hash = pos.position_hash();
+
+            assert!(pos.make_move(&mv) == MoveLegality::Legal);
+            assert_eq!(pos.to_fen(), expected);
+
+            pos.take_move();
+            assert_eq!(pos.to_fen(), fen);
+            assert_eq!(pos.position_hash(), hash);
+        }
+    }
+
+    #[test]
+    pub fn to_fen_full_move_from_odd_half_move_clock() {
+        let fen = "4k3/8/8/8/8/8/8/4K2R w K - 1 2";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        pos.make_move(&Move::encode_move(&Square::H1, &Square::H2));
+        assert_eq!(pos.to_fen(), "4k3/8/8/8/8/8/7R/4K3 b - - 2 2");
+        pos.make_move(&Move::encode_move(&Square::E8, &Square::D8));
+        assert_eq!(pos.to_fen(), "3k4/8/8/8/8/8/7R/4K3 w - - 3 3");
+
+        pos.take_move();
+        pos.take_move();
+        assert_eq!(pos.to_fen(), fen);
+    }
+
+    #[test]
+    pub fn same_position_ignores_move_counters_and_history() {
+        let fen = "1n2k3/8/8/8/8/8/8/1N2K2Q w - - 0 1";
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+        let new_position = |fen: &str| {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(fen);
+            Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                occ_masks,
+                &attack_checker,
+            )
+        };
+
+        let start = new_position(fen);
+        let mut pos = new_position(fen);
+        let ml = vec![
+            Move::encode_move(&Square::B1, &Square::C3),
+            Move::encode_move(&Square::B8, &Square::C6),
+            Move::encode_move(&Square::C3, &Square::B1),
+            Move::encode_move(&Square::C6, &Square::B8),
+        ];
+        for mv in ml {
+            pos.make_move(&mv);
+        }
+
+        assert!(pos.same_position(&start));
+        assert!(pos.position_hash() == start.position_hash());
+        assert!(pos != start);
+
+        // a different half move clock or move number doesn't matter
+        let later = new_position("1n2k3/8/8/8/8/8/8/1N2K2Q w - - 12 40");
+        assert!(later.same_position(&start));
+
+        // side to move, castling and en passant do
+        let black_to_move = new_position("1n2k3/8/8/8/8/8/8/1N2K2Q b - - 0 1");
+        assert!(!black_to_move.same_position(&start));
+
+        let castling = new_position("r3k3/8/8/8/8/8/8/R3K3 w Qq - 0 1");
+        let no_castling = new_position("r3k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        assert!(!castling.same_position(&no_castling));
+
+        let en_passant = new_position("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        let no_en_passant = new_position("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1");
+        assert!(!en_passant.same_position(&no_en_passant));
+    }
+
+    #[test]
+    pub fn same_hash_only_for_same_position() {
+        let attack_checker = AttackChecker::new();
+        let new_position = |fen: &str| {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(fen);
+            Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                OccupancyMasks::instance(),
+                &attack_checker,
+            )
+        };
+
+        // castling with only the one right held
+        let mut pos = new_position("r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1");
+        pos.make_move(&Move::encode_move_castle_kingside_white());
+        let castled = new_position("r3k2r/8/8/8/8/8/8/R4RK1 b kq - 1 1");
+        assert!(pos.same_position(&castled));
+        assert!(pos.position_hash() == castled.position_hash());
+
+        pos.make_move(&Move::encode_move_castle_queenside_black());
+        let castled = new_position("2kr3r/8/8/8/8/8/8/R4RK1 w - - 2 2");
+        assert!(pos.same_position(&castled));
+        assert!(pos.position_hash() == castled.position_hash());
+
+        // every pair of positions that differ hashes differently
+        let fens = [
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R w Kkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R w Qkq - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R w - - 0 1",
+            "r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 5 9",
+        ];
+        let positions: Vec<Position> = fens.iter().map(|fen| new_position(fen)).collect();
+        for first in &positions {
+            for second in &positions {
+                assert_eq!(
+                    first.position_hash() == second.position_hash(),
+                    first.same_position(second)
+                );
+            }
+        }
+    }
+
+    #[test]
+    pub fn hash_history_most_recent_first() {
+        let fen = "1n2k3/8/8/8/8/8/8/1N2K2Q w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+        assert_eq!(pos.hash_history().count(), 0);
+
+        let mut expected = vec![];
+        for (from_sq, to_sq) in [
+            (Square::B1, Square::C3),
+            (Square::B8, Square::C6),
+            (Square::C3, Square::B1),
+        ] {
+            expected.insert(0, pos.position_hash());
+            pos.make_move(&Move::encode_move(&from_sq, &to_sq));
+        }
+        assert_eq!(pos.hash_history().collect::<Vec<_>>(), expected);
+
+        pos.take_move();
+        assert_eq!(pos.hash_history().collect::<Vec<_>>(), expected[1..]);
+
+        let (copied, _) = pos.make_move_copied(&Move::encode_move(&Square::C3, &Square::B1));
+        assert_eq!(copied.hash_history().count(), 0);
+    }
+
+    #[test]
+    pub fn is_repetition_not_found_before_irreversible_move() {
+        let fen = "1n2k3/8/8/8/8/8/P7/1N2K2Q w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let ml = vec![
+            Move::encode_move(&Square::B1, &Square::C3),
+            Move::encode_move(&Square::B8, &Square::C6),
+            Move::encode_move(&Square::C3, &Square::B1),
+            Move::encode_move(&Square::C6, &Square::B8),
+            // pawn move resets the fifty move counter
+            Move::encode_move(&Square::A2, &Square::A3),
+            Move::encode_move(&Square::B8, &Square::C6),
+            Move::encode_move(&Square::B1, &Square::C3),
+            Move::encode_move(&Square::C6, &Square::B8),
+        ];
+        for mv in ml {
+            pos.make_move(&mv);
+        }
+
+        assert_eq!(pos.game_state.fifty_move_cntr, 3);
+        assert!(!pos.is_repetition());
+    }
+
+    #[test]
+    pub fn is_repetition_over_long_game() {
+        // knights shuffling between pawn moves, for over 200 moves
+        let fen = "k3n3/5ppp/8/8/8/8/PPP5/4N2K w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let shuffle = [
+            Move::encode_move(&Square::E1, &Square::D3),
+            Move::encode_move(&Square::E8, &Square::D6),
+            Move::encode_move(&Square::D3, &Square::E1),
+            Move::encode_move(&Square::D6, &Square::E8),
+        ];
+        let square = |rank: usize, file: usize| Square::new((rank * 8 + file) as u8).unwrap();
+
+        // every earlier position, so the whole game can be checked rather
+        // than just the positions since the last pawn move
+        let mut game_hashes = vec![pos.position_hash()];
+        let mut num_repetitions = 0;
+        let mut play = |pos: &mut Position, mv: &Move| {
+            pos.make_move(mv);
+            let repeated = game_hashes.contains(&pos.position_hash());
+            assert_eq!(
+                pos.is_repetition(),
+                repeated,
+                "after {} plies",
+                game_hashes.len()
+            );
+            if repeated {
+                num_repetitions += 1;
+            }
+            game_hashes.push(pos.position_hash());
+        };
+
+        // a, b and c pawns for white, f, g and h for black, one rank at a time
+        for round in 0..9 {
+            for _ in 0..12 {
+                for mv in shuffle.iter() {
+                    play(&mut pos, mv);
+                }
+            }
+
+            let advance = round / 3;
+            let white_file = round % 3;
+            let black_file = 5 + round % 3;
+            play(
+                &mut pos,
+                &Move::encode_move(
+                    &square(1 + advance, white_file),
+                    &square(2 + advance, white_file),
+                ),
+            );
+            play(
+                &mut pos,
+                &Move::encode_move(
+                    &square(6 - advance, black_file),
+                    &square(5 - advance, black_file),
+                ),
+            );
+        }
+
+        assert!(pos.move_counter().full_move() > 200);
+        // all but the first 3 plies of each knight shuffle
+        assert_eq!(num_repetitions, 9 * (12 * 4 - 3));
+    }
+
+    #[test]
+    pub fn is_fifty_move_draw_from_fen_half_move_clock() {
+        let fen = "1n2k3/8/8/8/8/8/8/1N2K2Q w - - 99 80";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+        assert!(!pos.is_fifty_move_draw());
+
+        pos.make_move(&Move::encode_move(&Square::B1, &Square::C3));
+        assert!(pos.is_fifty_move_draw());
+
+        pos.take_move();
+        assert!(!pos.is_fifty_move_draw());
+    }
+
+    #[test]
+    pub fn legal_destinations_as_expected() {
+        // white bishop on d2 is pinned by the black bishop on b4
+        let fen = "4k3/8/8/8/1b6/8/3B4/4K1N1 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let mut expected = Bitboard::default();
+        expected.set_bit(&Square::E2);
+        expected.set_bit(&Square::F3);
+        expected.set_bit(&Square::H3);
+        assert!(pos.legal_destinations(&Square::G1) == expected);
+
+        // pinned bishop can only move along the pin
+        let mut expected = Bitboard::default();
+        expected.set_bit(&Square::C3);
+        expected.set_bit(&Square::B4);
+        assert!(pos.legal_destinations(&Square::D2) == expected);
+
+        // empty square and opponent's piece
+        assert!(pos.legal_destinations(&Square::A1).is_empty());
+        assert!(pos.legal_destinations(&Square::B4).is_empty());
+    }
+
+    #[test]
+    pub fn is_legal_and_is_pseudo_legal_as_expected() {
+        // white bishop on d2 is pinned by the black bishop on b4
+        let fen = "4k3/8/8/8/1b6/8/3B4/4K1N1 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let knight_move = Move::encode_move(&Square::G1, &Square::F3);
+        assert!(pos.is_pseudo_legal(&knight_move));
+        assert!(pos.is_legal(&knight_move));
+
+        // leaves the king in check
+        let pinned_move = Move::encode_move(&Square::D2, &Square::E3);
+        assert!(pos.is_pseudo_legal(&pinned_move));
+        assert!(!pos.is_legal(&pinned_move));
+
+        // a knight moving like a bishop, an empty square, the opponent's piece
+        // and a castle without permission
+        for mv in [
+            Move::encode_move(&Square::G1, &Square::H2),
+            Move::encode_move(&Square::A1, &Square::A2),
+            Move::encode_move(&Square::B4, &Square::C3),
+            Move::encode_move_castle_kingside_white(),
+        ] {
+            assert!(!pos.is_pseudo_legal(&mv));
+            assert!(!pos.is_legal(&mv));
+        }
+
+        // the position is unchanged
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+        assert!(*pos.board() == board);
+    }
+
+    #[test]
+    pub fn make_move_copied_matches_make_move() {
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+        let orig_hash = pos.position_hash();
+
+        let mut move_list = MoveList::new();
+        MoveGenerator::new().generate_moves(&pos, &mut move_list);
+
+        for mv in move_list.iterator() {
+            let (copied, copied_legality) = pos.make_move_copied(mv);
+            // original is untouched
+            assert_eq!(pos.position_hash(), orig_hash);
+
+            let move_legality = pos.make_move(mv);
+
+            assert_eq!(copied_legality, move_legality);
+            assert_eq!(copied.board(), pos.board());
+            assert_eq!(copied.position_hash(), pos.position_hash());
+            assert_eq!(copied.side_to_move(), pos.side_to_move());
+            assert_eq!(copied.castle_permissions(), pos.castle_permissions());
+            assert_eq!(copied.en_passant_square(), pos.en_passant_square());
+            assert_eq!(copied.move_counter(), pos.move_counter());
+            assert_eq!(copied.fifty_move_counter(), pos.fifty_move_counter());
+
+            pos.take_move();
+        }
+    }
+
+    #[test]
+    pub fn moves_not_fitting_position_are_illegal_and_change_nothing() {
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let fen = "r3k2r/p6p/8/3pP3/8/8/P6P/RN2K2R w Kq d6 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+        let board_before = *pos.board();
+        let hash_before = pos.position_hash();
+
+        let moves = [
+            // empty from square
+            Move::encode_move(&Square::E4, &Square::E5),
+            // opponent's piece
+            Move::encode_move(&Square::A7, &Square::A6),
+            // capturing own piece
+            Move::encode_move(&Square::A1, &Square::B1),
+            // capturing the king
+            Move::encode_move(&Square::H1, &Square::E8),
+            // promotion not to the last rank
+            Move::encode_move_with_promotion(&Square::A2, &Square::A3, &Piece::Queen),
+            // en passant to the wrong square
+            Move::encode_move_en_passant(&Square::E5, &Square::F6),
+            // no permission to castle queen side, and a knight in the way
+            Move::encode_move_castle_queenside_white(),
+        ];
+        for mv in moves.iter() {
+            assert_eq!(pos.make_move(mv), MoveLegality::Illegal, "{}", mv);
+            assert!(*pos.board() == board_before);
+            assert_eq!(pos.position_hash(), hash_before);
+            assert_eq!(pos.side_to_move(), Colour::White);
+            pos.take_move();
+
+            let (copied, move_legality) = pos.make_move_copied(mv);
+            assert_eq!(move_legality, MoveLegality::Illegal);
+            assert!(*copied.board() == board_before);
+        }
+
+        // the position is still usable, and taking back a double pawn move
+        // restores the en passant square
+        let mv = Move::encode_move(&Square::H2, &Square::H4);
+        assert_eq!(pos.make_move(&mv), MoveLegality::Legal);
+        pos.take_move();
+        assert_eq!(pos.en_passant_square(), Some(Square::D6));
+        let mv = Move::encode_move_en_passant(&Square::E5, &Square::D6);
+        assert_eq!(pos.make_move(&mv), MoveLegality::Legal);
+        pos.take_move();
+        let mv = Move::encode_move_castle_kingside_white();
+        assert_eq!(pos.make_move(&mv), MoveLegality::Legal);
+        pos.take_move();
+        assert!(*pos.board() == board_before);
+        assert_eq!(pos.position_hash(), hash_before);
+    }
+
+    #[test]
+    pub fn game_state_and_undo_state_are_compact() {
+        // the castle permissions (with the rook files) don't fit in the last byte
+        assert_eq!(std::mem::size_of::<GameState>(), 24);
+        // saved on every make_move, so keep to 16 bytes
+        assert_eq!(std::mem::size_of::<UndoState>(), 16);
+    }
 }
@@ -3,4 +3,5 @@ pub mod castle_permissions;
 pub mod game_position;
 pub mod move_counter;
 pub mod position_history;
+pub mod variant;
 pub mod zobrist_keys;
@@ -1,3 +1,4 @@
+use crate::board::colour::Colour;
 use std::fmt;
 #[derive(Default, Eq, PartialEq, Hash, Clone, Copy)]
 pub struct MoveCounter {
@@ -12,16 +13,27 @@ impl MoveCounter {
             full_move: full_cntr,
         }
     }
-    pub fn incr_half_move(&mut self) -> bool {
+    // the full move number goes up after black's move, whatever the half
+    // move count started at
+    pub fn incr_half_move(&mut self, side_moved: &Colour) -> bool {
         self.half_move += 1;
 
-        if self.half_move % 2 == 0 {
+        if *side_moved == Colour::Black {
             self.full_move += 1;
             return true;
         }
         false
     }
 
+    // reverses incr_half_move
+    pub fn decr_half_move(&mut self, side_moved: &Colour) {
+        self.half_move -= 1;
+
+        if *side_moved == Colour::Black {
+            self.full_move -= 1;
+        }
+    }
+
     pub fn half_move(&self) -> u16 {
         self.half_move
     }
@@ -49,6 +61,7 @@ impl fmt::Display for MoveCounter {
 
 #[cfg(test)]
 pub mod tests {
+    use crate::board::colour::Colour;
     use crate::io::fen;
 
     use super::MoveCounter;
@@ -64,23 +77,44 @@ pub mod tests {
     }
 
     #[test]
-    pub fn full_move_incr_only_on_even_moves() {
-        let mut mc = MoveCounter::new(0, 0);
+    pub fn full_move_incr_only_after_black_moves() {
+        let mut mc = MoveCounter::new(1, 2);
 
-        mc.incr_half_move();
-        assert!(mc.half_move() == 1);
-        assert!(mc.full_move() == 0);
-
-        mc.incr_half_move();
+        mc.incr_half_move(&Colour::White);
         assert!(mc.half_move() == 2);
-        assert!(mc.full_move() == 1);
+        assert!(mc.full_move() == 2);
 
-        mc.incr_half_move();
+        mc.incr_half_move(&Colour::Black);
         assert!(mc.half_move() == 3);
-        assert!(mc.full_move() == 1);
+        assert!(mc.full_move() == 3);
 
-        mc.incr_half_move();
+        mc.incr_half_move(&Colour::White);
         assert!(mc.half_move() == 4);
-        assert!(mc.full_move() == 2);
+        assert!(mc.full_move() == 3);
+
+        mc.incr_half_move(&Colour::Black);
+        assert!(mc.half_move() == 5);
+        assert!(mc.full_move() == 4);
+    }
+
+    #[test]
+    pub fn decr_half_move_reverses_incr_half_move() {
+        let mut mc = MoveCounter::new(11, 12);
+
+        let sides = [
+            Colour::White,
+            Colour::Black,
+            Colour::White,
+            Colour::Black,
+            Colour::White,
+        ];
+        for side in sides.iter() {
+            mc.incr_half_move(side);
+        }
+        for side in sides.iter().rev() {
+            mc.decr_half_move(side);
+        }
+
+        assert_eq!(mc, MoveCounter::new(11, 12));
     }
 }
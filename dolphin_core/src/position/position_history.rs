@@ -1,27 +1,18 @@
 use super::zobrist_keys::ZobristHash;
-use crate::board::piece::Piece;
-use crate::moves::mov::Move;
-use crate::position::game_position::GameState;
+use crate::position::game_position::UndoState;
 use std::fmt;
 
-#[derive(Default, Eq, PartialEq, Copy, Clone)]
-struct Item {
-    game_state: GameState,
-    mov: Move,
-    capt_pce: Option<Piece>,
-}
-
 #[derive(Eq, Copy, Clone)]
 pub struct PositionHistory {
     count: u16,
-    history: [Item; PositionHistory::MAX_MOVE_HISTORY],
+    history: [UndoState; PositionHistory::MAX_MOVE_HISTORY],
 }
 
 impl Default for PositionHistory {
     fn default() -> Self {
         PositionHistory {
             count: 0,
-            history: [Item::default(); PositionHistory::MAX_MOVE_HISTORY],
+            history: [UndoState::default(); PositionHistory::MAX_MOVE_HISTORY],
         }
     }
 }
@@ -41,23 +32,6 @@ impl PartialEq for PositionHistory {
         true
     }
 }
-impl fmt::Display for Item {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Debug::fmt(&self, f)
-    }
-}
-
-impl fmt::Debug for Item {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut debug_str = String::new();
-
-        debug_str.push_str(&format!("GameState : {}\n", self.game_state));
-        debug_str.push_str(&format!("Move: : {}\n", self.mov));
-
-        write!(f, "{}", debug_str)
-    }
-}
-
 impl fmt::Debug for PositionHistory {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut debug_str = String::new();
@@ -87,38 +61,27 @@ impl PositionHistory {
     pub fn new() -> Box<PositionHistory> {
         Box::new(PositionHistory {
             count: 0,
-            history: [Item::default(); PositionHistory::MAX_MOVE_HISTORY],
+            history: [UndoState::default(); PositionHistory::MAX_MOVE_HISTORY],
         })
     }
 
     // push
-    pub fn push(&mut self, game_state: &GameState, mv: &Move, capt_pce: &Option<Piece>) {
+    pub fn push(&mut self, undo_state: UndoState) {
         debug_assert!(
             self.count <= (PositionHistory::MAX_MOVE_HISTORY - 1) as u16,
             "max length exceeded. {:?}",
             self.count
         );
 
-        let item = Item {
-            game_state: *game_state,
-            mov: *mv,
-            capt_pce: *capt_pce,
-        };
-
-        self.history[self.count as usize] = item;
+        self.history[self.count as usize] = undo_state;
         self.count += 1;
     }
 
-    pub fn pop(&mut self) -> (GameState, Move, Option<Piece>) {
+    pub fn pop(&mut self) -> UndoState {
         debug_assert!(self.count > 0, "attempt to pop, len = 0");
 
         self.count -= 1;
-
-        (
-            self.history[self.count as usize].game_state,
-            self.history[self.count as usize].mov,
-            self.history[self.count as usize].capt_pce,
-        )
+        self.history[self.count as usize]
     }
 
     pub fn len(&self) -> usize {
@@ -128,16 +91,30 @@ impl PositionHistory {
         self.len() == 0
     }
 
-    pub fn contains_position_hash(&self, hash: &ZobristHash, start_offset: usize) -> bool {
-        if start_offset > (self.count - 1).into() {
+    // hashes of the positions the moves were made from, most recent first
+    pub fn position_hashes(&self) -> impl Iterator<Item = ZobristHash> + '_ {
+        self.history[..self.len()]
+            .iter()
+            .rev()
+            .map(|undo_state| undo_state.position_hash())
+    }
+
+    // Whether the hash of the current position (the one after the last
+    // move) is one of the positions from start_offset on. Only every other
+    // entry, counting back, has the same side to move, and a position can't
+    // come round again in 2 plies, so the scan starts 4 plies back. Keeping
+    // start_offset at the last capture or pawn move means the cost depends
+    // on the 50 move counter, not the length of the game.
+    pub fn contains_repeated_hash(&self, hash: &ZobristHash, start_offset: usize) -> bool {
+        if start_offset > self.len() {
             panic!("offset is past end of position history");
         }
 
-        for i in start_offset..(self.count - 1) as usize {
-            if self.history[i].game_state.get_zobrist_hash() == *hash {
-                return true;
-            }
-        }
-        false
+        self.history[start_offset..self.len()]
+            .iter()
+            .rev()
+            .skip(3)
+            .step_by(2)
+            .any(|undo_state| undo_state.position_hash() == *hash)
     }
 }
@@ -0,0 +1,451 @@
+use crate::board::colour::Colour;
+use crate::board::file::File;
+use crate::board::game_board::Board;
+use crate::board::piece::Piece;
+use crate::board::rank::Rank;
+use crate::board::square::Square;
+use crate::moves::mov::Move;
+use crate::moves::mov::MoveType;
+use crate::moves::move_list::MoveList;
+use crate::position::castle_permissions::CastlePermission;
+use crate::position::game_position::MoveLegality;
+use crate::position::game_position::Position;
+use std::fmt;
+
+// How a game ended
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum GameResult {
+    Checkmate { winner: Colour },
+    Stalemate,
+    FiftyMoveRule,
+    // the side to move has no pieces, or no moves, left (eg, in Antichess)
+    NoMovesLeft { winner: Colour },
+}
+
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            GameResult::Checkmate {
+                winner: Colour::White,
+            } => "Checkmate, white wins",
+            GameResult::Checkmate {
+                winner: Colour::Black,
+            } => "Checkmate, black wins",
+            GameResult::Stalemate => "Stalemate, draw",
+            GameResult::FiftyMoveRule => "Draw by the fifty move rule",
+            GameResult::NoMovesLeft {
+                winner: Colour::White,
+            } => "No moves left, white wins",
+            GameResult::NoMovesLeft {
+                winner: Colour::Black,
+            } => "No moves left, black wins",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// The rules that differ between chess variants, so a new variant (eg,
+// King of the Hill, with its extra win condition) is added by implementing
+// these rather than forking the move generator and Position. The defaults
+// are the standard rules. Piece movement, and the king's castling squares,
+// are still the standard ones.
+pub trait Variant {
+    fn name(&self) -> &'static str;
+
+    // pieces a pawn can promote to
+    fn promotion_pieces(&self) -> &'static [Piece] {
+        &[Piece::Knight, Piece::Bishop, Piece::Rook, Piece::Queen]
+    }
+
+    // The files of the rooks that castle king side and queen side, from the
+    // back rank at the start of the game
+    fn castle_rook_files(&self, board: &Board, colour: &Colour) -> (Option<File>, Option<File>);
+
+    // the king is an ordinary piece that can be captured, so there's no check
+    fn is_king_capturable(&self) -> bool {
+        false
+    }
+
+    // Filters the pseudo-legal moves down to the legal ones, by making each
+    // move in turn. By default a move is legal if it doesn't leave the king
+    // attacked.
+    fn filter_legal_moves(
+        &self,
+        pos: &mut Position,
+        pseudo_legal: &MoveList,
+        move_list: &mut MoveList,
+    ) {
+        for mv in pseudo_legal.iterator() {
+            if pos.make_move(mv) == MoveLegality::Legal {
+                move_list.push(mv);
+            }
+            pos.take_move();
+        }
+    }
+
+    fn to_uci_string(&self, mv: &Move, _castle_permissions: &CastlePermission) -> String {
+        mv.to_uci_string()
+    }
+
+    // The result, if the game is over, given the number of legal moves the
+    // side to move has
+    fn game_result(&self, pos: &Position, num_legal_moves: usize) -> Option<GameResult> {
+        if num_legal_moves == 0 {
+            if pos.is_king_sq_attacked() {
+                return Some(GameResult::Checkmate {
+                    winner: pos.side_to_move().flip_side(),
+                });
+            }
+            return Some(GameResult::Stalemate);
+        }
+
+        if pos.is_fifty_move_draw() {
+            return Some(GameResult::FiftyMoveRule);
+        }
+        None
+    }
+
+    // sets the castling rook files for both colours from the start position
+    fn set_castle_rook_files(&self, board: &Board, castle_permissions: &mut CastlePermission) {
+        for colour in [Colour::White, Colour::Black] {
+            let (king_side, queen_side) = self.castle_rook_files(board, &colour);
+            castle_permissions.set_rook_files(
+                &colour,
+                &king_side.unwrap_or(File::H),
+                &queen_side.unwrap_or(File::A),
+            );
+        }
+    }
+}
+
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub struct Standard;
+
+impl Variant for Standard {
+    fn name(&self) -> &'static str {
+        "standard"
+    }
+
+    fn castle_rook_files(&self, _board: &Board, _colour: &Colour) -> (Option<File>, Option<File>) {
+        (Some(File::H), Some(File::A))
+    }
+}
+
+// Fischer random chess. The castling rooks are the outermost rooks either
+// side of the king (as "KQkq" means in X-FEN), and castling is written in
+// UCI as the king moving onto its rook. After castling the king and rook
+// are on the same squares as in standard chess.
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub struct Chess960;
+
+impl Variant for Chess960 {
+    fn name(&self) -> &'static str {
+        "chess960"
+    }
+
+    fn castle_rook_files(&self, board: &Board, colour: &Colour) -> (Option<File>, Option<File>) {
+        let back_rank = match colour {
+            Colour::White => Rank::R1,
+            Colour::Black => Rank::R8,
+        };
+        let king_file = board.king_square(colour).file();
+        let is_own_rook = |file: &File| {
+            Square::from_rank_file(&back_rank, file)
+                .and_then(|sq| board.get_piece_and_colour_on_square(&sq))
+                == Some((Piece::Rook, *colour))
+        };
+
+        let king_side = File::iterator()
+            .rev()
+            .take_while(|file| **file != king_file)
+            .find(|file| is_own_rook(file))
+            .copied();
+        let queen_side = File::iterator()
+            .take_while(|file| **file != king_file)
+            .find(|file| is_own_rook(file))
+            .copied();
+        (king_side, queen_side)
+    }
+
+    fn to_uci_string(&self, mv: &Move, castle_permissions: &CastlePermission) -> String {
+        mv.to_uci_string_chess960(castle_permissions)
+    }
+}
+
+// Antichess (giveaway chess). Captures are compulsory, the king can be
+// captured like any other piece, and the side to move wins when it has no
+// pieces or no moves left. Played from positions with no castling rights
+// (any castling moves are dropped). Pawns can't promote to a king, as
+// there's no Move encoding for it. Position::new still wants both kings on
+// the board, and search and evaluation are still for standard chess.
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub struct Antichess;
+
+impl Variant for Antichess {
+    fn name(&self) -> &'static str {
+        "antichess"
+    }
+
+    fn castle_rook_files(&self, _board: &Board, _colour: &Colour) -> (Option<File>, Option<File>) {
+        (None, None)
+    }
+
+    fn is_king_capturable(&self) -> bool {
+        true
+    }
+
+    fn filter_legal_moves(
+        &self,
+        pos: &mut Position,
+        pseudo_legal: &MoveList,
+        move_list: &mut MoveList,
+    ) {
+        let must_capture = pseudo_legal.iterator().any(|mv| pos.is_capture(mv));
+        pseudo_legal
+            .iterator()
+            .filter(|mv| mv.move_type() != MoveType::Castle)
+            .filter(|mv| !must_capture || pos.is_capture(mv))
+            .for_each(|mv| move_list.push(mv));
+    }
+
+    fn game_result(&self, pos: &Position, num_legal_moves: usize) -> Option<GameResult> {
+        if num_legal_moves == 0 {
+            return Some(GameResult::NoMovesLeft {
+                winner: pos.side_to_move(),
+            });
+        }
+
+        if pos.is_fifty_move_draw() {
+            return Some(GameResult::FiftyMoveRule);
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::board::colour::Colour;
+    use crate::board::file::File;
+    use crate::board::game_board::Board;
+    use crate::board::occupancy_masks::OccupancyMasks;
+    use crate::board::piece::Piece;
+    use crate::board::square::Square;
+    use crate::io::fen;
+    use crate::moves::mov::Move;
+    use crate::moves::move_gen::MoveGenerator;
+    use crate::moves::move_list::MoveList;
+    use crate::position::attack_checker::AttackChecker;
+    use crate::position::castle_permissions::CastlePermission;
+    use crate::position::game_position::Position;
+    use crate::position::variant::Antichess;
+    use crate::position::variant::Chess960;
+    use crate::position::variant::GameResult;
+    use crate::position::variant::Standard;
+    use crate::position::variant::Variant;
+
+    #[test]
+    pub fn castle_rook_files_for_each_variant() {
+        let (board, _, _, _, _) = fen::decompose_fen("rk2r3/8/8/8/8/8/8/1R2K1R1 w - - 0 1");
+
+        assert!(
+            Standard.castle_rook_files(&board, &Colour::White) == (Some(File::H), Some(File::A))
+        );
+
+        assert!(
+            Chess960.castle_rook_files(&board, &Colour::White) == (Some(File::G), Some(File::B))
+        );
+        assert!(
+            Chess960.castle_rook_files(&board, &Colour::Black) == (Some(File::E), Some(File::A))
+        );
+
+        let mut castle_permissions = CastlePermission::NO_CASTLE_PERMS_AVAIL;
+        Chess960.set_castle_rook_files(&board, &mut castle_permissions);
+        assert_eq!(
+            Chess960.to_uci_string(
+                &Move::encode_move_castle_kingside_white(),
+                &castle_permissions
+            ),
+            "e1g1"
+        );
+        assert_eq!(
+            Standard.to_uci_string(
+                &Move::encode_move_castle_kingside_white(),
+                &castle_permissions
+            ),
+            "e1g1"
+        );
+        assert_eq!(
+            Chess960.to_uci_string(
+                &Move::encode_move_castle_queenside_white(),
+                &castle_permissions
+            ),
+            "e1b1"
+        );
+    }
+
+    #[test]
+    pub fn game_result_from_legal_moves() {
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let fens = [
+            (
+                "R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1",
+                Some(GameResult::Checkmate {
+                    winner: Colour::White,
+                }),
+            ),
+            ("k7/8/1Q6/8/8/8/8/7K b - - 0 1", Some(GameResult::Stalemate)),
+            (
+                "k7/8/8/8/8/8/8/R6K b - - 100 80",
+                Some(GameResult::FiftyMoveRule),
+            ),
+            ("k7/8/8/8/8/8/8/R6K b - - 0 1", None),
+        ];
+
+        for (fen, expected) in fens {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(fen);
+            let mut pos = Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                occ_masks,
+                &attack_checker,
+            );
+
+            let mut move_list = MoveList::new();
+            let num_legal_moves =
+                MoveGenerator::new().generate_legal_moves(&mut pos, &mut move_list);
+            assert_eq!(
+                Standard.game_result(&pos, num_legal_moves as usize),
+                expected,
+                "{}",
+                fen
+            );
+        }
+    }
+
+    // a variant where pawns only promote to rooks or queens
+    struct RookOrQueen;
+
+    impl Variant for RookOrQueen {
+        fn name(&self) -> &'static str {
+            "rook-or-queen"
+        }
+
+        fn promotion_pieces(&self) -> &'static [Piece] {
+            &[Piece::Rook, Piece::Queen]
+        }
+
+        fn castle_rook_files(
+            &self,
+            board: &Board,
+            colour: &Colour,
+        ) -> (Option<File>, Option<File>) {
+            Standard.castle_rook_files(board, colour)
+        }
+    }
+
+    #[test]
+    pub fn move_gen_promotes_to_variant_pieces() {
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+        pos.set_variant(&RookOrQueen);
+
+        let mut move_list = MoveList::new();
+        MoveGenerator::new().generate_moves(&pos, &mut move_list);
+
+        for (piece, expected) in [
+            (Piece::Knight, false),
+            (Piece::Bishop, false),
+            (Piece::Rook, true),
+            (Piece::Queen, true),
+        ] {
+            let mv = Move::encode_move_with_promotion(&Square::A7, &Square::A8, &piece);
+            assert_eq!(move_list.contains(&mv), expected);
+        }
+    }
+
+    #[test]
+    pub fn antichess_perft_from_start_position() {
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1");
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+        pos.set_variant(&Antichess);
+
+        assert_eq!(legal_perft(&mut pos, 1), 20);
+        assert_eq!(legal_perft(&mut pos, 2), 400);
+        assert_eq!(legal_perft(&mut pos, 3), 8067);
+    }
+
+    #[test]
+    pub fn antichess_capture_compulsory_and_king_capturable() {
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("8/8/8/8/8/8/3k4/3Q3K w - - 0 1");
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+        pos.set_variant(&Antichess);
+
+        let mut move_list = MoveList::new();
+        MoveGenerator::new().generate_legal_moves(&mut pos, &mut move_list);
+        let king_capture = Move::encode_move(&Square::D1, &Square::D2);
+        assert_eq!(move_list.len(), 1);
+        assert!(move_list.contains(&king_capture));
+
+        pos.make_move(&king_capture);
+        let mut move_list = MoveList::new();
+        let num_legal_moves = MoveGenerator::new().generate_legal_moves(&mut pos, &mut move_list);
+        assert_eq!(
+            Antichess.game_result(&pos, num_legal_moves as usize),
+            Some(GameResult::NoMovesLeft {
+                winner: Colour::Black
+            })
+        );
+    }
+
+    fn legal_perft(pos: &mut Position, depth: u8) -> u64 {
+        let mut move_list = MoveList::new();
+        MoveGenerator::new().generate_legal_moves(pos, &mut move_list);
+        if depth == 1 {
+            return move_list.len() as u64;
+        }
+
+        let mut nodes = 0;
+        for mv in move_list.iterator() {
+            pos.make_move(mv);
+            nodes += legal_perft(pos, depth - 1);
+            pos.take_move();
+        }
+        nodes
+    }
+}
@@ -2,9 +2,6 @@ use super::castle_permissions::CastlePermission;
 use crate::board::colour::Colour;
 use crate::board::piece::Piece;
 use crate::board::square::Square;
-use rand::RngCore;
-use rand_xoshiro::rand_core::SeedableRng;
-use rand_xoshiro::Xoshiro256PlusPlus;
 
 pub type ZobristHash = u64;
 
@@ -16,34 +13,58 @@ pub struct ZobristKeys {
     en_passant_sq_keys: [ZobristHash; Square::NUM_SQUARES],
 }
 
-impl Default for ZobristKeys {
-    fn default() -> Self {
-        ZobristKeys {
-            piece_keys: [[[0; Piece::NUM_PIECE_TYPES]; Square::NUM_SQUARES]; Colour::NUM_COLOURS],
-            side_key: 0,
-            castle_keys: [0; CastlePermission::NUM_CASTLE_PERMS],
-            en_passant_sq_keys: [0; Square::NUM_SQUARES],
-        }
-    }
-}
+// The keys are generated at compile time from a fixed seed, so they're the
+// same in every build and every run (eg, for hashes saved to a file)
+const ZOBRIST_SEED: u64 = 0x646F_6C70_6869_6E21;
+
+static ZOBRIST_KEYS: ZobristKeys = ZobristKeys::generate(ZOBRIST_SEED);
 
 impl ZobristKeys {
-    pub fn new() -> Box<ZobristKeys> {
-        let mut rng = Xoshiro256PlusPlus::seed_from_u64(0);
+    pub fn instance() -> &'static ZobristKeys {
+        &ZOBRIST_KEYS
+    }
+
+    const fn generate(seed: u64) -> ZobristKeys {
+        let mut rng = SplitMix64(seed);
+
+        let mut piece_keys =
+            [[[0; Piece::NUM_PIECE_TYPES]; Square::NUM_SQUARES]; Colour::NUM_COLOURS];
+        let mut colour = 0;
+        while colour < Colour::NUM_COLOURS {
+            let mut sq = 0;
+            while sq < Square::NUM_SQUARES {
+                let mut piece = 0;
+                while piece < Piece::NUM_PIECE_TYPES {
+                    piece_keys[colour][sq][piece] = rng.next_u64();
+                    piece += 1;
+                }
+                sq += 1;
+            }
+            colour += 1;
+        }
+
+        let mut castle_keys = [0; CastlePermission::NUM_CASTLE_PERMS];
+        let mut i = 0;
+        while i < CastlePermission::NUM_CASTLE_PERMS {
+            castle_keys[i] = rng.next_u64();
+            i += 1;
+        }
+
+        let mut en_passant_sq_keys = [0; Square::NUM_SQUARES];
+        let mut sq = 0;
+        while sq < Square::NUM_SQUARES {
+            en_passant_sq_keys[sq] = rng.next_u64();
+            sq += 1;
+        }
 
-        let piece_keys = init_piece_keys(&mut rng);
-        let castle_keys = init_castle_keys(&mut rng);
-        let en_passant_sq_keys = init_en_passant_keys(&mut rng);
         let side_key = rng.next_u64();
 
-        let keys = ZobristKeys {
+        ZobristKeys {
             piece_keys,
+            side_key,
             castle_keys,
             en_passant_sq_keys,
-            side_key,
-        };
-
-        Box::new(keys)
+        }
     }
 
     pub const fn side(&self) -> ZobristHash {
@@ -80,38 +101,54 @@ impl ZobristKeys {
     }
 }
 
-fn init_piece_keys(
-    rng: &mut Xoshiro256PlusPlus,
-) -> [[[ZobristHash; Piece::NUM_PIECE_TYPES]; Square::NUM_SQUARES]; Colour::NUM_COLOURS] {
-    let mut retval = [[[0u64; Piece::NUM_PIECE_TYPES]; Square::NUM_SQUARES]; Colour::NUM_COLOURS];
-    for element in retval.iter_mut().flat_map(|r| r.iter_mut()) {
-        for i in element {
-            *i = rng.next_u64();
-        }
+// SplitMix64, simple enough to run at compile time, and good enough for
+// Zobrist keys
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    const fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
     }
-    retval
 }
-fn init_castle_keys(
-    rng: &mut Xoshiro256PlusPlus,
-) -> [ZobristHash; CastlePermission::NUM_CASTLE_PERMS] {
-    let mut retval = [0u64; CastlePermission::NUM_CASTLE_PERMS];
-    for item in retval.iter_mut().take(CastlePermission::NUM_CASTLE_PERMS) {
-        let seed = rng.next_u64();
-        *item = seed;
-    }
-    retval
+
+// 2^64 / golden ratio (Fibonacci hashing), used to spread the hash bits
+// before an index is taken from them
+const INDEX_MIX_MULTIPLIER: u64 = 0x9E37_79B9_7F4A_7C15;
+
+/// Derives a table index in the range 0..capacity from a hash.
+///
+/// The high half of the hash is folded into the low half and the result is
+/// mixed with a multiply. The high bits of the product are then scaled onto
+/// the table (multiply-shift), so all bits of the hash take part in the
+/// index, not just the low bits, and the capacity doesn't need to be a
+/// power of two.
+#[inline(always)]
+pub const fn hash_to_index(hash: ZobristHash, capacity: usize) -> usize {
+    let mixed = (hash ^ (hash >> 32)).wrapping_mul(INDEX_MIX_MULTIPLIER);
+    ((mixed as u128 * capacity as u128) >> 64) as usize
+}
+
+/// 16-bit key used to verify that a table entry belongs to the hash
+#[inline(always)]
+pub const fn verification_key_16(hash: ZobristHash) -> u16 {
+    (hash >> 48) as u16
 }
-fn init_en_passant_keys(rng: &mut Xoshiro256PlusPlus) -> [ZobristHash; Square::NUM_SQUARES] {
-    let mut retval = [0u64; Square::NUM_SQUARES];
-    for item in retval.iter_mut().take(Square::NUM_SQUARES) {
-        let seed = rng.next_u64();
-        *item = seed;
-    }
-    retval
+
+/// 32-bit key used to verify that a table entry belongs to the hash
+#[inline(always)]
+pub const fn verification_key_32(hash: ZobristHash) -> u32 {
+    (hash >> 32) as u32
 }
 
 #[cfg(test)]
 pub mod tests {
+    use super::hash_to_index;
+    use super::verification_key_16;
+    use super::verification_key_32;
     use super::ZobristHash;
     use super::ZobristKeys;
     use crate::board::colour::Colour;
@@ -120,7 +157,7 @@ pub mod tests {
 
     #[test]
     pub fn piece_square_hashes_all_different() {
-        let keys = ZobristKeys::new();
+        let keys = ZobristKeys::instance();
         let mut v: Vec<ZobristHash> = Vec::new();
 
         let pieces = [
@@ -156,7 +193,7 @@ pub mod tests {
 
     #[test]
     pub fn en_passant_hashes_all_different() {
-        let keys = ZobristKeys::new();
+        let keys = ZobristKeys::instance();
         let mut v: Vec<ZobristHash> = Vec::new();
 
         for sq in Square::iterator() {
@@ -176,9 +213,58 @@ pub mod tests {
         }
     }
 
+    #[test]
+    pub fn keys_fixed_at_compile_time() {
+        // changing these changes every hash (eg, in saved files)
+        let keys = ZobristKeys::instance();
+        assert_eq!(
+            keys.piece_square(&Piece::Pawn, &Colour::White, &Square::A1),
+            0x8EE8_5F9D_C1FA_CF14
+        );
+        assert_eq!(keys.side(), 0x882A_47F3_520E_5D64);
+    }
+
     #[test]
     pub fn side_hash_is_non_zero() {
-        let keys = ZobristKeys::new();
+        let keys = ZobristKeys::instance();
         assert!(keys.side() != 0);
     }
+
+    #[test]
+    pub fn hash_to_index_within_capacity() {
+        let keys = ZobristKeys::instance();
+
+        for capacity in [1, 7, 1000, 1 << 16, 3_000_017] {
+            for sq in Square::iterator() {
+                let hash = keys.piece_square(&Piece::Knight, &Colour::White, sq);
+                assert!(hash_to_index(hash, capacity) < capacity);
+            }
+            assert!(hash_to_index(0, capacity) < capacity);
+            assert!(hash_to_index(u64::MAX, capacity) < capacity);
+        }
+    }
+
+    #[test]
+    pub fn hash_to_index_high_bits_spread_over_power_of_two_table() {
+        // hashes that only differ in their high bits all land on the same
+        // slot with a simple mask/modulo, check they are spread out here
+        const CAPACITY: usize = 1 << 10;
+        const NUM_HASHES: u64 = 256;
+
+        let mut indexes: Vec<usize> = (0..NUM_HASHES)
+            .map(|i| hash_to_index(i << 48, CAPACITY))
+            .collect();
+        indexes.sort();
+        indexes.dedup();
+
+        assert!(indexes.len() > (NUM_HASHES as usize * 9) / 10);
+    }
+
+    #[test]
+    pub fn verification_keys_taken_from_high_bits() {
+        let hash: ZobristHash = 0xABCD_1234_5678_9EF0;
+
+        assert_eq!(verification_key_16(hash), 0xABCD);
+        assert_eq!(verification_key_32(hash), 0xABCD_1234);
+    }
 }
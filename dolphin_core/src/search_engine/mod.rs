@@ -1,3 +0,0 @@
-pub mod evaluate;
-pub mod search;
-pub mod tt;
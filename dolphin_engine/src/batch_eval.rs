@@ -0,0 +1,154 @@
+use crate::cli::{exit_with_usage, position_from_fen};
+use dolphin_core::{
+    moves::mov::Score,
+    position::{attack_checker::AttackChecker, game_position::Position},
+};
+use dolphin_search::{engine::SearchEngine, search::Search};
+use std::fs;
+use std::thread;
+
+// Searches each FEN in a file to a fixed depth and writes "FEN,score,bestmove"
+// CSV rows (eg, to label training positions):
+//      dolphin_engine eval FEN_FILE CSV_FILE [--depth N] [--threads N]
+// Scores are in centipawns from the side to move's point of view.
+
+const DEFAULT_DEPTH: u8 = 4;
+const TT_CAPACITY: usize = 1_000_000;
+
+const USAGE: &str = "usage: dolphin_engine eval FEN_FILE CSV_FILE [--depth N] [--threads N]";
+
+pub fn run(args: &[String]) {
+    let mut depth = DEFAULT_DEPTH;
+    let mut num_threads = 1;
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => depth = parse_num(args.next()),
+            "--threads" => num_threads = parse_num(args.next()),
+            _ if !arg.starts_with("--") => files.push(arg),
+            _ => exit_with_usage(USAGE),
+        }
+    }
+    let [fen_file, csv_file] = files[..] else {
+        exit_with_usage(USAGE);
+    };
+
+    let contents = fs::read_to_string(fen_file).expect("Unable to read FEN file");
+    let fens: Vec<&str> = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    let rows = evaluate_fens(&fens, depth, num_threads);
+
+    let mut csv = String::from("fen,score,bestmove\n");
+    for row in rows {
+        csv.push_str(&row);
+        csv.push('\n');
+    }
+    fs::write(csv_file, csv).expect("Unable to write CSV file");
+}
+
+// Splits the FENs between the threads, each with its own search (and TT).
+// Rows are returned in the same order as the FENs, less any that don't
+// parse.
+pub fn evaluate_fens(fens: &[&str], depth: u8, num_threads: usize) -> Vec<String> {
+    let attack_checker = AttackChecker::new();
+
+    let chunk_size = fens.len().div_ceil(num_threads.max(1)).max(1);
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = fens
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let attack_checker = &attack_checker;
+                scope.spawn(move || {
+                    let mut search = Search::new(TT_CAPACITY, depth);
+                    chunk
+                        .iter()
+                        .filter_map(|fen| {
+                            let mut pos = match position_from_fen(fen, attack_checker) {
+                                Ok(pos) => pos,
+                                Err(err) => {
+                                    eprintln!("Skipping FEN '{}': {}", fen, err);
+                                    return None;
+                                }
+                            };
+                            search.new_game();
+                            let (score, best_move) = evaluate(&mut search, &mut pos);
+                            Some(format!("{},{},{}", fen, score, best_move))
+                        })
+                        .collect::<Vec<String>>()
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().expect("Evaluation thread panicked"))
+            .collect()
+    })
+}
+
+// best move is empty if there are no legal moves
+fn evaluate(search: &mut impl SearchEngine, pos: &mut Position) -> (Score, String) {
+    match search.search(pos) {
+        Some(mv) => (search.score(), mv.to_uci_string()),
+        None => (search.score(), String::new()),
+    }
+}
+
+fn parse_num<T: std::str::FromStr + Default + PartialOrd>(arg: Option<&String>) -> T {
+    match arg.and_then(|n| n.parse::<T>().ok()) {
+        Some(n) if n > T::default() => n,
+        _ => exit_with_usage(USAGE),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::evaluate_fens;
+
+    #[test]
+    pub fn rows_in_fen_order_for_any_number_of_threads() {
+        let fens = [
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+            "k7/8/1K6/8/8/8/8/7R w - - 0 1",
+            "4k3/8/8/8/8/8/8/r3K3 b - - 0 1",
+        ];
+
+        let single = evaluate_fens(&fens, 2, 1);
+        let multi = evaluate_fens(&fens, 2, 4);
+        assert_eq!(single, multi);
+
+        assert_eq!(single.len(), fens.len());
+        for (row, fen) in single.iter().zip(fens.iter()) {
+            assert!(row.starts_with(&format!("{},", fen)));
+        }
+        // mate in 1
+        assert!(single[1].ends_with(",h1h8"));
+    }
+
+    #[test]
+    pub fn fens_that_dont_parse_are_skipped() {
+        let fens = [
+            "4k3/8/8/8/8/8/8/4K3 w",
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4K3 x - - 0 1",
+        ];
+
+        let rows = evaluate_fens(&fens, 1, 2);
+        assert_eq!(rows.len(), 1);
+        assert!(rows[0].starts_with(&format!("{},", fens[1])));
+    }
+
+    #[test]
+    pub fn position_without_moves_has_empty_best_move() {
+        // stalemate
+        let rows = evaluate_fens(&["k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"], 1, 2);
+        assert_eq!(rows, ["k7/2Q5/1K6/8/8/8/8/8 b - - 0 1,0,"]);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::cli::{exit_with_usage, position_from_fen};
+use dolphin_core::position::attack_checker::AttackChecker;
+use dolphin_search::search::{Search, SearchParams};
+use std::time::Instant;
+
+// Searches a fixed set of positions to a fixed depth and reports the nodes
+// and speed, so search changes (eg, pruning) can be compared on the same
+// work:
+//      dolphin_engine bench [--depth N] [--no-delta-pruning]
+
+const DEFAULT_DEPTH: u8 = 3;
+const TT_CAPACITY: usize = 1_000_000;
+
+const USAGE: &str = "usage: dolphin_engine bench [--depth N] [--no-delta-pruning]";
+
+// Endgames, as quiescence still searches every move and the middlegame
+// positions don't finish at a useful depth
+const BENCH_FENS: [&str; 6] = [
+    "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1",
+    "4k3/8/2n5/3q4/8/2N5/8/3RK3 w - - 0 1",
+    "4k3/pp6/8/3q4/8/8/PP6/3RK3 w - - 0 1",
+    "6k1/5ppp/8/8/8/8/5PPP/3R2K1 w - - 0 1",
+    "2r3k1/5ppp/8/8/8/8/5PPP/2R3K1 w - - 0 1",
+    "8/8/4k3/3n4/8/2B5/4K3/8 w - - 0 1",
+];
+
+pub fn run(args: &[String]) {
+    let mut depth = DEFAULT_DEPTH;
+    let mut params = SearchParams::default();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => {
+                depth = match args.next().and_then(|n| n.parse::<u8>().ok()) {
+                    Some(depth) if depth > 0 => depth,
+                    _ => exit_with_usage(USAGE),
+                }
+            }
+            "--no-delta-pruning" => params.delta_margin = None,
+            _ => exit_with_usage(USAGE),
+        }
+    }
+
+    let attack_checker = AttackChecker::new();
+    let mut total_nodes = 0;
+    let mut total_delta_pruned = 0;
+    let start = Instant::now();
+
+    for fen in BENCH_FENS {
+        let mut pos = position_from_fen(fen, &attack_checker).expect("Invalid bench FEN");
+        let mut search = Search::new(TT_CAPACITY, depth);
+        search.set_params(params);
+        search.search(&mut pos);
+
+        println!(
+            "{} : nodes {}, delta pruned {}",
+            fen,
+            search.nodes(),
+            search.delta_pruned()
+        );
+        total_nodes += search.nodes();
+        total_delta_pruned += search.delta_pruned();
+    }
+
+    let elapsed = start.elapsed();
+    println!(
+        "depth {}, nodes {}, delta pruned {}, time {} ms, nps {}",
+        depth,
+        total_nodes,
+        total_delta_pruned,
+        elapsed.as_millis(),
+        (total_nodes as f64 / elapsed.as_secs_f64().max(f64::EPSILON)) as u64
+    );
+}
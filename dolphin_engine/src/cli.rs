@@ -0,0 +1,33 @@
+use dolphin_core::{
+    board::occupancy_masks::OccupancyMasks,
+    io::fen::{self, FenError},
+    position::{attack_checker::AttackChecker, game_position::Position},
+};
+use std::process;
+
+// Helpers shared by the subcommands
+
+// The position, or why the FEN isn't one. FENs come from the command line
+// and from files, so a bad one is reported rather than exiting.
+pub fn position_from_fen<'a>(
+    fen: &str,
+    attack_checker: &'a AttackChecker,
+) -> Result<Position<'a>, FenError> {
+    let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+        fen::try_decompose_fen(fen)?;
+
+    Ok(Position::new(
+        board,
+        castle_permissions,
+        move_cntr,
+        en_pass_sq,
+        side_to_move,
+        OccupancyMasks::instance(),
+        attack_checker,
+    ))
+}
+
+pub fn exit_with_usage(usage: &str) -> ! {
+    eprintln!("{}", usage);
+    process::exit(1);
+}
@@ -0,0 +1,66 @@
+use std::process;
+
+// The release builds are compiled for increasing sets of CPU features (see
+// build_release_targets.sh). A build run on a CPU without one of its
+// features dies with SIGILL at the first instruction that needs it, so the
+// features are checked at startup instead. They're read from the target
+// features the compiler was given, so they can't disagree with the code.
+
+// the build's name, after the most advanced feature it was compiled for
+pub fn build_name() -> &'static str {
+    if cfg!(target_feature = "avx2") {
+        "avx2"
+    } else if cfg!(target_feature = "bmi2") {
+        "bmi2"
+    } else if cfg!(target_feature = "popcnt") {
+        "popcnt"
+    } else {
+        "generic"
+    }
+}
+
+// features the build was compiled for that the CPU doesn't have
+#[cfg(target_arch = "x86_64")]
+pub fn missing_cpu_features() -> Vec<&'static str> {
+    let mut missing = Vec::new();
+    if cfg!(target_feature = "popcnt") && !is_x86_feature_detected!("popcnt") {
+        missing.push("popcnt");
+    }
+    if cfg!(target_feature = "bmi2") && !is_x86_feature_detected!("bmi2") {
+        missing.push("bmi2");
+    }
+    if cfg!(target_feature = "avx2") && !is_x86_feature_detected!("avx2") {
+        missing.push("avx2");
+    }
+    missing
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+pub fn missing_cpu_features() -> Vec<&'static str> {
+    Vec::new()
+}
+
+pub fn exit_if_unsupported() {
+    let missing = missing_cpu_features();
+    if !missing.is_empty() {
+        eprintln!(
+            "This is the {} build of dolphin_engine, but the CPU doesn't support {}. \
+             Use a build for an older CPU (eg, the generic one).",
+            build_name(),
+            missing.join(", ")
+        );
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::build_name;
+    use super::missing_cpu_features;
+
+    #[test]
+    pub fn test_build_runs_on_the_cpu_it_was_built_on() {
+        assert!(missing_cpu_features().is_empty());
+        assert!(["generic", "popcnt", "bmi2", "avx2"].contains(&build_name()));
+    }
+}
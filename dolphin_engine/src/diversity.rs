@@ -0,0 +1,168 @@
+use crate::cli::{exit_with_usage, position_from_fen};
+use crate::play::parse_move;
+use dolphin_core::{
+    io::pgn::{PgnGame, PgnReader},
+    moves::mov::Move,
+    position::attack_checker::AttackChecker,
+};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::BufReader;
+
+// Reports how varied the openings are in a PGN file of self-play games,
+// to help decide on book and noise settings for training data:
+//      dolphin_engine diversity PGN_FILE [--plies N]
+// For each ply up to N it gives the number of distinct positions reached,
+// and the share of games whose first N plies repeat an earlier game's.
+
+const DEFAULT_PLIES: usize = 16;
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const USAGE: &str = "usage: dolphin_engine diversity PGN_FILE [--plies N]";
+
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct OpeningDiversity {
+    pub num_games: usize,
+    // for each ply, the games that reached it and the distinct positions
+    pub games_by_ply: Vec<usize>,
+    pub unique_positions_by_ply: Vec<usize>,
+    // games whose opening (the first plies, or the whole game if shorter)
+    // was played in an earlier game
+    pub repeated_lines: usize,
+}
+
+impl OpeningDiversity {
+    pub fn repeated_line_percent(&self) -> f64 {
+        if self.num_games == 0 {
+            return 0.0;
+        }
+        100.0 * self.repeated_lines as f64 / self.num_games as f64
+    }
+}
+
+pub fn run(args: &[String]) {
+    let mut max_plies = DEFAULT_PLIES;
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--plies" => {
+                max_plies = match args.next().and_then(|n| n.parse::<usize>().ok()) {
+                    Some(plies) if plies > 0 => plies,
+                    _ => exit_with_usage(USAGE),
+                }
+            }
+            _ if !arg.starts_with("--") => files.push(arg),
+            _ => exit_with_usage(USAGE),
+        }
+    }
+    let [pgn_file] = files[..] else {
+        exit_with_usage(USAGE);
+    };
+
+    let file = File::open(pgn_file).expect("Unable to open PGN file");
+    let diversity = opening_diversity(PgnReader::new(BufReader::new(file)), max_plies);
+
+    println!("games {}", diversity.num_games);
+    println!("ply  games  unique positions");
+    for (ply, (games, unique)) in diversity
+        .games_by_ply
+        .iter()
+        .zip(diversity.unique_positions_by_ply.iter())
+        .enumerate()
+    {
+        println!("{:>3}  {:>5}  {:>16}", ply + 1, games, unique);
+    }
+    println!(
+        "repeated lines (first {} plies) {:.1}%",
+        max_plies,
+        diversity.repeated_line_percent()
+    );
+}
+
+// Replays each game's opening, stopping at a move that doesn't parse or
+// isn't legal. Games start from their FEN tag, if they have one.
+pub fn opening_diversity(
+    games: impl Iterator<Item = PgnGame>,
+    max_plies: usize,
+) -> OpeningDiversity {
+    let attack_checker = AttackChecker::new();
+
+    let mut diversity = OpeningDiversity {
+        games_by_ply: vec![0; max_plies],
+        unique_positions_by_ply: vec![0; max_plies],
+        ..Default::default()
+    };
+    let mut positions_by_ply = vec![HashSet::new(); max_plies];
+    let mut lines: HashSet<(String, Vec<Move>)> = HashSet::new();
+
+    for game in games {
+        let start_fen = game.tag("FEN").unwrap_or(START_FEN).to_string();
+        let Ok(mut pos) = position_from_fen(&start_fen, &attack_checker) else {
+            continue;
+        };
+
+        let mut line = Vec::new();
+        for san in game.san_moves().into_iter().take(max_plies) {
+            let Some(mv) = parse_move(&mut pos, san) else {
+                break;
+            };
+            pos.make_move(&mv);
+
+            let ply = line.len();
+            diversity.games_by_ply[ply] += 1;
+            positions_by_ply[ply].insert(pos.position_hash());
+            line.push(mv);
+        }
+
+        diversity.num_games += 1;
+        if !lines.insert((start_fen, line)) {
+            diversity.repeated_lines += 1;
+        }
+    }
+
+    diversity.unique_positions_by_ply = positions_by_ply.iter().map(HashSet::len).collect();
+    diversity
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::opening_diversity;
+    use dolphin_core::io::pgn::PgnReader;
+
+    const GAMES: &str = r#"[Event "self-play"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 1-0
+
+[Event "self-play"]
+[Result "0-1"]
+
+1. e4 e5 2. Nf3 Nc6 3. Bb5 a6 0-1
+
+[Event "self-play"]
+[Result "1/2-1/2"]
+
+1. Nf3 e5 2. e4 Nc6 1/2-1/2
+
+[Event "self-play"]
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 Nc6 1-0
+"#;
+
+    #[test]
+    pub fn unique_positions_and_repeated_lines_counted() {
+        let diversity = opening_diversity(PgnReader::new(GAMES.as_bytes()), 4);
+
+        assert_eq!(diversity.num_games, 4);
+        assert_eq!(diversity.games_by_ply, vec![4, 4, 4, 4]);
+        // the third game transposes into the others at ply 4
+        assert_eq!(diversity.unique_positions_by_ply, vec![2, 2, 2, 1]);
+        // the second and fourth games repeat the first one's 4 plies
+        assert_eq!(diversity.repeated_lines, 2);
+        assert_eq!(diversity.repeated_line_percent(), 50.0);
+    }
+}
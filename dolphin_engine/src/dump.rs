@@ -0,0 +1,343 @@
+use crate::cli::{exit_with_usage, position_from_fen};
+use crate::cpu_target;
+use dolphin_core::{io::fen::FenError, moves::mov::Score, position::attack_checker::AttackChecker};
+use dolphin_search::{
+    evaluate::EvalParams,
+    search::{Search, SearchParams},
+};
+use std::fs;
+use std::process;
+
+// Reproducible searches, for bug reports:
+//      dolphin_engine dump FEN [--depth N] [--nodes N] [--tt N] [--no-delta-pruning]
+// searches the position and prints a JSON snapshot of everything the result
+// depends on (the position, the limits, the search and eval parameters and
+// the build), along with the result, and
+//      dolphin_engine replay SNAPSHOT
+// searches it again, from the JSON or a file holding it, and says whether
+// the result is the same. The search is single threaded and the Zobrist
+// keys are fixed, so a depth or node limited search always is, on the same
+// code. There's no parameter file, so there's no hash of one.
+
+const DEFAULT_DEPTH: u8 = 5;
+const DEFAULT_TT_CAPACITY: usize = 1_000_000;
+
+const DUMP_USAGE: &str =
+    "usage: dolphin_engine dump FEN [--depth N] [--nodes N] [--tt N] [--no-delta-pruning]";
+const REPLAY_USAGE: &str = "usage: dolphin_engine replay SNAPSHOT_JSON|SNAPSHOT_FILE";
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct SearchSnapshot {
+    pub fen: String,
+    pub depth: u8,
+    pub node_limit: Option<u64>,
+    pub tt_capacity: usize,
+    pub params: SearchParams,
+    pub eval_params: EvalParams,
+    // eg "0.1.0 release avx2"
+    pub build: String,
+    pub result: SearchResult,
+}
+
+#[derive(Debug, Default, Eq, PartialEq, Clone)]
+pub struct SearchResult {
+    // UCI, or "none" if there's no legal move
+    pub bestmove: String,
+    pub score: Score,
+    pub nodes: u64,
+}
+
+impl SearchSnapshot {
+    // the search's "go" command, as it would be sent over UCI
+    pub fn go_command(&self) -> String {
+        match self.node_limit {
+            Some(nodes) => format!("go depth {} nodes {}", self.depth, nodes),
+            None => format!("go depth {}", self.depth),
+        }
+    }
+
+    // the result is left as it is
+    pub fn search(&self) -> Result<SearchResult, FenError> {
+        let attack_checker = AttackChecker::new();
+        let mut pos = position_from_fen(&self.fen, &attack_checker)?;
+
+        let mut search = Search::new(self.tt_capacity, self.depth);
+        search.set_params(self.params);
+        search.set_eval_params(self.eval_params);
+        search.set_node_limit(self.node_limit);
+        let best_move = search.search(&mut pos);
+
+        Ok(SearchResult {
+            bestmove: best_move.map_or("none".to_string(), |mv| mv.to_uci_string()),
+            score: search.score(),
+            nodes: search.nodes(),
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"fen\":\"{}\",\"go\":\"{}\",\"depth\":{},\"nodes\":{},\"tt_capacity\":{},\
+             \"max_extensions\":{},\"max_ply\":{},\"delta_margin\":{},\"order_moves\":{},\
+             \"aspiration_window\":{},\"tempo\":{},\"grain\":{},\"build\":\"{}\",\
+             \"bestmove\":\"{}\",\"score\":{},\"result_nodes\":{}}}",
+            self.fen,
+            self.go_command(),
+            self.depth,
+            json_option(self.node_limit),
+            self.tt_capacity,
+            self.params.max_extensions,
+            self.params.max_ply,
+            json_option(self.params.delta_margin),
+            self.params.order_moves,
+            json_option(self.params.aspiration_window),
+            self.eval_params.tempo,
+            self.eval_params.grain,
+            self.build,
+            self.result.bestmove,
+            self.result.score,
+            self.result.nodes
+        )
+    }
+
+    pub fn from_json(json: &str) -> Result<SearchSnapshot, String> {
+        let fields = parse_flat_json(json)?;
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+                .ok_or(format!("missing \"{}\"", name))
+        };
+        let number = |name: &str| {
+            field(name)?
+                .parse::<i64>()
+                .map_err(|_| format!("\"{}\" isn't a number", name))
+        };
+        let optional = |name: &str| match field(name)? {
+            "null" => Ok(None),
+            _ => number(name).map(Some),
+        };
+
+        Ok(SearchSnapshot {
+            fen: field("fen")?.to_string(),
+            depth: number("depth")? as u8,
+            node_limit: optional("nodes")?.map(|nodes| nodes as u64),
+            tt_capacity: number("tt_capacity")? as usize,
+            params: SearchParams {
+                max_extensions: number("max_extensions")? as u8,
+                max_ply: number("max_ply")? as u8,
+                delta_margin: optional("delta_margin")?.map(|margin| margin as Score),
+                order_moves: field("order_moves")? == "true",
+                aspiration_window: optional("aspiration_window")?.map(|window| window as Score),
+            },
+            eval_params: EvalParams {
+                tempo: number("tempo")? as Score,
+                grain: number("grain")? as Score,
+            },
+            build: field("build")?.to_string(),
+            result: SearchResult {
+                bestmove: field("bestmove")?.to_string(),
+                score: number("score")? as Score,
+                nodes: number("result_nodes")? as u64,
+            },
+        })
+    }
+}
+
+pub fn build_info() -> String {
+    let profile = if cfg!(debug_assertions) {
+        "debug"
+    } else {
+        "release"
+    };
+    format!(
+        "{} {} {}",
+        env!("CARGO_PKG_VERSION"),
+        profile,
+        cpu_target::build_name()
+    )
+}
+
+pub fn run_dump(args: &[String]) {
+    let mut args = args.iter();
+    let Some(fen) = args.next() else {
+        exit_with_usage(DUMP_USAGE);
+    };
+
+    let mut snapshot = SearchSnapshot {
+        fen: fen.to_string(),
+        depth: DEFAULT_DEPTH,
+        node_limit: None,
+        tt_capacity: DEFAULT_TT_CAPACITY,
+        params: SearchParams::default(),
+        eval_params: EvalParams::default(),
+        build: build_info(),
+        result: SearchResult::default(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => {
+                snapshot.depth = match args.next().and_then(|n| n.parse::<u8>().ok()) {
+                    Some(depth) if depth > 0 => depth,
+                    _ => exit_with_usage(DUMP_USAGE),
+                }
+            }
+            "--nodes" => {
+                snapshot.node_limit = match args.next().and_then(|n| n.parse::<u64>().ok()) {
+                    Some(nodes) => Some(nodes),
+                    None => exit_with_usage(DUMP_USAGE),
+                }
+            }
+            "--tt" => {
+                snapshot.tt_capacity = match args.next().and_then(|n| n.parse::<usize>().ok()) {
+                    Some(capacity) if capacity > 0 => capacity,
+                    _ => exit_with_usage(DUMP_USAGE),
+                }
+            }
+            "--no-delta-pruning" => snapshot.params.delta_margin = None,
+            _ => exit_with_usage(DUMP_USAGE),
+        }
+    }
+
+    snapshot.result = search_or_exit(&snapshot);
+    println!("{}", snapshot.to_json());
+}
+
+pub fn run_replay(args: &[String]) {
+    let [arg] = args else {
+        exit_with_usage(REPLAY_USAGE);
+    };
+    let json = if arg.trim_start().starts_with('{') {
+        arg.to_string()
+    } else {
+        fs::read_to_string(arg).expect("Unable to read snapshot file")
+    };
+    let snapshot = SearchSnapshot::from_json(&json).unwrap_or_else(|err| {
+        eprintln!("Invalid snapshot: {}", err);
+        process::exit(1);
+    });
+
+    if snapshot.build != build_info() {
+        println!(
+            "snapshot from build {}, replaying on {}",
+            snapshot.build,
+            build_info()
+        );
+    }
+    println!("{} on {}", snapshot.go_command(), snapshot.fen);
+
+    let result = search_or_exit(&snapshot);
+    if result == snapshot.result {
+        println!("reproduced: {:?}", result);
+    } else {
+        println!("differs: was {:?}, now {:?}", snapshot.result, result);
+        process::exit(1);
+    }
+}
+
+fn search_or_exit(snapshot: &SearchSnapshot) -> SearchResult {
+    snapshot.search().unwrap_or_else(|err| {
+        eprintln!("{} in FEN '{}'", err, snapshot.fen);
+        process::exit(1);
+    })
+}
+
+fn json_option<T: ToString>(value: Option<T>) -> String {
+    value.map_or("null".to_string(), |value| value.to_string())
+}
+
+// Parses an object of strings, numbers, booleans and nulls (all a snapshot
+// has) into its keys and values, the values as they were written without
+// any quotes. Strings can't have escapes, as FENs and moves don't need them.
+fn parse_flat_json(json: &str) -> Result<Vec<(String, String)>, String> {
+    let body = json
+        .trim()
+        .strip_prefix('{')
+        .and_then(|rest| rest.strip_suffix('}'))
+        .ok_or("not a JSON object")?;
+
+    let mut fields = Vec::new();
+    let mut rest = body.trim();
+    while !rest.is_empty() {
+        let (key, after_key) = parse_string(rest)?;
+        let after_colon = after_key
+            .trim_start()
+            .strip_prefix(':')
+            .ok_or(format!("no value for \"{}\"", key))?
+            .trim_start();
+
+        let (value, after_value) = if after_colon.starts_with('"') {
+            parse_string(after_colon)?
+        } else {
+            let end = after_colon.find(',').unwrap_or(after_colon.len());
+            (after_colon[..end].trim().to_string(), &after_colon[end..])
+        };
+        fields.push((key, value));
+
+        rest = after_value.trim_start();
+        if let Some(after_comma) = rest.strip_prefix(',') {
+            rest = after_comma.trim_start();
+        } else if !rest.is_empty() {
+            return Err(format!("unexpected \"{}\"", rest));
+        }
+    }
+    Ok(fields)
+}
+
+// a quoted string at the start, and what follows it
+fn parse_string(text: &str) -> Result<(String, &str), String> {
+    let inner = text.strip_prefix('"').ok_or("expected a string")?;
+    let end = inner.find('"').ok_or("unterminated string")?;
+    Ok((inner[..end].to_string(), &inner[end + 1..]))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::build_info;
+    use super::SearchResult;
+    use super::SearchSnapshot;
+    use dolphin_search::evaluate::EvalParams;
+    use dolphin_search::search::SearchParams;
+
+    fn sample_snapshot() -> SearchSnapshot {
+        SearchSnapshot {
+            fen: "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1".to_string(),
+            depth: 3,
+            node_limit: Some(5000),
+            tt_capacity: 1000,
+            params: SearchParams {
+                delta_margin: None,
+                ..Default::default()
+            },
+            eval_params: EvalParams::default(),
+            build: build_info(),
+            result: SearchResult::default(),
+        }
+    }
+
+    #[test]
+    pub fn snapshot_read_back_from_json() {
+        let mut snapshot = sample_snapshot();
+        snapshot.result = SearchResult {
+            bestmove: "d1d5".to_string(),
+            score: 850,
+            nodes: 1234,
+        };
+        let json = snapshot.to_json();
+
+        assert!(json.contains("\"go\":\"go depth 3 nodes 5000\""));
+        assert_eq!(SearchSnapshot::from_json(&json), Ok(snapshot));
+        assert!(SearchSnapshot::from_json("{\"fen\":\"8/8 w\"}").is_err());
+        assert!(SearchSnapshot::from_json("not json").is_err());
+    }
+
+    #[test]
+    pub fn replayed_search_gives_same_result() {
+        let mut snapshot = sample_snapshot();
+        snapshot.result = snapshot.search().unwrap();
+        assert_eq!(snapshot.result.bestmove, "d1d5");
+
+        let replayed = SearchSnapshot::from_json(&snapshot.to_json()).unwrap();
+        assert_eq!(replayed.search().unwrap(), snapshot.result);
+    }
+}
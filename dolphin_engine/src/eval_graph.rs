@@ -0,0 +1,188 @@
+use crate::cli::{exit_with_usage, position_from_fen};
+use crate::play::parse_move;
+use dolphin_core::{
+    board::colour::Colour,
+    io::pgn::{PgnGame, PgnReader},
+    moves::mov::Score,
+    position::attack_checker::AttackChecker,
+};
+use dolphin_search::{evaluate::evaluate_board, search::Search};
+use std::fs::{self, File};
+use std::io::BufReader;
+
+// Annotates the games in a PGN file with the static and search eval after
+// each ply, for GUIs to draw the eval graph from:
+//      dolphin_engine evalgraph PGN_FILE OUT_FILE [--depth N]
+// Writes JSON if OUT_FILE ends in .json, otherwise "game,ply,move,static,search"
+// CSV rows. Evals are in centipawns from white's point of view, as graphs
+// are drawn. A game is annotated up to a move that doesn't parse or isn't
+// legal.
+
+const DEFAULT_DEPTH: u8 = 4;
+const TT_CAPACITY: usize = 1_000_000;
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const USAGE: &str = "usage: dolphin_engine evalgraph PGN_FILE OUT_FILE [--depth N]";
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct PlyEval {
+    // from 1, in file order
+    pub game: usize,
+    // from 1, the position after the ply's move
+    pub ply: usize,
+    // as it was in the PGN
+    pub san: String,
+    pub static_eval: Score,
+    pub search_eval: Score,
+}
+
+impl PlyEval {
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{}",
+            self.game, self.ply, self.san, self.static_eval, self.search_eval
+        )
+    }
+
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"game\":{},\"ply\":{},\"move\":\"{}\",\"static\":{},\"search\":{}}}",
+            self.game, self.ply, self.san, self.static_eval, self.search_eval
+        )
+    }
+}
+
+pub fn run(args: &[String]) {
+    let mut depth = DEFAULT_DEPTH;
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => {
+                depth = match args.next().and_then(|d| d.parse::<u8>().ok()) {
+                    Some(d) if d > 0 => d,
+                    _ => exit_with_usage(USAGE),
+                }
+            }
+            _ if !arg.starts_with("--") => files.push(arg),
+            _ => exit_with_usage(USAGE),
+        }
+    }
+    let [pgn_file, out_file] = files[..] else {
+        exit_with_usage(USAGE);
+    };
+
+    let file = File::open(pgn_file).expect("Unable to open PGN file");
+    let evals = annotate_games(PgnReader::new(BufReader::new(file)), depth);
+
+    let out = if out_file.ends_with(".json") {
+        let rows: Vec<String> = evals.iter().map(PlyEval::to_json).collect();
+        format!("[\n{}\n]\n", rows.join(",\n"))
+    } else {
+        let mut csv = String::from("game,ply,move,static,search\n");
+        for eval in evals.iter() {
+            csv.push_str(&eval.to_csv());
+            csv.push('\n');
+        }
+        csv
+    };
+    fs::write(out_file, out).expect("Unable to write eval file");
+}
+
+// Games start from their FEN tag, if they have one (and are skipped if it
+// doesn't parse). The TT is cleared
+// between games, but not between the plies of a game, as a GUI's engine
+// analysing the game wouldn't.
+pub fn annotate_games(games: impl Iterator<Item = PgnGame>, depth: u8) -> Vec<PlyEval> {
+    let attack_checker = AttackChecker::new();
+    let mut search = Search::new(TT_CAPACITY, depth);
+
+    let mut evals = Vec::new();
+    for (game_index, game) in games.enumerate() {
+        search.new_game();
+        let start_fen = game.tag("FEN").unwrap_or(START_FEN);
+        let Ok(mut pos) = position_from_fen(start_fen, &attack_checker) else {
+            continue;
+        };
+
+        for (ply, san) in game.san_moves().into_iter().enumerate() {
+            let Some(mv) = parse_move(&mut pos, san) else {
+                break;
+            };
+            pos.make_move(&mv);
+
+            let static_eval = evaluate_board(pos.board(), pos.side_to_move());
+            search.search(&mut pos);
+            evals.push(PlyEval {
+                game: game_index + 1,
+                ply: ply + 1,
+                san: san.to_string(),
+                static_eval: for_white(static_eval, pos.side_to_move()),
+                search_eval: for_white(search.score(), pos.side_to_move()),
+            });
+        }
+    }
+    evals
+}
+
+// evals are from the side to move's point of view
+fn for_white(score: Score, side_to_move: Colour) -> Score {
+    match side_to_move {
+        Colour::White => score,
+        Colour::Black => -score,
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::annotate_games;
+    use dolphin_core::io::pgn::PgnReader;
+
+    const GAMES: &str = r#"[Result "1-0"]
+
+1. e4 d5 2. exd5 Qxd5 1-0
+
+[Result "0-1"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/8/q3K3 b - - 0 1"]
+
+1... Qb2 2. Kf1 0-1
+"#;
+
+    #[test]
+    pub fn evals_per_ply_from_whites_point_of_view() {
+        let evals = annotate_games(PgnReader::new(GAMES.as_bytes()), 1);
+
+        let plies: Vec<(usize, usize, &str)> = evals
+            .iter()
+            .map(|eval| (eval.game, eval.ply, eval.san.as_str()))
+            .collect();
+        assert_eq!(
+            plies,
+            vec![
+                (1, 1, "e4"),
+                (1, 2, "d5"),
+                (1, 3, "exd5"),
+                (1, 4, "Qxd5"),
+                (2, 1, "Qb2"),
+                (2, 2, "Kf1"),
+            ]
+        );
+
+        // white's pawn up after exd5, and black's a queen up in the second game
+        assert!(evals[2].static_eval > 50);
+        assert!(evals[2].search_eval < evals[2].static_eval);
+        assert!(evals[4].static_eval < -800);
+        assert!(evals[5].search_eval < -800);
+
+        assert_eq!(
+            evals[0].to_csv(),
+            format!("1,1,e4,{},{}", evals[0].static_eval, evals[0].search_eval)
+        );
+        assert!(evals[0]
+            .to_json()
+            .starts_with("{\"game\":1,\"ply\":1,\"move\":\"e4\","));
+    }
+}
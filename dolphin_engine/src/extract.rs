@@ -0,0 +1,277 @@
+use crate::cli::{exit_with_usage, position_from_fen};
+use crate::play::parse_move;
+use dolphin_core::{
+    io::pgn::{PgnGame, PgnReader},
+    moves::{
+        mov::{MoveType, Score},
+        move_gen::MoveGenerator,
+        move_list::MoveList,
+    },
+    position::{
+        attack_checker::AttackChecker,
+        game_position::{MoveLegality, Position},
+    },
+};
+use dolphin_search::evaluate::evaluate_board;
+use std::collections::HashSet;
+use std::fs::{self, File as FsFile};
+use std::io::BufReader;
+
+// Extracts training positions (eg, for Texel tuning) from a PGN file of
+// self-play games, with each game's result:
+//      dolphin_engine extract PGN_FILE CSV_FILE [--skip-plies N] [--margin N]
+// Writes "fen,result" CSV rows, the result being 1.0, 0.5 or 0.0 from
+// white's point of view. The opening plies are skipped, as they're the
+// book (or noise), and so are positions that aren't quiet: the side to
+// move is in check, or has a capture or promotion that gains more than the
+// margin by the static eval, so the eval of the position isn't its value.
+// A position reached more than once is only written the first time.
+// Games without a result are skipped.
+
+const DEFAULT_SKIP_PLIES: usize = 8;
+const DEFAULT_MARGIN: Score = 50;
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const USAGE: &str = "usage: dolphin_engine extract PGN_FILE CSV_FILE [--skip-plies N] [--margin N]";
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct TrainingPosition {
+    pub fen: String,
+    // "1.0", "0.5" or "0.0", from white's point of view
+    pub result: &'static str,
+}
+
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub struct ExtractStats {
+    pub games: usize,
+    pub positions: usize,
+    pub quiet: usize,
+    pub unique: usize,
+}
+
+pub fn run(args: &[String]) {
+    let mut skip_plies = DEFAULT_SKIP_PLIES;
+    let mut margin = DEFAULT_MARGIN;
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--skip-plies" => {
+                skip_plies = match args.next().and_then(|n| n.parse::<usize>().ok()) {
+                    Some(plies) => plies,
+                    None => exit_with_usage(USAGE),
+                }
+            }
+            "--margin" => {
+                margin = match args.next().and_then(|n| n.parse::<Score>().ok()) {
+                    Some(margin) if margin >= 0 => margin,
+                    _ => exit_with_usage(USAGE),
+                }
+            }
+            _ if !arg.starts_with("--") => files.push(arg),
+            _ => exit_with_usage(USAGE),
+        }
+    }
+    let [pgn_file, csv_file] = files[..] else {
+        exit_with_usage(USAGE);
+    };
+
+    let file = FsFile::open(pgn_file).expect("Unable to open PGN file");
+    let (positions, stats) =
+        extract_positions(PgnReader::new(BufReader::new(file)), skip_plies, margin);
+
+    let mut csv = String::from("fen,result\n");
+    for position in positions {
+        csv.push_str(&format!("{},{}\n", position.fen, position.result));
+    }
+    fs::write(csv_file, csv).expect("Unable to write CSV file");
+
+    println!(
+        "games {}, positions {}, quiet {}, unique {}",
+        stats.games, stats.positions, stats.quiet, stats.unique
+    );
+}
+
+// Replays each game, stopping at a move that doesn't parse or isn't legal.
+// Games start from their FEN tag, if they have one.
+pub fn extract_positions(
+    games: impl Iterator<Item = PgnGame>,
+    skip_plies: usize,
+    margin: Score,
+) -> (Vec<TrainingPosition>, ExtractStats) {
+    let attack_checker = AttackChecker::new();
+    let move_gen = MoveGenerator::new();
+
+    let mut stats = ExtractStats::default();
+    let mut positions = Vec::new();
+    let mut seen = HashSet::new();
+
+    for game in games {
+        let Some(result) = game.result().and_then(result_for_white) else {
+            continue;
+        };
+        let start_fen = game.tag("FEN").unwrap_or(START_FEN);
+        let Ok(mut pos) = position_from_fen(start_fen, &attack_checker) else {
+            continue;
+        };
+        stats.games += 1;
+
+        for (ply, san) in game.san_moves().into_iter().enumerate() {
+            let Some(mv) = parse_move(&mut pos, san) else {
+                break;
+            };
+            pos.make_move(&mv);
+            if ply < skip_plies {
+                continue;
+            }
+
+            stats.positions += 1;
+            if !is_quiet(&pos, &move_gen, margin) {
+                continue;
+            }
+            stats.quiet += 1;
+            if seen.insert(pos.position_hash()) {
+                stats.unique += 1;
+                positions.push(TrainingPosition {
+                    fen: pos.to_fen(),
+                    result,
+                });
+            }
+        }
+    }
+    (positions, stats)
+}
+
+fn result_for_white(result: &str) -> Option<&'static str> {
+    match result {
+        "1-0" => Some("1.0"),
+        "1/2-1/2" => Some("0.5"),
+        "0-1" => Some("0.0"),
+        _ => None,
+    }
+}
+
+// Not in check, and no capture or promotion gains more than the margin over
+// the static eval. Only the one move is looked at, so a capture of a
+// defended piece counts as a gain: some quiet positions are lost, but no
+// tactical ones get through.
+fn is_quiet(pos: &Position, move_gen: &MoveGenerator, margin: Score) -> bool {
+    if pos.is_king_sq_attacked() {
+        return false;
+    }
+
+    let stand_pat = evaluate_board(pos.board(), pos.side_to_move());
+
+    let mut move_list = MoveList::new();
+    move_gen.generate_moves(pos, &mut move_list);
+
+    move_list
+        .iterator()
+        .filter(|mv| pos.is_capture(mv) || mv.move_type() == MoveType::Promotion)
+        .all(|mv| {
+            let (child, legality) = pos.make_move_copied(mv);
+            if legality == MoveLegality::Illegal {
+                return true;
+            }
+            let score = -evaluate_board(child.board(), child.side_to_move());
+            score.saturating_sub(stand_pat) <= margin
+        })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::extract_positions;
+    use super::is_quiet;
+    use super::ExtractStats;
+    use crate::cli::position_from_fen;
+    use dolphin_core::io::pgn::PgnReader;
+    use dolphin_core::moves::move_gen::MoveGenerator;
+    use dolphin_core::position::attack_checker::AttackChecker;
+
+    const GAMES: &str = r#"[Event "self-play"]
+[Result "1-0"]
+
+1. e4 d5 2. exd5 Qxd5 3. Nc3 1-0
+
+[Event "self-play"]
+[Result "0-1"]
+
+1. e4 d5 0-1
+
+[Event "self-play"]
+[Result "*"]
+
+1. d4 *
+"#;
+
+    #[test]
+    pub fn quiet_unique_positions_extracted_with_results() {
+        let (positions, stats) = extract_positions(PgnReader::new(GAMES.as_bytes()), 0, 50);
+
+        // d5 and exd5 leave a capture, Nc3 leaves the queen two pawns to
+        // take, and the second game's e4 has been seen already
+        let fens: Vec<(&str, &str)> = positions
+            .iter()
+            .map(|position| (position.fen.as_str(), position.result))
+            .collect();
+        assert_eq!(
+            fens,
+            vec![
+                (
+                    "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1",
+                    "1.0"
+                ),
+                (
+                    "rnb1kbnr/ppp1pppp/8/3q4/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3",
+                    "1.0"
+                ),
+            ]
+        );
+        assert_eq!(
+            stats,
+            ExtractStats {
+                games: 2,
+                positions: 7,
+                quiet: 3,
+                unique: 2,
+            }
+        );
+    }
+
+    #[test]
+    pub fn opening_plies_skipped() {
+        let (positions, stats) = extract_positions(PgnReader::new(GAMES.as_bytes()), 4, 50);
+
+        assert!(positions.is_empty());
+        assert_eq!(stats.positions, 1);
+    }
+
+    #[test]
+    pub fn middlegame_without_captures_is_quiet() {
+        let attack_checker = AttackChecker::new();
+        let move_gen = MoveGenerator::new();
+        // white has moves to its back rank, but nothing to take
+        let pos = position_from_fen(
+            "rnbqk2r/ppp1bppp/3p1n2/4p3/4P3/3P4/PPPNBPPP/R1BQK1NR w KQkq - 2 5",
+            &attack_checker,
+        )
+        .unwrap();
+
+        assert!(is_quiet(&pos, &move_gen, 50));
+    }
+
+    #[test]
+    pub fn en_passant_capture_isnt_quiet() {
+        let attack_checker = AttackChecker::new();
+        let move_gen = MoveGenerator::new();
+        let pos = position_from_fen(
+            "rn1qkbnr/pppbppp1/3p4/6Pp/8/5N2/PPPPPP1P/RNBQKB1R w KQkq h6 0 4",
+            &attack_checker,
+        )
+        .unwrap();
+
+        assert!(!is_quiet(&pos, &move_gen, 50));
+    }
+}
@@ -1,17 +1,54 @@
 use dolphin_core::{
     board::occupancy_masks::OccupancyMasks,
     io::fen,
-    position::{attack_checker::AttackChecker, game_position::Position, zobrist_keys::ZobristKeys},
-    search_engine::search::Search,
+    position::{attack_checker::AttackChecker, game_position::Position},
 };
+use dolphin_search::{
+    engine::SearchEngine,
+    mcts::Mcts,
+    search::{Search, SearchStrategy},
+    tree_dump::DumpFormat,
+};
+use std::env;
+use std::fs;
+
+mod batch_eval;
+mod bench;
+mod cli;
+mod cpu_target;
+mod diversity;
+mod dump;
+mod eval_graph;
+mod extract;
+mod play;
+mod puzzles;
+mod solve_mate;
+mod validate_pgn;
 
 fn main() {
+    cpu_target::exit_if_unsupported();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("play") => return play::run(&args[1..]),
+        Some("eval") => return batch_eval::run(&args[1..]),
+        Some("bench") => return bench::run(&args[1..]),
+        Some("diversity") => return diversity::run(&args[1..]),
+        Some("evalgraph") => return eval_graph::run(&args[1..]),
+        Some("extract") => return extract::run(&args[1..]),
+        Some("puzzles") => return puzzles::run(&args[1..]),
+        Some("dump") => return dump::run_dump(&args[1..]),
+        Some("replay") => return dump::run_replay(&args[1..]),
+        Some("solvemate") => return solve_mate::run(&args[1..]),
+        Some("validate-pgn") => return validate_pgn::run(&args[1..]),
+        _ => {}
+    }
+
     let fen = "2kr4/8/8/1p6/1Kn5/1P1q4/P7/8 w - - 0 1";
 
     let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) = fen::decompose_fen(fen);
 
-    let zobrist_keys = ZobristKeys::new();
-    let occ_masks = OccupancyMasks::new();
+    let occ_masks = OccupancyMasks::instance();
     let attack_checker = AttackChecker::new();
 
     let mut pos = Position::new(
@@ -20,11 +57,87 @@ fn main() {
         move_cntr,
         en_pass_sq,
         side_to_move,
-        &zobrist_keys,
-        &occ_masks,
+        occ_masks,
         &attack_checker,
     );
 
-    let mut search = Search::new(10000000000, 6);
-    search.search(&mut pos);
+    // --mcts N searches with the experimental MCTS searcher instead, for N
+    // iterations
+    if let Some(i) = args.iter().position(|arg| arg == "--mcts") {
+        let mut mcts = match args.get(i + 1).and_then(|n| n.parse::<u64>().ok()) {
+            Some(iterations) => Mcts::new(iterations),
+            None => panic!("usage: --mcts N"),
+        };
+        return print_search(&mut mcts, &mut pos);
+    }
+
+    let mut search = Search::new(10000000000, 5);
+    search.set_report_progress(true);
+    if args.iter().any(|arg| arg == "--copy-make") {
+        search.set_strategy(SearchStrategy::CopyMake);
+    }
+
+    // --nodes N stops the search after N nodes, reproducible on any machine
+    if let Some(i) = args.iter().position(|arg| arg == "--nodes") {
+        match args.get(i + 1).and_then(|n| n.parse::<u64>().ok()) {
+            Some(node_limit) => search.set_node_limit(Some(node_limit)),
+            None => panic!("usage: --nodes N"),
+        }
+    }
+
+    // --dump-tree PLY FILE writes the search tree as graphviz dot (.dot) or JSON
+    let dump_tree = args.iter().position(|arg| arg == "--dump-tree").map(|i| {
+        match (
+            args.get(i + 1).and_then(|p| p.parse::<u8>().ok()),
+            args.get(i + 2),
+        ) {
+            (Some(max_ply), Some(file)) => (max_ply, file),
+            _ => panic!("usage: --dump-tree PLY FILE"),
+        }
+    });
+    if let Some((max_ply, _)) = dump_tree {
+        search.set_tree_dump(Some(max_ply));
+    }
+
+    let best_move = search.search(&mut pos);
+    println!(
+        "bestmove {}, score {}, depth {}, seldepth {}, nodes {}, ebf {}, hashfull {}",
+        best_move.map_or("none".to_string(), |mv| mv.to_uci_string()),
+        search.score(),
+        search.completed_depth(),
+        search.seldepth(),
+        search.nodes(),
+        search
+            .branching_factor()
+            .map_or("-".to_string(), |ebf| format!("{:.2}", ebf)),
+        search.hashfull()
+    );
+    println!("nodes per depth {:?}", search.depth_nodes());
+    println!(
+        "tt {:?}, stale moves {}",
+        search.tt_stats(),
+        search.stale_moves()
+    );
+
+    if let (Some((_, file)), Some(tree_dump)) = (dump_tree, search.tree_dump()) {
+        let format = if file.ends_with(".dot") {
+            DumpFormat::Dot
+        } else {
+            DumpFormat::Json
+        };
+        fs::write(file, tree_dump.write(format)).expect("Unable to write the search tree");
+    }
+}
+
+fn print_search(engine: &mut impl SearchEngine, pos: &mut Position) {
+    let best_move = engine.search(pos);
+    let pv: Vec<String> = engine.pv().iter().map(|mv| mv.to_uci_string()).collect();
+    println!(
+        "{}: bestmove {}, score {}, nodes {}, pv {}",
+        engine.name(),
+        best_move.map_or("none".to_string(), |mv| mv.to_uci_string()),
+        engine.score(),
+        engine.nodes(),
+        pv.join(" ")
+    );
 }
@@ -0,0 +1,443 @@
+use crate::cli::{exit_with_usage, position_from_fen};
+use dolphin_core::{
+    board::{colour::Colour, file::File, piece::Piece, rank::Rank, square::Square},
+    moves::{
+        mov::{Move, MoveType},
+        move_gen::MoveGenerator,
+        move_list::MoveList,
+    },
+    position::{
+        attack_checker::AttackChecker,
+        game_position::Position,
+        variant::{GameResult, Standard, Variant},
+    },
+};
+use dolphin_search::{search::Search, strength::StrengthLimit};
+use std::io::{self, BufRead, Write};
+use std::process;
+
+// Plays a game against the engine in the terminal:
+//      dolphin_engine play [--depth N] [--elo N] [--black]
+// Moves are entered in UCI (eg "e2e4", "e7e8q") or algebraic notation
+// (eg "e4", "Nf3", "exd5", "e8=Q", "O-O"). Castling can also be entered as
+// the king taking its rook (eg "e1h1"), as Chess960 GUIs send it.
+// --elo weakens the engine to roughly that rating (800 to 2400).
+
+const USAGE: &str = "usage: dolphin_engine play [--depth N] [--elo N] [--black]";
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const DEFAULT_DEPTH: u8 = 4;
+const TT_CAPACITY: usize = 1_000_000;
+
+const HELP: &str =
+    "commands: <move> (eg e2e4, e4, Nf3, O-O), undo, new, resign, status, help, quit";
+
+pub fn run(args: &[String]) {
+    let mut depth = DEFAULT_DEPTH;
+    let mut user_colour = Colour::White;
+    let mut strength_limit = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--depth" => {
+                depth = match args.next().and_then(|d| d.parse::<u8>().ok()) {
+                    Some(d) if d > 0 => d,
+                    _ => exit_with_usage(USAGE),
+                }
+            }
+            "--elo" => {
+                strength_limit = match args.next().and_then(|elo| elo.parse::<u16>().ok()) {
+                    Some(elo) => Some(StrengthLimit::new(elo)),
+                    None => exit_with_usage(USAGE),
+                }
+            }
+            "--black" => user_colour = Colour::Black,
+            _ => exit_with_usage(USAGE),
+        }
+    }
+
+    let attack_checker = AttackChecker::new();
+
+    let mut pos = new_position(&attack_checker);
+    let mut moves_played: Vec<Move> = Vec::new();
+    let mut search = Search::new(TT_CAPACITY, depth);
+    search.set_strength_limit(strength_limit, process::id() as u64);
+    let mut game_over = false;
+
+    println!("{}", HELP);
+    println!("{}", pos.board());
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        if !game_over && pos.side_to_move() != user_colour {
+            match search.search(&mut pos) {
+                Some(mv) => {
+                    pos.make_move(&mv);
+                    moves_played.push(mv);
+                    println!("Engine plays {}", mv.to_uci_string());
+                    println!("{}", pos.board());
+                }
+                None => game_over = true,
+            }
+            if let Some(result) = game_result(&mut pos) {
+                println!("{}", result);
+                game_over = true;
+            }
+            continue;
+        }
+
+        print!("> ");
+        io::stdout().flush().expect("Unable to flush stdout");
+
+        let Some(Ok(line)) = lines.next() else {
+            return;
+        };
+
+        match line.trim() {
+            "" => {}
+            "quit" => return,
+            "help" => println!("{}", HELP),
+            // what the engine's last search did, eg if it seems to hang
+            "status" => println!("{}", search.status()),
+            "new" => {
+                pos = new_position(&attack_checker);
+                moves_played.clear();
+                search.new_game();
+                game_over = false;
+                println!("{}", pos.board());
+            }
+            "resign" => {
+                println!("You resign, the engine wins. Type \"new\" for another game");
+                game_over = true;
+            }
+            "undo" => {
+                // take back the engine's reply as well, so it's the user's turn again
+                if moves_played.pop().is_some() {
+                    pos.take_move();
+                    while pos.side_to_move() != user_colour && moves_played.pop().is_some() {
+                        pos.take_move();
+                    }
+                    game_over = false;
+                    println!("{}", pos.board());
+                } else {
+                    println!("No moves to undo");
+                }
+            }
+            _ if game_over => println!("The game is over. Type \"new\" for another game"),
+            text => match parse_move(&mut pos, text) {
+                Some(mv) => {
+                    pos.make_move(&mv);
+                    moves_played.push(mv);
+                    println!("{}", pos.board());
+                    if let Some(result) = game_result(&mut pos) {
+                        println!("{}", result);
+                        game_over = true;
+                    }
+                }
+                None => println!("Illegal or unrecognised move '{}'. {}", text, HELP),
+            },
+        }
+    }
+}
+
+fn new_position(attack_checker: &AttackChecker) -> Position<'_> {
+    position_from_fen(START_FEN, attack_checker).expect("Invalid start FEN")
+}
+
+// matches the text against the legal moves in the position, in either UCI
+// (standard or Chess960 castling) or algebraic (SAN) notation
+pub fn parse_move(pos: &mut Position, text: &str) -> Option<Move> {
+    let uci = text.to_ascii_lowercase();
+
+    let mut move_list = MoveList::new();
+    MoveGenerator::new().generate_legal_moves(pos, &mut move_list);
+
+    let uci_move = move_list.iterator().find(|mv| {
+        mv.to_uci_string() == uci || mv.to_uci_string_chess960(&pos.castle_permissions()) == uci
+    });
+    if let Some(mv) = uci_move {
+        return Some(*mv);
+    }
+
+    // a SAN move has to fit exactly one legal move
+    let san = SanMove::parse(text)?;
+    let mut matching = move_list.iterator().filter(|mv| san.fits(pos, mv));
+    match (matching.next(), matching.next()) {
+        (Some(mv), None) => Some(*mv),
+        _ => None,
+    }
+}
+
+// What a SAN move says about the move: the piece, where it goes and as
+// much of where it's from as the disambiguation gives. Captures are found
+// from the board, and check marks aren't needed, so neither is checked.
+#[derive(Debug, Eq, PartialEq)]
+enum SanMove {
+    Castle {
+        is_king_side: bool,
+    },
+    Piece {
+        piece: Piece,
+        from_file: Option<File>,
+        from_rank: Option<Rank>,
+        to_sq: Square,
+        promotion: Option<Piece>,
+    },
+}
+
+impl SanMove {
+    fn parse(text: &str) -> Option<SanMove> {
+        let san = text
+            .trim_end_matches(['+', '#', '!', '?'])
+            .replace('0', "O");
+        match san.as_str() {
+            "O-O" => return Some(SanMove::Castle { is_king_side: true }),
+            "O-O-O" => {
+                return Some(SanMove::Castle {
+                    is_king_side: false,
+                })
+            }
+            _ => {}
+        }
+
+        // a piece letter, if it isn't a pawn move
+        let mut chars: Vec<char> = san.chars().filter(|c| *c != 'x').collect();
+        let piece = match chars.first() {
+            Some(c) if c.is_ascii_uppercase() => {
+                let (piece, _) = Piece::from_char(*c)?;
+                chars.remove(0);
+                piece
+            }
+            _ => Piece::Pawn,
+        };
+
+        // a promotion piece, with or without the '='
+        let promotion = match chars.last() {
+            Some(c) if c.is_ascii_uppercase() => {
+                let (promotion, _) = Piece::from_char(*c)?;
+                chars.pop();
+                if chars.last() == Some(&'=') {
+                    chars.pop();
+                }
+                Some(promotion)
+            }
+            _ => None,
+        };
+
+        // the destination square, after any disambiguation
+        if chars.len() < 2 || chars.len() > 4 {
+            return None;
+        }
+        let (from, to) = chars.split_at(chars.len() - 2);
+        let to_sq = Square::get_from_string(&to.iter().collect::<String>())?;
+        let mut from_file = None;
+        let mut from_rank = None;
+        for c in from {
+            match (File::from_char(*c), Rank::from_char(*c)) {
+                (Some(file), _) if from_file.is_none() && from_rank.is_none() => {
+                    from_file = Some(file)
+                }
+                (_, Some(rank)) if from_rank.is_none() => from_rank = Some(rank),
+                _ => return None,
+            }
+        }
+
+        Some(SanMove::Piece {
+            piece,
+            from_file,
+            from_rank,
+            to_sq,
+            promotion,
+        })
+    }
+
+    fn fits(&self, pos: &Position, mv: &Move) -> bool {
+        let is_castle = mv.move_type() == MoveType::Castle;
+        match self {
+            SanMove::Castle { is_king_side } => {
+                is_castle && (mv.to_sq().file() == File::G) == *is_king_side
+            }
+            SanMove::Piece {
+                piece,
+                from_file,
+                from_rank,
+                to_sq,
+                promotion,
+            } => {
+                let from_sq = mv.from_sq();
+                let mv_promotion =
+                    (mv.move_type() == MoveType::Promotion).then(|| mv.decode_promotion_piece());
+                !is_castle
+                    && mv.to_sq() == *to_sq
+                    && pos.board().get_piece_on_square(&from_sq) == Some(*piece)
+                    && from_file.is_none_or(|file| from_sq.file() == file)
+                    && from_rank.is_none_or(|rank| from_sq.rank() == rank)
+                    && mv_promotion == *promotion
+            }
+        }
+    }
+}
+
+pub fn game_result(pos: &mut Position) -> Option<GameResult> {
+    let mut move_list = MoveList::new();
+    let num_legal_moves = MoveGenerator::new().generate_legal_moves(pos, &mut move_list);
+    Standard.game_result(pos, num_legal_moves as usize)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::game_result;
+    use super::new_position;
+    use super::parse_move;
+    use dolphin_core::board::occupancy_masks::OccupancyMasks;
+    use dolphin_core::board::piece::Piece;
+    use dolphin_core::board::square::Square;
+    use dolphin_core::io::fen;
+    use dolphin_core::moves::mov::Move;
+    use dolphin_core::position::attack_checker::AttackChecker;
+    use dolphin_core::position::game_position::Position;
+
+    #[test]
+    pub fn parse_move_accepts_legal_uci_and_san_moves() {
+        let attack_checker = AttackChecker::new();
+        let mut pos = new_position(&attack_checker);
+
+        assert_eq!(
+            parse_move(&mut pos, "e2e4"),
+            Some(Move::encode_move(&Square::E2, &Square::E4))
+        );
+        assert_eq!(
+            parse_move(&mut pos, "G1F3"),
+            Some(Move::encode_move(&Square::G1, &Square::F3))
+        );
+
+        // algebraic
+        assert_eq!(
+            parse_move(&mut pos, "d4"),
+            Some(Move::encode_move(&Square::D2, &Square::D4))
+        );
+        assert_eq!(
+            parse_move(&mut pos, "Nc3"),
+            Some(Move::encode_move(&Square::B1, &Square::C3))
+        );
+
+        assert_eq!(parse_move(&mut pos, "e2e5"), None);
+        // castling isn't legal yet, in either form
+        assert_eq!(parse_move(&mut pos, "e1g1"), None);
+        assert_eq!(parse_move(&mut pos, "e1h1"), None);
+        assert_eq!(parse_move(&mut pos, "Nd2"), None);
+        assert_eq!(parse_move(&mut pos, "e7e5"), None);
+        assert_eq!(parse_move(&mut pos, "xyz"), None);
+    }
+
+    #[test]
+    pub fn parse_move_san_disambiguation_allows_for_pins() {
+        let attack_checker = AttackChecker::new();
+        let position = |fen: &str| {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(fen);
+            Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                OccupancyMasks::instance(),
+                &attack_checker,
+            )
+        };
+        let nbd2 = Some(Move::encode_move(&Square::B1, &Square::D2));
+
+        // the knight on f3 is pinned, so only the one on b1 can go to d2
+        let mut pos = position("4k3/8/8/3b4/8/5N2/8/1N5K w - - 0 1");
+        assert_eq!(parse_move(&mut pos, "Nd2"), nbd2);
+        assert_eq!(parse_move(&mut pos, "Nbd2"), nbd2);
+        assert_eq!(parse_move(&mut pos, "Nfd2"), None);
+
+        // with no pin, it's ambiguous
+        let mut pos = position("4k3/8/8/8/8/5N2/8/1N5K w - - 0 1");
+        assert_eq!(parse_move(&mut pos, "Nd2"), None);
+        assert_eq!(parse_move(&mut pos, "Nbd2"), nbd2);
+        assert_eq!(parse_move(&mut pos, "N1d2"), nbd2);
+        assert_eq!(parse_move(&mut pos, "Nb1d2"), nbd2);
+        assert_eq!(parse_move(&mut pos, "Nb1xd2+"), nbd2);
+
+        // promotions, with or without the '='
+        let mut pos = position("3rk3/2P5/8/8/8/8/8/4K3 w - - 0 1");
+        let cxd8_queen = Some(Move::encode_move_with_promotion(
+            &Square::C7,
+            &Square::D8,
+            &Piece::Queen,
+        ));
+        assert_eq!(parse_move(&mut pos, "cxd8=Q+"), cxd8_queen);
+        assert_eq!(parse_move(&mut pos, "cxd8Q"), cxd8_queen);
+        assert_eq!(parse_move(&mut pos, "cxd8"), None);
+        assert_eq!(parse_move(&mut pos, "c8=K"), None);
+    }
+
+    #[test]
+    pub fn parse_move_accepts_both_castling_forms() {
+        let attack_checker = AttackChecker::new();
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1");
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+
+        let king_side = Some(Move::encode_move_castle_kingside_white());
+        let queen_side = Some(Move::encode_move_castle_queenside_white());
+        assert_eq!(parse_move(&mut pos, "e1g1"), king_side);
+        assert_eq!(parse_move(&mut pos, "e1h1"), king_side);
+        assert_eq!(parse_move(&mut pos, "O-O"), king_side);
+        assert_eq!(parse_move(&mut pos, "e1c1"), queen_side);
+        assert_eq!(parse_move(&mut pos, "e1a1"), queen_side);
+    }
+
+    #[test]
+    pub fn game_result_checkmate_and_stalemate() {
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let fens = [
+            (
+                "R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1",
+                Some("Checkmate, white wins"),
+            ),
+            ("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1", Some("Stalemate, draw")),
+            ("4k3/8/8/8/8/8/8/4K2R w K - 0 1", None),
+            (
+                "4k3/8/8/8/8/8/8/4K2R w K - 100 80",
+                Some("Draw by the fifty move rule"),
+            ),
+        ];
+
+        for (fen, expected) in fens {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(fen);
+            let mut pos = Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                occ_masks,
+                &attack_checker,
+            );
+
+            assert_eq!(
+                game_result(&mut pos)
+                    .map(|result| result.to_string())
+                    .as_deref(),
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use crate::cli::{exit_with_usage, position_from_fen};
+use crate::play::parse_move;
+use dolphin_core::{
+    io::pgn::{PgnGame, PgnReader},
+    moves::mov::Score,
+    position::attack_checker::AttackChecker,
+};
+use dolphin_search::search::Search;
+use std::fs::{self, File};
+use std::io::BufReader;
+
+// Generates tactics puzzles from the games in a PGN file (eg, self-play):
+//      dolphin_engine puzzles PGN_FILE EPD_FILE [--nodes N] [--swing N] [--plies N]
+// Every position in the games is searched, and one where the side to move
+// is better by at least the swing than it was before the opponent's move
+// (so the opponent has just blundered, and there's a way to punish it) is
+// a puzzle. The search is limited by nodes rather than depth, so it goes
+// deeper where there are fewer moves, as in endgames. Positions the side
+// to move was already winning aren't puzzles, as there's nothing to find.
+// Puzzles are written as EPD, with the best move (bm), the solution (pv)
+// up to --plies long, and the score (ce).
+
+const DEFAULT_NODES: u64 = 200_000;
+const DEFAULT_SWING: Score = 200;
+const DEFAULT_PLIES: usize = 5;
+
+const MAX_DEPTH: u8 = 20;
+const TT_CAPACITY: usize = 1_000_000;
+
+// a side this far ahead has already won, puzzle or not
+const DECIDED_SCORE: Score = 500;
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const USAGE: &str =
+    "usage: dolphin_engine puzzles PGN_FILE EPD_FILE [--nodes N] [--swing N] [--plies N]";
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Puzzle {
+    pub fen: String,
+    // in SAN, the best move first
+    pub solution: Vec<String>,
+    // from the side to move's point of view
+    pub score: Score,
+    // from 1, in file order
+    pub game: usize,
+    // the ply the puzzle position was reached at, from 1
+    pub ply: usize,
+}
+
+impl Puzzle {
+    // an EPD line: the FEN without the move counters, then the operations
+    pub fn to_epd(&self) -> String {
+        let epd_fields: Vec<&str> = self.fen.split(' ').take(4).collect();
+        format!(
+            "{} bm {}; pv {}; ce {}; id \"game {} ply {}\";",
+            epd_fields.join(" "),
+            self.solution[0],
+            self.solution.join(" "),
+            self.score,
+            self.game,
+            self.ply
+        )
+    }
+}
+
+pub fn run(args: &[String]) {
+    let mut nodes = DEFAULT_NODES;
+    let mut swing = DEFAULT_SWING;
+    let mut plies = DEFAULT_PLIES;
+    let mut files = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--nodes" => {
+                nodes = match args.next().and_then(|n| n.parse::<u64>().ok()) {
+                    Some(n) if n > 0 => n,
+                    _ => exit_with_usage(USAGE),
+                }
+            }
+            "--swing" => {
+                swing = match args.next().and_then(|n| n.parse::<Score>().ok()) {
+                    Some(n) if n > 0 => n,
+                    _ => exit_with_usage(USAGE),
+                }
+            }
+            "--plies" => {
+                plies = match args.next().and_then(|n| n.parse::<usize>().ok()) {
+                    Some(n) if n > 0 => n,
+                    _ => exit_with_usage(USAGE),
+                }
+            }
+            _ if !arg.starts_with("--") => files.push(arg),
+            _ => exit_with_usage(USAGE),
+        }
+    }
+    let [pgn_file, epd_file] = files[..] else {
+        exit_with_usage(USAGE);
+    };
+
+    let file = File::open(pgn_file).expect("Unable to open PGN file");
+    let puzzles = find_puzzles(PgnReader::new(BufReader::new(file)), nodes, swing, plies);
+
+    let mut epd = String::new();
+    for puzzle in puzzles.iter() {
+        epd.push_str(&puzzle.to_epd());
+        epd.push('\n');
+    }
+    fs::write(epd_file, epd).expect("Unable to write EPD file");
+
+    println!("puzzles {}", puzzles.len());
+}
+
+// Replays each game, up to a move that doesn't parse or isn't legal, from
+// its FEN tag if it has one. A game whose FEN tag doesn't parse is skipped.
+pub fn find_puzzles(
+    games: impl Iterator<Item = PgnGame>,
+    nodes: u64,
+    swing: Score,
+    plies: usize,
+) -> Vec<Puzzle> {
+    let attack_checker = AttackChecker::new();
+    let mut search = Search::new(TT_CAPACITY, MAX_DEPTH);
+    search.set_node_limit(Some(nodes));
+
+    let mut puzzles = Vec::new();
+    for (game_index, game) in games.enumerate() {
+        search.new_game();
+        let start_fen = game.tag("FEN").unwrap_or(START_FEN);
+        let Ok(mut pos) = position_from_fen(start_fen, &attack_checker) else {
+            continue;
+        };
+
+        // the score before the last move, from the point of view of the
+        // side that made it
+        search.search(&mut pos);
+        let mut prev_score = search.score();
+
+        for (ply, san) in game.san_moves().into_iter().enumerate() {
+            let Some(mv) = parse_move(&mut pos, san) else {
+                break;
+            };
+            pos.make_move(&mv);
+
+            if search.search(&mut pos).is_none() {
+                break;
+            }
+            let score = search.score();
+            let was = -prev_score;
+            prev_score = score;
+
+            if was >= DECIDED_SCORE || score.saturating_sub(was) < swing {
+                continue;
+            }
+            // no PV if the nodes ran out in the first depth
+            let solution: Vec<String> = search
+                .pv_to_san(&pos)
+                .split_whitespace()
+                .take(plies)
+                .map(str::to_string)
+                .collect();
+            if !solution.is_empty() {
+                puzzles.push(Puzzle {
+                    fen: pos.to_fen(),
+                    solution,
+                    score,
+                    game: game_index + 1,
+                    ply: ply + 1,
+                });
+            }
+        }
+    }
+    puzzles
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::find_puzzles;
+    use dolphin_core::io::pgn::PgnReader;
+
+    // the first game is already won, and in the second black's queen
+    // move walks into a knight fork
+    const GAMES: &str = r#"[Result "1-0"]
+[SetUp "1"]
+[FEN "6k1/5ppp/8/8/8/8/5PPP/4R1K1 w - - 0 1"]
+
+1. Re8# 1-0
+
+[Result "1-0"]
+[SetUp "1"]
+[FEN "4k3/7p/8/1N5q/8/8/P7/6K1 b - - 0 1"]
+
+1... Qd5 2. Nc7+ Kd8 3. Nxd5 1-0
+"#;
+
+    #[test]
+    pub fn puzzle_found_after_blunder() {
+        let puzzles = find_puzzles(PgnReader::new(GAMES.as_bytes()), 20_000, 200, 3);
+
+        assert_eq!(puzzles.len(), 1);
+        let puzzle = &puzzles[0];
+        assert_eq!((puzzle.game, puzzle.ply), (2, 1));
+        assert_eq!(puzzle.solution.len(), 3);
+        assert!(puzzle.score > 200);
+        assert!(puzzle
+            .to_epd()
+            .starts_with("4k3/7p/8/1N1q4/8/8/P7/6K1 w - - bm Nc7; pv Nc7 "));
+    }
+}
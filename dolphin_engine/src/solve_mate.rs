@@ -0,0 +1,85 @@
+use crate::cli::{exit_with_usage, position_from_fen};
+use dolphin_core::{
+    moves::mov::Move,
+    position::{attack_checker::AttackChecker, game_position::Position},
+};
+use dolphin_search::mate_solver::{MateResult, MateSolver};
+use std::process;
+use std::time::Instant;
+
+// Solves a mate with the proof-number solver, rather than searching with
+// alpha-beta, and prints the main line (or the whole proof with --tree):
+//      dolphin_engine solvemate FEN [--nodes N] [--moves N] [--tree]
+
+const DEFAULT_MAX_NODES: u64 = 10_000_000;
+
+const USAGE: &str = "usage: dolphin_engine solvemate FEN [--nodes N] [--moves N] [--tree]";
+
+pub fn run(args: &[String]) {
+    let mut max_nodes = DEFAULT_MAX_NODES;
+    let mut max_moves = None;
+    let mut print_tree = false;
+
+    let mut args = args.iter();
+    let fen = args.next().unwrap_or_else(|| exit_with_usage(USAGE));
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--nodes" => {
+                max_nodes = match args.next().and_then(|n| n.parse::<u64>().ok()) {
+                    Some(nodes) if nodes > 0 => nodes,
+                    _ => exit_with_usage(USAGE),
+                }
+            }
+            "--moves" => {
+                max_moves = match args.next().and_then(|n| n.parse::<u16>().ok()) {
+                    Some(moves) if moves > 0 => Some(moves),
+                    _ => exit_with_usage(USAGE),
+                }
+            }
+            "--tree" => print_tree = true,
+            _ => exit_with_usage(USAGE),
+        }
+    }
+
+    let attack_checker = AttackChecker::new();
+    let mut pos = position_from_fen(fen, &attack_checker).unwrap_or_else(|err| {
+        eprintln!("{} in FEN '{}'", err, fen);
+        process::exit(1);
+    });
+
+    let mut solver = MateSolver::new(max_nodes);
+    solver.set_max_moves(max_moves);
+    let start = Instant::now();
+    let result = solver.solve(&mut pos);
+    let elapsed = start.elapsed();
+
+    match result {
+        MateResult::Mate(line) => {
+            println!(
+                "mate in {} : {}",
+                line.len().div_ceil(2),
+                pv_to_san(&pos, &line)
+            );
+            if print_tree {
+                println!("{}", solver.proof_tree(&pos));
+            }
+        }
+        MateResult::NoMate => println!("no mate"),
+        MateResult::Unknown => println!("unknown"),
+    }
+    println!("nodes {}, time {} ms", solver.nodes(), elapsed.as_millis());
+}
+
+// replayed on copies, as Search::pv_to_san does
+fn pv_to_san(pos: &Position, line: &[Move]) -> String {
+    let mut san_moves = Vec::new();
+    let mut scratch: Option<Position> = None;
+
+    for mv in line {
+        let current = scratch.as_ref().unwrap_or(pos);
+        san_moves.push(mv.to_string_with_board(current.board(), current.occupancy_masks()));
+        let (next, _) = current.make_move_copied(mv);
+        scratch = Some(next);
+    }
+    san_moves.join(" ")
+}
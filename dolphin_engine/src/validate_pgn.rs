@@ -0,0 +1,312 @@
+use crate::cli::{exit_with_usage, position_from_fen};
+use crate::play::{game_result, parse_move};
+use dolphin_core::{
+    board::colour::Colour,
+    io::{
+        fen::FenError,
+        pgn::{PgnGame, PgnReader},
+    },
+    position::{attack_checker::AttackChecker, variant::GameResult},
+};
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::process;
+
+// Checks the games in a PGN file are legal:
+//      dolphin_engine validate-pgn PGN_FILE
+// Each game is replayed through the legal move generator, and the first
+// problem with it is reported: a move that isn't legal (or doesn't parse),
+// a FEN tag that doesn't parse, or tags that don't agree with each other or
+// with the game. It's a check on PGN files from elsewhere, and, run over a
+// big database, a check on the move generator too, as every move in it
+// should be found.
+// Exits with 1 if any game has a problem.
+
+const START_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+const USAGE: &str = "usage: dolphin_engine validate-pgn PGN_FILE";
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum PgnProblem {
+    // the move isn't one of the legal moves in the position
+    IllegalMove {
+        move_number: u16,
+        side_to_move: Colour,
+        san: String,
+    },
+    MissingResult,
+    // the result at the end of the move text isn't the Result tag
+    ResultMismatch {
+        tag: String,
+        movetext: String,
+    },
+    // the game ends in checkmate or stalemate, but the Result tag says otherwise
+    WrongResult {
+        tag: String,
+        game_result: GameResult,
+    },
+    // a FEN tag needs [SetUp "1"]
+    FenWithoutSetUp,
+    // the FEN tag doesn't parse
+    InvalidFen {
+        fen: String,
+        error: FenError,
+    },
+}
+
+impl fmt::Display for PgnProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnProblem::IllegalMove {
+                move_number,
+                side_to_move: Colour::White,
+                san,
+            } => write!(f, "illegal move {}. {}", move_number, san),
+            PgnProblem::IllegalMove {
+                move_number,
+                side_to_move: Colour::Black,
+                san,
+            } => write!(f, "illegal move {}... {}", move_number, san),
+            PgnProblem::MissingResult => write!(f, "no Result tag"),
+            PgnProblem::ResultMismatch { tag, movetext } => write!(
+                f,
+                "Result tag is {}, but the move text ends {}",
+                tag, movetext
+            ),
+            PgnProblem::WrongResult { tag, game_result } => {
+                write!(
+                    f,
+                    "Result tag is {}, but the game ends: {}",
+                    tag, game_result
+                )
+            }
+            PgnProblem::FenWithoutSetUp => write!(f, "FEN tag without SetUp \"1\""),
+            PgnProblem::InvalidFen { fen, error } => write!(f, "{} in FEN tag '{}'", error, fen),
+        }
+    }
+}
+
+pub fn run(args: &[String]) {
+    let [pgn_file] = args else {
+        exit_with_usage(USAGE);
+    };
+
+    let file = File::open(pgn_file).expect("Unable to open PGN file");
+    let attack_checker = AttackChecker::new();
+
+    let mut num_games = 0;
+    let mut num_invalid = 0;
+    for game in PgnReader::new(BufReader::new(file)) {
+        num_games += 1;
+        if let Err(problem) = validate_game(&game, &attack_checker) {
+            num_invalid += 1;
+            println!(
+                "game {} ({} - {}): {}",
+                num_games,
+                game.tag("White").unwrap_or("?"),
+                game.tag("Black").unwrap_or("?"),
+                problem
+            );
+        }
+    }
+
+    println!("games {}, invalid {}", num_games, num_invalid);
+    if num_invalid > 0 {
+        process::exit(1);
+    }
+}
+
+// The number of plies in the game, or its first problem. The tags are
+// checked before the moves, and the result against the final position
+// after them.
+pub fn validate_game(game: &PgnGame, attack_checker: &AttackChecker) -> Result<usize, PgnProblem> {
+    let Some(tag) = game.result() else {
+        return Err(PgnProblem::MissingResult);
+    };
+    if let Some(movetext) = movetext_result(game) {
+        if movetext != tag {
+            return Err(PgnProblem::ResultMismatch {
+                tag: tag.to_string(),
+                movetext: movetext.to_string(),
+            });
+        }
+    }
+    if game.tag("FEN").is_some() && game.tag("SetUp") != Some("1") {
+        return Err(PgnProblem::FenWithoutSetUp);
+    }
+
+    let start_fen = game.tag("FEN").unwrap_or(START_FEN);
+    let mut pos =
+        position_from_fen(start_fen, attack_checker).map_err(|error| PgnProblem::InvalidFen {
+            fen: start_fen.to_string(),
+            error,
+        })?;
+
+    let san_moves = game.san_moves();
+    for san in san_moves.iter() {
+        let Some(mv) = parse_move(&mut pos, san) else {
+            return Err(PgnProblem::IllegalMove {
+                move_number: pos.move_counter().full_move(),
+                side_to_move: pos.side_to_move(),
+                san: san.to_string(),
+            });
+        };
+        pos.make_move(&mv);
+    }
+
+    // the fifty move rule has to be claimed, so a game can go on past it
+    let Some(final_result) = game_result(&mut pos) else {
+        return Ok(san_moves.len());
+    };
+    let expected = match final_result {
+        GameResult::Checkmate {
+            winner: Colour::White,
+        } => "1-0",
+        GameResult::Checkmate {
+            winner: Colour::Black,
+        } => "0-1",
+        GameResult::Stalemate => "1/2-1/2",
+        _ => return Ok(san_moves.len()),
+    };
+    if tag != expected {
+        return Err(PgnProblem::WrongResult {
+            tag: tag.to_string(),
+            game_result: final_result,
+        });
+    }
+    Ok(san_moves.len())
+}
+
+// the game termination marker, if the move text ends with one
+fn movetext_result(game: &PgnGame) -> Option<&str> {
+    game.movetext
+        .split_whitespace()
+        .next_back()
+        .filter(|token| matches!(*token, "1-0" | "0-1" | "1/2-1/2" | "*"))
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::validate_game;
+    use super::PgnProblem;
+    use dolphin_core::board::colour::Colour;
+    use dolphin_core::io::fen::FenError;
+    use dolphin_core::io::pgn::PgnGame;
+    use dolphin_core::io::pgn::PgnReader;
+    use dolphin_core::position::attack_checker::AttackChecker;
+    use dolphin_core::position::variant::GameResult;
+
+    const GAMES: &str = r#"[Result "0-1"]
+
+1. f3 e5 2. g4 Qh4# 0-1
+
+[Result "1-0"]
+
+1. e4 e5 2. Nf3 Nc6 3. Ke3 1-0
+
+[Result "1-0"]
+
+1. f3 e5 2. g4 Qh4# 1-0
+
+[Result "1/2-1/2"]
+
+1. f3 e5 2. g4 Qh4# 0-1
+
+[Result "1-0"]
+[FEN "7k/8/6K1/8/8/8/8/5Q2 w - - 0 1"]
+
+1. Qf8# 1-0
+
+[Result "*"]
+[SetUp "1"]
+[FEN "7k/8/6K1/8/8/8/8/5Q2 w - - 0 1"]
+
+1. Qf8# *
+
+[Event "no result"]
+
+1. e4 c5
+
+[Result "*"]
+[SetUp "1"]
+[FEN "4k3/8/8/8/8/8/8/4K3 w"]
+
+1. Kd2 *
+"#;
+
+    fn read_games() -> Vec<PgnGame> {
+        PgnReader::new(GAMES.as_bytes()).collect()
+    }
+
+    #[test]
+    pub fn legal_game_validated() {
+        let attack_checker = AttackChecker::new();
+        let games = read_games();
+
+        assert_eq!(validate_game(&games[0], &attack_checker), Ok(4));
+    }
+
+    #[test]
+    pub fn san_move_with_a_pinned_twin_validated() {
+        // the knight on f3 is pinned, so Nd2 needs no disambiguation
+        let pgn = r#"[Result "*"]
+[SetUp "1"]
+[FEN "4k3/8/8/3b4/8/5N2/8/1N5K w - - 0 1"]
+
+1. Nd2 Kd7 2. Nc4 *
+"#;
+        let attack_checker = AttackChecker::new();
+        let games: Vec<PgnGame> = PgnReader::new(pgn.as_bytes()).collect();
+
+        assert_eq!(validate_game(&games[0], &attack_checker), Ok(3));
+    }
+
+    #[test]
+    pub fn first_problem_reported_for_each_game() {
+        let attack_checker = AttackChecker::new();
+        let problems: Vec<PgnProblem> = read_games()
+            .iter()
+            .skip(1)
+            .map(|game| validate_game(game, &attack_checker).unwrap_err())
+            .collect();
+
+        assert_eq!(
+            problems,
+            vec![
+                PgnProblem::IllegalMove {
+                    move_number: 3,
+                    side_to_move: Colour::White,
+                    san: "Ke3".to_string(),
+                },
+                PgnProblem::WrongResult {
+                    tag: "1-0".to_string(),
+                    game_result: GameResult::Checkmate {
+                        winner: Colour::Black
+                    },
+                },
+                PgnProblem::ResultMismatch {
+                    tag: "1/2-1/2".to_string(),
+                    movetext: "0-1".to_string(),
+                },
+                PgnProblem::FenWithoutSetUp,
+                PgnProblem::WrongResult {
+                    tag: "*".to_string(),
+                    game_result: GameResult::Checkmate {
+                        winner: Colour::White
+                    },
+                },
+                PgnProblem::MissingResult,
+                PgnProblem::InvalidFen {
+                    fen: "4k3/8/8/8/8/8/8/4K3 w".to_string(),
+                    error: FenError::FieldCount(2),
+                },
+            ]
+        );
+        assert_eq!(problems[0].to_string(), "illegal move 3. Ke3");
+        assert_eq!(
+            problems[6].to_string(),
+            "Expected 6 fields, found 2 in FEN tag '4k3/8/8/8/8/8/8/4K3 w'"
+        );
+    }
+}
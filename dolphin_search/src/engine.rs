@@ -0,0 +1,85 @@
+use crate::search::Search;
+use dolphin_core::moves::mov::Move;
+use dolphin_core::moves::mov::Score;
+use dolphin_core::position::game_position::Position;
+
+// What a searcher has to provide, so another one (eg, an MCTS experiment,
+// or a fixed-depth searcher for teaching) can be used in place of the
+// alpha-beta Search without touching the callers.
+pub trait SearchEngine {
+    fn name(&self) -> &'static str;
+
+    // forgets anything kept from the last game (eg, the TT)
+    fn new_game(&mut self);
+
+    // The best move for the side to move, or None if there are no legal
+    // moves. The position is the same afterwards.
+    fn search(&mut self, pos: &mut Position) -> Option<Move>;
+
+    // score of the last search, from the side to move's point of view
+    fn score(&self) -> Score;
+
+    fn nodes(&self) -> u64;
+
+    // principal variation of the last search, best move first
+    fn pv(&self) -> &[Move];
+}
+
+impl SearchEngine for Search {
+    fn name(&self) -> &'static str {
+        "alpha-beta"
+    }
+
+    fn new_game(&mut self) {
+        Search::new_game(self);
+    }
+
+    fn search(&mut self, pos: &mut Position) -> Option<Move> {
+        Search::search(self, pos)
+    }
+
+    fn score(&self) -> Score {
+        Search::score(self)
+    }
+
+    fn nodes(&self) -> u64 {
+        Search::nodes(self)
+    }
+
+    fn pv(&self) -> &[Move] {
+        Search::pv(self)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::engine::SearchEngine;
+    use crate::search::Search;
+    use dolphin_core::board::occupancy_masks::OccupancyMasks;
+    use dolphin_core::io::fen;
+    use dolphin_core::position::attack_checker::AttackChecker;
+    use dolphin_core::position::game_position::Position;
+
+    #[test]
+    pub fn search_usable_through_trait_object() {
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+
+        let mut engine: Box<dyn SearchEngine> = Box::new(Search::new(1000, 2));
+        let best_move = engine.search(&mut pos).unwrap();
+
+        assert_eq!(best_move.to_uci_string(), "a1a8");
+        assert_eq!(engine.pv().first(), Some(&best_move));
+        assert!(engine.nodes() > 0);
+    }
+}
@@ -0,0 +1,785 @@
+// Values for piece square arrays are taken from
+// https://www.chessprogramming.org/Simplified_Evaluation_Function
+
+use dolphin_core::board::bitboard::Bitboard;
+use dolphin_core::board::colour::Colour;
+use dolphin_core::board::game_board::Board;
+use dolphin_core::board::piece::Piece;
+
+use dolphin_core::moves::mov::Score;
+
+#[rustfmt::skip]
+const PAWN_SQ_VALUE: [i8; Board::NUM_SQUARES] = [
+    0,      0,      0,      0,      0,      0,      0,      0,
+    5,      10,     10,     -20,    -20,    10,     10,     5, 
+    5,      -5,     -10,    0,      0,      -10,    -5,     5, 
+    0,      0,      0,      20,     20,     0,      0,      0,  
+    5,      5,      10,     25,     25,     10,     5,      5,  
+    10,     10,     20,     30,     30,     20,     10,     10, 
+    50,     50,     50,     50,     50,     50,     50,     50, 
+    0,      0,      0,      0,      0,      0,      0,      0, 
+];
+
+#[rustfmt::skip]
+const KNIGHT_SQ_VALUE: [i8; Board::NUM_SQUARES] = [
+    -50,    -40,    -30,    -30,    -30,    -30,    -40,    -50,
+    -40,    -20,    0,      5,      5,      0,      -20,    -40, 
+    -30,    5,      10,     15,     15,     10,     5,      -30, 
+    -30,    0,      15,     20,     20,     15,     0,      -30, 
+    -30,    5,      15,     20,     20,     15,     5,      -30, 
+    -30,    0,      10,     15,     15,     10,     0,      -30, 
+    -40,    -20,    0,      0,      0,      0,      -20,    -40, 
+    -50,    -40,    -30,    -30,    -30,    -30,    -40,    -50, 
+];
+
+#[rustfmt::skip]
+const BISHOP_SQ_VALUE: [i8; Board::NUM_SQUARES] = [
+    -20,    -10,    -10,    -10,    -10,    -10,    -10,    -20,
+    -10,    5,      0,      0,      0,      0,      5,      -10, 
+    -10,    10,     10,     10,     10,     10,     10,     -10, 
+    -10,    0,      10,     10,     10,     10,     0,      -10, 
+    -10,    5,      5,      10,     10,     5,      5,      -10, 
+    -10,    0,      5,      10,     10,     5,      0,      -10, 
+    -10,    0,      0,      0,      0,      0,      0,      -10, 
+    -20,    -10,    -10,    -10,    -10,    -10,    -10,    -20, 
+];
+
+#[rustfmt::skip]
+const ROOK_SQ_VALUE: [i8; Board::NUM_SQUARES] = [
+    0,      0,      0,      5,      5,      0,      0,      0,
+    -5,     0,      0,      0,      0,      0,      0,      -5, 
+    -5,     0,      0,      0,      0,      0,      0,      -5, 
+    -5,     0,      0,      0,      0,      0,      0,      -5, 
+    -5,     0,      0,      0,      0,      0,      0,      -5, 
+    -5,     0,      0,      0,      0,      0,      0,      -5, 
+    5,      10,     10,     10,     10,     10,     10,     5, 
+    0,      0,      0,      0,      0,      0,      0,      0, 
+];
+
+#[rustfmt::skip]
+const QUEEN_SQ_VALUE: [i8; Board::NUM_SQUARES] = [
+    -20,    -10,    -10,    -5,     -5,     -10,    -10,    -20,
+    -10,    0,      5,      0,      0,      0,      0,      -10, 
+    -10,    5,      5,      5,      5,      5,      0,      -10, 
+    0,      0,      5,      5,      5,      5,      0,      -5, 
+    -5,     0,      5,      5,      5,      5,      0,      -5, 
+    -10,    0,      5,      5,      5,      5,      0,      -10,
+    -10,    0,      0,      0,      0,      0,      0,      -10, 
+    -20,    -10,    -10,    -5,     -5,     -10,    -10,    -20, 
+];
+
+#[rustfmt::skip]
+const KING_SQ_VALUE: [i8; Board::NUM_SQUARES] = [
+    20,     30,     10,     0,      0,      10,     30,     20,
+    20,     20,     0,      0,      0,      0,      20,     20, 
+    -10,    -20,    -20,    -20,    -20,    -20,    -20,    -10, 
+    -20,    -30,    -30,    -40,    -40,    -30,    -30,    -20, 
+    -30,    -40,    -40,    -50,    -50,    -40,    -40,    -30, 
+    -30,    -40,    -40,    -50,    -50,    -40,    -40,    -30, 
+    -30,    -40,    -40,    -50,    -50,    -40,    -40,    -30, 
+    -30,    -40,    -40,    -50,    -50,    -40,    -40,    -30, 
+];
+
+static PIECE_MAP: [(Piece, &[i8; Board::NUM_SQUARES]); 6] = [
+    (Piece::Pawn, &PAWN_SQ_VALUE),
+    (Piece::Bishop, &BISHOP_SQ_VALUE),
+    (Piece::Knight, &KNIGHT_SQ_VALUE),
+    (Piece::Rook, &ROOK_SQ_VALUE),
+    (Piece::Queen, &QUEEN_SQ_VALUE),
+    (Piece::King, &KING_SQ_VALUE),
+];
+
+// Phase weights of the non-pawn pieces, which add up to MAX_GAME_PHASE for
+// the starting position
+const PHASE_WEIGHTS: [(Piece, Score); 4] = [
+    (Piece::Knight, 1),
+    (Piece::Bishop, 1),
+    (Piece::Rook, 2),
+    (Piece::Queen, 4),
+];
+pub const MAX_GAME_PHASE: Score = 24;
+
+// Passed pawn bonuses by rank, counted from the pawn's own side (so index 1
+// is its starting rank)
+const PASSED_PAWN_BONUS: [Score; 8] = [0, 5, 10, 20, 35, 60, 100, 0];
+// on top of the passed pawn bonus, for a passer with another passer beside
+// or just ahead/behind it, and for one defended by a pawn
+const CONNECTED_PASSER_BONUS: [Score; 8] = [0, 0, 5, 10, 15, 25, 40, 0];
+const SUPPORTED_PASSER_BONUS: [Score; 8] = [0, 0, 5, 10, 15, 20, 30, 0];
+
+// A passer outside the square of the enemy king, with no enemy pieces left
+// to stop it, will queen
+const UNSTOPPABLE_PAWN_BONUS: Score = 600;
+
+// Per square the own king is nearer than the enemy king to the square in
+// front of a passer, times the passer's rank. Scaled in as the pieces come
+// off, so it's at full weight with only kings and pawns left.
+const PASSER_KING_PROXIMITY_WEIGHT: Score = 2;
+
+// King tropism: per square a piece is nearer the enemy king than the far
+// side of the board, as pieces near the king make for attacks. Scaled out
+// as the pieces come off, so it's gone with only kings and pawns left.
+const KING_TROPISM_WEIGHTS: [(Piece, Score); 4] = [
+    (Piece::Knight, 3),
+    (Piece::Bishop, 2),
+    (Piece::Rook, 2),
+    (Piece::Queen, 4),
+];
+
+// Weights of the terms that aren't about the pieces, which can be tuned
+// without touching the tables
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct EvalParams {
+    // bonus for the side to move, which can usually improve its position
+    pub tempo: Score,
+    // The score is rounded (towards 0) to a multiple of this, so the search
+    // isn't swayed by, and the TT doesn't churn on, differences of a few
+    // centipawns. 1 keeps every centipawn.
+    pub grain: Score,
+}
+
+impl Default for EvalParams {
+    fn default() -> Self {
+        EvalParams {
+            tempo: 10,
+            grain: 4,
+        }
+    }
+}
+
+const FILE_A_MASK: u64 = 0x0101_0101_0101_0101;
+const RANK_1_MASK: u64 = 0xff;
+
+// From MAX_GAME_PHASE with all the pieces on the board (or more, after
+// promotions) down to 0 when only kings and pawns are left
+pub fn game_phase(board: &Board) -> Score {
+    let phase: Score = PHASE_WEIGHTS
+        .iter()
+        .map(|(pce, weight)| {
+            let count = board.squares_of(pce, &Colour::White).count()
+                + board.squares_of(pce, &Colour::Black).count();
+            count as Score * weight
+        })
+        .sum();
+    phase.min(MAX_GAME_PHASE)
+}
+
+// Evaluation terms, recorded per colour in an EvalTrace
+#[derive(Eq, PartialEq, Clone, Copy)]
+pub enum EvalTerm {
+    Material(Piece),
+    PieceSquare(Piece),
+    PassedPawn,
+    ConnectedPasser,
+    SupportedPasser,
+    UnstoppablePawn,
+    PasserKingProximity,
+    KingTropism,
+    Tempo,
+}
+
+impl EvalTerm {
+    const NUM_TERMS: usize = 2 * Piece::NUM_PIECE_TYPES + 7;
+
+    const fn as_index(&self) -> usize {
+        match self {
+            EvalTerm::Material(pce) => pce.as_index(),
+            EvalTerm::PieceSquare(pce) => Piece::NUM_PIECE_TYPES + pce.as_index(),
+            EvalTerm::PassedPawn => 2 * Piece::NUM_PIECE_TYPES,
+            EvalTerm::ConnectedPasser => 2 * Piece::NUM_PIECE_TYPES + 1,
+            EvalTerm::SupportedPasser => 2 * Piece::NUM_PIECE_TYPES + 2,
+            EvalTerm::UnstoppablePawn => 2 * Piece::NUM_PIECE_TYPES + 3,
+            EvalTerm::PasserKingProximity => 2 * Piece::NUM_PIECE_TYPES + 4,
+            EvalTerm::KingTropism => 2 * Piece::NUM_PIECE_TYPES + 5,
+            EvalTerm::Tempo => 2 * Piece::NUM_PIECE_TYPES + 6,
+        }
+    }
+}
+
+// The contribution of every evaluation term for each colour (from that
+// colour's point of view), and how many times it was applied, so tests can
+// check individual terms rather than the total
+#[derive(Default, Eq, PartialEq, Clone, Copy)]
+pub struct EvalTrace {
+    scores: [[Score; EvalTerm::NUM_TERMS]; Colour::NUM_COLOURS],
+    counts: [[u8; EvalTerm::NUM_TERMS]; Colour::NUM_COLOURS],
+}
+
+impl EvalTrace {
+    pub fn score(&self, term: EvalTerm, colour: &Colour) -> Score {
+        self.scores[colour.as_index()][term.as_index()]
+    }
+
+    pub fn count(&self, term: EvalTerm, colour: &Colour) -> u8 {
+        self.counts[colour.as_index()][term.as_index()]
+    }
+
+    // sum of all the terms for the colour
+    pub fn total(&self, colour: &Colour) -> Score {
+        self.scores[colour.as_index()].iter().sum()
+    }
+
+    fn add(&mut self, term: EvalTerm, colour: &Colour, score: Score) {
+        self.scores[colour.as_index()][term.as_index()] += score;
+        self.counts[colour.as_index()][term.as_index()] += 1;
+    }
+}
+
+pub fn evaluate_board(board: &Board, side_to_move: Colour) -> Score {
+    evaluate(board, side_to_move, &EvalParams::default(), None)
+}
+
+pub fn evaluate_board_with_params(
+    board: &Board,
+    side_to_move: Colour,
+    params: &EvalParams,
+) -> Score {
+    evaluate(board, side_to_move, params, None)
+}
+
+// As evaluate_board, also returning the contribution of each term. The
+// score is their total, rounded to the grain.
+pub fn trace_evaluation(board: &Board, side_to_move: Colour) -> (Score, EvalTrace) {
+    let mut trace = EvalTrace::default();
+    let score = evaluate(
+        board,
+        side_to_move,
+        &EvalParams::default(),
+        Some(&mut trace),
+    );
+    (score, trace)
+}
+
+// the terms are only recorded if there's a trace to fill
+fn evaluate(
+    board: &Board,
+    side_to_move: Colour,
+    params: &EvalParams,
+    mut trace: Option<&mut EvalTrace>,
+) -> Score {
+    let mut score = board.get_net_material();
+
+    // white
+    PIECE_MAP.iter().for_each(|(pce, map)| {
+        board.squares_of(pce, &Colour::White).for_each(|sq| {
+            let sq_value = map[sq.as_index()] as Score;
+            score += sq_value;
+            if let Some(trace) = &mut trace {
+                trace.add(EvalTerm::Material(*pce), &Colour::White, pce.value());
+                trace.add(EvalTerm::PieceSquare(*pce), &Colour::White, sq_value);
+            }
+        });
+    });
+
+    // black (note negative score, and mirror'ed table lookup)
+    PIECE_MAP.iter().for_each(|(pce, map)| {
+        board.squares_of(pce, &Colour::Black).for_each(|sq| {
+            let sq_value = map[63 - sq.as_index()] as Score;
+            score -= sq_value;
+            if let Some(trace) = &mut trace {
+                trace.add(EvalTerm::Material(*pce), &Colour::Black, pce.value());
+                trace.add(EvalTerm::PieceSquare(*pce), &Colour::Black, sq_value);
+            }
+        });
+    });
+
+    score += evaluate_passed_pawns(board, &Colour::White, &side_to_move, &mut trace);
+    score -= evaluate_passed_pawns(board, &Colour::Black, &side_to_move, &mut trace);
+
+    score += evaluate_king_tropism(board, &Colour::White, &mut trace);
+    score -= evaluate_king_tropism(board, &Colour::Black, &mut trace);
+
+    if let Some(trace) = &mut trace {
+        trace.add(EvalTerm::Tempo, &side_to_move, params.tempo);
+    }
+    let score = if side_to_move == Colour::White {
+        score
+    } else {
+        -score
+    } + params.tempo;
+
+    // towards 0, so both sides' scores round the same way
+    let grain = params.grain.max(1);
+    score / grain * grain
+}
+
+// The passed pawns of one colour, from that colour's point of view
+fn evaluate_passed_pawns(
+    board: &Board,
+    colour: &Colour,
+    side_to_move: &Colour,
+    trace: &mut Option<&mut EvalTrace>,
+) -> Score {
+    let enemy = colour.flip_side();
+    let pawns = board.get_piece_bitboard(&Piece::Pawn, colour).into_u64();
+    let enemy_pawns = board.get_piece_bitboard(&Piece::Pawn, &enemy).into_u64();
+
+    let passers = board
+        .squares_of(&Piece::Pawn, colour)
+        .filter(|sq| front_span(sq.as_index(), colour) & enemy_pawns == 0)
+        .fold(0u64, |passers, sq| passers | 1 << sq.as_index());
+    if passers == 0 {
+        return 0;
+    }
+
+    let endgame_weight = MAX_GAME_PHASE - game_phase(board);
+    let enemy_has_pieces = PHASE_WEIGHTS
+        .iter()
+        .any(|(pce, _)| !board.get_piece_bitboard(pce, &enemy).is_empty());
+    let own_king = board.king_square(colour).as_index();
+    let enemy_king = board.king_square(&enemy).as_index();
+    let occupied = board.occupied().into_u64();
+
+    let mut score = 0;
+    let mut apply = |term: EvalTerm, bonus: Score| {
+        score += bonus;
+        if let Some(trace) = trace {
+            trace.add(term, colour, bonus);
+        }
+    };
+
+    for sq in Bitboard::new(passers).iterator() {
+        let sq = sq.as_index();
+        let rank = relative_rank(sq, colour);
+        let abs_rank = sq / 8;
+        let neighbours = adjacent_files(sq % 8);
+
+        apply(EvalTerm::PassedPawn, PASSED_PAWN_BONUS[rank]);
+
+        let alongside = RANK_1_MASK << (8 * abs_rank)
+            | RANK_1_MASK << (8 * (abs_rank - 1))
+            | RANK_1_MASK << (8 * (abs_rank + 1));
+        if passers & neighbours & alongside != 0 {
+            apply(EvalTerm::ConnectedPasser, CONNECTED_PASSER_BONUS[rank]);
+        }
+
+        let behind = match colour {
+            Colour::White => RANK_1_MASK << (8 * (abs_rank - 1)),
+            Colour::Black => RANK_1_MASK << (8 * (abs_rank + 1)),
+        };
+        if pawns & neighbours & behind != 0 {
+            apply(EvalTerm::SupportedPasser, SUPPORTED_PASSER_BONUS[rank]);
+        }
+
+        // rule of the square - the enemy king can't reach the queening
+        // square in time, and nothing is in the way of the pawn
+        let path = front_span(sq, colour) & FILE_A_MASK << (sq % 8);
+        if !enemy_has_pieces && path & occupied == 0 {
+            let queening_sq = match colour {
+                Colour::White => 56 + sq % 8,
+                Colour::Black => sq % 8,
+            };
+            // a double step from the starting rank
+            let pawn_moves = (7 - rank.max(2)) as Score;
+            let tempo = (*side_to_move == enemy) as Score;
+            if distance(enemy_king, queening_sq) - tempo > pawn_moves {
+                apply(EvalTerm::UnstoppablePawn, UNSTOPPABLE_PAWN_BONUS);
+            }
+        }
+
+        if endgame_weight > 0 {
+            let front_sq = match colour {
+                Colour::White => sq + 8,
+                Colour::Black => sq - 8,
+            };
+            let nearer = distance(enemy_king, front_sq) - distance(own_king, front_sq);
+            let bonus = nearer * rank as Score * PASSER_KING_PROXIMITY_WEIGHT * endgame_weight
+                / MAX_GAME_PHASE;
+            apply(EvalTerm::PasserKingProximity, bonus);
+        }
+    }
+    score
+}
+
+// The pieces of one colour near the enemy king, from that colour's point
+// of view
+fn evaluate_king_tropism(
+    board: &Board,
+    colour: &Colour,
+    trace: &mut Option<&mut EvalTrace>,
+) -> Score {
+    let middlegame_weight = game_phase(board);
+    if middlegame_weight == 0 {
+        return 0;
+    }
+    let enemy_king = board.king_square(&colour.flip_side()).as_index();
+
+    let closeness: Score = KING_TROPISM_WEIGHTS
+        .iter()
+        .map(|(pce, weight)| {
+            board
+                .squares_of(pce, colour)
+                .map(|sq| (7 - distance(sq.as_index(), enemy_king)) * weight)
+                .sum::<Score>()
+        })
+        .sum();
+    let score = closeness * middlegame_weight / MAX_GAME_PHASE;
+
+    if let Some(trace) = trace {
+        trace.add(EvalTerm::KingTropism, colour, score);
+    }
+    score
+}
+
+// the squares ahead of a pawn on its own and the adjacent files
+fn front_span(sq: usize, colour: &Colour) -> u64 {
+    let files = FILE_A_MASK << (sq % 8) | adjacent_files(sq % 8);
+    let ahead = match colour {
+        Colour::White => (!0u64).checked_shl(8 * (sq / 8 + 1) as u32).unwrap_or(0),
+        Colour::Black => (1u64 << (8 * (sq / 8))) - 1,
+    };
+    files & ahead
+}
+
+fn adjacent_files(file: usize) -> u64 {
+    let mut files = 0;
+    if file > 0 {
+        files |= FILE_A_MASK << (file - 1);
+    }
+    if file < 7 {
+        files |= FILE_A_MASK << (file + 1);
+    }
+    files
+}
+
+// 0 for the colour's back rank, up to 7 for the queening rank
+const fn relative_rank(sq: usize, colour: &Colour) -> usize {
+    match colour {
+        Colour::White => sq / 8,
+        Colour::Black => 7 - sq / 8,
+    }
+}
+
+// king moves between the squares
+fn distance(sq1: usize, sq2: usize) -> Score {
+    let file_distance = (sq1 % 8).abs_diff(sq2 % 8);
+    let rank_distance = (sq1 / 8).abs_diff(sq2 / 8);
+    file_distance.max(rank_distance) as Score
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::evaluate::EvalParams;
+    use crate::evaluate::EvalTerm;
+    use dolphin_core::board::colour::Colour;
+    use dolphin_core::board::occupancy_masks::OccupancyMasks;
+    use dolphin_core::board::piece::Piece;
+    use dolphin_core::io::fen;
+    use dolphin_core::position::attack_checker::AttackChecker;
+    use dolphin_core::position::game_position::Position;
+
+    #[test]
+    pub fn evaluate_sample_white_position() {
+        let fen = "k7/8/1P3B2/P6P/3Q4/1N6/3K4/7R w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let score = super::evaluate_board(pos.board(), Colour::White);
+        assert_eq!(score, 3152);
+
+        // Pawn = 100,
+        // Knight = 320,
+        // Bishop = 330,
+        // Rook = 500,
+        // Queen = 900,
+        // King = 20000,
+
+        // white material = 22350
+        //  - 3x pawns      = 300
+        //  - 1x knight     = 320
+        //  - 1x rook       = 500
+        //  - 1x bishop     = 330
+        //  - 1x queen      = 900
+        //  - 1x king       = 20000
+        //
+        // black material = 20000
+        //  - 1x king       = 20000
+
+        //
+        // white piece positions = 35
+        //  - Pawns: 5 + 10 + 5 = 20
+        //  - Knight: 5         = 5
+        //  - Queen: 5          = 5
+        //  - King: 0           = 0
+        //  - Bishop: 5         = 5
+        //  - Rook: 0           = 0
+        //
+        // Black position pieces = 20
+        //
+        // white passed pawns = 770
+        //  - Passed: 60 + 35 + 35           = 130
+        //  - Connected: b6 + a5 = 25 + 15   = 40
+        //  - Supported: b6 = 20             = 20
+        //  - Unstoppable: h5                = 600
+        //  - King proximity: -26 - 10 + 16  = -20
+        //
+        // white king tropism = 7
+        //  - Knight: 2 * 3, Bishop: 2 * 2,
+        //    Queen: 3 * 4, Rook: 0 * 2      = 22
+        //  - at a game phase of 8 / 24      = 7
+        //
+        // tempo (white to move) = 10
+        //
+        // expected score   = (22350 - 20000) + (35 - 20) + 770 + 7 + 10
+        //                  = 3152
+    }
+
+    #[test]
+    pub fn evaluate_sample_black_position() {
+        let fen = "1k6/1pp3q1/5b2/1n6/7p/8/3K4/8 b - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let score = super::evaluate_board(pos.board(), Colour::White);
+        assert_eq!(score, -1948);
+
+        // white material = 20000
+        //  - 1x king       = 20000
+        //
+        // black material = 21850
+        //  - 3x pawns      = 300
+        //  - 1x knight     = 320
+        //  - 1x bishop     = 330
+        //  - 1x queen      = 900
+        //  - 1x king       = 20000
+        //
+        // Black piece positions    = 65
+        //  - Pawns: 10 + 10 + 5 = 25
+        //  - Knight: 5          = 5
+        //  - Queen: 0           = 0
+        //  - King: 30           = 30
+        //  - Bishop: 5          = 5
+        //
+        // White position pieces = 0
+        //
+        // Black passed pawns = 39
+        //  - Passed: 5 + 5 + 35             = 45
+        //  - Connected: b7 + c7 = 0 + 0     = 0
+        //  - Unstoppable: none, the white king is in h4's square
+        //    when it's white to move
+        //  - King proximity: -2 - 2 - 2     = -6
+        //
+        // Black king tropism = 6
+        //  - Knight: 4 * 3, Bishop: 3 * 2,
+        //    Queen: 2 * 4                   = 26
+        //  - at a game phase of 6 / 24      = 6
+        //
+        // tempo (white to move) = 10
+        //
+        // expected score   = (20000 - 21850) + (0 - 60) - 39 - 6 + 10
+        //                  = -1948
+    }
+
+    #[test]
+    pub fn tempo_and_grain_from_params() {
+        let fen = "k7/8/1P3B2/P6P/3Q4/1N6/3K4/7R w - - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+        let eval = |side_to_move, tempo, grain| {
+            let params = EvalParams { tempo, grain };
+            super::evaluate_board_with_params(&board, side_to_move, &params)
+        };
+
+        // 3142 without either
+        assert_eq!(eval(Colour::White, 0, 1), 3142);
+        assert_eq!(eval(Colour::Black, 0, 1), -3142);
+        assert_eq!(eval(Colour::White, 20, 1), 3162);
+        assert_eq!(eval(Colour::Black, 20, 1), -3122);
+
+        // rounded towards 0 for either side
+        assert_eq!(eval(Colour::White, 0, 10), 3140);
+        assert_eq!(eval(Colour::Black, 0, 10), -3140);
+        assert_eq!(eval(Colour::White, 0, 0), 3142);
+
+        assert_eq!(
+            super::evaluate_board(&board, Colour::White),
+            eval(Colour::White, 10, 4)
+        );
+    }
+
+    #[test]
+    pub fn game_phase_from_non_pawn_pieces() {
+        let fens = [
+            (
+                "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+                24,
+            ),
+            ("4k3/pppppppp/8/8/8/8/PPPPPPPP/4K3 w - - 0 1", 0),
+            ("4k3/8/8/8/8/8/8/R2QK3 w - - 0 1", 6),
+            // promoted queens don't take it past the maximum
+            ("qqqqk3/8/8/8/8/8/8/QQQQK3 w - - 0 1", 24),
+        ];
+
+        for (fen, expected) in fens {
+            let (board, _, _, _, _) = fen::decompose_fen(fen);
+            assert_eq!(super::game_phase(&board), expected);
+        }
+    }
+
+    #[test]
+    pub fn trace_records_each_term_per_colour() {
+        let fen = "k7/8/1P3B2/P6P/3Q4/1N6/3K4/7R w - - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+
+        let (score, trace) = super::trace_evaluation(&board, Colour::White);
+        assert_eq!(score, super::evaluate_board(&board, Colour::White));
+        // 3152, already a multiple of the grain
+        assert_eq!(
+            trace.total(&Colour::White) - trace.total(&Colour::Black),
+            3152
+        );
+        assert_eq!(score, 3152);
+        assert_eq!(trace.score(EvalTerm::Tempo, &Colour::White), 10);
+        assert_eq!(trace.count(EvalTerm::Tempo, &Colour::Black), 0);
+
+        // one square value per pawn (5 + 10 + 5)
+        let pawn_squares = EvalTerm::PieceSquare(Piece::Pawn);
+        assert_eq!(trace.count(pawn_squares, &Colour::White), 3);
+        assert_eq!(trace.score(pawn_squares, &Colour::White), 20);
+        assert_eq!(trace.count(pawn_squares, &Colour::Black), 0);
+
+        assert_eq!(
+            trace.count(EvalTerm::Material(Piece::Queen), &Colour::White),
+            1
+        );
+        assert_eq!(
+            trace.score(EvalTerm::Material(Piece::Queen), &Colour::White),
+            900
+        );
+        assert_eq!(
+            trace.score(EvalTerm::Material(Piece::King), &Colour::Black),
+            20000
+        );
+        assert_eq!(
+            trace.score(EvalTerm::PieceSquare(Piece::King), &Colour::Black),
+            20
+        );
+
+        // black to move flips the score, and the tempo goes to black
+        let (black_score, black_trace) = super::trace_evaluation(&board, Colour::Black);
+        assert_eq!(black_score, -3132);
+        assert_eq!(black_trace.score(EvalTerm::Tempo, &Colour::Black), 10);
+        assert_eq!(black_trace.count(EvalTerm::Tempo, &Colour::White), 0);
+        assert_eq!(
+            black_trace.total(&Colour::White),
+            trace.total(&Colour::White) - 10
+        );
+    }
+
+    #[test]
+    pub fn king_tropism_for_pieces_near_the_enemy_king() {
+        let tropism = |fen| {
+            let (board, _, _, _, _) = fen::decompose_fen(fen);
+            let (_, trace) = super::trace_evaluation(&board, Colour::White);
+            trace.score(EvalTerm::KingTropism, &Colour::White)
+        };
+
+        // a knight two squares from the king (5 * 3) and a rook as far away
+        // as it can be (0 * 2), at a game phase of 3
+        assert_eq!(tropism("6k1/8/5N2/8/8/8/8/R3K3 w - - 0 1"), 1);
+        // the knight nearer the king with the other pieces on
+        assert!(
+            tropism("rnbq2k1/pppppppp/5N2/8/8/8/PPPPPPPP/R1BQKB1R w - - 0 1")
+                > tropism("rnbq2k1/pppppppp/8/8/8/5N2/PPPPPPPP/R1BQKB1R w - - 0 1")
+        );
+        // nothing once only kings and pawns are left
+        assert_eq!(tropism("6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1"), 0);
+    }
+
+    #[test]
+    pub fn passed_pawns_connected_and_supported_by_rank() {
+        // b3 and c4 are passed, d4 and e6 aren't
+        let fen = "r3k3/8/4p3/8/2PP4/1P6/8/R3K3 w - - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+        let (_, trace) = super::trace_evaluation(&board, Colour::White);
+
+        assert_eq!(trace.count(EvalTerm::PassedPawn, &Colour::White), 2);
+        assert_eq!(trace.score(EvalTerm::PassedPawn, &Colour::White), 10 + 20);
+        assert_eq!(trace.count(EvalTerm::PassedPawn, &Colour::Black), 0);
+
+        assert_eq!(trace.count(EvalTerm::ConnectedPasser, &Colour::White), 2);
+        assert_eq!(
+            trace.score(EvalTerm::ConnectedPasser, &Colour::White),
+            5 + 10
+        );
+
+        // c4 is defended by b3
+        assert_eq!(trace.count(EvalTerm::SupportedPasser, &Colour::White), 1);
+        assert_eq!(trace.score(EvalTerm::SupportedPasser, &Colour::White), 10);
+
+        // black still has a rook to stop them
+        assert_eq!(trace.count(EvalTerm::UnstoppablePawn, &Colour::White), 0);
+    }
+
+    #[test]
+    pub fn unstoppable_pawn_by_rule_of_the_square() {
+        // the black king is 4 moves from a8, the pawn 3
+        let fen = "8/8/8/P3k3/8/8/8/K7 w - - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+
+        let (_, trace) = super::trace_evaluation(&board, Colour::White);
+        assert_eq!(trace.count(EvalTerm::UnstoppablePawn, &Colour::White), 1);
+        assert_eq!(
+            trace.score(EvalTerm::UnstoppablePawn, &Colour::White),
+            super::UNSTOPPABLE_PAWN_BONUS
+        );
+
+        // with black to move, the king gets into the square
+        let (_, trace) = super::trace_evaluation(&board, Colour::Black);
+        assert_eq!(trace.count(EvalTerm::UnstoppablePawn, &Colour::White), 0);
+
+        // a knight can still stop it
+        let fen = "8/8/8/P3k3/8/8/8/K5n1 w - - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+        let (_, trace) = super::trace_evaluation(&board, Colour::White);
+        assert_eq!(trace.count(EvalTerm::UnstoppablePawn, &Colour::White), 0);
+    }
+
+    #[test]
+    pub fn king_nearer_passer_scores_higher_in_pawn_endings() {
+        let near_fen = "8/8/8/P3k3/1K6/8/8/8 w - - 0 1";
+        let far_fen = "8/8/8/P3k3/8/8/8/7K w - - 0 1";
+
+        let (near_board, _, _, _, _) = fen::decompose_fen(near_fen);
+        let (far_board, _, _, _, _) = fen::decompose_fen(far_fen);
+        let (_, near_trace) = super::trace_evaluation(&near_board, Colour::White);
+        let (_, far_trace) = super::trace_evaluation(&far_board, Colour::White);
+
+        let proximity = EvalTerm::PasserKingProximity;
+        assert!(near_trace.score(proximity, &Colour::White) > 0);
+        assert!(far_trace.score(proximity, &Colour::White) < 0);
+
+        // not counted with all the pieces on the board
+        let fen = "rnbqkbnr/ppp3pp/8/4P3/8/8/PPP1PPPP/RNBQKBNR w KQkq - 0 1";
+        let (board, _, _, _, _) = fen::decompose_fen(fen);
+        let (_, trace) = super::trace_evaluation(&board, Colour::White);
+        // e5 and e2
+        assert_eq!(trace.count(EvalTerm::PassedPawn, &Colour::White), 2);
+        assert_eq!(trace.count(proximity, &Colour::White), 0);
+    }
+}
@@ -0,0 +1,12 @@
+pub mod engine;
+pub mod evaluate;
+pub mod mate_solver;
+pub mod mcts;
+pub mod move_order;
+pub mod pv_table;
+pub mod search;
+pub mod search_stack;
+pub mod strength;
+pub mod time_manager;
+pub mod tree_dump;
+pub mod tt;
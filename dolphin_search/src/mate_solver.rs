@@ -0,0 +1,385 @@
+use dolphin_core::moves::mov::Move;
+use dolphin_core::moves::move_gen::MoveGenerator;
+use dolphin_core::moves::move_list::MoveList;
+use dolphin_core::position::game_position::Position;
+use std::mem;
+
+// Proof-number search, for solving mates. The tree grows best first from
+// the most proving node: the leaf whose outcome would go furthest towards
+// proving or disproving the mate, judged by how many leaves are left to
+// solve below each move. It follows a forced line as deep as it goes
+// without a depth limit, so it finds long mates, and composed problems
+// with quiet keys, that alpha-beta only reaches with a very deep search.
+//
+// The side to move at the root is the attacker. Draws (stalemate,
+// repetition and the fifty move rule) disprove the mate, as does running
+// out of moves when there's a limit on them. A proof isn't necessarily the
+// quickest mate, so once one is found the solver looks again for a mate
+// in fewer moves, until there isn't one.
+
+const INFINITE: u32 = u32::MAX;
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum MateResult {
+    // the main line of the proof, ending in mate
+    Mate(Vec<Move>),
+    NoMate,
+    // the node limit was reached first
+    Unknown,
+}
+
+struct Node {
+    mv: Move,
+    children: Vec<usize>,
+    expanded: bool,
+    // the attacker is to move, so one mating move is enough; otherwise every
+    // defence has to be mated
+    attacker_to_move: bool,
+    // leaves left to solve to prove (or disprove) the mate from here
+    proof: u32,
+    disproof: u32,
+}
+
+impl Node {
+    fn new(mv: Move, attacker_to_move: bool) -> Node {
+        Node {
+            mv,
+            children: Vec::new(),
+            expanded: false,
+            attacker_to_move,
+            proof: 1,
+            disproof: 1,
+        }
+    }
+
+    const fn is_solved(&self) -> bool {
+        self.proof == 0 || self.disproof == 0
+    }
+}
+
+pub struct MateSolver {
+    max_nodes: u64,
+    // nodes at this ply can't be mated any more
+    max_plies: Option<u16>,
+    move_gen: MoveGenerator,
+    tree: Vec<Node>,
+    // the tree of the quickest mate found
+    proof: Vec<Node>,
+    nodes: u64,
+}
+
+impl MateSolver {
+    // expands up to max_nodes nodes per solve
+    pub fn new(max_nodes: u64) -> Self {
+        MateSolver {
+            max_nodes,
+            max_plies: None,
+            move_gen: MoveGenerator::new(),
+            tree: Vec::new(),
+            proof: Vec::new(),
+            nodes: 0,
+        }
+    }
+
+    // only looks for mates in at most this many moves, None for any length
+    pub fn set_max_moves(&mut self, max_moves: Option<u16>) {
+        self.max_plies = max_moves.map(|moves| 2 * moves.max(1) - 1);
+    }
+
+    // nodes expanded in the last solve
+    pub const fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    // The position is the same afterwards
+    pub fn solve(&mut self, pos: &mut Position) -> MateResult {
+        self.proof.clear();
+        self.nodes = 0;
+
+        let mut result = self.solve_within(pos, self.max_plies);
+        while let MateResult::Mate(line) = &result {
+            if line.len() < 3 {
+                break;
+            }
+            let max_plies = line.len() as u16 - 2;
+            match self.solve_within(pos, Some(max_plies)) {
+                quicker @ MateResult::Mate(_) => result = quicker,
+                // the mate found is the quickest, or the nodes ran out
+                _ => break,
+            }
+        }
+        result
+    }
+
+    // The proof of the last solve, one move per line and indented by ply:
+    // the attacker's mating move at each of its turns, and every defence.
+    // Empty if there's no proof.
+    pub fn proof_tree(&self, pos: &Position) -> String {
+        let mut lines = Vec::new();
+        if !self.proof.is_empty() {
+            self.write_proof_tree(0, pos, 0, &Self::proof_depths(&self.proof), &mut lines);
+        }
+        lines.join("\n")
+    }
+
+    fn solve_within(&mut self, pos: &mut Position, max_plies: Option<u16>) -> MateResult {
+        self.tree.clear();
+        self.tree.push(Node::new(Move::default(), true));
+
+        while !self.tree[0].is_solved() && self.nodes < self.max_nodes {
+            let path = self.select_most_proving(pos);
+            self.expand(*path.last().unwrap(), pos, path.len() - 1, max_plies);
+
+            for _ in 1..path.len() {
+                pos.take_move();
+            }
+            for &node in path.iter().rev() {
+                self.update(node);
+            }
+        }
+
+        if self.tree[0].proof == 0 {
+            self.proof = mem::take(&mut self.tree);
+            MateResult::Mate(self.main_line())
+        } else if self.tree[0].disproof == 0 {
+            MateResult::NoMate
+        } else {
+            MateResult::Unknown
+        }
+    }
+
+    // makes the moves down to the most proving node, returning the path
+    fn select_most_proving(&self, pos: &mut Position) -> Vec<usize> {
+        let mut path = vec![0];
+        let mut node = 0;
+        while self.tree[node].expanded {
+            let parent = &self.tree[node];
+            let children = parent.children.iter().copied();
+            node = if parent.attacker_to_move {
+                children.min_by_key(|&child| self.tree[child].proof)
+            } else {
+                children.min_by_key(|&child| self.tree[child].disproof)
+            }
+            .expect("Unsolved node has no children");
+
+            pos.make_move(&self.tree[node].mv);
+            path.push(node);
+        }
+        path
+    }
+
+    fn expand(&mut self, node: usize, pos: &mut Position, ply: usize, max_plies: Option<u16>) {
+        self.tree[node].expanded = true;
+        self.nodes += 1;
+
+        let mut move_list = MoveList::new();
+        self.move_gen.generate_legal_moves(pos, &mut move_list);
+
+        let attacker_to_move = self.tree[node].attacker_to_move;
+        let is_mate = move_list.is_empty() && pos.is_king_sq_attacked();
+        let is_draw =
+            move_list.is_empty() || (ply > 0 && (pos.is_repetition() || pos.is_fifty_move_draw()));
+        let out_of_moves = max_plies.is_some_and(|max| ply >= max as usize);
+
+        if is_mate && !attacker_to_move {
+            self.set_values(node, 0, INFINITE);
+        } else if is_mate || is_draw || out_of_moves {
+            self.set_values(node, INFINITE, 0);
+        } else {
+            for mv in move_list.iterator() {
+                self.tree.push(Node::new(*mv, !attacker_to_move));
+                let child = self.tree.len() - 1;
+                self.tree[node].children.push(child);
+            }
+            self.update(node);
+        }
+    }
+
+    // the node's numbers from its children's, if it has any
+    fn update(&mut self, node: usize) {
+        let children = &self.tree[node].children;
+        if children.is_empty() {
+            return;
+        }
+        let proofs = children.iter().map(|&child| self.tree[child].proof);
+        let disproofs = children.iter().map(|&child| self.tree[child].disproof);
+
+        // one proven move proves the attacker's node, but all the defender's
+        // moves have to be
+        let (proof, disproof) = if self.tree[node].attacker_to_move {
+            (
+                proofs.min().unwrap(),
+                disproofs.fold(0, u32::saturating_add),
+            )
+        } else {
+            (
+                proofs.fold(0, u32::saturating_add),
+                disproofs.min().unwrap(),
+            )
+        };
+        self.set_values(node, proof, disproof);
+    }
+
+    fn set_values(&mut self, node: usize, proof: u32, disproof: u32) {
+        self.tree[node].proof = proof;
+        self.tree[node].disproof = disproof;
+    }
+
+    // Plies to mate below each proven node, with the attacker mating as
+    // quickly as it can and the defender holding out as long as it can.
+    // Children come after their parents, so one pass from the end will do.
+    fn proof_depths(proof: &[Node]) -> Vec<Option<u16>> {
+        let mut depths = vec![None; proof.len()];
+        for (i, node) in proof.iter().enumerate().rev() {
+            if node.proof != 0 {
+                continue;
+            }
+            let child_depths = node.children.iter().filter_map(|&child| depths[child]);
+            depths[i] = if node.children.is_empty() {
+                Some(0)
+            } else if node.attacker_to_move {
+                child_depths.min().map(|depth| depth + 1)
+            } else {
+                child_depths.max().map(|depth| depth + 1)
+            };
+        }
+        depths
+    }
+
+    fn main_line(&self) -> Vec<Move> {
+        let depths = Self::proof_depths(&self.proof);
+        let mut line = Vec::new();
+        let mut node = 0;
+        while let Some(child) = self.proof_child(node, &depths) {
+            line.push(self.proof[child].mv);
+            node = child;
+        }
+        line
+    }
+
+    // the attacker's quickest mate, or the defender's longest defence
+    fn proof_child(&self, node: usize, depths: &[Option<u16>]) -> Option<usize> {
+        let children = self.proof[node]
+            .children
+            .iter()
+            .copied()
+            .filter(|&child| depths[child].is_some());
+        if self.proof[node].attacker_to_move {
+            children.min_by_key(|&child| depths[child])
+        } else {
+            children.max_by_key(|&child| depths[child])
+        }
+    }
+
+    fn write_proof_tree(
+        &self,
+        node: usize,
+        pos: &Position,
+        ply: usize,
+        depths: &[Option<u16>],
+        lines: &mut Vec<String>,
+    ) {
+        let children: Vec<usize> = if self.proof[node].attacker_to_move {
+            self.proof_child(node, depths).into_iter().collect()
+        } else {
+            self.proof[node].children.clone()
+        };
+
+        for child in children {
+            let mv = self.proof[child].mv;
+            let san = mv.to_string_with_board(pos.board(), pos.occupancy_masks());
+            lines.push(format!("{}{}", "  ".repeat(ply), san));
+
+            let (next, _) = pos.make_move_copied(&mv);
+            self.write_proof_tree(child, &next, ply + 1, depths, lines);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::mate_solver::MateResult;
+    use crate::mate_solver::MateSolver;
+    use dolphin_core::board::occupancy_masks::OccupancyMasks;
+    use dolphin_core::io::fen;
+    use dolphin_core::moves::move_gen::MoveGenerator;
+    use dolphin_core::moves::move_list::MoveList;
+    use dolphin_core::position::attack_checker::AttackChecker;
+    use dolphin_core::position::game_position::Position;
+
+    // the rooks need two moves to mate, eg Ra7 and Rb8
+    const LADDER_MATE_IN_2: &str = "7k/8/8/8/8/8/R7/1R4K1 w - - 0 1";
+
+    #[test]
+    pub fn mate_in_two_proven_with_main_line_ending_in_mate() {
+        let attack_checker = AttackChecker::new();
+        let mut pos = new_position(LADDER_MATE_IN_2, &attack_checker);
+        let expected = new_position(LADDER_MATE_IN_2, &attack_checker);
+
+        for max_moves in [None, Some(2), Some(5)] {
+            let mut solver = MateSolver::new(100_000);
+            solver.set_max_moves(max_moves);
+
+            let MateResult::Mate(line) = solver.solve(&mut pos) else {
+                panic!("No mate found, max moves {:?}", max_moves);
+            };
+            assert!(pos == expected);
+            assert_eq!(line.len(), 3);
+
+            let mut mated = new_position(LADDER_MATE_IN_2, &attack_checker);
+            for mv in line.iter() {
+                assert!(mated.is_legal(mv));
+                mated.make_move(mv);
+            }
+            let mut move_list = MoveList::new();
+            MoveGenerator::new().generate_legal_moves(&mut mated, &mut move_list);
+            assert!(move_list.is_empty() && mated.is_king_sq_attacked());
+
+            // the attacker's move then every defence, then the mates
+            let tree = solver.proof_tree(&pos);
+            let tree_lines: Vec<&str> = tree.lines().collect();
+            assert!(!tree_lines[0].starts_with(' '));
+            assert!(tree_lines[1..].iter().all(|line| line.starts_with("  ")));
+        }
+    }
+
+    #[test]
+    pub fn no_mate_within_the_move_limit() {
+        let attack_checker = AttackChecker::new();
+        let mut pos = new_position(LADDER_MATE_IN_2, &attack_checker);
+
+        let mut solver = MateSolver::new(100_000);
+        solver.set_max_moves(Some(1));
+        assert_eq!(solver.solve(&mut pos), MateResult::NoMate);
+        assert!(solver.proof_tree(&pos).is_empty());
+
+        // a lone king can't mate at all
+        let mut pos = new_position("7k/8/8/8/8/8/8/6K1 w - - 0 1", &attack_checker);
+        solver.set_max_moves(Some(3));
+        assert_eq!(solver.solve(&mut pos), MateResult::NoMate);
+    }
+
+    #[test]
+    pub fn unknown_when_node_limit_reached() {
+        let attack_checker = AttackChecker::new();
+        let mut pos = new_position(LADDER_MATE_IN_2, &attack_checker);
+
+        let mut solver = MateSolver::new(3);
+        assert_eq!(solver.solve(&mut pos), MateResult::Unknown);
+        assert_eq!(solver.nodes(), 3);
+    }
+
+    fn new_position<'a>(fen: &str, attack_checker: &'a AttackChecker) -> Position<'a> {
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+        Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            attack_checker,
+        )
+    }
+}
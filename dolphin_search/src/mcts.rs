@@ -0,0 +1,338 @@
+use crate::engine::SearchEngine;
+use crate::evaluate::evaluate_board;
+use dolphin_core::moves::mov::Move;
+use dolphin_core::moves::mov::Score;
+use dolphin_core::moves::move_gen::MoveGenerator;
+use dolphin_core::moves::move_list::MoveList;
+use dolphin_core::position::game_position::Position;
+
+// Experimental Monte Carlo Tree Search (UCT), to compare against the
+// alpha-beta Search on the same evaluation. There are no random playouts:
+// a new leaf is valued by the static evaluation, turned into an expected
+// result (0 a loss, 1 a win) for the side to move. Checkmates and draws
+// are valued exactly. The tree is rebuilt for every search.
+
+// weight of exploration against exploitation in the UCT formula
+pub const DEFAULT_EXPLORATION: f64 = 1.4;
+
+// centipawns of advantage for odds of 10 to 1, turning evaluations into an
+// expected result and back
+const EVAL_SCALE: f64 = 400.0;
+
+// expected results are kept off 0 and 1 when turned back into a score
+const MAX_EXPECTED_RESULT: f64 = 0.999;
+
+const RESULT_DRAW: f64 = 0.5;
+const RESULT_LOSS: f64 = 0.0;
+
+struct Node {
+    mv: Move,
+    children: Vec<usize>,
+    expanded: bool,
+    // result for the side to move, where the game is over
+    terminal: Option<f64>,
+    visits: u32,
+    // sum of the results, for the side that made the move into the node
+    total: f64,
+}
+
+impl Node {
+    fn new(mv: Move) -> Node {
+        Node {
+            mv,
+            children: Vec::new(),
+            expanded: false,
+            terminal: None,
+            visits: 0,
+            total: 0.0,
+        }
+    }
+
+    fn mean(&self) -> f64 {
+        self.total / self.visits as f64
+    }
+}
+
+pub struct Mcts {
+    iterations: u64,
+    exploration: f64,
+    move_gen: MoveGenerator,
+    tree: Vec<Node>,
+    nodes: u64,
+    score: Score,
+    pv: Vec<Move>,
+}
+
+impl Mcts {
+    // runs the number of iterations (one leaf expanded for each) per search
+    pub fn new(iterations: u64) -> Self {
+        Mcts {
+            iterations,
+            exploration: DEFAULT_EXPLORATION,
+            move_gen: MoveGenerator::new(),
+            tree: Vec::new(),
+            nodes: 0,
+            score: 0,
+            pv: Vec::new(),
+        }
+    }
+
+    pub fn set_iterations(&mut self, iterations: u64) {
+        self.iterations = iterations;
+    }
+
+    pub fn set_exploration(&mut self, exploration: f64) {
+        self.exploration = exploration;
+    }
+
+    // visits to each root move in the last search
+    pub fn root_visits(&self) -> Vec<(Move, u32)> {
+        match self.tree.first() {
+            Some(root) => root
+                .children
+                .iter()
+                .map(|&child| (self.tree[child].mv, self.tree[child].visits))
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+
+    // selection, expansion, evaluation and backup for a single leaf
+    fn iterate(&mut self, pos: &mut Position) {
+        let mut path = vec![0];
+        let mut node = 0;
+        while self.tree[node].expanded && self.tree[node].terminal.is_none() {
+            node = self.select_child(node);
+            pos.make_move(&self.tree[node].mv);
+            path.push(node);
+        }
+
+        // result for the side to move at the leaf
+        let result = match self.tree[node].terminal {
+            Some(result) => result,
+            None => self.expand(node, pos),
+        };
+
+        // each node holds results for the side that moved into it, which
+        // alternates going back up the tree
+        for (i, &idx) in path.iter().rev().enumerate() {
+            let node = &mut self.tree[idx];
+            node.visits += 1;
+            node.total += if i % 2 == 0 { 1.0 - result } else { result };
+        }
+
+        for _ in 1..path.len() {
+            pos.take_move();
+        }
+    }
+
+    fn select_child(&self, parent: usize) -> usize {
+        let log_parent_visits = (self.tree[parent].visits.max(1) as f64).ln();
+
+        let uct = |child: usize| {
+            let node = &self.tree[child];
+            if node.visits == 0 {
+                return f64::INFINITY;
+            }
+            node.mean() + self.exploration * (log_parent_visits / node.visits as f64).sqrt()
+        };
+
+        // the first child with the highest UCT value
+        self.tree[parent]
+            .children
+            .iter()
+            .copied()
+            .fold(None, |best: Option<(usize, f64)>, child| {
+                let value = uct(child);
+                match best {
+                    Some((_, best_value)) if best_value >= value => best,
+                    _ => Some((child, value)),
+                }
+            })
+            .map(|(child, _)| child)
+            .expect("Node has no children")
+    }
+
+    // Adds the node's children, returning the result for the side to move.
+    // The root is only over if there are no moves, so a move is still found
+    // in a drawn position.
+    fn expand(&mut self, node: usize, pos: &mut Position) -> f64 {
+        self.tree[node].expanded = true;
+        self.nodes += 1;
+
+        let mut move_list = MoveList::new();
+        self.move_gen.generate_legal_moves(pos, &mut move_list);
+
+        let terminal = if move_list.is_empty() && pos.is_king_sq_attacked() {
+            Some(RESULT_LOSS)
+        } else if move_list.is_empty()
+            || (node != 0 && (pos.is_fifty_move_draw() || pos.is_repetition()))
+        {
+            Some(RESULT_DRAW)
+        } else {
+            None
+        };
+        if let Some(result) = terminal {
+            self.tree[node].terminal = terminal;
+            return result;
+        }
+
+        for mv in move_list.iterator() {
+            self.tree.push(Node::new(*mv));
+            let child = self.tree.len() - 1;
+            self.tree[node].children.push(child);
+        }
+        to_expected_result(evaluate_board(pos.board(), pos.side_to_move()))
+    }
+
+    // the most visited line from the root
+    fn most_visited_line(&self) -> Vec<Move> {
+        let mut line = Vec::new();
+        let mut node = 0;
+        while !self.tree[node].children.is_empty() {
+            let child = self.most_visited_child(node);
+            if self.tree[child].visits == 0 {
+                break;
+            }
+            line.push(self.tree[child].mv);
+            node = child;
+        }
+        line
+    }
+
+    // the first child with the most visits
+    fn most_visited_child(&self, node: usize) -> usize {
+        let children = &self.tree[node].children;
+        children
+            .iter()
+            .copied()
+            .rev()
+            .max_by_key(|&child| self.tree[child].visits)
+            .expect("Node has no children")
+    }
+}
+
+fn to_expected_result(score: Score) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-score as f64 / EVAL_SCALE))
+}
+
+fn to_score(expected_result: f64) -> Score {
+    let result = expected_result.clamp(1.0 - MAX_EXPECTED_RESULT, MAX_EXPECTED_RESULT);
+    (-EVAL_SCALE * (1.0 / result - 1.0).log10()).round() as Score
+}
+
+impl SearchEngine for Mcts {
+    fn name(&self) -> &'static str {
+        "mcts"
+    }
+
+    fn new_game(&mut self) {
+        self.tree.clear();
+    }
+
+    fn search(&mut self, pos: &mut Position) -> Option<Move> {
+        self.tree.clear();
+        self.tree.push(Node::new(Move::default()));
+        self.nodes = 0;
+
+        for _ in 0..self.iterations.max(1) {
+            self.iterate(pos);
+            if self.tree[0].terminal.is_some() {
+                break;
+            }
+        }
+
+        self.pv = self.most_visited_line();
+        // the best move's results are for the side to move at the root
+        self.score = match self.tree[0].terminal {
+            Some(result) => to_score(result),
+            None => to_score(self.tree[self.most_visited_child(0)].mean()),
+        };
+        self.pv.first().copied()
+    }
+
+    fn score(&self) -> Score {
+        self.score
+    }
+
+    // leaves expanded
+    fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    fn pv(&self) -> &[Move] {
+        &self.pv
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::engine::SearchEngine;
+    use crate::mcts::Mcts;
+    use dolphin_core::board::occupancy_masks::OccupancyMasks;
+    use dolphin_core::io::fen;
+    use dolphin_core::position::attack_checker::AttackChecker;
+    use dolphin_core::position::game_position::Position;
+
+    #[test]
+    pub fn mcts_finds_mate_and_hanging_queen() {
+        let attack_checker = AttackChecker::new();
+
+        let fens = [
+            ("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", "a1a8"),
+            ("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1", "d1d5"),
+        ];
+
+        for (fen, expected) in fens {
+            let mut pos = new_position(fen, &attack_checker);
+            let mut mcts = Mcts::new(2000);
+
+            let best_move = mcts.search(&mut pos).unwrap();
+            assert_eq!(best_move.to_uci_string(), expected, "{}", fen);
+            assert_eq!(mcts.pv().first(), Some(&best_move));
+            assert!(mcts.score() > 0);
+            // revisits of the mate don't expand anything
+            assert!(mcts.nodes() > 0 && mcts.nodes() <= 2000);
+        }
+    }
+
+    #[test]
+    pub fn mcts_leaves_position_unchanged() {
+        let attack_checker = AttackChecker::new();
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let mut pos = new_position(fen, &attack_checker);
+        let expected = new_position(fen, &attack_checker);
+
+        let mut mcts = Mcts::new(500);
+        mcts.search(&mut pos).unwrap();
+
+        assert!(pos == expected);
+        let visits: u32 = mcts.root_visits().iter().map(|(_, visits)| visits).sum();
+        assert_eq!(visits, 500 - 1);
+    }
+
+    #[test]
+    pub fn mcts_no_move_when_mated() {
+        let attack_checker = AttackChecker::new();
+        let mut pos = new_position("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1", &attack_checker);
+
+        let mut mcts = Mcts::new(100);
+        assert_eq!(mcts.search(&mut pos), None);
+        assert!(mcts.score() < 0);
+        assert!(mcts.pv().is_empty());
+    }
+
+    fn new_position<'a>(fen: &str, attack_checker: &'a AttackChecker) -> Position<'a> {
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+        Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            attack_checker,
+        )
+    }
+}
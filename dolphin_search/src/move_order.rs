@@ -0,0 +1,87 @@
+use dolphin_core::moves::mov::Move;
+use dolphin_core::moves::mov::MoveType;
+use dolphin_core::moves::mov::Score;
+use dolphin_core::moves::move_list::MoveList;
+use dolphin_core::position::game_position::Position;
+use std::cmp::Reverse;
+
+// Moves are searched best first, by category. A promotion usually gains
+// more than a capture, and is as easily missed among the quiet moves, so
+// promotions have a category of their own above the captures. Moves in the
+// same category keep the order they were generated in.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
+enum MoveCategory {
+    Quiet,
+    // most valuable victim, then least valuable attacker
+    Capture,
+    // by the piece promoted to, then what's captured
+    Promotion,
+}
+
+pub fn order_moves(pos: &Position, move_list: &mut MoveList) {
+    move_list.sort_by_key(|mv| Reverse(order_key(pos, mv)));
+}
+
+// higher is searched first
+fn order_key(pos: &Position, mv: &Move) -> (MoveCategory, Score, Score) {
+    let captured = pos.captured_piece(mv).map(|pce| pce.value());
+
+    if mv.move_type() == MoveType::Promotion {
+        let promoted = mv.decode_promotion_piece().value();
+        return (MoveCategory::Promotion, promoted, captured.unwrap_or(0));
+    }
+
+    match captured {
+        Some(victim) => {
+            let attacker = pos
+                .board()
+                .get_piece_on_square(&mv.from_sq())
+                .map_or(0, |pce| pce.value());
+            (MoveCategory::Capture, victim, -attacker)
+        }
+        None => (MoveCategory::Quiet, 0, 0),
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::move_order::order_moves;
+    use dolphin_core::board::occupancy_masks::OccupancyMasks;
+    use dolphin_core::io::fen;
+    use dolphin_core::moves::move_gen::MoveGenerator;
+    use dolphin_core::moves::move_list::MoveList;
+    use dolphin_core::position::attack_checker::AttackChecker;
+    use dolphin_core::position::game_position::Position;
+
+    #[test]
+    pub fn promotions_ordered_above_captures_above_quiet_moves() {
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("1r2k3/P7/8/8/8/8/3q4/2QK4 w - - 0 1");
+        let attack_checker = AttackChecker::new();
+        let pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+
+        let mut move_list = MoveList::new();
+        MoveGenerator::new().generate_moves(&pos, &mut move_list);
+        order_moves(&pos, &mut move_list);
+
+        let ordered: Vec<String> = move_list.iterator().map(|mv| mv.to_uci_string()).collect();
+        let expected = [
+            // by the piece promoted to, captures first
+            "a7b8q", "a7a8q", "a7b8r", "a7a8r", "a7b8b", "a7a8b", "a7b8n", "a7a8n",
+            // the queen takes before the king does
+            "c1d2", "d1d2",
+        ];
+        assert_eq!(ordered[..expected.len()], expected);
+        assert!(ordered[expected.len()..]
+            .iter()
+            .all(|mv| mv != "c1d2" && mv != "d1d2"));
+    }
+}
@@ -0,0 +1,69 @@
+use dolphin_core::moves::mov::Move;
+
+// Triangular PV table. The line at each ply is the best move found there
+// followed by the line at the ply below, copied up as the search unwinds.
+// Unlike reading the PV back out of the TT, entries can't be overwritten by
+// other parts of the tree, so every move in the line was actually searched.
+#[derive(Default)]
+pub struct PvTable {
+    lines: Vec<Vec<Move>>,
+}
+
+impl PvTable {
+    // called on entering a node, before any of its children are searched
+    pub fn clear_ply(&mut self, ply: u8) {
+        let ply = ply as usize;
+        if self.lines.len() <= ply + 1 {
+            self.lines.resize_with(ply + 2, Vec::new);
+        }
+        self.lines[ply].clear();
+        self.lines[ply + 1].clear();
+    }
+
+    // the move is the new best move at this ply
+    pub fn update(&mut self, ply: u8, mv: &Move) {
+        let ply = ply as usize;
+        let (parents, children) = self.lines.split_at_mut(ply + 1);
+        let line = &mut parents[ply];
+
+        line.clear();
+        line.push(*mv);
+        line.extend_from_slice(&children[0]);
+    }
+
+    pub fn line(&self, ply: u8) -> &[Move] {
+        self.lines
+            .get(ply as usize)
+            .map(|line| line.as_slice())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::pv_table::PvTable;
+    use dolphin_core::board::square::Square;
+    use dolphin_core::moves::mov::Move;
+
+    #[test]
+    pub fn child_line_copied_up_behind_best_move() {
+        let mut pv_table = PvTable::default();
+        let mv1 = Move::encode_move(&Square::E2, &Square::E4);
+        let mv2 = Move::encode_move(&Square::E7, &Square::E5);
+        let mv3 = Move::encode_move(&Square::G1, &Square::F3);
+
+        pv_table.clear_ply(0);
+        pv_table.clear_ply(1);
+        pv_table.clear_ply(2);
+        pv_table.update(2, &mv3);
+        pv_table.update(1, &mv2);
+        pv_table.update(0, &mv1);
+        assert_eq!(pv_table.line(0), [mv1, mv2, mv3]);
+
+        // a new child search replaces the old line below it
+        pv_table.clear_ply(1);
+        pv_table.update(0, &mv3);
+        assert_eq!(pv_table.line(0), [mv3]);
+        assert!(pv_table.line(5).is_empty());
+    }
+}
@@ -0,0 +1,2194 @@
+use crate::evaluate::evaluate_board_with_params;
+use crate::evaluate::game_phase;
+use crate::evaluate::EvalParams;
+use crate::evaluate::MAX_GAME_PHASE;
+use crate::move_order::order_moves;
+use crate::pv_table::PvTable;
+use crate::search_stack::SearchStack;
+use crate::strength::StrengthLimit;
+use crate::time_manager::TimeBudget;
+use crate::tree_dump::NodeResult;
+use crate::tree_dump::TreeDump;
+use crate::tt::TransTable;
+use crate::tt::TransType;
+use crate::tt::TtStats;
+use dolphin_core::board::piece::Piece;
+use dolphin_core::moves::mov::Move;
+use dolphin_core::moves::mov::MoveType;
+use dolphin_core::moves::mov::Score;
+use dolphin_core::moves::move_gen::MoveGenerator;
+use dolphin_core::moves::move_gen::Promotions;
+use dolphin_core::moves::move_list::MoveList;
+use dolphin_core::position::game_position::MoveLegality;
+use dolphin_core::position::game_position::Position;
+use dolphin_core::position::zobrist_keys::ZobristHash;
+use rand::RngCore;
+use rand_xoshiro::rand_core::SeedableRng;
+use rand_xoshiro::Xoshiro256PlusPlus;
+use std::fmt;
+use std::time::Duration;
+use std::time::Instant;
+
+const SCORE_INFINITE: Score = 30000;
+const SCORE_MATE: Score = 29000;
+const SCORE_DRAW: Score = 0;
+// scores beyond this are "mate in N plies", allowing for the deepest possible ply
+const SCORE_MATE_THRESHOLD: Score = SCORE_MATE - u8::MAX as Score;
+
+// nodes between checks of the time limit
+const TIME_CHECK_INTERVAL: u64 = 1024;
+
+// Root move scores only get noise added at shallow depths, enough to pick
+// between moves of similar value without changing the result of a deep search
+const VARIETY_MAX_DEPTH: u8 = 4;
+
+// Limits on how far the search can go past its nominal depth, how much of
+// quiescence is pruned, and whether moves are ordered
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct SearchParams {
+    // A move that gives check is searched one ply deeper, up to this many
+    // times along a line, so a run of checks (eg a perpetual) can't keep
+    // the search going. 0 turns the extensions off.
+    pub max_extensions: u8,
+    // Nodes at this ply are evaluated rather than searched, whatever the
+    // depth left, including quiescence
+    pub max_ply: u8,
+    // Delta pruning: quiescence skips a move if the stand pat score plus
+    // the value of what it captures (and any promotion) plus this margin
+    // still can't raise alpha. None searches every move.
+    pub delta_margin: Option<Score>,
+    // Promotions, then captures, are searched before quiet moves. Off
+    // searches moves in the order they're generated (after any TT move),
+    // to compare against.
+    pub order_moves: bool,
+    // Each iteration after the first searches a window this far either
+    // side of the last score, and searches again with that side widened
+    // if the score falls outside it. None searches the full window.
+    pub aspiration_window: Option<Score>,
+}
+
+impl Default for SearchParams {
+    fn default() -> Self {
+        SearchParams {
+            max_extensions: 8,
+            max_ply: 64,
+            delta_margin: Some(200),
+            order_moves: true,
+            aspiration_window: Some(50),
+        }
+    }
+}
+
+// What the search is set up with and what the last search did, for
+// diagnosing hangs and misconfiguration over a long match. The search is
+// single threaded, so there's no count of threads.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct SearchStatus {
+    pub tt_capacity: usize,
+    pub tt_bytes: usize,
+    // permille of the TT used by the last search
+    pub hashfull: u16,
+    pub max_depth: u8,
+    pub completed_depth: u8,
+    pub seldepth: u8,
+    pub nodes: u64,
+    // the time limit, or the budget's maximum if that's shorter
+    pub time_allocated: Option<Duration>,
+    pub time_used: Duration,
+}
+
+impl fmt::Display for SearchStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time_allocated = self
+            .time_allocated
+            .map_or("none".to_string(), |time| time.as_millis().to_string());
+        write!(
+            f,
+            "tt {} entries ({} MB) hashfull {} depth {}/{} seldepth {} nodes {} time {} ms of {}",
+            self.tt_capacity,
+            self.tt_bytes / (1024 * 1024),
+            self.hashfull,
+            self.completed_depth,
+            self.max_depth,
+            self.seldepth,
+            self.nodes,
+            self.time_used.as_millis(),
+            time_allocated
+        )
+    }
+}
+
+struct Variety {
+    max_noise: Score,
+    rng: Xoshiro256PlusPlus,
+}
+
+// How moves are made and unmade while searching
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum SearchStrategy {
+    // make_move/take_move on a single position
+    #[default]
+    MakeTake,
+    // make_move_copied, dropping the copy afterwards
+    CopyMake,
+}
+
+// Where the PV is taken from after each depth
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub enum PvMode {
+    // follow the best moves stored in the TT, which costs nothing during the
+    // search but can be cut short or wrong if entries have been overwritten
+    #[default]
+    TransTable,
+    // keep a triangular PV table during the search, for analysis and debugging
+    Triangular,
+}
+
+#[derive(Default)]
+pub struct Search {
+    // input to search
+    max_depth: u8,
+    strategy: SearchStrategy,
+    variety: Option<Variety>,
+    tree_dump: Option<TreeDump>,
+    node_limit: Option<u64>,
+    time_limit: Option<Duration>,
+    time_budget: Option<TimeBudget>,
+    instant_move_below: Option<Duration>,
+    move_gen: MoveGenerator,
+    pv_mode: PvMode,
+    currmove_delay: Option<Duration>,
+    report_progress: bool,
+    contempt: Score,
+    params: SearchParams,
+    eval_params: EvalParams,
+
+    // runtime info
+    tt: TransTable,
+    score: Score,
+    // whether the score is exact, or a bound from a root window that failed
+    score_bound: TransType,
+    nodes: u64,
+    // nodes searched by each completed depth, from depth 1
+    depth_nodes: Vec<u64>,
+    completed_depth: u8,
+    seldepth: u8,
+    // check extensions made along the line being searched
+    line_extensions: u8,
+    // moves from the TT that weren't legal in the position they were for
+    stale_moves: u64,
+    // quiescence moves skipped by delta pruning
+    delta_pruned: u64,
+    // root searches repeated as the score fell outside the aspiration window
+    aspiration_researches: u64,
+    stopped: bool,
+    search_stack: SearchStack,
+    // the positions played in the game before the root, most recent first
+    game_hashes: Vec<ZobristHash>,
+    pv_table: PvTable,
+    pv: Vec<Move>,
+    start_time: Option<Instant>,
+    // how long the last search took
+    time_used: Duration,
+    root_move_nodes: Vec<(Move, u64)>,
+}
+
+impl Search {
+    pub fn new(tt_capacity: usize, max_depth: u8) -> Self {
+        Search {
+            tt: TransTable::new(tt_capacity),
+            max_depth,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_strategy(&mut self, strategy: SearchStrategy) {
+        self.strategy = strategy;
+    }
+
+    // Adds up to +/- max_noise to the root move scores at low depths, so games
+    // from the same position (eg self-play without a book) don't all follow the
+    // same line. The noise is reproducible for a given seed. 0 turns it off.
+    pub fn set_variety(&mut self, max_noise: Score, seed: u64) {
+        self.variety = if max_noise > 0 {
+            Some(Variety {
+                max_noise,
+                rng: Xoshiro256PlusPlus::seed_from_u64(seed),
+            })
+        } else {
+            None
+        };
+    }
+
+    // Plays at roughly the Elo of the limit, by setting the node limit and
+    // the variety (with the seed) from it, replacing any set before. None
+    // goes back to full strength, with neither set.
+    pub fn set_strength_limit(&mut self, strength_limit: Option<StrengthLimit>, seed: u64) {
+        match strength_limit {
+            Some(strength_limit) => {
+                self.set_node_limit(Some(strength_limit.node_limit()));
+                self.set_variety(strength_limit.max_error(), seed);
+            }
+            None => {
+                self.set_node_limit(None);
+                self.set_variety(0, seed);
+            }
+        }
+    }
+
+    // Centipawns the engine will give up to avoid a draw (negative to seek
+    // one). It's scaled down as pieces come off, to nothing once only kings
+    // and pawns are left, where a draw is often the best either side can do.
+    pub fn set_contempt(&mut self, contempt: Score) {
+        self.contempt = contempt;
+    }
+
+    // Which promotions are searched. Defaults to all of them, fewer makes
+    // for a faster (but occasionally wrong) search.
+    pub fn set_promotions(&mut self, promotions: Promotions) {
+        self.move_gen.set_promotions(promotions);
+    }
+
+    pub fn set_params(&mut self, params: SearchParams) {
+        self.params = params;
+    }
+
+    pub fn set_eval_params(&mut self, eval_params: EvalParams) {
+        self.eval_params = eval_params;
+    }
+
+    pub fn set_pv_mode(&mut self, pv_mode: PvMode) {
+        self.pv_mode = pv_mode;
+    }
+
+    // Prints "info currmove <move> currmovenumber <n>" as each root move is
+    // searched, once the search has been running for the delay (so short
+    // searches stay quiet). None turns it off.
+    pub fn set_currmove_delay(&mut self, delay: Option<Duration>) {
+        self.currmove_delay = delay;
+    }
+
+    // Prints a line as each depth completes (or is re-searched outside the
+    // aspiration window), and the time budget's decisions. Off by default,
+    // so tools running many searches stay quiet.
+    pub fn set_report_progress(&mut self, report_progress: bool) {
+        self.report_progress = report_progress;
+    }
+
+    // Records the search tree down to max_ply for the following searches, or
+    // stops recording if None. Only the last iteration of a search is kept.
+    pub fn set_tree_dump(&mut self, max_ply: Option<u8>) {
+        self.tree_dump = max_ply.map(TreeDump::new);
+    }
+
+    pub fn tree_dump(&self) -> Option<&TreeDump> {
+        self.tree_dump.as_ref()
+    }
+
+    // Stops the search once it has visited this many nodes, returning the
+    // result of the last completed depth (depth 1 is always completed). As
+    // the search is single threaded and the Zobrist keys have a fixed seed,
+    // a node limited search gives the same result on any machine.
+    pub fn set_node_limit(&mut self, node_limit: Option<u64>) {
+        self.node_limit = node_limit;
+    }
+
+    // Stops the search once it has run for this long (eg, the budget from a
+    // TimeManager), returning the result of the last completed depth as for
+    // the node limit
+    pub fn set_time_limit(&mut self, time_limit: Option<Duration>) {
+        self.time_limit = time_limit;
+    }
+
+    // Sizes the search by a budget from a TimeManager, which is updated as
+    // each depth completes. The decisions are reported as "info string" when
+    // progress is reported.
+    pub fn set_time_budget(&mut self, time_budget: Option<TimeBudget>) {
+        self.time_budget = time_budget;
+    }
+
+    // For bullet: with less time than this for the move (from the time
+    // limit or budget), the move is played without searching, so the reply
+    // can't be late however little time is left. It's the TT's move for the
+    // position if there's a legal one, otherwise the first legal move in
+    // search order (promotions, then captures). None always searches.
+    pub fn set_instant_move_below(&mut self, threshold: Option<Duration>) {
+        self.instant_move_below = threshold;
+    }
+
+    // the budget as it was at the end of the last search
+    pub fn time_budget(&self) -> Option<&TimeBudget> {
+        self.time_budget.as_ref()
+    }
+
+    // nodes visited by the last search, including quiescence
+    pub const fn nodes(&self) -> u64 {
+        self.nodes
+    }
+
+    // nodes searched by each completed depth of the last search, from depth 1
+    pub fn depth_nodes(&self) -> &[u64] {
+        &self.depth_nodes
+    }
+
+    // The effective branching factor of the last completed depth, its nodes
+    // over those of the depth before. Lower is better, so it's the measure
+    // for changes to pruning and move ordering. None before depth 2.
+    pub fn branching_factor(&self) -> Option<f64> {
+        match self.depth_nodes[..] {
+            [.., prev, last] if prev > 0 => Some(last as f64 / prev as f64),
+            _ => None,
+        }
+    }
+
+    pub const fn completed_depth(&self) -> u8 {
+        self.completed_depth
+    }
+
+    // deepest ply reached by the last search, including extensions and
+    // quiescence, as opposed to the nominal depth
+    pub const fn seldepth(&self) -> u8 {
+        self.seldepth
+    }
+
+    // score of the last search, from the point of view of the side to move
+    pub const fn score(&self) -> Score {
+        self.score
+    }
+
+    // Exact once a depth completes. If the search stops while a depth is
+    // being searched again after failing its aspiration window, the score
+    // is the bound that depth reported.
+    pub const fn score_bound(&self) -> TransType {
+        self.score_bound
+    }
+
+    // TT moves the last search found weren't legal in their position. More
+    // than the odd one points at a bug in the TT or the hashing.
+    pub const fn stale_moves(&self) -> u64 {
+        self.stale_moves
+    }
+
+    // quiescence moves the last search skipped by delta pruning
+    pub const fn delta_pruned(&self) -> u64 {
+        self.delta_pruned
+    }
+
+    // root searches the last search repeated with a wider window
+    pub const fn aspiration_researches(&self) -> u64 {
+        self.aspiration_researches
+    }
+
+    // permille of the TT used by the last search
+    pub fn hashfull(&self) -> u16 {
+        self.tt.hashfull()
+    }
+
+    pub fn status(&self) -> SearchStatus {
+        SearchStatus {
+            tt_capacity: self.tt.capacity(),
+            tt_bytes: self.tt.memory_bytes(),
+            hashfull: self.tt.hashfull(),
+            max_depth: self.max_depth,
+            completed_depth: self.completed_depth,
+            seldepth: self.seldepth,
+            nodes: self.nodes,
+            time_allocated: self.time_allocated(),
+            time_used: self.time_used,
+        }
+    }
+
+    // TT stores made by the last search, by replacement reason
+    pub fn tt_stats(&self) -> TtStats {
+        self.tt.stats()
+    }
+
+    // nodes searched below each legal root move in the last depth searched,
+    // in the order they were searched
+    pub fn root_move_nodes(&self) -> &[(Move, u64)] {
+        &self.root_move_nodes
+    }
+
+    // principal variation of the last completed depth
+    pub fn pv(&self) -> &[Move] {
+        &self.pv
+    }
+
+    // The PV in algebraic notation (eg "Nf3 d5 g3"), for logging. The moves
+    // are replayed on copies, so the position is left as it is.
+    pub fn pv_to_san(&self, pos: &Position) -> String {
+        let mut san_moves = Vec::new();
+        let mut scratch: Option<Position> = None;
+
+        for mv in self.pv.iter() {
+            let current = scratch.as_ref().unwrap_or(pos);
+            san_moves.push(mv.to_string_with_board(current.board(), current.occupancy_masks()));
+            let (next, _) = current.make_move_copied(mv);
+            scratch = Some(next);
+        }
+        san_moves.join(" ")
+    }
+
+    // clears anything learnt from the previous game
+    pub fn new_game(&mut self) {
+        self.tt.clear();
+    }
+
+    // returns the best move found, or None if there are no legal moves
+    pub fn search(&mut self, pos: &mut Position) -> Option<Move> {
+        self.tt.new_search();
+        self.nodes = 0;
+        self.depth_nodes.clear();
+        self.completed_depth = 0;
+        self.seldepth = 0;
+        self.line_extensions = 0;
+        self.stale_moves = 0;
+        self.delta_pruned = 0;
+        self.aspiration_researches = 0;
+        self.stopped = false;
+        self.pv.clear();
+        self.game_hashes = pos.hash_history().collect();
+        self.start_time = Some(Instant::now());
+
+        if let Some(mv) = self
+            .single_legal_reply(pos)
+            .or_else(|| self.instant_move(pos))
+        {
+            self.pv.push(mv);
+            self.time_used = self
+                .start_time
+                .map_or(Duration::ZERO, |start| start.elapsed());
+            return Some(mv);
+        }
+
+        // iterative deepening
+        for depth in 1..=self.max_depth {
+            self.root_move_nodes.clear();
+            if let Some(tree_dump) = &mut self.tree_dump {
+                tree_dump.clear();
+            }
+            let nodes_before = self.nodes;
+            let score = self.aspiration_search(pos, depth);
+            if self.stopped {
+                break;
+            }
+            self.score = score;
+            self.score_bound = TransType::Exact;
+            self.completed_depth = depth;
+            self.depth_nodes.push(self.nodes - nodes_before);
+
+            self.pv = match self.pv_mode {
+                PvMode::TransTable => self.get_pv_line(pos, depth),
+                PvMode::Triangular => self.pv_table.line(0).to_vec(),
+            };
+
+            if self.report_progress {
+                let ebf = self
+                    .branching_factor()
+                    .map_or("-".to_string(), |ebf| format!("{:.2}", ebf));
+                println!(
+                    "SEARCH: depth : {}, score : {}{}, ebf : {}, PV Line : {}",
+                    depth,
+                    score,
+                    bound_label(self.score_bound),
+                    ebf,
+                    self.pv_to_san(pos)
+                );
+            }
+
+            if self.time_budget_used(score) {
+                break;
+            }
+        }
+
+        self.time_used = self
+            .start_time
+            .map_or(Duration::ZERO, |start| start.elapsed());
+        self.pv.first().copied()
+    }
+
+    // Searches a window around the last iteration's score, which cuts off
+    // more of the tree than the full window. A score outside the window is
+    // only a bound, so it's reported as one and the side it fell out of is
+    // widened (doubling each time) until the score lands inside. Mate scores
+    // and the first iteration get the full window.
+    fn aspiration_search(&mut self, pos: &mut Position, depth: u8) -> Score {
+        let mut window = match self.params.aspiration_window {
+            Some(window) if depth > 1 && self.score.abs() < SCORE_MATE_THRESHOLD => window,
+            _ => return self.alpha_beta(pos, -SCORE_INFINITE, SCORE_INFINITE, depth, 0),
+        };
+        let mut alpha = self.score.saturating_sub(window).max(-SCORE_INFINITE);
+        let mut beta = self.score.saturating_add(window).min(SCORE_INFINITE);
+        loop {
+            let score = self.alpha_beta(pos, alpha, beta, depth, 0);
+            if self.stopped {
+                return score;
+            }
+            let bound = bound_type(score, alpha, beta);
+            match bound {
+                TransType::Exact => return score,
+                TransType::Alpha if alpha == -SCORE_INFINITE => return score,
+                TransType::Beta if beta == SCORE_INFINITE => return score,
+                _ => {}
+            }
+            self.aspiration_researches += 1;
+            self.score = score;
+            self.score_bound = bound;
+            if self.report_progress {
+                println!(
+                    "SEARCH: depth : {}, score : {}{}, re-searching",
+                    depth,
+                    score,
+                    bound_label(bound)
+                );
+            }
+            window = window.saturating_mul(2);
+            if bound == TransType::Alpha {
+                alpha = score.saturating_sub(window).max(-SCORE_INFINITE);
+            } else {
+                beta = score.saturating_add(window).min(SCORE_INFINITE);
+            }
+        }
+    }
+
+    // With the clock running, a move that's the only legal reply is played
+    // without searching, as no amount of searching will change it. Searches
+    // without a time limit still search it, for the score. The score is the
+    // TT's for the move if it has one, else the static eval.
+    fn single_legal_reply(&mut self, pos: &mut Position) -> Option<Move> {
+        if self.time_limit.is_none() && self.time_budget.is_none() {
+            return None;
+        }
+
+        let mut move_list = MoveList::new();
+        if self.move_gen.generate_legal_moves(pos, &mut move_list) != 1 {
+            return None;
+        }
+        let mv = move_list.get_move_at_offset(0);
+
+        self.score = match self.tt.get(pos.position_hash()) {
+            Some((_, _, score, tt_move)) if tt_move == mv => score_from_tt(score, 0),
+            _ => evaluate_board_with_params(pos.board(), pos.side_to_move(), &self.eval_params),
+        };
+        Some(mv)
+    }
+
+    // the move to play without searching when there's too little time to
+    // search, setting the score
+    fn instant_move(&mut self, pos: &mut Position) -> Option<Move> {
+        let (Some(threshold), Some(allocated)) = (self.instant_move_below, self.time_allocated())
+        else {
+            return None;
+        };
+        if allocated >= threshold {
+            return None;
+        }
+
+        let mut move_list = MoveList::new();
+        self.move_gen.generate_legal_moves(pos, &mut move_list);
+
+        let hash = pos.position_hash();
+        if let Some((_, _, score, mv)) = self.tt.get(hash) {
+            if move_list.iterator().any(|legal| *legal == mv) {
+                self.score = score_from_tt(score, 0);
+                return Some(mv);
+            }
+        }
+
+        order_moves(pos, &mut move_list);
+        let mv = move_list.iterator().next().copied()?;
+        self.score = evaluate_board_with_params(pos.board(), pos.side_to_move(), &self.eval_params);
+        Some(mv)
+    }
+
+    // updates the time budget with the completed depth, returning true if
+    // there isn't time to start another
+    fn time_budget_used(&mut self, score: Score) -> bool {
+        let (Some(time_budget), Some(best_move), Some(start_time)) =
+            (&mut self.time_budget, self.pv.first(), self.start_time)
+        else {
+            return false;
+        };
+
+        time_budget.update(score, best_move);
+        if self.report_progress {
+            println!(
+                "info string time {} target {}ms",
+                time_budget.decision(),
+                time_budget.target().as_millis()
+            );
+        }
+        start_time.elapsed() >= time_budget.target()
+    }
+
+    fn get_pv_line(&mut self, pos: &mut Position, depth: u8) -> Vec<Move> {
+        let mut retval = Vec::<Move>::new();
+
+        let mut i = 0u8;
+
+        while i < depth {
+            let Some(pv_move) = self.tt_move(pos) else {
+                break;
+            };
+            pos.make_move(&pv_move);
+            retval.push(pv_move);
+            i += 1;
+        }
+
+        for _ in 0..i {
+            pos.take_move();
+        }
+
+        retval
+    }
+
+    // The position's move from the TT, if it's legal here. One that isn't
+    // (eg, after a hash collision) is counted as stale.
+    fn tt_move(&mut self, pos: &mut Position) -> Option<Move> {
+        let mv = self.tt.get_move_for_position_hash(pos.position_hash())?;
+        if !pos.is_legal(&mv) {
+            self.stale_moves += 1;
+            return None;
+        }
+        Some(mv)
+    }
+
+    // As tt_move, checking the move against the moves already generated for
+    // the position rather than generating them again. Whether it leaves the
+    // king in check is left to the search, which makes the move anyway.
+    fn generated_tt_move(&mut self, pos: &Position, move_list: &MoveList) -> Option<Move> {
+        let mv = self.tt.get_move_for_position_hash(pos.position_hash())?;
+        if !move_list.contains(&mv) {
+            self.stale_moves += 1;
+            return None;
+        }
+        Some(mv)
+    }
+
+    // searches the node, adding it to the tree dump if enabled
+    fn alpha_beta(
+        &mut self,
+        pos: &mut Position,
+        alpha: Score,
+        beta: Score,
+        depth: u8,
+        ply: u8,
+    ) -> Score {
+        let traced = match &mut self.tree_dump {
+            Some(tree_dump) => {
+                let mv = self.search_stack.last_move();
+                tree_dump.open_node(mv, ply, depth, alpha, beta)
+            }
+            None => false,
+        };
+
+        let (score, result) = self.alpha_beta_node(pos, alpha, beta, depth, ply);
+
+        if traced {
+            if let Some(tree_dump) = &mut self.tree_dump {
+                tree_dump.close_node(score, result);
+            }
+        }
+        score
+    }
+
+    fn alpha_beta_node(
+        &mut self,
+        pos: &mut Position,
+        mut alpha: Score,
+        beta: Score,
+        depth: u8,
+        ply: u8,
+    ) -> (Score, NodeResult) {
+        if self.pv_mode == PvMode::Triangular {
+            self.pv_table.clear_ply(ply);
+        }
+
+        if self.count_node() {
+            return (0, NodeResult::Stopped);
+        }
+
+        if ply > 0 && (self.is_repetition(pos) || pos.is_fifty_move_draw()) {
+            // the draw depends on the moves played to reach this position, so
+            // return without adding the score to the TT, where it could be
+            // picked up when the same position is reached by another path
+            return (self.draw_score(pos, ply), NodeResult::Draw);
+        }
+
+        self.seldepth = self.seldepth.max(ply);
+        if ply >= self.params.max_ply {
+            let score =
+                evaluate_board_with_params(pos.board(), pos.side_to_move(), &self.eval_params);
+            return (score, NodeResult::MaxPly);
+        }
+
+        if depth == 0 {
+            return (
+                self.quiesence(pos, alpha, beta, ply),
+                NodeResult::Quiescence,
+            );
+        }
+
+        if ply > 0 {
+            if let Some((tt_type, tt_depth, tt_score, _)) = self.tt.get(pos.position_hash()) {
+                if tt_depth >= depth {
+                    let score = score_from_tt(tt_score, ply);
+                    if tt_type == TransType::Exact || bound_type(score, alpha, beta) == tt_type {
+                        return (score, NodeResult::TtCutoff);
+                    }
+                }
+            }
+        }
+
+        let mut num_legal_moves = 0;
+
+        // TODO: check if timer expired
+
+        let old_alpha = alpha;
+
+        let mut move_list = MoveList::new();
+        self.move_gen.generate_moves(pos, &mut move_list);
+
+        // the best move from an earlier search of the position
+        let mut tt_move = self.generated_tt_move(pos, &move_list);
+
+        // fail-soft: the best score is kept even when it's outside the
+        // window, giving the TT (and the caller) a tighter bound
+        let mut best_score = -SCORE_INFINITE;
+        let mut best_move: Option<Move> = None;
+
+        if self.params.order_moves {
+            order_moves(pos, &mut move_list);
+        }
+        if let Some(tt_move) = tt_move {
+            // the sort is stable, so the rest keep their order
+            move_list.sort_by_key(|mv| *mv != tt_move);
+        }
+
+        for i in 0..move_list.len() {
+            let mv = move_list.get_move_at_offset(i);
+            let nodes_before = self.nodes;
+
+            // note: alpha/beta are swapped, and sign is reversed
+            let Some(score) = self.score_move(pos, &mv, |search, child| {
+                if ply == 0 {
+                    search.report_current_move(&mv, num_legal_moves + 1);
+                }
+                let extension = search.check_extension(child);
+                search.line_extensions += extension;
+                let score =
+                    -search.alpha_beta(child, -beta, -alpha, depth - 1 + extension, ply + 1);
+                search.line_extensions -= extension;
+                score
+            }) else {
+                // generated, but it leaves the king in check
+                if tt_move == Some(mv) {
+                    self.stale_moves += 1;
+                    tt_move = None;
+                }
+                continue;
+            };
+            // the score is meaningless if the search was stopped part way
+            if self.stopped {
+                return (0, NodeResult::Stopped);
+            }
+            num_legal_moves += 1;
+            if ply == 0 {
+                self.root_move_nodes.push((mv, self.nodes - nodes_before));
+            }
+
+            let score = if ply == 0 {
+                self.add_variety(score, depth)
+            } else {
+                score
+            };
+
+            if score > best_score {
+                best_score = score;
+            }
+            if score > alpha {
+                if score >= beta {
+                    self.tt.add(
+                        TransType::Beta,
+                        depth,
+                        score_to_tt(score, ply),
+                        pos.position_hash(),
+                        mv,
+                    );
+                    return (score, NodeResult::BetaCutoff);
+                }
+                best_move = Some(mv);
+                alpha = score;
+                if self.pv_mode == PvMode::Triangular {
+                    self.pv_table.update(ply, &mv);
+                }
+            }
+        }
+
+        // check for mate
+        if num_legal_moves == 0 {
+            if pos.is_king_sq_attacked() {
+                return (-SCORE_MATE + ply as Score, NodeResult::Checkmate);
+            } else {
+                return (self.draw_score(pos, ply), NodeResult::Stalemate);
+            }
+        }
+
+        // a node that failed low has no best move, so it keeps the one the
+        // position already had, if any
+        self.tt.add(
+            bound_type(best_score, old_alpha, beta),
+            depth,
+            score_to_tt(best_score, ply),
+            pos.position_hash(),
+            best_move.or(tt_move).unwrap_or_default(),
+        );
+        (best_score, NodeResult::Searched)
+    }
+
+    // Makes the move using the search strategy, scores the resulting position
+    // and restores the original. Returns None if the move is illegal.
+    fn score_move(
+        &mut self,
+        pos: &mut Position,
+        mv: &Move,
+        score_child: impl FnOnce(&mut Search, &mut Position) -> Score,
+    ) -> Option<Score> {
+        self.search_stack.push(pos.position_hash(), mv);
+
+        let score = match self.strategy {
+            SearchStrategy::MakeTake => {
+                let move_legality = pos.make_move(mv);
+                let score = match move_legality {
+                    MoveLegality::Legal => Some(score_child(self, pos)),
+                    MoveLegality::Illegal => None,
+                };
+                pos.take_move();
+                score
+            }
+            SearchStrategy::CopyMake => {
+                let (mut child, move_legality) = pos.make_move_copied(mv);
+                match move_legality {
+                    MoveLegality::Legal => Some(score_child(self, &mut child)),
+                    MoveLegality::Illegal => None,
+                }
+            }
+        };
+
+        self.search_stack.pop();
+        score
+    }
+
+    // Counts the node, returning true if the search should stop. The first
+    // depth is always completed, so there's a move to play.
+    fn count_node(&mut self) -> bool {
+        self.nodes += 1;
+        if self.completed_depth == 0 {
+            return false;
+        }
+
+        if let Some(node_limit) = self.node_limit {
+            if self.nodes > node_limit {
+                self.stopped = true;
+            }
+        }
+        // the clock is only read every so often, it's slow next to a node
+        if let (Some(time_limit), Some(start_time)) = (self.time_allocated(), self.start_time) {
+            if self.nodes.is_multiple_of(TIME_CHECK_INTERVAL) && start_time.elapsed() >= time_limit
+            {
+                self.stopped = true;
+            }
+        }
+        self.stopped
+    }
+
+    // the time limit or the budget's maximum, whichever stops the search first
+    fn time_allocated(&self) -> Option<Duration> {
+        let budget_limit = self.time_budget.map(|time_budget| time_budget.maximum());
+        match (self.time_limit, budget_limit) {
+            (Some(time_limit), Some(budget_limit)) => Some(time_limit.min(budget_limit)),
+            (time_limit, budget_limit) => time_limit.or(budget_limit),
+        }
+    }
+
+    fn report_current_move(&self, mv: &Move, move_number: u32) {
+        let (Some(delay), Some(start_time)) = (self.currmove_delay, self.start_time) else {
+            return;
+        };
+        if start_time.elapsed() >= delay {
+            println!(
+                "info currmove {} currmovenumber {}",
+                mv.to_uci_string(),
+                move_number
+            );
+        }
+    }
+
+    // A draw is worth minus the contempt to the side to move at the root, and
+    // the contempt to the opponent (who moves at the odd plies)
+    fn draw_score(&self, pos: &Position, ply: u8) -> Score {
+        if self.contempt == 0 {
+            return SCORE_DRAW;
+        }
+
+        let contempt = self.contempt * game_phase(pos.board()) / MAX_GAME_PHASE;
+        if ply.is_multiple_of(2) {
+            SCORE_DRAW - contempt
+        } else {
+            SCORE_DRAW + contempt
+        }
+    }
+
+    fn add_variety(&mut self, score: Score, depth: u8) -> Score {
+        let Some(variety) = &mut self.variety else {
+            return score;
+        };
+
+        if depth > VARIETY_MAX_DEPTH || score.abs() >= SCORE_MATE_THRESHOLD {
+            return score;
+        }
+
+        let range = (2 * variety.max_noise + 1) as u64;
+        let noise = (variety.rng.next_u64() % range) as Score - variety.max_noise;
+        score + noise
+    }
+
+    fn is_repetition(&self, pos: &Position) -> bool {
+        if pos.is_repetition() {
+            return true;
+        }
+
+        // copy-make positions don't have any history, so check the search path
+        // and then the game before the root (only as far back as the last
+        // capture or pawn move). Equal hashes stand for
+        // Position::same_position, not ==. As for Position::is_repetition,
+        // only every other position from 4 plies back can match.
+        self.search_stack
+            .position_hashes()
+            .chain(self.game_hashes.iter().copied())
+            .take(pos.fifty_move_counter() as usize)
+            .skip(3)
+            .step_by(2)
+            .any(|hash| hash == pos.position_hash())
+    }
+
+    // one more ply for a move that gives check, while the line has
+    // extensions left
+    fn check_extension(&self, child: &Position) -> u8 {
+        if self.line_extensions < self.params.max_extensions && child.is_king_sq_attacked() {
+            1
+        } else {
+            0
+        }
+    }
+
+    fn quiesence(&mut self, pos: &mut Position, mut alpha: Score, beta: Score, ply: u8) -> Score {
+        // TODO check repetition
+        // TODO checkl 50 move counter
+        // TODO check max depth
+
+        if self.count_node() {
+            return 0;
+        }
+        self.seldepth = self.seldepth.max(ply);
+
+        // stand pat
+        let stand_pat_score =
+            evaluate_board_with_params(pos.board(), pos.side_to_move(), &self.eval_params);
+        if stand_pat_score >= beta || ply >= self.params.max_ply {
+            return stand_pat_score;
+        }
+        if stand_pat_score > alpha {
+            alpha = stand_pat_score;
+        }
+        let mut best_score = stand_pat_score;
+
+        // there's no stand pat when in check, so nothing can be pruned
+        let delta_margin = match self.params.delta_margin {
+            Some(margin) if !pos.is_king_sq_attacked() => Some(margin),
+            _ => None,
+        };
+
+        let mut move_list = MoveList::new();
+        self.move_gen.generate_moves(pos, &mut move_list);
+
+        if self.params.order_moves {
+            order_moves(pos, &mut move_list);
+        }
+
+        for i in 0..move_list.len() {
+            let mv = move_list.get_move_at_offset(i);
+
+            if let Some(margin) = delta_margin {
+                let best_case = stand_pat_score
+                    .saturating_add(max_gain(pos, &mv))
+                    .saturating_add(margin);
+                if best_case <= alpha {
+                    // the most the move could have scored
+                    best_score = best_score.max(best_case);
+                    self.delta_pruned += 1;
+                    continue;
+                }
+            }
+
+            // note: alpha/beta are swapped, and sign is reversed
+            let Some(score) = self.score_move(pos, &mv, |search, child| {
+                -search.quiesence(child, -beta, -alpha, ply + 1)
+            }) else {
+                continue;
+            };
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+            }
+            if score > alpha {
+                if score >= beta {
+                    return score;
+                }
+                alpha = score;
+            }
+        }
+
+        best_score
+    }
+}
+
+// What a score means for a window: failing low (at or below alpha) it's an
+// upper bound, failing high (at or above beta) a lower bound
+pub fn bound_type(score: Score, alpha: Score, beta: Score) -> TransType {
+    if score <= alpha {
+        TransType::Alpha
+    } else if score >= beta {
+        TransType::Beta
+    } else {
+        TransType::Exact
+    }
+}
+
+// as UCI marks a score that's only a bound
+fn bound_label(bound: TransType) -> &'static str {
+    match bound {
+        TransType::Exact => "",
+        TransType::Alpha => " upperbound",
+        TransType::Beta => " lowerbound",
+    }
+}
+
+// the most the move can gain in material: what it captures, plus what a
+// pawn gains by promoting
+fn max_gain(pos: &Position, mv: &Move) -> Score {
+    let captured = pos.captured_piece(mv).map_or(0, |pce| pce.value());
+    if mv.move_type() == MoveType::Promotion {
+        return captured + mv.decode_promotion_piece().value() - Piece::Pawn.value();
+    }
+    captured
+}
+
+// Mate scores are relative to the root ("mate in N plies from the root"), but
+// a TT entry can be picked up at any ply. Store them relative to the node
+// instead, and convert back to the root distance when read.
+fn score_to_tt(score: Score, ply: u8) -> Score {
+    if score >= SCORE_MATE_THRESHOLD {
+        score + ply as Score
+    } else if score <= -SCORE_MATE_THRESHOLD {
+        score - ply as Score
+    } else {
+        score
+    }
+}
+
+fn score_from_tt(score: Score, ply: u8) -> Score {
+    if score >= SCORE_MATE_THRESHOLD {
+        score - ply as Score
+    } else if score <= -SCORE_MATE_THRESHOLD {
+        score + ply as Score
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::bound_type;
+    use super::score_from_tt;
+    use super::score_to_tt;
+    use super::PvMode;
+    use super::Search;
+    use super::SearchParams;
+    use super::SearchStrategy;
+    use super::SCORE_DRAW;
+    use super::SCORE_INFINITE;
+    use super::SCORE_MATE;
+    use super::SCORE_MATE_THRESHOLD;
+    use super::VARIETY_MAX_DEPTH;
+    use crate::evaluate::evaluate_board;
+    use crate::strength::StrengthLimit;
+    use crate::time_manager::TimeControl;
+    use crate::time_manager::TimeDecision;
+    use crate::time_manager::TimeManager;
+    use crate::tt::TransType;
+    use dolphin_core::board::occupancy_masks::OccupancyMasks;
+    use dolphin_core::board::square::Square;
+    use dolphin_core::io::fen;
+    use dolphin_core::moves::mov::Move;
+    use dolphin_core::moves::mov::Score;
+    use dolphin_core::position::attack_checker::AttackChecker;
+    use dolphin_core::position::game_position::MoveLegality;
+    use dolphin_core::position::game_position::Position;
+    use std::time::Duration;
+
+    #[test]
+    pub fn repeated_position_draw_score_not_stored_in_tt() {
+        // white is a queen up, but the position has been repeated
+        let fen = "1n2k3/8/8/8/8/8/8/1N2K2Q w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        for (from_sq, to_sq) in [
+            (Square::B1, Square::C3),
+            (Square::B8, Square::C6),
+            (Square::C3, Square::B1),
+            (Square::C6, Square::B8),
+        ] {
+            pos.make_move(&Move::encode_move(&from_sq, &to_sq));
+        }
+        assert!(pos.is_repetition());
+
+        let mut search = Search::new(1000, 2);
+        let score = search.alpha_beta(&mut pos, -SCORE_INFINITE, SCORE_INFINITE, 2, 1);
+
+        assert_eq!(score, SCORE_DRAW);
+        // a draw score stored here would be returned for this position when it's
+        // reached without the repetition, where white is simply winning
+        assert!(search.tt.get(pos.position_hash()).is_none());
+    }
+
+    #[test]
+    pub fn repetition_scored_with_contempt_scaled_by_phase() {
+        // knights and a queen left, a quarter of the starting phase
+        let fen = "1n2k3/8/8/8/8/8/8/1N2K2Q w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+        for (from_sq, to_sq) in [
+            (Square::B1, Square::C3),
+            (Square::B8, Square::C6),
+            (Square::C3, Square::B1),
+            (Square::C6, Square::B8),
+        ] {
+            pos.make_move(&Move::encode_move(&from_sq, &to_sq));
+        }
+
+        let mut search = Search::new(1000, 2);
+        search.set_contempt(48);
+
+        // good for the opponent of the side to move at the root, bad for it
+        let score = search.alpha_beta(&mut pos, -SCORE_INFINITE, SCORE_INFINITE, 2, 1);
+        assert_eq!(score, SCORE_DRAW + 12);
+        let score = search.alpha_beta(&mut pos, -SCORE_INFINITE, SCORE_INFINITE, 2, 2);
+        assert_eq!(score, SCORE_DRAW - 12);
+    }
+
+    #[test]
+    pub fn no_contempt_in_king_and_pawn_endings() {
+        // stalemate
+        let fen = "k7/P7/1K6/8/8/8/8/8 b - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let mut search = Search::new(1000, 1);
+        search.set_contempt(48);
+        let score = search.alpha_beta(&mut pos, -SCORE_INFINITE, SCORE_INFINITE, 1, 0);
+        assert_eq!(score, SCORE_DRAW);
+    }
+
+    #[test]
+    pub fn position_without_repetition_not_scored_as_draw() {
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+        assert!(!pos.is_repetition());
+
+        let mut search = Search::new(1000, 1);
+        let score = search.alpha_beta(&mut pos, -SCORE_INFINITE, SCORE_INFINITE, 1, 1);
+
+        assert!(score > SCORE_DRAW);
+        assert!(search.tt.get(pos.position_hash()).is_some());
+    }
+
+    #[test]
+    pub fn tree_dump_records_last_iteration_to_max_ply() {
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let mut search = Search::new(1000, 2);
+        assert!(search.tree_dump().is_none());
+
+        search.set_tree_dump(Some(1));
+        search.search(&mut pos);
+
+        // root and the 15 legal moves
+        let tree_dump = search.tree_dump().unwrap();
+        assert_eq!(tree_dump.num_nodes(), 16);
+        assert!(tree_dump
+            .to_json()
+            .starts_with("[{\"move\":null,\"ply\":0,\"depth\":2,"));
+
+        search.set_tree_dump(None);
+        assert!(search.tree_dump().is_none());
+    }
+
+    #[test]
+    pub fn check_extensions_capped_on_perpetual_check_lines() {
+        // both queens can keep checking the exposed kings
+        let fen = "7k/6pp/4Q3/8/8/8/q5PP/6K1 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+
+        let mut nodes = Vec::new();
+        for max_extensions in [0, 1] {
+            let mut search = Search::new(100000, 2);
+            search.set_params(SearchParams {
+                max_extensions,
+                ..Default::default()
+            });
+            assert!(search.search(&mut pos).is_some());
+            assert_eq!(search.completed_depth(), 2);
+            assert!(search.seldepth() > 2);
+            assert!(search.seldepth() <= SearchParams::default().max_ply);
+            nodes.push(search.nodes());
+        }
+        // the checking lines are searched deeper
+        assert!(nodes[0] < nodes[1]);
+
+        // with no cap on the extensions, the ply limit still stops the lines
+        // of checks, whatever the extensions and quiescence want
+        let mut search = Search::new(100000, 2);
+        search.set_params(SearchParams {
+            max_extensions: u8::MAX,
+            max_ply: 3,
+            ..Default::default()
+        });
+        assert!(search.search(&mut pos).is_some());
+        assert_eq!(search.completed_depth(), 2);
+        assert_eq!(search.seldepth(), 3);
+    }
+
+    #[test]
+    pub fn delta_pruning_skips_hopeless_quiescence_moves() {
+        // once the queen is taken, lines that leave it hanging have quiet
+        // moves in quiescence that can't get back to alpha
+        let fen = "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+
+        let mut results = Vec::new();
+        for delta_margin in [None, Some(200)] {
+            let mut search = Search::new(100000, 2);
+            search.set_params(SearchParams {
+                delta_margin,
+                ..Default::default()
+            });
+            let best_move = search.search(&mut pos);
+            results.push((best_move, search.nodes(), search.delta_pruned()));
+        }
+
+        let (best_move, nodes, delta_pruned) = results[0];
+        assert_eq!(delta_pruned, 0);
+        assert_eq!(results[1].0, best_move);
+        assert!(results[1].1 < nodes);
+        assert!(results[1].2 > 0);
+    }
+
+    #[test]
+    pub fn bound_type_matches_window() {
+        assert_eq!(bound_type(-10, -10, 10), TransType::Alpha);
+        assert_eq!(bound_type(-11, -10, 10), TransType::Alpha);
+        assert_eq!(bound_type(10, -10, 10), TransType::Beta);
+        assert_eq!(bound_type(11, -10, 10), TransType::Beta);
+        assert_eq!(bound_type(0, -10, 10), TransType::Exact);
+    }
+
+    #[test]
+    pub fn fail_soft_bounds_stored_with_matching_tt_flags() {
+        let fen = "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+        let hash = pos.position_hash();
+
+        let search_window = |pos: &mut Position, alpha: Score, beta: Score| {
+            let mut search = Search::new(10000, 1);
+            search.set_params(SearchParams {
+                delta_margin: None,
+                ..Default::default()
+            });
+            let score = search.alpha_beta(pos, alpha, beta, 1, 0);
+            let (tt_type, _, tt_score, _) = search.tt.get(hash).unwrap();
+            (score, tt_type, tt_score)
+        };
+
+        let (exact, tt_type, tt_score) = search_window(&mut pos, -SCORE_INFINITE, SCORE_INFINITE);
+        assert_eq!(tt_type, TransType::Exact);
+        assert_eq!(tt_score, exact);
+
+        // failing high gives a lower bound, which can be past beta
+        let (score, tt_type, tt_score) = search_window(&mut pos, exact - 200, exact - 100);
+        assert_eq!(tt_type, TransType::Beta);
+        assert_eq!(tt_score, score);
+        assert!(score >= exact - 100 && score <= exact);
+
+        // failing low gives an upper bound, which can be below alpha
+        let (score, tt_type, tt_score) = search_window(&mut pos, exact + 100, exact + 200);
+        assert_eq!(tt_type, TransType::Alpha);
+        assert_eq!(tt_score, score);
+        assert!(score <= exact + 100 && score >= exact);
+    }
+
+    #[test]
+    pub fn root_score_exact_with_full_window() {
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1");
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+
+        let mut search = Search::new(10000, 2);
+        search.search(&mut pos);
+        assert_eq!(search.score_bound(), TransType::Exact);
+        assert!(search.score() >= SCORE_MATE_THRESHOLD);
+    }
+
+    #[test]
+    pub fn aspiration_window_researches_to_full_window_score() {
+        let fen = "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1";
+        let attack_checker = AttackChecker::new();
+
+        let mut results = Vec::new();
+        for aspiration_window in [None, Some(1)] {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(fen);
+            let mut pos = Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                OccupancyMasks::instance(),
+                &attack_checker,
+            );
+            let mut search = Search::new(100000, 4);
+            search.set_params(SearchParams {
+                aspiration_window,
+                ..Default::default()
+            });
+            let best_move = search.search(&mut pos);
+            assert_eq!(search.completed_depth(), 4);
+            assert_eq!(search.score_bound(), TransType::Exact);
+            results.push((best_move, search.score(), search.aspiration_researches()));
+        }
+
+        assert_eq!(results[0].2, 0);
+        // a window that narrow can't hold the score as it changes with depth
+        assert!(results[1].2 > 0);
+        assert_eq!(results[0].0, results[1].0);
+        assert_eq!(results[0].1, results[1].1);
+    }
+
+    #[test]
+    pub fn ordered_promotions_search_fewer_nodes() {
+        // both sides have pawns a move from queening
+        let fen = "8/PP5k/8/8/8/8/6pp/K7 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+
+        let mut results = Vec::new();
+        for order_moves in [false, true] {
+            let mut search = Search::new(100000, 2);
+            search.set_params(SearchParams {
+                order_moves,
+                ..Default::default()
+            });
+            let best_move = search.search(&mut pos).unwrap();
+            results.push((best_move.to_uci_string(), search.nodes()));
+        }
+        assert_eq!(results[0].0, results[1].0);
+        assert!(results[1].0.ends_with('q'));
+        // the queening moves are searched first, and cut off the rest
+        assert!(results[1].1 < results[0].1 / 10);
+    }
+
+    #[test]
+    pub fn time_limited_search_completes_first_depth() {
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+
+        let mut search = Search::new(10000, 50);
+        search.set_time_limit(Some(Duration::ZERO));
+
+        assert!(search.search(&mut pos).is_some());
+        assert!(search.completed_depth() >= 1);
+        assert!(search.completed_depth() < 50);
+    }
+
+    #[test]
+    pub fn status_reports_tt_depth_and_time() {
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+
+        let mut search = Search::new(1000, 3);
+        search.search(&mut pos);
+        let status = search.status();
+        assert_eq!(status.tt_capacity, 1000);
+        assert_eq!(status.tt_bytes, 8000);
+        assert!(status.hashfull > 0);
+        assert_eq!((status.completed_depth, status.max_depth), (3, 3));
+        assert_eq!(status.seldepth, search.seldepth());
+        assert_eq!(status.nodes, search.nodes());
+        assert_eq!(status.time_allocated, None);
+
+        // the shorter of the time limit and the budget
+        let time_control = TimeControl {
+            remaining: Duration::from_secs(60),
+            increment: Duration::ZERO,
+            moves_to_go: Some(1),
+        };
+        search.set_time_budget(Some(TimeManager::default().budget(&time_control, 20)));
+        search.set_time_limit(Some(Duration::from_millis(10)));
+        search.search(&mut pos);
+        let status = search.status();
+        assert_eq!(status.time_allocated, Some(Duration::from_millis(10)));
+        assert!(status.to_string().contains("of 10"));
+    }
+
+    #[test]
+    pub fn single_legal_reply_played_without_searching_on_the_clock() {
+        // the king in check has only one move
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("k7/8/1K6/8/8/8/8/R7 b - - 0 1");
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+
+        let time_control = TimeControl {
+            remaining: Duration::from_secs(60),
+            ..Default::default()
+        };
+        let mut search = Search::new(10000, 10);
+        search.set_time_budget(Some(TimeManager::default().budget(&time_control, 1)));
+
+        // the only move is played without searching
+        assert_eq!(
+            search.search(&mut pos),
+            Some(Move::encode_move(&Square::A8, &Square::B8))
+        );
+        assert_eq!(search.completed_depth(), 0);
+        assert_eq!(search.nodes(), 0);
+        assert_eq!(search.pv(), [Move::encode_move(&Square::A8, &Square::B8)]);
+        assert_eq!(
+            search.score(),
+            evaluate_board(pos.board(), pos.side_to_move())
+        );
+        assert_eq!(
+            search
+                .time_budget()
+                .map(|time_budget| time_budget.decision()),
+            Some(TimeDecision::Forced)
+        );
+
+        // without a time limit it's searched as usual
+        let mut search = Search::new(10000, 2);
+        assert_eq!(
+            search.search(&mut pos),
+            Some(Move::encode_move(&Square::A8, &Square::B8))
+        );
+        assert_eq!(search.completed_depth(), 2);
+    }
+
+    #[test]
+    pub fn instant_move_played_without_searching_when_short_of_time() {
+        // the queen hangs, but Kf2 comes first from the TT below
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1");
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+        let takes_queen = Move::encode_move(&Square::D1, &Square::D5);
+
+        // the capture comes first with nothing in the TT
+        let mut search = Search::new(10000, 4);
+        search.set_instant_move_below(Some(Duration::from_millis(5)));
+        search.set_time_limit(Some(Duration::from_millis(1)));
+        assert_eq!(search.search(&mut pos), Some(takes_queen));
+        assert_eq!(search.nodes(), 0);
+        assert_eq!(search.pv(), [takes_queen]);
+
+        // the TT move is played over it
+        let king_move = Move::encode_move(&Square::E1, &Square::F2);
+        search
+            .tt
+            .add(TransType::Exact, 3, 55, pos.position_hash(), king_move);
+        assert_eq!(search.search(&mut pos), Some(king_move));
+        assert_eq!(search.nodes(), 0);
+        assert_eq!(search.score(), 55);
+
+        // with time to spare it's searched
+        search.set_time_limit(Some(Duration::from_secs(10)));
+        assert_eq!(search.search(&mut pos), Some(takes_queen));
+        assert!(search.nodes() > 0);
+    }
+
+    #[test]
+    pub fn node_limited_search_stops_and_is_reproducible() {
+        let fen = "4k3/8/8/8/8/8/8/R3K3 w - - 0 1";
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut results = Vec::new();
+        for _ in 0..2 {
+            let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                fen::decompose_fen(fen);
+            let mut pos = Position::new(
+                board,
+                castle_permissions,
+                move_cntr,
+                en_pass_sq,
+                side_to_move,
+                occ_masks,
+                &attack_checker,
+            );
+
+            let mut search = Search::new(10000, 50);
+            search.set_node_limit(Some(5000));
+            let best_move = search.search(&mut pos);
+
+            assert!(best_move.is_some());
+            assert!(search.completed_depth() >= 1);
+            assert!(search.completed_depth() < 50);
+            // stops as soon as the limit is passed
+            assert!(search.nodes() > 5000);
+            assert!(search.nodes() < 5000 + 64);
+
+            results.push((
+                best_move,
+                search.score(),
+                search.nodes(),
+                search.completed_depth(),
+            ));
+        }
+
+        assert!(results[0] == results[1]);
+    }
+
+    #[test]
+    pub fn strength_limit_sets_node_limit_until_cleared() {
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+
+        let strength_limit = StrengthLimit::new(StrengthLimit::MIN_ELO);
+        let mut search = Search::new(10000, 3);
+        search.set_strength_limit(Some(strength_limit), 1234);
+        assert!(search.search(&mut pos).is_some());
+        assert!(search.completed_depth() < 3);
+        assert!(search.nodes() < strength_limit.node_limit() + 64);
+
+        // back to full strength
+        search.set_strength_limit(None, 1234);
+        assert!(search.search(&mut pos).is_some());
+        assert_eq!(search.completed_depth(), 3);
+    }
+
+    #[test]
+    pub fn pv_to_san_replays_pv_without_changing_position() {
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("k7/8/1K6/8/8/8/8/7R w - - 0 1");
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+        let hash_before = pos.position_hash();
+
+        let mut search = Search::new(1000, 2);
+        search.search(&mut pos);
+
+        assert_eq!(search.pv()[0], Move::encode_move(&Square::H1, &Square::H8));
+        assert!(search.pv_to_san(&pos).starts_with("Rh8"));
+        assert_eq!(pos.position_hash(), hash_before);
+    }
+
+    #[test]
+    pub fn triangular_pv_replays_as_legal_moves() {
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        for fen in [
+            "k7/8/1K6/8/8/8/8/7R w - - 0 1",
+            "4k3/4p3/8/8/8/8/3P4/4K3 w - - 0 1",
+        ] {
+            let mut best_moves = Vec::new();
+            for pv_mode in [PvMode::TransTable, PvMode::Triangular] {
+                let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                    fen::decompose_fen(fen);
+                let mut pos = Position::new(
+                    board,
+                    castle_permissions,
+                    move_cntr,
+                    en_pass_sq,
+                    side_to_move,
+                    occ_masks,
+                    &attack_checker,
+                );
+
+                let mut search = Search::new(1000, 3);
+                search.set_pv_mode(pv_mode);
+                best_moves.push(search.search(&mut pos));
+
+                assert!(!search.pv().is_empty());
+                for mv in search.pv() {
+                    assert_eq!(pos.make_move(mv), MoveLegality::Legal);
+                }
+            }
+            assert_eq!(best_moves[0], best_moves[1]);
+        }
+    }
+
+    #[test]
+    pub fn root_move_nodes_recorded_for_each_legal_move() {
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen("4k3/4p3/8/8/8/8/3P4/4K3 w - - 0 1");
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let mut search = Search::new(1000, 2);
+        search.search(&mut pos);
+
+        // Kd1, Ke2, Kf1, Kf2, d3, d4
+        let root_move_nodes = search.root_move_nodes();
+        assert_eq!(root_move_nodes.len(), 6);
+        assert!(root_move_nodes.iter().all(|(_, nodes)| *nodes > 0));
+        assert!(root_move_nodes.iter().map(|(_, nodes)| nodes).sum::<u64>() < search.nodes());
+    }
+
+    #[test]
+    pub fn mate_score_stored_relative_to_node() {
+        // mate found 10 plies from the root, at a node 4 plies from the root
+        let root_score = SCORE_MATE - 10;
+        let tt_score = score_to_tt(root_score, 4);
+        assert_eq!(tt_score, SCORE_MATE - 6);
+
+        // same node reached 8 plies from the root is mate in 14 from the root
+        assert_eq!(score_from_tt(tt_score, 8), SCORE_MATE - 14);
+        assert_eq!(score_from_tt(tt_score, 4), root_score);
+    }
+
+    #[test]
+    pub fn mated_score_stored_relative_to_node() {
+        let root_score = -SCORE_MATE + 200;
+        let tt_score = score_to_tt(root_score, 150);
+        assert_eq!(tt_score, -SCORE_MATE + 50);
+
+        assert_eq!(score_from_tt(tt_score, 250), -SCORE_MATE + 300);
+        assert_eq!(score_from_tt(tt_score, 150), root_score);
+    }
+
+    #[test]
+    pub fn non_mate_scores_unchanged_by_tt_adjustment() {
+        for score in [-1500, -1, 0, 1, 1500] {
+            assert_eq!(score_to_tt(score, 20), score);
+            assert_eq!(score_from_tt(score, 20), score);
+        }
+    }
+
+    #[test]
+    pub fn mate_distance_preserved_when_read_from_tt_at_another_ply() {
+        // Rh8#
+        let fen = "k7/8/1K6/8/8/8/8/7R w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let mut search = Search::new(1000, 2);
+        let score = search.alpha_beta(&mut pos, -SCORE_INFINITE, SCORE_INFINITE, 2, 3);
+        assert_eq!(score, SCORE_MATE - 4);
+
+        // stored as mate in 1 from the position itself
+        let (_, _, tt_score, _) = search.tt.get(pos.position_hash()).unwrap();
+        assert_eq!(tt_score, SCORE_MATE - 1);
+
+        // a TT hit deeper in the tree reports the mate relative to that ply
+        let score = search.alpha_beta(&mut pos, -SCORE_INFINITE, SCORE_INFINITE, 2, 7);
+        assert_eq!(score, SCORE_MATE - 8);
+    }
+
+    #[test]
+    pub fn copy_make_search_matches_make_take_search() {
+        let fens = [
+            "k7/8/1K6/8/8/8/8/7R w - - 0 1",
+            "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+            "4k3/4p3/8/8/8/8/3P4/4K3 w - - 0 1",
+        ];
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        for fen in fens {
+            let mut scores = Vec::new();
+            for strategy in [SearchStrategy::MakeTake, SearchStrategy::CopyMake] {
+                let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+                    fen::decompose_fen(fen);
+                let mut pos = Position::new(
+                    board,
+                    castle_permissions,
+                    move_cntr,
+                    en_pass_sq,
+                    side_to_move,
+                    occ_masks,
+                    &attack_checker,
+                );
+
+                let mut search = Search::new(1000, 2);
+                search.set_strategy(strategy);
+                scores.push(search.alpha_beta(&mut pos, -SCORE_INFINITE, SCORE_INFINITE, 2, 0));
+            }
+            assert_eq!(scores[0], scores[1]);
+        }
+    }
+
+    #[test]
+    pub fn copy_make_search_detects_repetition_on_search_path() {
+        let fen = "1n2k3/8/8/8/8/8/8/1N2K2Q w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let mut search = Search::new(1000, 2);
+        search.set_strategy(SearchStrategy::CopyMake);
+
+        let mut positions = vec![pos];
+        for (from_sq, to_sq) in [
+            (Square::B1, Square::C3),
+            (Square::B8, Square::C6),
+            (Square::C3, Square::B1),
+            (Square::C6, Square::B8),
+        ] {
+            let prev = positions.last().unwrap();
+            let mv = Move::encode_move(&from_sq, &to_sq);
+            search.search_stack.push(prev.position_hash(), &mv);
+            let (next, _) = prev.make_move_copied(&mv);
+            positions.push(next);
+        }
+
+        let mut pos = positions.pop().unwrap();
+        assert!(!pos.is_repetition());
+
+        let score = search.alpha_beta(&mut pos, -SCORE_INFINITE, SCORE_INFINITE, 2, 4);
+        assert_eq!(score, SCORE_DRAW);
+    }
+
+    #[test]
+    pub fn copy_make_search_detects_repetition_of_game_positions() {
+        let fen = "1n2k3/8/8/8/8/8/8/1N2K2Q w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        // moves played in the game, not searched
+        for (from_sq, to_sq) in [
+            (Square::B1, Square::C3),
+            (Square::B8, Square::C6),
+            (Square::C3, Square::B1),
+        ] {
+            pos.make_move(&Move::encode_move(&from_sq, &to_sq));
+        }
+
+        // a queen down, black heads for the repetition
+        let mut search = Search::new(1000, 1);
+        search.set_strategy(SearchStrategy::CopyMake);
+        let mv = search.search(&mut pos);
+
+        assert_eq!(mv, Some(Move::encode_move(&Square::C6, &Square::B8)));
+        assert_eq!(search.score(), SCORE_DRAW);
+    }
+
+    #[test]
+    pub fn stale_tt_move_not_followed() {
+        let fen = "k7/8/1K6/8/8/8/8/R7 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        // a rook move through the king, as a hash collision could leave
+        let mut search = Search::new(1000, 1);
+        let stale_move = Move::encode_move(&Square::A1, &Square::C7);
+        search
+            .tt
+            .add(TransType::Exact, 1, 0, pos.position_hash(), stale_move);
+
+        assert!(search.get_pv_line(&mut pos, 3).is_empty());
+        assert_eq!(search.stale_moves(), 1);
+
+        // a legal TT move is followed
+        let legal_move = Move::encode_move(&Square::A1, &Square::A7);
+        search
+            .tt
+            .add(TransType::Exact, 1, 0, pos.position_hash(), legal_move);
+        assert_eq!(search.get_pv_line(&mut pos, 1), [legal_move]);
+        assert_eq!(search.stale_moves(), 1);
+    }
+
+    #[test]
+    pub fn tt_move_searched_first_and_kept_on_fail_low() {
+        let fen = "4k3/8/8/3q4/8/8/8/3RK3 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+        let attack_checker = AttackChecker::new();
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            OccupancyMasks::instance(),
+            &attack_checker,
+        );
+        let hash = pos.position_hash();
+
+        // a quiet move, which ordering alone would search after Rxd5
+        let tt_move = Move::encode_move(&Square::E1, &Square::F2);
+        let mut search = Search::new(1000, 2);
+        search.tt.add(TransType::Exact, 1, 0, hash, tt_move);
+
+        // every move fails low, so the node has no best move of its own
+        search.alpha_beta(&mut pos, 20000, 20001, 2, 0);
+        assert_eq!(search.root_move_nodes()[0].0, tt_move);
+        let (tt_type, _, _, mv) = search.tt.get(hash).unwrap();
+        assert_eq!(tt_type, TransType::Alpha);
+        assert_eq!(mv, tt_move);
+        assert_eq!(search.stale_moves(), 0);
+
+        // with no earlier move, the entry has none rather than a stale one
+        let mut search = Search::new(1000, 2);
+        search.alpha_beta(&mut pos, 20000, 20001, 2, 0);
+        assert!(search.tt.contains_position_hash(hash));
+        assert!(search.tt.get_move_for_position_hash(hash).is_none());
+        assert!(search.get_pv_line(&mut pos, 2).is_empty());
+        assert_eq!(search.stale_moves(), 0);
+        // a move the generator doesn't make here, or one into check, is
+        // stale and isn't kept
+        for stale_move in [
+            Move::encode_move(&Square::A1, &Square::A2),
+            Move::encode_move(&Square::E1, &Square::D2),
+        ] {
+            let mut search = Search::new(1000, 2);
+            search.tt.add(TransType::Exact, 1, 0, hash, stale_move);
+            search.alpha_beta(&mut pos, 20000, 20001, 2, 0);
+            assert_eq!(search.stale_moves(), 1);
+            assert!(search.tt.get_move_for_position_hash(hash).is_none());
+        }
+    }
+
+    #[test]
+    pub fn branching_factor_from_nodes_per_depth() {
+        let fen = "k7/8/1K6/8/8/8/8/R7 w - - 0 1";
+        let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) =
+            fen::decompose_fen(fen);
+
+        let occ_masks = OccupancyMasks::instance();
+        let attack_checker = AttackChecker::new();
+
+        let mut pos = Position::new(
+            board,
+            castle_permissions,
+            move_cntr,
+            en_pass_sq,
+            side_to_move,
+            occ_masks,
+            &attack_checker,
+        );
+
+        let mut search = Search::new(1000, 3);
+        search.search(&mut pos);
+
+        let depth_nodes = search.depth_nodes().to_vec();
+        assert_eq!(depth_nodes.len(), 3);
+        assert_eq!(depth_nodes.iter().sum::<u64>(), search.nodes());
+        assert_eq!(
+            search.branching_factor(),
+            Some(depth_nodes[2] as f64 / depth_nodes[1] as f64)
+        );
+
+        // nothing to compare the first depth with
+        let mut search = Search::new(1000, 1);
+        search.search(&mut pos);
+        assert_eq!(search.depth_nodes().len(), 1);
+        assert_eq!(search.branching_factor(), None);
+    }
+
+    #[test]
+    pub fn variety_noise_within_bounds_and_reproducible() {
+        let mut search1 = Search::new(1, 2);
+        let mut search2 = Search::new(1, 2);
+        search1.set_variety(10, 1234);
+        search2.set_variety(10, 1234);
+
+        let mut num_changed = 0;
+        for _ in 0..1000 {
+            let score1 = search1.add_variety(50, 1);
+            let score2 = search2.add_variety(50, 1);
+
+            assert_eq!(score1, score2);
+            assert!((40..=60).contains(&score1));
+            if score1 != 50 {
+                num_changed += 1;
+            }
+        }
+        assert!(num_changed > 0);
+    }
+
+    #[test]
+    pub fn variety_not_added_to_deep_searches_or_mate_scores() {
+        let mut search = Search::new(1, 2);
+        search.set_variety(10, 1234);
+
+        for _ in 0..100 {
+            assert_eq!(search.add_variety(50, VARIETY_MAX_DEPTH + 1), 50);
+            assert_eq!(search.add_variety(SCORE_MATE - 3, 1), SCORE_MATE - 3);
+            assert_eq!(search.add_variety(-SCORE_MATE + 3, 1), -SCORE_MATE + 3);
+        }
+    }
+
+    #[test]
+    pub fn variety_off_by_default_and_when_zero() {
+        let mut search = Search::new(1, 2);
+        assert_eq!(search.add_variety(50, 1), 50);
+
+        search.set_variety(10, 1234);
+        search.set_variety(0, 1234);
+        assert_eq!(search.add_variety(50, 1), 50);
+    }
+}
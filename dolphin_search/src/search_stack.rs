@@ -0,0 +1,75 @@
+use dolphin_core::moves::mov::Move;
+use dolphin_core::position::zobrist_keys::ZobristHash;
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+pub struct SearchStackEntry {
+    // the position the move was made from
+    pub position_hash: ZobristHash,
+    pub current_move: Move,
+}
+
+// The moves of the line currently being searched, root first, one entry per
+// ply. Per-ply search state goes here rather than being passed down through
+// the recursion, so a node can look back at the plies before it.
+#[derive(Default)]
+pub struct SearchStack {
+    entries: Vec<SearchStackEntry>,
+}
+
+impl SearchStack {
+    pub fn push(&mut self, position_hash: ZobristHash, mv: &Move) {
+        self.entries.push(SearchStackEntry {
+            position_hash,
+            current_move: *mv,
+        });
+    }
+
+    pub fn pop(&mut self) {
+        self.entries.pop();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    // the move that led to the current node
+    pub fn last_move(&self) -> Option<Move> {
+        self.entries.last().map(|entry| entry.current_move)
+    }
+
+    // hashes of the positions leading to the current node, most recent first
+    pub fn position_hashes(&self) -> impl Iterator<Item = ZobristHash> + '_ {
+        self.entries.iter().rev().map(|entry| entry.position_hash)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::search_stack::SearchStack;
+    use dolphin_core::board::square::Square;
+    use dolphin_core::moves::mov::Move;
+
+    #[test]
+    pub fn entries_pushed_and_popped_in_order() {
+        let mut stack = SearchStack::default();
+        assert!(stack.is_empty());
+        assert_eq!(stack.last_move(), None);
+
+        let mv1 = Move::encode_move(&Square::E2, &Square::E4);
+        let mv2 = Move::encode_move(&Square::E7, &Square::E5);
+        stack.push(1, &mv1);
+        stack.push(2, &mv2);
+
+        assert_eq!(stack.len(), 2);
+        assert_eq!(stack.last_move(), Some(mv2));
+        assert_eq!(stack.position_hashes().collect::<Vec<_>>(), [2, 1]);
+
+        stack.pop();
+        assert_eq!(stack.last_move(), Some(mv1));
+        assert_eq!(stack.position_hashes().collect::<Vec<_>>(), [1]);
+    }
+}
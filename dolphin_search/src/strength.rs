@@ -0,0 +1,84 @@
+use dolphin_core::moves::mov::Score;
+
+// Plays weaker than full strength (as for UCI_LimitStrength/UCI_Elo) by
+// limiting the nodes searched per move and adding noise to the root move
+// scores, both scaled from the Elo. The mapping is a rough fit, not a
+// calibrated rating.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct StrengthLimit {
+    elo: u16,
+}
+
+// nodes per move at MIN_ELO, doubling every ELO_PER_DOUBLING
+const MIN_ELO_NODES: u64 = 100;
+const ELO_PER_DOUBLING: u16 = 200;
+
+// the most a root move score can be off by at MIN_ELO, falling to none at MAX_ELO
+const MIN_ELO_MAX_ERROR: Score = 200;
+
+impl StrengthLimit {
+    pub const MIN_ELO: u16 = 800;
+    pub const MAX_ELO: u16 = 2400;
+
+    // the Elo is clamped to MIN_ELO..=MAX_ELO
+    pub fn new(elo: u16) -> Self {
+        StrengthLimit {
+            elo: elo.clamp(StrengthLimit::MIN_ELO, StrengthLimit::MAX_ELO),
+        }
+    }
+
+    pub const fn elo(&self) -> u16 {
+        self.elo
+    }
+
+    pub fn node_limit(&self) -> u64 {
+        let steps = self.elo - StrengthLimit::MIN_ELO;
+        let doublings = steps / ELO_PER_DOUBLING;
+        let part = (steps % ELO_PER_DOUBLING) as u64;
+
+        // linear between the doublings
+        let nodes = MIN_ELO_NODES << doublings;
+        nodes + nodes * part / ELO_PER_DOUBLING as u64
+    }
+
+    pub fn max_error(&self) -> Score {
+        let range = (StrengthLimit::MAX_ELO - StrengthLimit::MIN_ELO) as i32;
+        let below_max = (StrengthLimit::MAX_ELO - self.elo) as i32;
+        (MIN_ELO_MAX_ERROR as i32 * below_max / range) as Score
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::strength::StrengthLimit;
+
+    #[test]
+    pub fn elo_clamped_to_range() {
+        assert_eq!(StrengthLimit::new(0).elo(), StrengthLimit::MIN_ELO);
+        assert_eq!(StrengthLimit::new(1500).elo(), 1500);
+        assert_eq!(StrengthLimit::new(3500).elo(), StrengthLimit::MAX_ELO);
+    }
+
+    #[test]
+    pub fn nodes_rise_and_error_falls_with_elo() {
+        let weakest = StrengthLimit::new(StrengthLimit::MIN_ELO);
+        assert_eq!(weakest.node_limit(), 100);
+        assert_eq!(weakest.max_error(), 200);
+
+        assert_eq!(StrengthLimit::new(1000).node_limit(), 200);
+        assert_eq!(StrengthLimit::new(1100).node_limit(), 300);
+        assert_eq!(StrengthLimit::new(1600).max_error(), 100);
+
+        let strongest = StrengthLimit::new(StrengthLimit::MAX_ELO);
+        assert_eq!(strongest.node_limit(), 25600);
+        assert_eq!(strongest.max_error(), 0);
+
+        let mut prev = weakest;
+        for elo in (StrengthLimit::MIN_ELO..=StrengthLimit::MAX_ELO).step_by(50) {
+            let limit = StrengthLimit::new(elo);
+            assert!(limit.node_limit() >= prev.node_limit());
+            assert!(limit.max_error() <= prev.max_error());
+            prev = limit;
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use dolphin_core::moves::mov::Move;
+use dolphin_core::moves::mov::Score;
+use std::fmt;
+use std::time::Duration;
+
+// Moves assumed to be left in the game when the time control doesn't say
+const DEFAULT_MOVES_TO_GO: u32 = 25;
+
+// A budget can stretch to this many times the allocation when the search
+// is unsettled, as long as the clock allows
+const MAX_BUDGET_SCALE: u32 = 3;
+
+// centipawns the root score has to fall by between depths to count as a drop
+const SCORE_DROP_MARGIN: Score = 30;
+
+// depths in a row with the same best move before the search counts as settled
+const STABLE_DEPTHS: u32 = 4;
+
+// the share (in percent) of the allocation used for each decision
+const SCORE_DROPPED_PERCENT: u32 = 200;
+const BEST_MOVE_CHANGING_PERCENT: u32 = 150;
+const STABLE_PERCENT: u32 = 60;
+
+// The clock for the side to move
+#[derive(Debug, Default, Eq, PartialEq, Clone, Copy)]
+pub struct TimeControl {
+    pub remaining: Duration,
+    pub increment: Duration,
+    // moves until the next time control, if it isn't sudden death
+    pub moves_to_go: Option<u32>,
+}
+
+// Works out how long to search for a move. The move overhead is taken off
+// every budget to allow for the time the move spends getting to the clock
+// (GUI, network lag, eg playing on Lichess), so a budget that looks safe
+// doesn't lose on time.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct TimeManager {
+    move_overhead: Duration,
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        TimeManager {
+            move_overhead: TimeManager::DEFAULT_MOVE_OVERHEAD,
+        }
+    }
+}
+
+impl TimeManager {
+    pub const DEFAULT_MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+
+    pub fn new(move_overhead: Duration) -> Self {
+        TimeManager { move_overhead }
+    }
+
+    pub fn set_move_overhead(&mut self, move_overhead: Duration) {
+        self.move_overhead = move_overhead;
+    }
+
+    pub const fn move_overhead(&self) -> Duration {
+        self.move_overhead
+    }
+
+    // An even share of the remaining time over the moves left, plus most of
+    // the increment, less the move overhead. Never more than the time left
+    // on the clock (less the overhead), so it can be zero when the clock is
+    // nearly out.
+    pub fn allocate(&self, time_control: &TimeControl) -> Duration {
+        let moves_to_go = time_control
+            .moves_to_go
+            .unwrap_or(DEFAULT_MOVES_TO_GO)
+            .max(1);
+
+        let budget = time_control.remaining / moves_to_go + time_control.increment * 3 / 4;
+        let available = time_control.remaining.saturating_sub(self.move_overhead);
+
+        budget.saturating_sub(self.move_overhead).min(available)
+    }
+
+    // A budget for a search that adjusts the allocation as each depth
+    // completes. A position with a single legal move gets no time at all.
+    pub fn budget(&self, time_control: &TimeControl, num_legal_moves: usize) -> TimeBudget {
+        let allocation = self.allocate(time_control);
+        let maximum = (allocation * MAX_BUDGET_SCALE)
+            .min(time_control.remaining.saturating_sub(self.move_overhead));
+
+        let decision = if num_legal_moves == 1 {
+            TimeDecision::Forced
+        } else {
+            TimeDecision::Normal
+        };
+
+        TimeBudget {
+            allocation,
+            maximum,
+            decision,
+            last_depth: None,
+            stable_depths: 0,
+        }
+    }
+}
+
+// Why the budget is what it is, after the last completed depth
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum TimeDecision {
+    Normal,
+    // only one legal move, so reply straight away
+    Forced,
+    // the score fell since the last depth, take longer to find a way out
+    ScoreDropped,
+    // the best move changed since the last depth
+    BestMoveChanging,
+    // the best move hasn't changed for a while
+    Stable,
+}
+
+impl fmt::Display for TimeDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            TimeDecision::Normal => "normal",
+            TimeDecision::Forced => "forced",
+            TimeDecision::ScoreDropped => "score dropped",
+            TimeDecision::BestMoveChanging => "best move changing",
+            TimeDecision::Stable => "stable",
+        };
+        write!(f, "{}", label)
+    }
+}
+
+// The time for one search, updated with the result of each completed depth.
+// Another depth is only started while the time used is within the target,
+// and the search is stopped part way through a depth at the maximum.
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub struct TimeBudget {
+    allocation: Duration,
+    maximum: Duration,
+    decision: TimeDecision,
+    // score and best move of the last completed depth
+    last_depth: Option<(Score, Move)>,
+    stable_depths: u32,
+}
+
+impl TimeBudget {
+    pub fn update(&mut self, score: Score, best_move: &Move) {
+        if self.decision == TimeDecision::Forced {
+            return;
+        }
+
+        if let Some((last_score, last_move)) = self.last_depth {
+            if *best_move == last_move {
+                self.stable_depths += 1;
+            } else {
+                self.stable_depths = 0;
+            }
+
+            self.decision = if last_score.saturating_sub(score) >= SCORE_DROP_MARGIN {
+                TimeDecision::ScoreDropped
+            } else if self.stable_depths == 0 {
+                TimeDecision::BestMoveChanging
+            } else if self.stable_depths >= STABLE_DEPTHS {
+                TimeDecision::Stable
+            } else {
+                TimeDecision::Normal
+            };
+        }
+        self.last_depth = Some((score, *best_move));
+    }
+
+    // time the search can use before it stops starting new depths
+    pub fn target(&self) -> Duration {
+        let percent = match self.decision {
+            TimeDecision::Normal => 100,
+            TimeDecision::Forced => 0,
+            TimeDecision::ScoreDropped => SCORE_DROPPED_PERCENT,
+            TimeDecision::BestMoveChanging => BEST_MOVE_CHANGING_PERCENT,
+            TimeDecision::Stable => STABLE_PERCENT,
+        };
+        (self.allocation * percent / 100).min(self.maximum)
+    }
+
+    // time after which the search is stopped, even part way through a depth
+    pub const fn maximum(&self) -> Duration {
+        self.maximum
+    }
+
+    pub const fn decision(&self) -> TimeDecision {
+        self.decision
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::time_manager::TimeControl;
+    use crate::time_manager::TimeDecision;
+    use crate::time_manager::TimeManager;
+    use dolphin_core::board::square::Square;
+    use dolphin_core::moves::mov::Move;
+    use std::time::Duration;
+
+    #[test]
+    pub fn remaining_time_shared_over_moves_to_go() {
+        let time_manager = TimeManager::new(Duration::ZERO);
+
+        let time_control = TimeControl {
+            remaining: Duration::from_secs(100),
+            increment: Duration::ZERO,
+            moves_to_go: Some(10),
+        };
+        assert_eq!(
+            time_manager.allocate(&time_control),
+            Duration::from_secs(10)
+        );
+
+        // sudden death
+        let time_control = TimeControl {
+            moves_to_go: None,
+            ..time_control
+        };
+        assert_eq!(time_manager.allocate(&time_control), Duration::from_secs(4));
+    }
+
+    #[test]
+    pub fn move_overhead_taken_off_budget() {
+        let time_manager = TimeManager::new(Duration::from_millis(100));
+
+        let time_control = TimeControl {
+            remaining: Duration::from_secs(10),
+            increment: Duration::from_secs(2),
+            moves_to_go: Some(10),
+        };
+        // 1s share + 1.5s of the increment - 0.1s overhead
+        assert_eq!(
+            time_manager.allocate(&time_control),
+            Duration::from_millis(2400)
+        );
+    }
+
+    #[test]
+    pub fn budget_never_more_than_clock_less_overhead() {
+        let time_manager = TimeManager::default();
+
+        let time_control = TimeControl {
+            remaining: Duration::from_millis(200),
+            increment: Duration::from_secs(1),
+            moves_to_go: Some(1),
+        };
+        assert_eq!(
+            time_manager.allocate(&time_control),
+            Duration::from_millis(170)
+        );
+
+        let time_control = TimeControl {
+            remaining: Duration::from_millis(20),
+            ..time_control
+        };
+        assert_eq!(time_manager.allocate(&time_control), Duration::ZERO);
+    }
+
+    #[test]
+    pub fn budget_follows_score_and_best_move_between_depths() {
+        let time_manager = TimeManager::new(Duration::ZERO);
+        let time_control = TimeControl {
+            remaining: Duration::from_secs(100),
+            increment: Duration::ZERO,
+            moves_to_go: Some(10),
+        };
+        let mv1 = Move::encode_move(&Square::E2, &Square::E4);
+        let mv2 = Move::encode_move(&Square::D2, &Square::D4);
+
+        let mut budget = time_manager.budget(&time_control, 20);
+        assert_eq!(budget.decision(), TimeDecision::Normal);
+        assert_eq!(budget.target(), Duration::from_secs(10));
+        assert_eq!(budget.maximum(), Duration::from_secs(30));
+
+        budget.update(50, &mv1);
+        assert_eq!(budget.decision(), TimeDecision::Normal);
+
+        budget.update(40, &mv2);
+        assert_eq!(budget.decision(), TimeDecision::BestMoveChanging);
+        assert_eq!(budget.target(), Duration::from_secs(15));
+
+        budget.update(0, &mv2);
+        assert_eq!(budget.decision(), TimeDecision::ScoreDropped);
+        assert_eq!(budget.target(), Duration::from_secs(20));
+
+        for _ in 0..3 {
+            budget.update(0, &mv2);
+        }
+        assert_eq!(budget.decision(), TimeDecision::Stable);
+        assert_eq!(budget.target(), Duration::from_secs(6));
+    }
+
+    #[test]
+    pub fn single_legal_move_gets_no_time() {
+        let time_manager = TimeManager::default();
+        let time_control = TimeControl {
+            remaining: Duration::from_secs(60),
+            ..Default::default()
+        };
+
+        let mut budget = time_manager.budget(&time_control, 1);
+        assert_eq!(budget.decision(), TimeDecision::Forced);
+        assert_eq!(budget.target(), Duration::ZERO);
+
+        // still forced whatever the search finds
+        budget.update(-500, &Move::encode_move(&Square::E1, &Square::E2));
+        assert_eq!(budget.decision(), TimeDecision::Forced);
+    }
+
+    #[test]
+    pub fn budget_maximum_within_clock() {
+        let time_manager = TimeManager::new(Duration::from_millis(100));
+        let time_control = TimeControl {
+            remaining: Duration::from_millis(1000),
+            increment: Duration::ZERO,
+            moves_to_go: Some(1),
+        };
+
+        let budget = time_manager.budget(&time_control, 20);
+        assert_eq!(budget.target(), Duration::from_millis(900));
+        assert_eq!(budget.maximum(), Duration::from_millis(900));
+    }
+}
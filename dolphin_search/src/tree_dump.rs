@@ -0,0 +1,229 @@
+use dolphin_core::moves::mov::Move;
+use dolphin_core::moves::mov::Score;
+use std::fmt;
+
+// Why the search stopped at a node
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum NodeResult {
+    // all moves searched
+    Searched,
+    // a move scored >= beta
+    BetaCutoff,
+    // score taken from the transposition table
+    TtCutoff,
+    // repetition or fifty move rule
+    Draw,
+    Checkmate,
+    Stalemate,
+    // depth ran out, scored by quiescence
+    Quiescence,
+    // too deep (see SearchParams::max_ply), scored by evaluation
+    MaxPly,
+    // node limit reached
+    Stopped,
+}
+
+impl fmt::Display for NodeResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self, f)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum DumpFormat {
+    Json,
+    Dot,
+}
+
+struct Node {
+    mv: Option<Move>,
+    ply: u8,
+    depth: u8,
+    alpha: Score,
+    beta: Score,
+    score: Score,
+    result: NodeResult,
+    children: Vec<usize>,
+}
+
+// Records the alpha-beta search tree down to a small ply, for debugging
+// pruning problems that perft can't show. Only the nodes of the last search
+// are kept.
+pub struct TreeDump {
+    max_ply: u8,
+    nodes: Vec<Node>,
+    // nodes currently being searched, root first
+    open_nodes: Vec<usize>,
+}
+
+impl TreeDump {
+    pub fn new(max_ply: u8) -> Self {
+        TreeDump {
+            max_ply,
+            nodes: Vec::new(),
+            open_nodes: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.nodes.clear();
+        self.open_nodes.clear();
+    }
+
+    pub fn num_nodes(&self) -> usize {
+        self.nodes.len()
+    }
+
+    // Starts recording a node, returning false if it's too deep to record
+    // (in which case close_node mustn't be called for it)
+    pub fn open_node(
+        &mut self,
+        mv: Option<Move>,
+        ply: u8,
+        depth: u8,
+        alpha: Score,
+        beta: Score,
+    ) -> bool {
+        if ply > self.max_ply {
+            return false;
+        }
+
+        let offset = self.nodes.len();
+        self.nodes.push(Node {
+            mv,
+            ply,
+            depth,
+            alpha,
+            beta,
+            score: 0,
+            result: NodeResult::Searched,
+            children: Vec::new(),
+        });
+        if let Some(parent) = self.open_nodes.last() {
+            self.nodes[*parent].children.push(offset);
+        }
+        self.open_nodes.push(offset);
+        true
+    }
+
+    pub fn close_node(&mut self, score: Score, result: NodeResult) {
+        let offset = self.open_nodes.pop().expect("No open node to close");
+        self.nodes[offset].score = score;
+        self.nodes[offset].result = result;
+    }
+
+    pub fn write(&self, format: DumpFormat) -> String {
+        match format {
+            DumpFormat::Json => self.to_json(),
+            DumpFormat::Dot => self.to_dot(),
+        }
+    }
+
+    // roots are the nodes without a parent (one per search call)
+    fn roots(&self) -> Vec<usize> {
+        let mut is_child = vec![false; self.nodes.len()];
+        for node in self.nodes.iter() {
+            for child in node.children.iter() {
+                is_child[*child] = true;
+            }
+        }
+        (0..self.nodes.len()).filter(|n| !is_child[*n]).collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        let roots: Vec<String> = self.roots().iter().map(|n| self.node_json(*n)).collect();
+        format!("[{}]", roots.join(","))
+    }
+
+    fn node_json(&self, offset: usize) -> String {
+        let node = &self.nodes[offset];
+        let mv = match node.mv {
+            Some(mv) => format!("\"{}\"", mv.to_uci_string()),
+            None => "null".to_string(),
+        };
+        let children: Vec<String> = node.children.iter().map(|c| self.node_json(*c)).collect();
+
+        format!(
+            "{{\"move\":{},\"ply\":{},\"depth\":{},\"alpha\":{},\"beta\":{},\"score\":{},\"result\":\"{}\",\"children\":[{}]}}",
+            mv,
+            node.ply,
+            node.depth,
+            node.alpha,
+            node.beta,
+            node.score,
+            node.result,
+            children.join(",")
+        )
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph search {\n");
+        for (offset, node) in self.nodes.iter().enumerate() {
+            let mv = match node.mv {
+                Some(mv) => mv.to_uci_string(),
+                None => "root".to_string(),
+            };
+            dot.push_str(&format!(
+                "  n{} [label=\"{}\\nd={} [{}, {}]\\n{} {}\"];\n",
+                offset, mv, node.depth, node.alpha, node.beta, node.score, node.result
+            ));
+            for child in node.children.iter() {
+                dot.push_str(&format!("  n{} -> n{};\n", offset, child));
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::tree_dump::NodeResult;
+    use crate::tree_dump::TreeDump;
+    use dolphin_core::board::square::Square;
+    use dolphin_core::moves::mov::Move;
+
+    fn sample_tree() -> TreeDump {
+        let mut tree_dump = TreeDump::new(1);
+
+        assert!(tree_dump.open_node(None, 0, 2, -100, 100));
+        let mv = Move::encode_move(&Square::E2, &Square::E4);
+        assert!(tree_dump.open_node(Some(mv), 1, 1, -100, 100));
+        // too deep to record
+        assert!(!tree_dump.open_node(None, 2, 0, -100, 100));
+        tree_dump.close_node(-5, NodeResult::BetaCutoff);
+        tree_dump.close_node(5, NodeResult::Searched);
+
+        tree_dump
+    }
+
+    #[test]
+    pub fn nodes_recorded_up_to_max_ply() {
+        let mut tree_dump = sample_tree();
+        assert_eq!(tree_dump.num_nodes(), 2);
+
+        tree_dump.clear();
+        assert_eq!(tree_dump.num_nodes(), 0);
+    }
+
+    #[test]
+    pub fn tree_written_as_json() {
+        let tree_dump = sample_tree();
+
+        let expected = "[{\"move\":null,\"ply\":0,\"depth\":2,\"alpha\":-100,\"beta\":100,\"score\":5,\"result\":\"Searched\",\"children\":[\
+            {\"move\":\"e2e4\",\"ply\":1,\"depth\":1,\"alpha\":-100,\"beta\":100,\"score\":-5,\"result\":\"BetaCutoff\",\"children\":[]}]}]";
+        assert_eq!(tree_dump.to_json(), expected);
+    }
+
+    #[test]
+    pub fn tree_written_as_dot() {
+        let tree_dump = sample_tree();
+
+        let dot = tree_dump.to_dot();
+        assert!(dot.starts_with("digraph search {\n"));
+        assert!(dot.contains("n0 [label=\"root\\nd=2 [-100, 100]\\n5 Searched\"];"));
+        assert!(dot.contains("n1 [label=\"e2e4\\nd=1 [-100, 100]\\n-5 BetaCutoff\"];"));
+        assert!(dot.contains("n0 -> n1;"));
+        assert!(dot.ends_with("}\n"));
+    }
+}
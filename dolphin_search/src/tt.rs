@@ -0,0 +1,642 @@
+use dolphin_core::moves::mov::Move;
+use dolphin_core::moves::mov::Score;
+use dolphin_core::position::zobrist_keys;
+use dolphin_core::position::zobrist_keys::ZobristHash;
+use std::boxed::Box;
+use std::fmt;
+use std::mem;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum TransType {
+    #[default]
+    Exact,
+    // failed low, the score is an upper bound
+    Alpha,
+    // failed high, the score is a lower bound
+    Beta,
+}
+
+impl fmt::Display for TransType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self, f)
+    }
+}
+
+#[derive(Clone, Copy, Eq, PartialEq, Hash)]
+struct TransEntry {
+    verification_key: u16,
+    trans_type: TransType,
+    score: Score,
+    depth: u8,
+    mv: Move,
+    age: u8,
+}
+
+// An entry is packed into a single u64 so it can be read and written with
+// one atomic operation. This lets search threads share the table without
+// locking, and a reader can never see half of one entry and half of another.
+//
+// xxxx xxxx xxxx xxxx ---- ---- ---- ---- ---- ---- ---- ---- ---- ---- ---- ----    verification key
+// ---- ---- ---- ---- xxxx xx-- ---- ---- ---- ---- ---- ---- ---- ---- ---- ----    age
+// ---- ---- ---- ---- ---- --xx ---- ---- ---- ---- ---- ---- ---- ---- ---- ----    bound (00 unused, 01 exact, 10 alpha, 11 beta)
+// ---- ---- ---- ---- ---- ---- xxxx xxxx ---- ---- ---- ---- ---- ---- ---- ----    depth
+// ---- ---- ---- ---- ---- ---- ---- ---- xxxx xxxx xxxx xxxx ---- ---- ---- ----    score
+// ---- ---- ---- ---- ---- ---- ---- ---- ---- ---- ---- ---- xxxx xxxx xxxx xxxx    move
+enum PackShift {
+    Score = 16,
+    Depth = 32,
+    Bound = 40,
+    Age = 42,
+    Key = 48,
+}
+
+const PACK_MASK_BOUND: u64 = 0b11;
+const PACK_MASK_AGE: u64 = 0b11_1111;
+
+const BOUND_UNUSED: u64 = 0b00;
+const BOUND_EXACT: u64 = 0b01;
+const BOUND_ALPHA: u64 = 0b10;
+const BOUND_BETA: u64 = 0b11;
+
+impl TransEntry {
+    fn pack(&self) -> u64 {
+        let bound = match self.trans_type {
+            TransType::Exact => BOUND_EXACT,
+            TransType::Alpha => BOUND_ALPHA,
+            TransType::Beta => BOUND_BETA,
+        };
+
+        (self.mv.as_u16() as u64)
+            | ((self.score as u16 as u64) << PackShift::Score as u64)
+            | ((self.depth as u64) << PackShift::Depth as u64)
+            | (bound << PackShift::Bound as u64)
+            | (((self.age as u64) & PACK_MASK_AGE) << PackShift::Age as u64)
+            | ((self.verification_key as u64) << PackShift::Key as u64)
+    }
+
+    fn unpack(packed: u64) -> Option<TransEntry> {
+        let trans_type = match (packed >> PackShift::Bound as u64) & PACK_MASK_BOUND {
+            BOUND_UNUSED => return None,
+            BOUND_EXACT => TransType::Exact,
+            BOUND_ALPHA => TransType::Alpha,
+            _ => TransType::Beta,
+        };
+
+        Some(TransEntry {
+            verification_key: (packed >> PackShift::Key as u64) as u16,
+            trans_type,
+            score: (packed >> PackShift::Score as u64) as u16 as Score,
+            depth: (packed >> PackShift::Depth as u64) as u8,
+            mv: Move::from_u16(packed as u16),
+            age: ((packed >> PackShift::Age as u64) & PACK_MASK_AGE) as u8,
+        })
+    }
+}
+
+// What happened to each store since the start of the search, for sizing
+// the table and checking the replacement policy
+#[derive(Debug, Default, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct TtStats {
+    // into an empty slot
+    pub stores: u64,
+    // overwrote the same position
+    pub same_position: u64,
+    // overwrote an entry from an older search
+    pub older_generation: u64,
+    // overwrote a shallower (or same depth) entry
+    pub deeper: u64,
+    // the existing entry was kept
+    pub rejected: u64,
+}
+
+#[derive(Clone, Copy)]
+enum StoreOutcome {
+    Stored,
+    SamePosition,
+    OlderGeneration,
+    Deeper,
+    Rejected,
+}
+
+impl StoreOutcome {
+    const NUM_OUTCOMES: usize = 5;
+}
+
+// entries sampled when estimating how full the table is
+const HASHFULL_SAMPLE_SIZE: usize = 1000;
+
+// Entries are aged by search generation: the generation is incremented at the
+// start of each search ("go"), and a ponder search continues in the generation
+// of the "go ponder" that started it. Entries from older generations are
+// replaced first, otherwise the deeper entry is kept. The table is cleared for
+// a new game, so nothing from one game is seen in the next.
+pub struct TransTable {
+    entries: Box<[AtomicU64]>,
+    capacity: usize,
+    generation: u8,
+    store_counts: [AtomicU64; StoreOutcome::NUM_OUTCOMES],
+}
+
+impl Default for TransTable {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
+impl TransTable {
+    pub fn new(capacity: usize) -> Self {
+        let array = (0..capacity)
+            .map(|_| AtomicU64::new(0))
+            .collect::<Vec<AtomicU64>>()
+            .into_boxed_slice();
+
+        TransTable {
+            entries: array,
+            capacity,
+            generation: 0,
+            store_counts: Default::default(),
+        }
+    }
+
+    pub fn new_search(&mut self) {
+        self.generation = (self.generation + 1) & PACK_MASK_AGE as u8;
+        self.reset_stats();
+    }
+
+    pub fn clear(&mut self) {
+        for entry in self.entries.iter() {
+            entry.store(0, Ordering::Relaxed);
+        }
+        self.generation = 0;
+        self.reset_stats();
+    }
+
+    fn reset_stats(&self) {
+        for count in self.store_counts.iter() {
+            count.store(0, Ordering::Relaxed);
+        }
+    }
+
+    pub fn stats(&self) -> TtStats {
+        let count =
+            |outcome: StoreOutcome| self.store_counts[outcome as usize].load(Ordering::Relaxed);
+        TtStats {
+            stores: count(StoreOutcome::Stored),
+            same_position: count(StoreOutcome::SamePosition),
+            older_generation: count(StoreOutcome::OlderGeneration),
+            deeper: count(StoreOutcome::Deeper),
+            rejected: count(StoreOutcome::Rejected),
+        }
+    }
+
+    // Permille of the table used by the current search (as UCI "hashfull"),
+    // estimated from the first entries rather than scanning the whole table
+    pub fn hashfull(&self) -> u16 {
+        let sample_size = self.capacity.min(HASHFULL_SAMPLE_SIZE);
+        let num_used = self.entries[..sample_size]
+            .iter()
+            .filter_map(|n| TransEntry::unpack(n.load(Ordering::Relaxed)))
+            .filter(|entry| entry.age == self.generation)
+            .count();
+        (num_used * 1000 / sample_size) as u16
+    }
+
+    // in entries
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub const fn memory_bytes(&self) -> usize {
+        self.capacity * mem::size_of::<AtomicU64>()
+    }
+
+    pub const fn generation(&self) -> u8 {
+        self.generation
+    }
+
+    pub fn add(&self, tt_type: TransType, depth: u8, score: Score, hash: ZobristHash, mv: Move) {
+        let offset = self.convert_hash_to_offset(hash, self.capacity);
+
+        let tte = TransEntry {
+            verification_key: zobrist_keys::verification_key_16(hash),
+            trans_type: tt_type,
+            depth,
+            score,
+            mv,
+            age: self.generation,
+        };
+
+        let existing = TransEntry::unpack(self.entries[offset].load(Ordering::Relaxed));
+        let outcome = match existing {
+            Some(existing) => self.store_outcome(&existing, &tte),
+            None => StoreOutcome::Stored,
+        };
+        if !matches!(outcome, StoreOutcome::Rejected) {
+            self.entries[offset].store(tte.pack(), Ordering::Relaxed);
+        }
+        self.store_counts[outcome as usize].fetch_add(1, Ordering::Relaxed);
+    }
+
+    // the replacement policy
+    fn store_outcome(&self, existing: &TransEntry, new: &TransEntry) -> StoreOutcome {
+        if existing.verification_key == new.verification_key {
+            StoreOutcome::SamePosition
+        } else if existing.age != self.generation {
+            StoreOutcome::OlderGeneration
+        } else if new.depth >= existing.depth {
+            StoreOutcome::Deeper
+        } else {
+            StoreOutcome::Rejected
+        }
+    }
+
+    pub fn contains_position_hash(&self, hash: ZobristHash) -> bool {
+        self.find_entry(hash).is_some()
+    }
+
+    // None if the entry has no move, as when the node failed low and had
+    // no earlier move to keep
+    pub fn get_move_for_position_hash(&self, hash: ZobristHash) -> Option<Move> {
+        self.find_entry(hash)
+            .map(|entry| entry.mv)
+            .filter(|mv| *mv != Move::default())
+    }
+
+    pub fn probe(
+        &self,
+        hash: ZobristHash,
+        depth: u8,
+        alpha: Score,
+        beta: Score,
+    ) -> Option<(TransType, Score)> {
+        let entry = self.find_entry(hash)?;
+
+        if entry.depth >= depth {
+            if entry.trans_type == TransType::Exact {
+                return Some((entry.trans_type, entry.score));
+            }
+
+            // fail-soft: the bound itself, which may be past alpha or beta
+            if entry.trans_type == TransType::Alpha && entry.score <= alpha {
+                return Some((entry.trans_type, entry.score));
+            }
+
+            if entry.trans_type == TransType::Beta && entry.score >= beta {
+                return Some((entry.trans_type, entry.score));
+            }
+        }
+
+        None
+    }
+
+    pub fn get(&self, hash: ZobristHash) -> Option<(TransType, u8, Score, Move)> {
+        self.find_entry(hash)
+            .map(|tte| (tte.trans_type, tte.depth, tte.score, tte.mv))
+    }
+
+    pub fn get_num_used(&self) -> u32 {
+        self.entries_in_use().count() as u32
+    }
+    pub fn get_num_trans_type_exact(&self) -> u32 {
+        self.count_tt_types(TransType::Exact)
+    }
+    pub fn get_num_trans_type_alpha(&self) -> u32 {
+        self.count_tt_types(TransType::Alpha)
+    }
+    pub fn get_num_trans_type_beta(&self) -> u32 {
+        self.count_tt_types(TransType::Beta)
+    }
+
+    fn count_tt_types(&self, tt_type: TransType) -> u32 {
+        self.entries_in_use()
+            .filter(|n| n.trans_type == tt_type)
+            .count() as u32
+    }
+
+    fn entries_in_use(&self) -> impl Iterator<Item = TransEntry> + '_ {
+        self.entries
+            .iter()
+            .filter_map(|n| TransEntry::unpack(n.load(Ordering::Relaxed)))
+    }
+
+    // returns the entry in the hash's slot, provided it was stored for this hash
+    // and not for another one mapping to the same slot
+    fn find_entry(&self, hash: ZobristHash) -> Option<TransEntry> {
+        let offset = self.convert_hash_to_offset(hash, self.capacity);
+
+        let entry = TransEntry::unpack(self.entries[offset].load(Ordering::Relaxed))?;
+        if entry.verification_key == zobrist_keys::verification_key_16(hash) {
+            return Some(entry);
+        }
+        None
+    }
+
+    #[inline]
+    fn convert_hash_to_offset(&self, hash: ZobristHash, capacity: usize) -> usize {
+        zobrist_keys::hash_to_index(hash, capacity)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::TransEntry;
+    use super::TransTable;
+    use super::TransType;
+    use super::TtStats;
+    use crate::tt::Score;
+    use dolphin_core::board::piece::Piece;
+    use dolphin_core::board::square::Square;
+    use dolphin_core::moves::mov::Move;
+    use dolphin_core::position::zobrist_keys;
+    use dolphin_core::position::zobrist_keys::ZobristHash;
+    use std::sync::atomic::AtomicU64;
+    use std::sync::atomic::Ordering;
+    use std::thread;
+
+    #[test]
+    pub fn add_and_get_multiple_no_collisions_verify_contents_as_expected() {
+        const NUM_TO_TEST: usize = 30000;
+        const DEPTH: u8 = 5;
+        const TT_ENTRY_TYPE: TransType = TransType::Alpha;
+
+        let target_move = Move::encode_move(&Square::A1, &Square::A2);
+
+        let hashes = hashes_for_distinct_slots(NUM_TO_TEST);
+
+        let tt = TransTable::new(NUM_TO_TEST);
+        // add to TT
+        for (i, hash) in hashes.iter().enumerate() {
+            let score = i as Score;
+            let depth = DEPTH;
+            let trans_type = TT_ENTRY_TYPE;
+
+            tt.add(trans_type, depth, score, *hash, target_move);
+        }
+        assert!(tt.get_num_used() == NUM_TO_TEST as u32);
+
+        // retrieve and verify
+        for (i, hash) in hashes.iter().enumerate() {
+            let tte: Option<(TransType, u8, Score, Move)> = tt.get(*hash);
+
+            assert!(tte.is_some());
+            let trans_type = tte.unwrap().0;
+            let depth = tte.unwrap().1;
+            let score = tte.unwrap().2;
+            let mv = tte.unwrap().3;
+
+            assert!(score == i as Score);
+            assert!(depth == DEPTH);
+            assert!(trans_type == TT_ENTRY_TYPE);
+            assert!(mv == target_move);
+        }
+    }
+
+    #[test]
+    pub fn get_hash_sharing_slot_not_found() {
+        const CAPACITY: usize = 1000;
+
+        let mv = Move::encode_move(&Square::A1, &Square::A2);
+        let hash: ZobristHash = 0x1234_5678_9ABC_DEF0;
+
+        // find another hash that maps onto the same slot
+        let offset = zobrist_keys::hash_to_index(hash, CAPACITY);
+        let other_hash = (1..)
+            .map(|i: u64| hash.wrapping_add(i << 48))
+            .find(|h| zobrist_keys::hash_to_index(*h, CAPACITY) == offset)
+            .unwrap();
+
+        let tt = TransTable::new(CAPACITY);
+        tt.add(TransType::Exact, 1, 10, hash, mv);
+
+        assert!(tt.get(hash).is_some());
+        assert!(tt.get(other_hash).is_none());
+        assert!(tt.get_move_for_position_hash(other_hash).is_none());
+    }
+
+    #[test]
+    pub fn pack_unpack_entry_as_expected() {
+        let mv = Move::encode_move_with_promotion(&Square::B7, &Square::A8, &Piece::Knight);
+
+        for trans_type in [TransType::Exact, TransType::Alpha, TransType::Beta] {
+            for score in [Score::MIN, -29000, -1, 0, 1, 29000, Score::MAX] {
+                for depth in [0, 1, 63, u8::MAX] {
+                    let tte = TransEntry {
+                        verification_key: 0xBEEF,
+                        trans_type,
+                        score,
+                        depth,
+                        mv,
+                        age: 0b10_1010,
+                    };
+
+                    let unpacked = TransEntry::unpack(tte.pack());
+                    assert!(unpacked == Some(tte));
+                }
+            }
+        }
+    }
+
+    #[test]
+    pub fn unused_slot_not_found() {
+        let tt = TransTable::new(100);
+
+        assert_eq!(tt.get_num_used(), 0);
+        assert!(tt.get(0).is_none());
+        assert!(tt.get(0x1234_5678_9ABC_DEF0).is_none());
+    }
+
+    #[test]
+    pub fn concurrent_add_and_get_never_returns_torn_entries() {
+        // several threads write to, and read from, a small table. Every field
+        // of each entry is derived from its hash, so a read that mixed the
+        // fields of two different writes would be detected as inconsistent
+        const NUM_THREADS: u64 = 4;
+        const NUM_HASHES: u64 = 5000;
+
+        fn expected_entry(hash: ZobristHash) -> (u8, Score, Move) {
+            let from_sq = Square::new((hash % 64) as u8).unwrap();
+            let to_sq = Square::new(((hash >> 8) % 64) as u8).unwrap();
+            (
+                (hash >> 16) as u8,
+                (hash >> 24) as u16 as Score,
+                Move::encode_move(&from_sq, &to_sq),
+            )
+        }
+
+        let tt = TransTable::new(64);
+        let num_hits = AtomicU64::new(0);
+
+        thread::scope(|scope| {
+            for t in 0..NUM_THREADS {
+                let tt = &tt;
+                let num_hits = &num_hits;
+                scope.spawn(move || {
+                    for i in 0..NUM_HASHES {
+                        let hash = (i * NUM_THREADS + t).wrapping_mul(0x2545_F491_4F6C_DD1D);
+                        let (depth, score, mv) = expected_entry(hash);
+                        tt.add(TransType::Exact, depth, score, hash, mv);
+
+                        // read back what this, and the other threads, have written
+                        for other in 0..NUM_THREADS {
+                            let read_hash =
+                                (i * NUM_THREADS + other).wrapping_mul(0x2545_F491_4F6C_DD1D);
+                            if let Some((_, depth, score, mv)) = tt.get(read_hash) {
+                                assert!((depth, score, mv) == expected_entry(read_hash));
+                                num_hits.fetch_add(1, Ordering::Relaxed);
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        // make sure the readers actually found entries
+        assert!(num_hits.load(Ordering::Relaxed) > 0);
+    }
+
+    // generates hashes that each map onto a different TT slot
+    fn hashes_for_distinct_slots(capacity: usize) -> Vec<ZobristHash> {
+        let mut used = vec![false; capacity];
+        let mut retval = Vec::new();
+
+        let mut candidate: ZobristHash = 0;
+        while retval.len() < capacity {
+            candidate = candidate.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let offset = zobrist_keys::hash_to_index(candidate, capacity);
+            if !used[offset] {
+                used[offset] = true;
+                retval.push(candidate);
+            }
+        }
+        retval
+    }
+
+    #[test]
+    pub fn shallower_entry_from_same_search_does_not_replace_deeper_entry() {
+        let tt = TransTable::new(1);
+        let mv = Move::encode_move(&Square::A1, &Square::A2);
+        let (hash1, hash2) = (1u64 << 48, 2u64 << 48);
+
+        tt.add(TransType::Exact, 6, 100, hash1, mv);
+        tt.add(TransType::Exact, 3, 200, hash2, mv);
+
+        assert_eq!(tt.get(hash1), Some((TransType::Exact, 6, 100, mv)));
+        assert!(tt.get(hash2).is_none());
+
+        // deeper or equal depth replaces
+        tt.add(TransType::Beta, 6, 300, hash2, mv);
+        assert!(tt.get(hash1).is_none());
+        assert_eq!(tt.get(hash2), Some((TransType::Beta, 6, 300, mv)));
+    }
+
+    #[test]
+    pub fn same_position_always_replaced() {
+        let tt = TransTable::new(1);
+        let mv = Move::encode_move(&Square::A1, &Square::A2);
+        let hash = 1u64 << 48;
+
+        tt.add(TransType::Exact, 6, 100, hash, mv);
+        tt.add(TransType::Alpha, 2, -50, hash, mv);
+
+        assert_eq!(tt.get(hash), Some((TransType::Alpha, 2, -50, mv)));
+    }
+
+    #[test]
+    pub fn entry_from_older_search_replaced_by_shallower_entry() {
+        let mut tt = TransTable::new(1);
+        let mv = Move::encode_move(&Square::A1, &Square::A2);
+        let (hash1, hash2) = (1u64 << 48, 2u64 << 48);
+
+        tt.add(TransType::Exact, 10, 100, hash1, mv);
+
+        tt.new_search();
+        tt.add(TransType::Exact, 1, 200, hash2, mv);
+
+        assert!(tt.get(hash1).is_none());
+        assert_eq!(tt.get(hash2), Some((TransType::Exact, 1, 200, mv)));
+    }
+
+    #[test]
+    pub fn stats_count_stores_by_replacement_reason() {
+        let mut tt = TransTable::new(1);
+        let mv = Move::encode_move(&Square::A1, &Square::A2);
+        let (hash1, hash2, hash3) = (1u64 << 48, 2u64 << 48, 3u64 << 48);
+
+        tt.add(TransType::Exact, 6, 100, hash1, mv);
+        tt.add(TransType::Exact, 7, 100, hash1, mv);
+        tt.add(TransType::Exact, 3, 100, hash2, mv);
+        tt.add(TransType::Exact, 8, 100, hash2, mv);
+
+        let expected = TtStats {
+            stores: 1,
+            same_position: 1,
+            older_generation: 0,
+            deeper: 1,
+            rejected: 1,
+        };
+        assert_eq!(tt.stats(), expected);
+
+        // counts start again for each search
+        tt.new_search();
+        tt.add(TransType::Exact, 1, 100, hash3, mv);
+        let expected = TtStats {
+            older_generation: 1,
+            ..Default::default()
+        };
+        assert_eq!(tt.stats(), expected);
+
+        tt.clear();
+        assert_eq!(tt.stats(), TtStats::default());
+    }
+
+    #[test]
+    pub fn hashfull_counts_entries_from_current_search() {
+        let mut tt = TransTable::new(4000);
+        let mv = Move::encode_move(&Square::A1, &Square::A2);
+        assert_eq!(tt.hashfull(), 0);
+
+        // every other slot
+        for hash in hashes_for_distinct_slots(4000) {
+            if zobrist_keys::hash_to_index(hash, 4000) & 1 == 0 {
+                tt.add(TransType::Exact, 1, 0, hash, mv);
+            }
+        }
+        assert_eq!(tt.hashfull(), 500);
+
+        // entries from the previous search don't count
+        tt.new_search();
+        assert_eq!(tt.hashfull(), 0);
+    }
+
+    #[test]
+    pub fn generation_wraps_within_packed_age_bits() {
+        let mut tt = TransTable::new(1);
+        for _ in 0..64 {
+            tt.new_search();
+        }
+        assert_eq!(tt.generation(), 0);
+    }
+
+    #[test]
+    pub fn entries_do_not_leak_into_new_game() {
+        let mut tt = TransTable::new(1000);
+        let mv = Move::encode_move(&Square::A1, &Square::A2);
+        let hashes = hashes_for_distinct_slots(100);
+
+        tt.new_search();
+        for hash in hashes.iter() {
+            tt.add(TransType::Exact, 5, 100, *hash, mv);
+        }
+        assert_eq!(tt.get_num_used(), 100);
+
+        tt.clear();
+
+        assert_eq!(tt.get_num_used(), 0);
+        assert_eq!(tt.generation(), 0);
+        for hash in hashes.iter() {
+            assert!(tt.get(*hash).is_none());
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{prelude::*, BufReader},
+    path::{Path, PathBuf},
+};
+
+// Counts of the root moves already done for a deep perft, so a run that
+// takes hours or days can be stopped and picked up again. Each root move's
+// count is appended to the file as soon as it's done:
+//
+//      fen rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1
+//      depth 8
+//      a2a3 2863411653
+//      b2b3 3579299617
+//      ...
+pub struct Checkpoint {
+    path: PathBuf,
+    completed: Vec<(String, u64)>,
+}
+
+impl Checkpoint {
+    // Picks up the counts from an earlier run, or starts a new file. A file
+    // for a different position or depth is an error rather than being
+    // overwritten.
+    pub fn open(path: impl AsRef<Path>, fen: &str, depth: u8) -> Checkpoint {
+        let path = path.as_ref().to_path_buf();
+        let header = [format!("fen {}", fen.trim()), format!("depth {}", depth)];
+
+        if !path.exists() {
+            let mut file = File::create(&path).expect("Unable to create checkpoint");
+            for line in header.iter() {
+                writeln!(file, "{}", line).expect("Unable to write checkpoint");
+            }
+            return Checkpoint {
+                path,
+                completed: Vec::new(),
+            };
+        }
+
+        let file = File::open(&path).expect("Unable to open checkpoint");
+        let lines: Vec<String> = BufReader::new(file)
+            .lines()
+            .map(|l| l.expect("Could not read checkpoint line"))
+            .collect();
+
+        if lines.len() < 2 || lines[0] != header[0] || lines[1] != header[1] {
+            panic!(
+                "Checkpoint {} is for another position or depth",
+                path.display()
+            );
+        }
+
+        let completed = lines[2..]
+            .iter()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let (mv, nodes) = line
+                    .split_once(' ')
+                    .expect("Invalid checkpoint line, expected '<move> <nodes>'");
+                (mv.to_string(), nodes.parse().expect("Invalid node count"))
+            })
+            .collect();
+
+        Checkpoint { path, completed }
+    }
+
+    // the count for the root move, if an earlier run got through it
+    pub fn nodes_for(&self, uci_move: &str) -> Option<u64> {
+        self.completed
+            .iter()
+            .find(|(mv, _)| mv == uci_move)
+            .map(|(_, nodes)| *nodes)
+    }
+
+    pub fn num_completed(&self) -> usize {
+        self.completed.len()
+    }
+
+    pub fn record(&mut self, uci_move: &str, nodes: u64) {
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&self.path)
+            .expect("Unable to open checkpoint");
+        writeln!(file, "{} {}", uci_move, nodes).expect("Unable to write checkpoint");
+        file.sync_data().expect("Unable to write checkpoint");
+
+        self.completed.push((uci_move.to_string(), nodes));
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::checkpoint::Checkpoint;
+    use std::env;
+    use std::fs;
+    use std::panic;
+    use std::path::PathBuf;
+    use std::process;
+
+    const FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    fn temp_path(name: &str) -> PathBuf {
+        let path = env::temp_dir().join(format!("perft-{}-{}.checkpoint", name, process::id()));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    pub fn recorded_counts_picked_up_on_reopen() {
+        let path = temp_path("reopen");
+
+        let mut checkpoint = Checkpoint::open(&path, FEN, 8);
+        assert_eq!(checkpoint.num_completed(), 0);
+        checkpoint.record("a2a3", 2863411653);
+        checkpoint.record("b2b3", 3579299617);
+
+        let checkpoint = Checkpoint::open(&path, FEN, 8);
+        assert_eq!(checkpoint.num_completed(), 2);
+        assert_eq!(checkpoint.nodes_for("b2b3"), Some(3579299617));
+        assert_eq!(checkpoint.nodes_for("e2e4"), None);
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    pub fn checkpoint_for_other_depth_rejected() {
+        let path = temp_path("other-depth");
+        Checkpoint::open(&path, FEN, 8);
+
+        let result = panic::catch_unwind(|| Checkpoint::open(&path, FEN, 9));
+        fs::remove_file(&path).unwrap();
+        assert!(result.is_err());
+    }
+}
@@ -1,43 +1,139 @@
 extern crate core_affinity;
 extern crate dolphin_core;
 
+use checkpoint::Checkpoint;
 use dolphin_core::board::occupancy_masks::OccupancyMasks;
 use dolphin_core::io::fen;
 use dolphin_core::moves::move_gen::MoveGenerator;
+use dolphin_core::moves::move_list::MoveList;
+use dolphin_core::moves::perft;
 use dolphin_core::position::attack_checker::AttackChecker;
 use dolphin_core::position::game_position::Position;
-use dolphin_core::position::zobrist_keys::ZobristKeys;
+use std::env;
+use std::process;
+use std::time::Duration;
 use std::time::Instant;
+use time_budget::TimeBudget;
 
+mod checkpoint;
 mod epd_parser;
-mod perft_runner;
+mod time_budget;
+
+const USAGE: &str =
+    "usage: perft [EPD_FILE | --chess960] [--position-time SECS] [--total-time SECS] [--stats]
+       perft --fen FEN --depth N [--checkpoint FILE]
+       perft --fen FEN --depth N --divide [--chess960]";
+
+// depth from which a single position run warns if it isn't checkpointed
+const CHECKPOINT_DEPTH: u8 = 8;
 
 fn main() {
     // Pin current thread to a core
     let core_ids = core_affinity::get_core_ids().unwrap();
     core_affinity::set_for_current(core_ids[0]);
 
-    let epd_rows = epd_parser::extract_epd(
-        "/Users/eddiemcnally/dev/rust/dolphin/perft/resources/perftsuite.epd".to_string(),
-    );
+    let mut epd_file = None;
+    let mut per_position = None;
+    let mut total = None;
+    let mut show_stats = false;
+    let mut fen = None;
+    let mut depth = None;
+    let mut checkpoint_file = None;
+    let mut divide = false;
+    let mut chess960 = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--position-time" => per_position = Some(parse_secs(args.next())),
+            "--total-time" => total = Some(parse_secs(args.next())),
+            "--stats" => show_stats = true,
+            "--fen" => fen = Some(args.next().unwrap_or_else(|| exit_with_usage())),
+            "--depth" => depth = Some(parse_depth(args.next())),
+            "--checkpoint" => {
+                checkpoint_file = Some(args.next().unwrap_or_else(|| exit_with_usage()))
+            }
+            "--divide" => divide = true,
+            "--chess960" => chess960 = true,
+            _ if !arg.starts_with("--") => epd_file = Some(arg),
+            _ => exit_with_usage(),
+        }
+    }
+
+    if let Some(fen) = fen {
+        let depth = depth.unwrap_or_else(|| exit_with_usage());
+        if divide {
+            process_divide(&fen, depth, chess960);
+        } else {
+            process_deep(&fen, depth, checkpoint_file);
+        }
+        return;
+    }
 
-    for epd in &epd_rows {
+    // --chess960 on its own runs the Chess960 (and DFRC) suite
+    let epd_file = epd_file.unwrap_or_else(|| {
+        if chess960 {
+            concat!(env!("CARGO_MANIFEST_DIR"), "/resources/chess960.epd").to_string()
+        } else {
+            concat!(env!("CARGO_MANIFEST_DIR"), "/resources/perftsuite.epd").to_string()
+        }
+    });
+    let epd_rows = epd_parser::extract_epd(epd_file);
+    let budget = TimeBudget::new(per_position, total);
+
+    for (i, epd) in epd_rows.iter().enumerate() {
         println!("Testing FEN '{}'", epd.fen);
 
+        let allowance = budget.position_allowance(epd_rows.len() - i);
+        let position_start = Instant::now();
+
+        let mut prev_depth: Option<(Duration, u64)> = None;
         for depth in 1..7 {
-            process_row(epd, depth);
+            if let (Some(allowance), Some((prev_elapsed, prev_nodes))) = (allowance, prev_depth) {
+                if time_budget::would_exceed(
+                    allowance,
+                    position_start.elapsed(),
+                    prev_elapsed,
+                    prev_nodes,
+                    epd.depth_map[&depth],
+                ) {
+                    println!("Skipping depth {} and deeper, over time budget", depth);
+                    break;
+                }
+            }
+
+            let elapsed = process_row(epd, depth, show_stats);
+            prev_depth = Some((elapsed, epd.depth_map[&depth]));
         }
     }
 }
 
-fn process_row(row: &epd_parser::EpdRow, depth: u8) {
+fn parse_secs(arg: Option<String>) -> Duration {
+    match arg.and_then(|s| s.parse::<f64>().ok()) {
+        Some(secs) if secs >= 0.0 => Duration::from_secs_f64(secs),
+        _ => exit_with_usage(),
+    }
+}
+
+fn parse_depth(arg: Option<String>) -> u8 {
+    match arg.and_then(|s| s.parse::<u8>().ok()) {
+        Some(depth) if depth > 0 => depth,
+        _ => exit_with_usage(),
+    }
+}
+
+fn exit_with_usage() -> ! {
+    eprintln!("{}", USAGE);
+    process::exit(1);
+}
+
+fn process_row(row: &epd_parser::EpdRow, depth: u8, show_stats: bool) -> Duration {
     let fen = &row.fen;
 
     let expected_moves = &row.depth_map[&depth];
     let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) = fen::decompose_fen(fen);
 
-    let zobrist_keys = ZobristKeys::new();
-    let occ_masks = OccupancyMasks::new();
+    let occ_masks = OccupancyMasks::instance();
     let attack_checker = AttackChecker::new();
 
     let mut pos = Position::new(
@@ -46,16 +142,20 @@ fn process_row(row: &epd_parser::EpdRow, depth: u8) {
         move_cntr,
         en_pass_sq,
         side_to_move,
-        &zobrist_keys,
-        &occ_masks,
+        occ_masks,
         &attack_checker,
     );
-    let mov_generator = MoveGenerator::new();
 
     let now = Instant::now();
-    let num_moves = perft_runner::perft(depth, &mut pos, &mov_generator);
-    let elapsed_in_secs = now.elapsed().as_secs_f64();
-    let nodes_per_sec = (num_moves as f64 / elapsed_in_secs) as u64;
+    let num_moves = if show_stats {
+        let stats = perft::perft_with_stats(&mut pos, depth);
+        println!("{:?}", stats);
+        stats.nodes
+    } else {
+        perft::perft(&mut pos, depth)
+    };
+    let elapsed = now.elapsed();
+    let nodes_per_sec = (num_moves as f64 / elapsed.as_secs_f64()) as u64;
 
     if *expected_moves != num_moves {
         println!(
@@ -68,4 +168,127 @@ fn process_row(row: &epd_parser::EpdRow, depth: u8) {
         "#Nodes/Sec: {}, Depth: {}, #Expected: {}, #found: {}",
         nodes_per_sec, depth, expected_moves, num_moves
     );
+
+    elapsed
+}
+
+// The count under each root move, as Stockfish's "go perft" prints it, so
+// the two can be diffed line by line: UCI moves (with the promotion piece,
+// and castling as the king's move, or as the king taking its rook with
+// --chess960, as for Stockfish's UCI_Chess960), sorted, then the total.
+fn process_divide(fen: &str, depth: u8, chess960: bool) {
+    let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) = fen::decompose_fen(fen);
+
+    let occ_masks = OccupancyMasks::instance();
+    let attack_checker = AttackChecker::new();
+
+    let mut pos = Position::new(
+        board,
+        castle_permissions,
+        move_cntr,
+        en_pass_sq,
+        side_to_move,
+        occ_masks,
+        &attack_checker,
+    );
+
+    let mut counts: Vec<(String, u64)> = perft::divide(&mut pos, depth)
+        .iter()
+        .map(|(mv, nodes)| {
+            let uci = if chess960 {
+                mv.to_uci_string_chess960(&castle_permissions)
+            } else {
+                mv.to_uci_string()
+            };
+            (uci, *nodes)
+        })
+        .collect();
+    counts.sort();
+
+    for (uci, nodes) in counts.iter() {
+        println!("{}: {}", uci, nodes);
+    }
+    println!();
+    println!(
+        "Nodes searched: {}",
+        counts.iter().map(|(_, nodes)| nodes).sum::<u64>()
+    );
+}
+
+// A single position, one root move at a time, with the count for each root
+// move written to the checkpoint file (if there is one) as it's done. A run
+// restarted with the same checkpoint skips the root moves already counted.
+fn process_deep(fen: &str, depth: u8, checkpoint_file: Option<String>) {
+    let (board, move_cntr, castle_permissions, side_to_move, en_pass_sq) = fen::decompose_fen(fen);
+
+    let occ_masks = OccupancyMasks::instance();
+    let attack_checker = AttackChecker::new();
+
+    let mut pos = Position::new(
+        board,
+        castle_permissions,
+        move_cntr,
+        en_pass_sq,
+        side_to_move,
+        occ_masks,
+        &attack_checker,
+    );
+
+    let mut checkpoint = checkpoint_file.map(|path| Checkpoint::open(path, fen, depth));
+    match &checkpoint {
+        Some(checkpoint) if checkpoint.num_completed() > 0 => println!(
+            "Resuming from checkpoint, {} root moves already counted",
+            checkpoint.num_completed()
+        ),
+        None if depth >= CHECKPOINT_DEPTH => {
+            println!("No checkpoint file, an interrupted run will start again")
+        }
+        _ => {}
+    }
+
+    let mut move_list = MoveList::new();
+    let num_root_moves = MoveGenerator::new().generate_legal_moves(&mut pos, &mut move_list);
+
+    let now = Instant::now();
+    let mut num_moves = 0;
+    for (i, mv) in move_list.iterator().enumerate() {
+        let uci = mv.to_uci_string();
+
+        let nodes = match checkpoint
+            .as_ref()
+            .and_then(|checkpoint| checkpoint.nodes_for(&uci))
+        {
+            Some(nodes) => {
+                println!(
+                    "[{}/{}] {}: {} (checkpoint)",
+                    i + 1,
+                    num_root_moves,
+                    uci,
+                    nodes
+                );
+                nodes
+            }
+            None => {
+                pos.make_move(mv);
+                let nodes = perft::perft(&mut pos, depth - 1);
+                pos.take_move();
+
+                if let Some(checkpoint) = &mut checkpoint {
+                    checkpoint.record(&uci, nodes);
+                }
+                println!(
+                    "[{}/{}] {}: {}, elapsed {:.1}s",
+                    i + 1,
+                    num_root_moves,
+                    uci,
+                    nodes,
+                    now.elapsed().as_secs_f64()
+                );
+                nodes
+            }
+        };
+        num_moves += nodes;
+    }
+
+    println!("Depth: {}, #found: {}", depth, num_moves);
 }
@@ -0,0 +1,121 @@
+use std::time::Duration;
+use std::time::Instant;
+
+// Limits how long the EPD suite is allowed to run. Depths that are
+// estimated to go past the limit for a position are skipped.
+pub struct TimeBudget {
+    per_position: Option<Duration>,
+    total: Option<Duration>,
+    start: Instant,
+}
+
+impl TimeBudget {
+    pub fn new(per_position: Option<Duration>, total: Option<Duration>) -> Self {
+        TimeBudget {
+            per_position,
+            total,
+            start: Instant::now(),
+        }
+    }
+
+    // Time available to the next position. The remaining total time is shared
+    // evenly between the positions still to run, so the early positions
+    // can't use up the time of the later ones.
+    pub fn position_allowance(&self, positions_remaining: usize) -> Option<Duration> {
+        let share_of_total = self.total.map(|total| {
+            let remaining = total.saturating_sub(self.start.elapsed());
+            remaining / positions_remaining.max(1) as u32
+        });
+
+        match (self.per_position, share_of_total) {
+            (Some(per_pos), Some(share)) => Some(per_pos.min(share)),
+            (per_pos, share) => per_pos.or(share),
+        }
+    }
+}
+
+// Estimates the time to run the next depth, assuming time is proportional
+// to the number of nodes (known in advance from the EPD row).
+pub fn estimated_duration(prev_elapsed: Duration, prev_nodes: u64, next_nodes: u64) -> Duration {
+    if prev_nodes == 0 {
+        return Duration::ZERO;
+    }
+    prev_elapsed.mul_f64(next_nodes as f64 / prev_nodes as f64)
+}
+
+pub fn would_exceed(
+    allowance: Duration,
+    used: Duration,
+    prev_elapsed: Duration,
+    prev_nodes: u64,
+    next_nodes: u64,
+) -> bool {
+    used + estimated_duration(prev_elapsed, prev_nodes, next_nodes) > allowance
+}
+
+#[cfg(test)]
+pub mod tests {
+    use crate::time_budget::estimated_duration;
+    use crate::time_budget::would_exceed;
+    use crate::time_budget::TimeBudget;
+    use std::time::Duration;
+
+    #[test]
+    pub fn unlimited_budget_has_no_allowance() {
+        let budget = TimeBudget::new(None, None);
+        assert_eq!(budget.position_allowance(10), None);
+    }
+
+    #[test]
+    pub fn per_position_allowance_used_when_no_total() {
+        let budget = TimeBudget::new(Some(Duration::from_secs(5)), None);
+        assert_eq!(budget.position_allowance(10), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    pub fn total_budget_shared_between_remaining_positions() {
+        let budget = TimeBudget::new(None, Some(Duration::from_secs(1000)));
+
+        let allowance = budget.position_allowance(10).unwrap();
+        assert!(allowance <= Duration::from_secs(100));
+        assert!(allowance > Duration::from_secs(99));
+    }
+
+    #[test]
+    pub fn smallest_of_per_position_and_total_share_used() {
+        let budget = TimeBudget::new(
+            Some(Duration::from_secs(5)),
+            Some(Duration::from_secs(1000)),
+        );
+        assert_eq!(budget.position_allowance(10), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    pub fn estimated_duration_scales_with_node_count() {
+        let estimate = estimated_duration(Duration::from_millis(10), 564, 5640);
+        assert_eq!(estimate, Duration::from_millis(100));
+    }
+
+    #[test]
+    pub fn depth_skipped_when_estimate_exceeds_allowance() {
+        let allowance = Duration::from_secs(1);
+
+        // 0.1s used, next depth estimated at 0.5s
+        assert!(!would_exceed(
+            allowance,
+            Duration::from_millis(100),
+            Duration::from_millis(50),
+            100,
+            1000
+        ));
+
+        // 0.1s used, next depth estimated at 5s
+        assert!(would_exceed(
+            allowance,
+            Duration::from_millis(100),
+            Duration::from_millis(50),
+            100,
+            100_000
+        ));
+    }
+}